@@ -0,0 +1,131 @@
+//! Banned client IPs and the per-IP request windows behind auto-banning.
+//! Bans are persisted in `banned_sources` and mirrored here so the check on
+//! every request never touches the database; request counts live only in
+//! memory and start over on restart.
+
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::db::Database;
+use crate::error::AspError;
+
+/// Request windows kept before expired ones are pruned.
+const MAX_TRACKED_SOURCES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+struct Window {
+    started: Instant,
+    requests: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct BanList {
+    banned: RwLock<HashSet<IpAddr>>,
+    windows: Mutex<HashMap<IpAddr, Window>>,
+}
+
+impl BanList {
+    /// Replace the in-memory list with the bans stored in `db`. Returns how
+    /// many were loaded; rows that are not an IP address are skipped.
+    pub fn load(&self, db: &Database) -> Result<usize, AspError> {
+        let loaded: HashSet<IpAddr> = db
+            .list_banned_sources()?
+            .iter()
+            .filter_map(|row| row.ip.parse().ok())
+            .collect();
+        let count = loaded.len();
+        *self.banned.write().unwrap_or_else(|p| p.into_inner()) = loaded;
+        Ok(count)
+    }
+
+    pub fn is_banned(&self, ip: &IpAddr) -> bool {
+        self.banned
+            .read()
+            .unwrap_or_else(|p| p.into_inner())
+            .contains(ip)
+    }
+
+    pub fn insert(&self, ip: IpAddr) {
+        self.banned
+            .write()
+            .unwrap_or_else(|p| p.into_inner())
+            .insert(ip);
+    }
+
+    /// Lift the ban on `ip` and forget its request count.
+    pub fn remove(&self, ip: &IpAddr) {
+        self.banned
+            .write()
+            .unwrap_or_else(|p| p.into_inner())
+            .remove(ip);
+        self.windows
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .remove(ip);
+    }
+
+    /// Count one request from `ip` at `now`. Returns `true` when this request
+    /// takes `ip` over `threshold` requests within the current `window`,
+    /// which starts at the first request after the previous one ended.
+    pub fn record(&self, ip: IpAddr, threshold: u32, window: Duration, now: Instant) -> bool {
+        let mut windows = self.windows.lock().unwrap_or_else(|p| p.into_inner());
+        if windows.len() >= MAX_TRACKED_SOURCES && !windows.contains_key(&ip) {
+            windows.retain(|_, w| now.duration_since(w.started) < window);
+        }
+
+        let entry = windows.entry(ip).or_insert(Window {
+            started: now,
+            requests: 0,
+        });
+        if now.duration_since(entry.started) >= window {
+            *entry = Window {
+                started: now,
+                requests: 0,
+            };
+        }
+        entry.requests = entry.requests.saturating_add(1);
+        entry.requests > threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_trips_once_the_window_holds_more_than_the_threshold() {
+        let bans = BanList::default();
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let other: IpAddr = "198.51.100.1".parse().unwrap();
+        let window = Duration::from_secs(60);
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            assert!(!bans.record(ip, 3, window, start));
+        }
+        assert!(!bans.record(other, 3, window, start));
+        assert!(bans.record(ip, 3, window, start + Duration::from_secs(59)));
+
+        // A new window starts the count over
+        assert!(!bans.record(ip, 3, window, start + window));
+    }
+
+    #[test]
+    fn remove_clears_ban_and_count() {
+        let bans = BanList::default();
+        let ip: IpAddr = "2001:db8::1".parse().unwrap();
+        let window = Duration::from_secs(60);
+        let now = Instant::now();
+
+        bans.record(ip, 1, window, now);
+        assert!(bans.record(ip, 1, window, now));
+        bans.insert(ip);
+        assert!(bans.is_banned(&ip));
+
+        bans.remove(&ip);
+        assert!(!bans.is_banned(&ip));
+        assert!(!bans.record(ip, 1, window, now));
+    }
+}
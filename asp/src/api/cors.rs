@@ -0,0 +1,85 @@
+//! Cross-origin policy, driven by `ALLOWED_ORIGINS`.
+//!
+//! - `*`: any origin may call any route (the old permissive behaviour).
+//! - A list of origins: only those may call the API from a browser.
+//! - Unset: any origin may read, but mutating requests carrying an `Origin`
+//!   header are refused. Non-browser clients send no `Origin` and are
+//!   unaffected either way.
+
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderName, HeaderValue, Method};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+use crate::api::middleware::{OPERATION_ID_HEADER, REQUEST_ID_HEADER};
+use crate::error::AspError;
+use crate::AppState;
+
+/// `CorsLayer` for the configured origins.
+pub fn cors_layer(allowed_origins: &[String]) -> CorsLayer {
+    if allowed_origins.iter().any(|o| o == "*") {
+        return CorsLayer::permissive();
+    }
+
+    let exposed = [
+        HeaderName::from_static(REQUEST_ID_HEADER),
+        HeaderName::from_static(OPERATION_ID_HEADER),
+    ];
+    if allowed_origins.is_empty() {
+        return CorsLayer::new()
+            .allow_origin(Any)
+            .allow_methods([Method::GET, Method::HEAD])
+            .expose_headers(exposed);
+    }
+
+    let origins: Vec<HeaderValue> = allowed_origins
+        .iter()
+        .filter_map(|o| HeaderValue::from_str(o).ok())
+        .collect();
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods([Method::GET, Method::HEAD, Method::POST])
+        .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION])
+        .expose_headers(exposed)
+}
+
+/// Axum middleware: refuse mutating requests from browser origins that are
+/// not allowed, rather than relying on the browser to drop the response
+/// after the transaction has already been relayed.
+pub async fn reject_disallowed_origin(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let mutating = !matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    if let Some(origin) = req.headers().get(header::ORIGIN).filter(|_| mutating) {
+        if !origin_allowed(&state.config.allowed_origins, origin) {
+            let origin = String::from_utf8_lossy(origin.as_bytes());
+            return AspError::Forbidden(format!("Origin {origin} is not allowed")).into_response();
+        }
+    }
+    next.run(req).await
+}
+
+fn origin_allowed(allowed_origins: &[String], origin: &HeaderValue) -> bool {
+    allowed_origins
+        .iter()
+        .any(|allowed| allowed == "*" || allowed.as_bytes() == origin.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn origin_matching() {
+        let origin = HeaderValue::from_static("https://app.zylith.xyz");
+        assert!(origin_allowed(&["*".into()], &origin));
+        assert!(origin_allowed(&["https://app.zylith.xyz".into()], &origin));
+        assert!(!origin_allowed(&["https://evil.xyz".into()], &origin));
+        assert!(!origin_allowed(&[], &origin));
+    }
+}
@@ -0,0 +1,72 @@
+//! In-memory success/failure counts of the mutating operations, reported
+//! under `operations` in `/status`. They start at zero on every restart; the
+//! persistent record is the audit log behind `/operations`.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::api::types::OperationCount;
+
+/// Operations counted, i.e. the routes behind `record_operation`.
+pub const COUNTED_OPERATIONS: [&str; 5] = ["deposit", "withdraw", "swap", "mint", "burn"];
+
+#[derive(Debug, Default)]
+struct Outcomes {
+    ok: AtomicU64,
+    err: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+pub struct OperationCounters {
+    outcomes: [Outcomes; COUNTED_OPERATIONS.len()],
+}
+
+impl OperationCounters {
+    /// Count one `kind` request; unknown kinds are ignored.
+    pub fn record(&self, kind: &str, ok: bool) {
+        let Some(i) = COUNTED_OPERATIONS.iter().position(|k| *k == kind) else {
+            return;
+        };
+        let counter = if ok {
+            &self.outcomes[i].ok
+        } else {
+            &self.outcomes[i].err
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Current counts for every operation, including those never called.
+    pub fn snapshot(&self) -> BTreeMap<String, OperationCount> {
+        COUNTED_OPERATIONS
+            .iter()
+            .zip(&self.outcomes)
+            .map(|(kind, outcomes)| {
+                let count = OperationCount {
+                    ok: outcomes.ok.load(Ordering::Relaxed),
+                    err: outcomes.err.load(Ordering::Relaxed),
+                };
+                (kind.to_string(), count)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_by_kind_and_outcome() {
+        let counters = OperationCounters::default();
+        counters.record("deposit", true);
+        counters.record("deposit", true);
+        counters.record("swap", false);
+        counters.record("simulate-swap", true);
+
+        let counts = counters.snapshot();
+        assert_eq!(counts.len(), COUNTED_OPERATIONS.len());
+        assert_eq!((counts["deposit"].ok, counts["deposit"].err), (2, 0));
+        assert_eq!((counts["swap"].ok, counts["swap"].err), (0, 1));
+        assert_eq!((counts["burn"].ok, counts["burn"].err), (0, 0));
+    }
+}
@@ -0,0 +1,429 @@
+use std::sync::Arc;
+
+use std::net::IpAddr;
+
+use axum::body::{Body, Bytes};
+use axum::extract::{Path, Query, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::api::types::{
+    BanRequest, BanResponse, CircuitProofStats, ExportedCommitment, ImportResponse,
+    ProofStatsResponse, ResubmitRootResponse, ResyncQuery, ResyncResponse, UnbanResponse,
+    VacuumResponse, VerifyTreeQuery, VerifyTreeResponse,
+};
+use crate::api::validation::{validate_decimal, validate_hex_u256};
+use crate::db::queries::CommitmentRow;
+use crate::error::AspError;
+use crate::sync::events::{submit_root_if_changed, SyncReport};
+use crate::AppState;
+
+/// Endpoint: POST /admin/resync?from_block=
+/// Rewind event sync so the next cycle rescans from `from_block`. Events that
+/// are already known are skipped, so this only fills in what was missed.
+pub async fn resync(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ResyncQuery>,
+) -> Result<Json<ResyncResponse>, AspError> {
+    let from_block = query.from_block.unwrap_or(state.config.sync_start_block);
+
+    state
+        .db
+        .set_sync_state("last_block", &from_block.to_string())?;
+    tracing::info!(from_block, "Event sync rewound by admin request");
+
+    Ok(Json(ResyncResponse {
+        status: "ok".to_string(),
+        from_block,
+    }))
+}
+
+/// Endpoint: POST /admin/sync-now
+/// Run one event sync cycle immediately instead of waiting for the next poll.
+/// Waits for a cycle that is already running to finish first.
+pub async fn sync_now(State(state): State<Arc<AppState>>) -> Result<Json<SyncReport>, AspError> {
+    let syncer = state
+        .syncer
+        .as_ref()
+        .ok_or_else(|| AspError::Config("Event sync is not configured".into()))?;
+    let report = syncer.sync_once(&state).await?;
+    tracing::info!(
+        new_commitments = report.new_commitments,
+        new_nullifiers = report.new_nullifiers,
+        from_block = report.from_block,
+        to_block = report.to_block,
+        "Event sync run by admin request"
+    );
+    Ok(Json(report))
+}
+
+/// Endpoint: POST /admin/vacuum
+/// Reclaim space from deleted rows and truncate the WAL. Runs on a blocking
+/// thread since `VACUUM` rewrites the whole file.
+pub async fn vacuum(State(state): State<Arc<AppState>>) -> Result<Json<VacuumResponse>, AspError> {
+    let (before, after) = tokio::task::spawn_blocking(move || state.db.vacuum())
+        .await
+        .map_err(|e| AspError::Internal(format!("Vacuum task failed: {e}")))??;
+    tracing::info!(
+        db_bytes_before = before.db_bytes,
+        wal_bytes_before = before.wal_bytes,
+        db_bytes_after = after.db_bytes,
+        wal_bytes_after = after.wal_bytes,
+        "Database vacuumed by admin request"
+    );
+
+    Ok(Json(VacuumResponse {
+        status: "ok".to_string(),
+        db_bytes_before: before.db_bytes,
+        wal_bytes_before: before.wal_bytes,
+        db_bytes_after: after.db_bytes,
+        wal_bytes_after: after.wal_bytes,
+    }))
+}
+
+/// Endpoint: POST /admin/verify-tree?repair=
+/// Compare the worker's tree with one rebuilt from the database commitments.
+/// With `repair=true`, a mismatch is fixed by rebuilding the worker tree from
+/// the database and recording (and, with a relayer, submitting) its root.
+pub async fn verify_tree(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<VerifyTreeQuery>,
+) -> Result<Json<VerifyTreeResponse>, AspError> {
+    let leaves: Vec<String> = state
+        .db
+        .get_all_commitments()?
+        .into_iter()
+        .map(|c| c.commitment)
+        .collect();
+    let db_leaf_count = leaves.len() as u32;
+
+    let mut worker = state.worker.lock().await;
+    let worker_root = worker.get_root().await?;
+    let rebuilt_root = worker.compute_root(&leaves).await?;
+    let consistent = worker_root == rebuilt_root;
+
+    let repaired = !consistent && query.repair.unwrap_or(false);
+    if repaired {
+        worker.build_tree(&leaves).await?;
+    }
+    drop(worker);
+
+    if consistent {
+        tracing::info!(db_leaf_count, root = %worker_root, "Tree verified by admin request");
+    } else {
+        tracing::warn!(
+            db_leaf_count,
+            worker_root = %worker_root,
+            rebuilt_root = %rebuilt_root,
+            repaired,
+            "Worker tree does not match the database"
+        );
+    }
+
+    if repaired {
+        submit_root_if_changed(&state).await?;
+        // Proof-only mode submits nothing, so record the rebuilt root here
+        if db_leaf_count > 0
+            && state.db.get_latest_root()?.as_deref() != Some(rebuilt_root.as_str())
+        {
+            state.db.insert_root(&rebuilt_root, db_leaf_count, None)?;
+        }
+        state.refresh_tree()?;
+    }
+
+    Ok(Json(VerifyTreeResponse {
+        consistent,
+        db_leaf_count,
+        worker_root,
+        rebuilt_root,
+        repaired,
+    }))
+}
+
+/// Endpoint: POST /admin/resubmit-root
+/// Send the worker's current root to the coordinator again, e.g. after the
+/// original `submit_merkle_root` was dropped from the mempool. Nothing is sent
+/// when the on-chain root already matches.
+pub async fn resubmit_root(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ResubmitRootResponse>, AspError> {
+    let relayer = state
+        .relayer
+        .as_ref()
+        .ok_or_else(|| AspError::Config("No relayer is configured".into()))?;
+
+    let leaf_count = state.db.get_leaf_count()?;
+    if leaf_count == 0 {
+        return Err(AspError::InvalidInput(
+            "The tree is empty; there is no root to resubmit".into(),
+        ));
+    }
+    let root = state.worker.lock().await.get_root().await?;
+
+    let relayer = relayer.lock().await;
+    if relayer.get_onchain_root().await? == root {
+        tracing::info!(root = %root, "Merkle root already on-chain; not resubmitted");
+        return Ok(Json(ResubmitRootResponse {
+            root,
+            leaf_count,
+            resubmitted: false,
+            tx_hash: None,
+        }));
+    }
+    let tx_hash = relayer.submit_merkle_root(&root).await?;
+    drop(relayer);
+
+    state.db.insert_root(&root, leaf_count, Some(&tx_hash))?;
+    state.refresh_tree()?;
+    tracing::info!(
+        root = %root,
+        leaf_count,
+        tx_hash = %tx_hash,
+        "Merkle root resubmitted by admin request"
+    );
+
+    Ok(Json(ResubmitRootResponse {
+        root,
+        leaf_count,
+        resubmitted: true,
+        tx_hash: Some(tx_hash),
+    }))
+}
+
+/// Content type of `/admin/export` and `/admin/import` bodies.
+const NDJSON: &str = "application/x-ndjson";
+
+/// Export lines are sent to the client in chunks of about this many bytes.
+const EXPORT_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Chunks buffered ahead of a slow client before the export pauses reading.
+const EXPORT_BUFFERED_CHUNKS: usize = 4;
+
+/// Endpoint: GET /admin/export
+/// Stream every commitment as NDJSON (`{leaf_index, commitment, deposit_tx}`
+/// per line, in leaf order). Rows are read off one query on a blocking thread
+/// and handed over in bounded chunks, so memory stays flat however large the
+/// tree is and the query only advances as fast as the client reads.
+pub async fn export(State(state): State<Arc<AppState>>) -> Response {
+    let (sender, receiver) = mpsc::channel::<Result<Bytes, AspError>>(EXPORT_BUFFERED_CHUNKS);
+
+    tokio::task::spawn_blocking(move || {
+        let mut chunk = Vec::with_capacity(EXPORT_CHUNK_BYTES);
+        let mut rows = 0u64;
+        let result = state.db.for_each_commitment(|row| {
+            let line = ExportedCommitment {
+                leaf_index: row.leaf_index,
+                commitment: row.commitment,
+                deposit_tx: row.deposit_tx,
+            };
+            // Serializing a struct of strings and integers cannot fail
+            let _ = serde_json::to_writer(&mut chunk, &line);
+            chunk.push(b'\n');
+            rows += 1;
+            if chunk.len() < EXPORT_CHUNK_BYTES {
+                return true;
+            }
+            let full = std::mem::replace(&mut chunk, Vec::with_capacity(EXPORT_CHUNK_BYTES));
+            // A closed channel means the client went away
+            sender.blocking_send(Ok(Bytes::from(full))).is_ok()
+        });
+
+        match result {
+            Ok(()) => {
+                if !chunk.is_empty() {
+                    let _ = sender.blocking_send(Ok(Bytes::from(chunk)));
+                }
+                tracing::info!(rows, "Commitments exported by admin request");
+            }
+            Err(e) => {
+                tracing::error!(rows, error = %e, "Commitment export failed");
+                // Ends the body with an error so the client sees a truncated export
+                let _ = sender.blocking_send(Err(e));
+            }
+        }
+    });
+
+    (
+        [(header::CONTENT_TYPE, NDJSON)],
+        Body::from_stream(ReceiverStream::new(receiver)),
+    )
+        .into_response()
+}
+
+/// Endpoint: POST /admin/import
+/// Load commitments from an `/admin/export` NDJSON body in one transaction.
+/// Rows already present with the same commitment are skipped and new rows
+/// must continue the tree without gaps; any bad line rejects the whole body.
+/// The worker tree is then rebuilt and its root recorded (and, with a
+/// relayer, submitted) as in `/admin/verify-tree?repair=true`.
+pub async fn import(
+    State(state): State<Arc<AppState>>,
+    body: Bytes,
+) -> Result<Json<ImportResponse>, AspError> {
+    let rows = parse_import(&body)?;
+    if let Some(last) = rows.last() {
+        state.config.ensure_tree_capacity(last.leaf_index)?;
+    }
+
+    // Hold the worker so no deposit lands between the import and the rebuild
+    let mut worker = state.worker.lock().await;
+    let (imported, skipped) = state.db.import_commitments(&rows)?;
+    let leaves: Vec<String> = state
+        .db
+        .get_all_commitments()?
+        .into_iter()
+        .map(|c| c.commitment)
+        .collect();
+    let leaf_count = leaves.len() as u32;
+    let root = if imported > 0 {
+        Some(worker.build_tree(&leaves).await?)
+    } else {
+        None
+    };
+    drop(worker);
+
+    if let Some(ref root) = root {
+        submit_root_if_changed(&state).await?;
+        // Proof-only mode submits nothing, so record the rebuilt root here
+        if state.db.get_latest_root()?.as_deref() != Some(root.as_str()) {
+            state.db.insert_root(root, leaf_count, None)?;
+        }
+        state.refresh_tree()?;
+    }
+    tracing::info!(
+        imported,
+        skipped,
+        leaf_count,
+        "Commitments imported by admin request"
+    );
+
+    let root = match root {
+        Some(root) => Some(root),
+        None => state.db.get_latest_root()?,
+    };
+    Ok(Json(ImportResponse {
+        imported,
+        skipped,
+        leaf_count,
+        root,
+    }))
+}
+
+/// Parse and validate an NDJSON import body; blank lines are ignored.
+fn parse_import(body: &[u8]) -> Result<Vec<CommitmentRow>, AspError> {
+    let text = std::str::from_utf8(body)
+        .map_err(|_| AspError::InvalidInput("import body is not UTF-8".into()))?;
+    let mut rows = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row: ExportedCommitment = serde_json::from_str(line)
+            .map_err(|e| AspError::InvalidInput(format!("import line {}: {e}", number + 1)))?;
+        validate_decimal(&row.commitment, "commitment")?;
+        if let Some(ref tx) = row.deposit_tx {
+            validate_hex_u256(tx, "deposit_tx")?;
+        }
+        rows.push(CommitmentRow {
+            leaf_index: row.leaf_index,
+            commitment: row.commitment,
+            deposit_tx: row.deposit_tx,
+        });
+    }
+    Ok(rows)
+}
+
+/// Endpoint: POST /admin/ban
+/// Ban a client IP from every non-admin route, across restarts.
+pub async fn ban(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<BanRequest>,
+) -> Result<Json<BanResponse>, AspError> {
+    let ip = parse_ip(&req.ip)?;
+    let reason = req.reason.unwrap_or_else(|| "banned by operator".to_string());
+
+    let row = state.db.ban_source(&ip.to_string(), &reason, false)?;
+    state.bans.insert(ip);
+    tracing::info!(ip = %ip, reason = %reason, "Source banned by admin request");
+
+    Ok(Json(BanResponse {
+        ip: row.ip,
+        reason: row.reason,
+        automatic: row.automatic,
+        banned_at: row.banned_at,
+    }))
+}
+
+/// Endpoint: DELETE /admin/ban/{ip}
+/// Lift a ban, manual or automatic. Unbanning an IP that is not banned is
+/// not an error; `removed` reports whether it was.
+pub async fn unban(
+    State(state): State<Arc<AppState>>,
+    Path(ip): Path<String>,
+) -> Result<Json<UnbanResponse>, AspError> {
+    let ip = parse_ip(&ip)?;
+
+    let removed = state.db.unban_source(&ip.to_string())?;
+    state.bans.remove(&ip);
+    tracing::info!(ip = %ip, removed, "Source unbanned by admin request");
+
+    Ok(Json(UnbanResponse {
+        ip: ip.to_string(),
+        removed,
+    }))
+}
+
+/// Endpoint: GET /admin/proof-stats
+/// Count, mean and 95th percentile of the recorded proof generation times,
+/// per circuit.
+pub async fn proof_stats(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ProofStatsResponse>, AspError> {
+    let circuits = state
+        .db
+        .get_proof_durations()?
+        .into_iter()
+        .map(|(circuit, durations)| circuit_stats(circuit, &durations))
+        .collect();
+    Ok(Json(ProofStatsResponse { circuits }))
+}
+
+/// Stats over `durations`, which must be sorted ascending and non-empty.
+/// The p95 is the nearest-rank value.
+fn circuit_stats(circuit: String, durations: &[u64]) -> CircuitProofStats {
+    let count = durations.len();
+    let total: u64 = durations.iter().sum();
+    let rank = (count * 95).div_ceil(100);
+    CircuitProofStats {
+        circuit,
+        count: count as u64,
+        avg_ms: total / count as u64,
+        p95_ms: durations[rank.saturating_sub(1)],
+    }
+}
+
+fn parse_ip(raw: &str) -> Result<IpAddr, AspError> {
+    raw.trim()
+        .parse()
+        .map_err(|_| AspError::InvalidInput(format!("'{raw}' is not an IP address")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn p95_is_the_nearest_rank() {
+        let durations: Vec<u64> = (1..=40).map(|i| i * 10).collect();
+        let stats = circuit_stats("swap".into(), &durations);
+        assert_eq!(stats.count, 40);
+        assert_eq!(stats.avg_ms, 205);
+        assert_eq!(stats.p95_ms, 380);
+
+        let single = circuit_stats("mint".into(), &[7]);
+        assert_eq!((single.avg_ms, single.p95_ms), (7, 7));
+    }
+}
@@ -1,23 +1,32 @@
 use std::sync::Arc;
 
 use axum::extract::State;
-use axum::Json;
+use axum::{Extension, Json};
 
-use crate::api::types::{BurnRequest, BurnResponse};
+use crate::api::handlers::nullifier::{ensure_unspent, hand_off};
+use crate::api::middleware::OperationOutcome;
+use crate::api::signature::SignedJson;
+use crate::api::types::{BurnRequest, BurnResponse, DryRunResponse};
 use crate::api::validation::{
-    validate_address, validate_decimal, validate_secret, validate_tick_range,
+    validate_address, validate_decimal, validate_distinct_notes, validate_liquidity_matches,
+    validate_secret, validate_tick_alignment, validate_tick_range, validate_u128_limb,
+    TICK_OFFSET,
 };
 use crate::error::AspError;
+use crate::prover::{CircuitType, ProofResult};
+use crate::relayer::PoolCalls;
+use crate::webhook::WebhookEvent;
 use crate::AppState;
 
-const TICK_OFFSET: i32 = 887272;
-
 fn validate_burn_request(req: &BurnRequest) -> Result<(), AspError> {
     // Position note
     validate_secret(&req.position_note.secret, "position_note.secret")?;
     validate_secret(&req.position_note.nullifier, "position_note.nullifier")?;
     validate_decimal(&req.position_note.liquidity, "position_note.liquidity")?;
     validate_tick_range(req.position_note.tick_lower, req.position_note.tick_upper)?;
+    let tick_spacing = req.pool_key.tick_spacing;
+    validate_tick_alignment(req.position_note.tick_lower, tick_spacing, "tick_lower")?;
+    validate_tick_alignment(req.position_note.tick_upper, tick_spacing, "tick_upper")?;
 
     // Output notes
     for (prefix, note) in [
@@ -26,8 +35,8 @@ fn validate_burn_request(req: &BurnRequest) -> Result<(), AspError> {
     ] {
         validate_secret(&note.secret, &format!("{prefix}.secret"))?;
         validate_secret(&note.nullifier, &format!("{prefix}.nullifier"))?;
-        validate_decimal(&note.amount_low, &format!("{prefix}.amount_low"))?;
-        validate_decimal(&note.amount_high, &format!("{prefix}.amount_high"))?;
+        validate_u128_limb(&note.amount_low, &format!("{prefix}.amount_low"))?;
+        validate_u128_limb(&note.amount_high, &format!("{prefix}.amount_high"))?;
         validate_address(&note.token, &format!("{prefix}.token"))?;
     }
 
@@ -38,21 +47,38 @@ fn validate_burn_request(req: &BurnRequest) -> Result<(), AspError> {
     if req.liquidity == 0 {
         return Err(AspError::InvalidInput("liquidity must be > 0".into()));
     }
+    validate_liquidity_matches(
+        &req.position_note.liquidity,
+        req.liquidity,
+        "position_note.liquidity",
+    )?;
+
+    validate_distinct_notes(&[
+        ("position_note", &req.position_note.secret, &req.position_note.nullifier),
+        ("output_note_0", &req.output_note_0.secret, &req.output_note_0.nullifier),
+        ("output_note_1", &req.output_note_1.secret, &req.output_note_1.nullifier),
+    ])?;
 
     Ok(())
 }
 
-pub async fn shielded_burn(
-    State(state): State<Arc<AppState>>,
-    Json(req): Json<BurnRequest>,
-) -> Result<Json<BurnResponse>, AspError> {
-    validate_burn_request(&req)?;
+/// Output of the "build & prove" half of a burn: everything needed to submit,
+/// produced without touching the relayer or mutating the tree/DB.
+pub(crate) struct BurnProof {
+    pub nullifier_hash: String,
+    pub output_commitment_0: String,
+    pub output_commitment_1: String,
+    pub proof: ProofResult,
+}
 
-    tracing::info!(
-        leaf_index = req.position_note.leaf_index,
-        "Processing shielded burn"
-    );
+/// Validate the request and generate the burn proof for the position note.
+pub(crate) async fn build_burn_proof(
+    state: &AppState,
+    req: &BurnRequest,
+) -> Result<BurnProof, AspError> {
+    validate_burn_request(req)?;
 
+    let _slot = state.proof_slots.try_acquire()?;
     let mut worker = state.worker.lock().await;
 
     // 1. Convert signed ticks to unsigned
@@ -93,6 +119,7 @@ pub async fn shielded_burn(
             &req.position_note.liquidity,
         )
         .await?;
+    ensure_unspent(state, &position.nullifier_hash)?;
 
     // 5. Build burn circuit inputs
     let inputs = serde_json::json!({
@@ -123,8 +150,60 @@ pub async fn shielded_burn(
     });
 
     // 6. Generate burn proof
-    let proof_result = worker.generate_proof("burn", inputs).await?;
+    let proof_result = worker.generate_proof(CircuitType::Burn, inputs).await?;
     drop(worker);
+    state.record_proof_time(CircuitType::Burn, proof_result.duration_ms);
+    proof_result.ensure_layout(CircuitType::Burn)?;
+
+    Ok(BurnProof {
+        nullifier_hash: position.nullifier_hash,
+        output_commitment_0: output0.commitment,
+        output_commitment_1: output1.commitment,
+        proof: proof_result,
+    })
+}
+
+pub async fn shielded_burn(
+    State(state): State<Arc<AppState>>,
+    SignedJson { value: req, auth }: SignedJson<BurnRequest>,
+) -> Result<(Extension<OperationOutcome>, Json<BurnResponse>), AspError> {
+    tracing::info!(
+        leaf_index = req.position_note.leaf_index,
+        "Processing shielded burn"
+    );
+
+    auth.verify_for_leaf(&state.db, req.position_note.leaf_index)?;
+
+    let BurnProof {
+        nullifier_hash,
+        output_commitment_0,
+        output_commitment_1,
+        proof: proof_result,
+    } = state
+        .within_proof_deadline(build_burn_proof(&state, &req))
+        .await?;
+    let (amount_0, amount_1) = burn_amounts(&req);
+
+    if req.self_submit {
+        let call = PoolCalls::from_config(&state.config)?.shielded_burn(
+            &req.pool_key,
+            &proof_result.calldata,
+            req.liquidity,
+        )?;
+        let call = hand_off(&state, CircuitType::Burn, &[&nullifier_hash], call)?;
+        return Ok((
+            Extension(OperationOutcome::default()),
+            Json(BurnResponse {
+                status: "awaiting_submission".to_string(),
+                tx_hash: String::new(),
+                new_commitment_0: output_commitment_0,
+                new_commitment_1: output_commitment_1,
+                amount_0,
+                amount_1,
+                call: Some(call),
+            }),
+        ));
+    }
 
     // 7. Submit to pool.shielded_burn
     let tx_hash = if let Some(ref relayer) = state.relayer {
@@ -139,52 +218,60 @@ pub async fn shielded_burn(
     // 8. Record position nullifier as spent
     state
         .db
-        .insert_nullifier(&position.nullifier_hash, "burn", Some(&tx_hash))?;
-
-    // 9. Insert output commitments into Merkle tree
-    let mut worker = state.worker.lock().await;
-    let mut last_root = String::new();
-
-    // Insert output commitment 0 if non-zero
-    if !output0.commitment.is_empty() && output0.commitment != "0" {
-        let leaf_index = state.db.get_leaf_count()?;
-        state
-            .db
-            .insert_commitment(leaf_index, &output0.commitment, Some(&tx_hash))?;
-        last_root = worker.insert_leaf(&output0.commitment).await?;
-        tracing::debug!(leaf_index = leaf_index, "Inserted output_commitment_0");
-    }
-
-    // Insert output commitment 1 if non-zero
-    if !output1.commitment.is_empty() && output1.commitment != "0" {
-        let leaf_index = state.db.get_leaf_count()?;
-        state
-            .db
-            .insert_commitment(leaf_index, &output1.commitment, Some(&tx_hash))?;
-        last_root = worker.insert_leaf(&output1.commitment).await?;
-        tracing::debug!(leaf_index = leaf_index, "Inserted output_commitment_1");
-    }
+        .insert_nullifier(&nullifier_hash, CircuitType::Burn, Some(&tx_hash))?;
+    state.publish_nullifier(&nullifier_hash, CircuitType::Burn);
 
-    drop(worker);
+    // 9. Insert non-zero output commitments into Merkle tree
+    let commitments: Vec<&str> = [&output_commitment_0, &output_commitment_1]
+        .into_iter()
+        .filter(|c| !c.is_empty() && *c != "0")
+        .map(String::as_str)
+        .collect();
+    let (leaf_indices, last_root) = state.append_leaves(&commitments, &tx_hash).await?;
 
     // 10. Store the final root in DB (if we inserted anything)
+    let mut root_tx = None;
     if !last_root.is_empty() {
         let new_count = state.db.get_leaf_count()?;
         state.db.insert_root(&last_root, new_count, Some(&tx_hash))?;
+        state.refresh_tree()?;
 
         // 11. Submit the new Merkle root to Coordinator on-chain
         if let Some(ref relayer) = state.relayer {
             let relayer = relayer.lock().await;
-            let root_tx = relayer.submit_merkle_root(&last_root).await?;
-            tracing::info!(tx_hash = %root_tx, "Merkle root submitted on-chain after burn");
+            let submitted = relayer.submit_merkle_root(&last_root).await?;
+            tracing::info!(tx_hash = %submitted, "Merkle root submitted on-chain after burn");
+            root_tx = Some(submitted);
         } else {
             tracing::warn!("No relayer configured — root stored locally only");
         }
     }
 
     tracing::info!(tx_hash = %tx_hash, "Shielded burn confirmed");
+    let root = (!last_root.is_empty()).then_some(last_root.as_str());
+    state.notify(WebhookEvent::new("burn", &tx_hash, None, root));
+
+    let outcome = OperationOutcome {
+        tx_hash: Some(tx_hash.clone()),
+        root_tx,
+        leaf_indices,
+    };
+    Ok((
+        Extension(outcome),
+        Json(BurnResponse {
+            status: "confirmed".to_string(),
+            tx_hash,
+            new_commitment_0: output_commitment_0.clone(),
+            new_commitment_1: output_commitment_1.clone(),
+            amount_0,
+            amount_1,
+            call: None,
+        }),
+    ))
+}
 
-    // Echo back the amounts used in the ZK proof so the SDK can save notes correctly.
+/// Echo back the amounts used in the ZK proof so the SDK can save notes correctly.
+fn burn_amounts(req: &BurnRequest) -> (String, String) {
     // These are the amounts committed into the output note commitments (private circuit inputs).
     // For all realistic ERC-20 token amounts the high part is 0, so amount == low.
     let amount_0_low: u128 = req.output_note_0.amount_low.parse().unwrap_or(0);
@@ -203,12 +290,25 @@ pub async fn shielded_burn(
         format!("{}", amount_1_high.saturating_mul(u128::MAX).saturating_add(amount_1_low))
     };
 
-    Ok(Json(BurnResponse {
-        status: "confirmed".to_string(),
-        tx_hash,
-        new_commitment_0: output0.commitment.clone(),
-        new_commitment_1: output1.commitment.clone(),
-        amount_0,
-        amount_1,
+    (amount_0, amount_1)
+}
+
+/// Endpoint: POST /burn/prove
+/// Dry run: generate the burn proof and return it without submitting on-chain,
+/// recording the nullifier, or inserting leaves. Signed like `/burn`, since
+/// the proof could be submitted by anyone.
+pub async fn prove_burn(
+    State(state): State<Arc<AppState>>,
+    SignedJson { value: req, auth }: SignedJson<BurnRequest>,
+) -> Result<Json<DryRunResponse>, AspError> {
+    auth.verify_for_leaf(&state.db, req.position_note.leaf_index)?;
+    let built = build_burn_proof(&state, &req).await?;
+
+    Ok(Json(DryRunResponse {
+        status: "proved".to_string(),
+        circuit: CircuitType::Burn,
+        nullifier_hashes: vec![built.nullifier_hash],
+        calldata: built.proof.calldata,
+        public_signals: built.proof.public_signals,
     }))
 }
@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::Json;
+
+use crate::api::types::{
+    CommitmentResponse, ComputeCommitmentRequest, ComputePositionCommitmentRequest,
+};
+use crate::api::validation::{
+    validate_address, validate_decimal, validate_secret, validate_tick_range,
+    validate_u128_limb, TICK_OFFSET,
+};
+use crate::error::AspError;
+use crate::AppState;
+
+/// Endpoint: POST /compute-commitment
+/// Canonical note commitment and nullifier hash, so clients can check their
+/// local Poseidon derivation against the server's.
+pub async fn compute_commitment(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ComputeCommitmentRequest>,
+) -> Result<Json<CommitmentResponse>, AspError> {
+    validate_secret(&req.secret, "secret")?;
+    validate_secret(&req.nullifier, "nullifier")?;
+    validate_u128_limb(&req.amount_low, "amount_low")?;
+    validate_u128_limb(&req.amount_high, "amount_high")?;
+    validate_address(&req.token, "token")?;
+
+    let mut worker = state.worker.lock().await;
+    let result = worker
+        .compute_commitment(
+            &req.secret,
+            &req.nullifier,
+            &req.amount_low,
+            &req.amount_high,
+            &req.token,
+        )
+        .await?;
+    drop(worker);
+
+    Ok(Json(CommitmentResponse {
+        commitment: result.commitment,
+        nullifier_hash: result.nullifier_hash,
+    }))
+}
+
+/// Endpoint: POST /compute-position-commitment
+/// Canonical position commitment. Ticks are given signed; TICK_OFFSET is
+/// applied here exactly as mint/burn do.
+pub async fn compute_position_commitment(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ComputePositionCommitmentRequest>,
+) -> Result<Json<CommitmentResponse>, AspError> {
+    validate_secret(&req.secret, "secret")?;
+    validate_secret(&req.nullifier, "nullifier")?;
+    validate_decimal(&req.liquidity, "liquidity")?;
+    validate_tick_range(req.tick_lower, req.tick_upper)?;
+
+    let tick_lower_unsigned = (req.tick_lower + TICK_OFFSET) as u32;
+    let tick_upper_unsigned = (req.tick_upper + TICK_OFFSET) as u32;
+
+    let mut worker = state.worker.lock().await;
+    let result = worker
+        .compute_position_commitment(
+            &req.secret,
+            &req.nullifier,
+            tick_lower_unsigned as i32,
+            tick_upper_unsigned as i32,
+            &req.liquidity,
+        )
+        .await?;
+    drop(worker);
+
+    Ok(Json(CommitmentResponse {
+        commitment: result.commitment,
+        nullifier_hash: result.nullifier_hash,
+    }))
+}
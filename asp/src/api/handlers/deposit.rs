@@ -1,25 +1,45 @@
 use std::sync::Arc;
 
-use axum::extract::State;
-use axum::Json;
+use axum::extract::{Path, State};
+use axum::{Extension, Json};
 
-use crate::api::types::{DepositRequest, DepositResponse};
-use crate::api::validation::validate_hex_u256;
+use crate::api::middleware::OperationOutcome;
+use crate::api::signature::parse_auth_pubkey;
+use crate::api::types::{CommitmentStatusResponse, DepositRequest, DepositResponse};
+use crate::api::validation::{
+    note_amount, validate_address, validate_decimal, validate_hex_u256, validate_secret,
+    validate_u128_limb,
+};
+use crate::db::queries::DepositMetadata;
+use crate::deposit_queue::DepositJob;
 use crate::error::AspError;
+use crate::relayer::classify_deposit_error;
+use crate::webhook::WebhookEvent;
 use crate::AppState;
 
 pub async fn deposit(
     State(state): State<Arc<AppState>>,
     Json(req): Json<DepositRequest>,
-) -> Result<Json<DepositResponse>, AspError> {
+) -> Result<(Extension<OperationOutcome>, Json<DepositResponse>), AspError> {
     validate_hex_u256(&req.commitment, "commitment")?;
+    let metadata = deposit_metadata(&req)?;
+    let auth_pubkey = req
+        .auth_pubkey
+        .as_deref()
+        .map(|k| parse_auth_pubkey(k, "auth_pubkey"))
+        .transpose()?
+        .map(|key| format!("0x{}", hex::encode(key.to_bytes())));
 
     tracing::info!("Processing deposit");
 
     let commitment_decimal = hex_to_decimal(&req.commitment)?;
+    check_denomination(&state, &req, &commitment_decimal).await?;
 
-    // 1. Get next leaf index BEFORE insert (= current count)
+    // 1. Get next leaf index BEFORE insert (= current count). No other leaf
+    // is appended until this one is relayed or rolled back.
+    let _appending = state.tree_writes.lock().await;
     let leaf_index = state.db.get_leaf_count()?;
+    state.config.ensure_tree_capacity(leaf_index)?;
 
     // 2. Insert leaf into local Merkle tree via worker
     let mut worker = state.worker.lock().await;
@@ -30,25 +50,69 @@ pub async fn deposit(
     state
         .db
         .insert_commitment(leaf_index, &commitment_decimal, None)?;
+    if metadata != DepositMetadata::default() {
+        state.db.set_deposit_metadata(leaf_index, &metadata)?;
+    }
+    if let Some(ref pubkey) = auth_pubkey {
+        state.db.set_auth_pubkey(leaf_index, pubkey)?;
+    }
 
     // 4. Store root in DB
     let new_count = leaf_index + 1;
     state.db.insert_root(&root, new_count, None)?;
+    state.refresh_tree()?;
 
     let root_hex = decimal_to_hex(&root);
 
-    // 5. Relay on-chain: deposit commitment + submit merkle root
-    if let Some(ref relayer) = state.relayer {
+    if state.config.deposit_queue && state.relayer.is_some() {
+        return enqueue_deposit(&state, leaf_index, commitment_decimal, root, root_hex).await;
+    }
+
+    // 5. Relay on-chain: deposit commitment + submit merkle root.
+    // If either call fails, undo the local insert so the tree never holds a leaf
+    // whose root was not submitted. A commitment that did land on-chain before the
+    // failure is re-ingested by the event sync task from its CommitmentAdded event.
+    let (deposit_tx, root_tx) = if let Some(ref relayer) = state.relayer {
         let relayer = relayer.lock().await;
 
-        let tx_hash = relayer.deposit(&commitment_decimal).await?;
-        tracing::info!(tx_hash = %tx_hash, "Deposit commitment submitted on-chain");
+        let relayed = async {
+            let tx_hash = relayer
+                .deposit(&commitment_decimal)
+                .await
+                .map_err(classify_deposit_error)?;
+            tracing::info!(tx_hash = %tx_hash, "Deposit commitment submitted on-chain");
 
-        let root_tx = relayer.submit_merkle_root(&root).await?;
-        tracing::info!(tx_hash = %root_tx, "Merkle root submitted on-chain");
+            let root_tx = relayer.submit_merkle_root(&root).await?;
+            tracing::info!(tx_hash = %root_tx, "Merkle root submitted on-chain");
+            Ok::<_, AspError>((tx_hash, root_tx))
+        }
+        .await;
+        drop(relayer);
+
+        match relayed {
+            Ok((tx_hash, root_tx)) => {
+                state.db.set_deposit_tx(leaf_index, &tx_hash)?;
+                state.notify(WebhookEvent::new(
+                    "deposit",
+                    &tx_hash,
+                    Some(leaf_index),
+                    Some(&root),
+                ));
+                (Some(tx_hash), Some(root_tx))
+            }
+            Err(e) => {
+                rollback_deposit(&state, leaf_index).await?;
+                return Err(e);
+            }
+        }
     } else {
+        // Proof-only node: the leaf is usable for proofs against this tree,
+        // but nothing was submitted on-chain.
         tracing::warn!("No relayer configured — deposit stored locally only");
-    }
+        (None, None)
+    };
+    let status = if deposit_tx.is_some() { "confirmed" } else { "local" };
+    state.publish_commitment(leaf_index, &commitment_decimal, &root);
 
     tracing::info!(
         leaf_index = leaf_index,
@@ -56,14 +120,245 @@ pub async fn deposit(
         "Deposit processed successfully"
     );
 
-    Ok(Json(DepositResponse {
-        status: "confirmed".to_string(),
+    let outcome = OperationOutcome {
+        tx_hash: deposit_tx,
+        root_tx,
+        leaf_indices: vec![leaf_index],
+    };
+    Ok((
+        Extension(outcome),
+        Json(DepositResponse {
+            status: status.to_string(),
+            leaf_index,
+            calldata: vec![], // No user-side calldata needed — ASP relayed it
+            root: root_hex,
+        }),
+    ))
+}
+
+/// Hand a locally inserted deposit to the background submitter and answer
+/// with `pending`; its progress is reported by `GET /commitment/{commitment}`.
+async fn enqueue_deposit(
+    state: &AppState,
+    leaf_index: u32,
+    commitment: String,
+    root: String,
+    root_hex: String,
+) -> Result<(Extension<OperationOutcome>, Json<DepositResponse>), AspError> {
+    state.db.set_deposit_status(leaf_index, "pending", None)?;
+    let job = DepositJob {
         leaf_index,
-        calldata: vec![], // No user-side calldata needed — ASP relayed it
-        root: root_hex,
+        commitment,
+        root,
+    };
+    if let Err(e) = state.deposit_queue.enqueue(job) {
+        rollback_deposit(state, leaf_index).await?;
+        return Err(e);
+    }
+    tracing::info!(leaf_index = leaf_index, root = %root_hex, "Deposit queued for submission");
+
+    let outcome = OperationOutcome {
+        leaf_indices: vec![leaf_index],
+        ..Default::default()
+    };
+    Ok((
+        Extension(outcome),
+        Json(DepositResponse {
+            status: "pending".to_string(),
+            leaf_index,
+            calldata: vec![],
+            root: root_hex,
+        }),
+    ))
+}
+
+/// Endpoint: GET /commitment/{commitment}
+/// Where a deposited commitment (hex or decimal) stands: its leaf and, for
+/// queued deposits, whether it has been relayed yet.
+pub async fn get_commitment_status(
+    State(state): State<Arc<AppState>>,
+    Path(commitment): Path<String>,
+) -> Result<Json<CommitmentStatusResponse>, AspError> {
+    let commitment_decimal = field_to_decimal(&commitment, "commitment")?;
+    let row = state
+        .db
+        .get_deposit_status(&commitment_decimal)?
+        .ok_or(AspError::UnknownCommitment(commitment))?;
+
+    // Leaves relayed inline or synced from chain carry no explicit status
+    let status = row.status.unwrap_or_else(|| {
+        if row.deposit_tx.is_some() {
+            "confirmed".to_string()
+        } else {
+            "local".to_string()
+        }
+    });
+    Ok(Json(CommitmentStatusResponse {
+        commitment: row.commitment,
+        leaf_index: row.leaf_index,
+        status,
+        deposit_tx: row.deposit_tx,
+        root_tx: row.root_tx,
+        error: row.error,
     }))
 }
 
+/// Remove a just-inserted leaf from the DB and rebuild the worker tree without it.
+async fn rollback_deposit(state: &AppState, leaf_index: u32) -> Result<(), AspError> {
+    state.db.rollback_commitment(leaf_index)?;
+    state.refresh_tree()?;
+
+    let leaves: Vec<String> = state
+        .db
+        .get_all_commitments()?
+        .into_iter()
+        .map(|c| c.commitment)
+        .collect();
+    let mut worker = state.worker.lock().await;
+    worker.build_tree(&leaves).await?;
+    drop(worker);
+
+    tracing::warn!(leaf_index = leaf_index, "Deposit rolled back after failed on-chain submission");
+    Ok(())
+}
+
+/// Validate the optional token/amount on a deposit and normalize them for
+/// storage. The amount limbs must be given together.
+fn deposit_metadata(req: &DepositRequest) -> Result<DepositMetadata, AspError> {
+    if let Some(ref token) = req.token {
+        validate_address(token, "token")?;
+    }
+    match (&req.amount_low, &req.amount_high) {
+        (Some(low), Some(high)) => {
+            validate_u128_limb(low, "amount_low")?;
+            validate_u128_limb(high, "amount_high")?;
+        }
+        (None, None) => {}
+        _ => {
+            return Err(AspError::InvalidInput(
+                "amount_low and amount_high must be provided together".into(),
+            ))
+        }
+    }
+
+    Ok(DepositMetadata {
+        token: req.token.as_deref().map(normalize_hex),
+        amount_low: req.amount_low.as_deref().map(normalize_decimal),
+        amount_high: req.amount_high.as_deref().map(normalize_decimal),
+    })
+}
+
+/// With `ALLOWED_DENOMINATIONS` set, require the deposit to declare a full
+/// note whose amount is an allowed denomination and whose commitment is the
+/// one being deposited. The commitment alone hides the amount.
+async fn check_denomination(
+    state: &AppState,
+    req: &DepositRequest,
+    commitment_decimal: &str,
+) -> Result<(), AspError> {
+    let allowed = &state.config.allowed_denominations;
+    if allowed.is_empty() {
+        return Ok(());
+    }
+
+    let (Some(secret), Some(nullifier), Some(token), Some(low), Some(high)) = (
+        req.secret.as_deref(),
+        req.nullifier.as_deref(),
+        req.token.as_deref(),
+        req.amount_low.as_deref(),
+        req.amount_high.as_deref(),
+    ) else {
+        return Err(AspError::InvalidInput(
+            "this pool accepts fixed denominations only: deposits must include \
+             secret, nullifier, token, amount_low and amount_high"
+                .into(),
+        ));
+    };
+    validate_secret(secret, "secret")?;
+    validate_secret(nullifier, "nullifier")?;
+
+    let amount = note_amount(low, high);
+    if !allowed.contains(&amount) {
+        let allowed: Vec<String> = allowed.iter().map(|d| d.to_string()).collect();
+        return Err(AspError::InvalidInput(format!(
+            "amount {amount} is not an allowed denomination (allowed: {})",
+            allowed.join(", ")
+        )));
+    }
+
+    let mut worker = state.worker.lock().await;
+    let note = worker
+        .compute_commitment(secret, nullifier, low, high, token)
+        .await?;
+    drop(worker);
+    if note.commitment != commitment_decimal {
+        return Err(AspError::InvalidInput(
+            "commitment does not match the declared note".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Reject spending the note at `leaf_index` if its token or balance differ
+/// from what was declared when it was deposited. Leaves deposited without
+/// metadata (or learned from chain sync) are not checked.
+pub(crate) fn check_note_matches_deposit(
+    state: &AppState,
+    leaf_index: u32,
+    token: &str,
+    amount_low: &str,
+    amount_high: &str,
+) -> Result<(), AspError> {
+    let Some(deposited) = state.db.get_deposit_metadata(leaf_index)? else {
+        return Ok(());
+    };
+
+    if let Some(ref deposited_token) = deposited.token {
+        if *deposited_token != normalize_hex(token) {
+            return Err(AspError::InvalidInput(format!(
+                "Note token does not match deposit at leaf {leaf_index}"
+            )));
+        }
+    }
+    if let (Some(low), Some(high)) = (&deposited.amount_low, &deposited.amount_high) {
+        if *low != normalize_decimal(amount_low) || *high != normalize_decimal(amount_high) {
+            return Err(AspError::InvalidInput(format!(
+                "Note balance does not match deposit at leaf {leaf_index}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Canonical lowercase hex without leading zeros (input must be valid hex).
+fn normalize_hex(value: &str) -> String {
+    hex_to_decimal(value)
+        .map(|dec| decimal_to_hex(&dec))
+        .unwrap_or_else(|_| value.to_lowercase())
+}
+
+/// Canonical decimal without leading zeros (input must be valid decimal).
+pub(crate) fn normalize_decimal(value: &str) -> String {
+    use num_bigint::BigUint;
+    use num_traits::Num;
+
+    BigUint::from_str_radix(value, 10)
+        .map(|big| big.to_str_radix(10))
+        .unwrap_or_else(|_| value.to_string())
+}
+
+/// Parse a field element given as `0x` hex or decimal into the canonical
+/// decimal form the database stores.
+pub(crate) fn field_to_decimal(value: &str, field_name: &str) -> Result<String, AspError> {
+    if value.starts_with("0x") || value.starts_with("0X") {
+        validate_hex_u256(value, field_name)?;
+        hex_to_decimal(value)
+    } else {
+        validate_decimal(value, field_name)?;
+        Ok(normalize_decimal(value))
+    }
+}
+
 /// Convert a hex string (0x...) to decimal string for the worker.
 pub fn hex_to_decimal(hex: &str) -> Result<String, AspError> {
     use num_bigint::BigUint;
@@ -93,6 +388,12 @@ pub fn decimal_to_hex(dec: &str) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn normalize_hex_and_decimal() {
+        assert_eq!(normalize_hex("0x00AB"), "0xab");
+        assert_eq!(normalize_decimal("0010"), "10");
+    }
+
     #[test]
     fn hex_to_decimal_zero() {
         assert_eq!(hex_to_decimal("0x0").unwrap(), "0");
@@ -1,17 +1,24 @@
 use std::sync::Arc;
 
 use axum::extract::State;
-use axum::Json;
+use axum::{Extension, Json};
 
-use crate::api::types::{MintRequest, MintResponse};
+use crate::api::handlers::deposit::check_note_matches_deposit;
+use crate::api::handlers::nullifier::{ensure_unspent, hand_off};
+use crate::api::middleware::OperationOutcome;
+use crate::api::signature::SignedJson;
+use crate::api::types::{DryRunResponse, MintRequest, MintResponse};
 use crate::api::validation::{
-    validate_address, validate_decimal, validate_secret, validate_tick_range,
+    validate_address, validate_decimal, validate_distinct_notes, validate_liquidity_matches,
+    validate_secret, validate_tick_alignment, validate_tick_range, validate_u128_limb,
+    TICK_OFFSET,
 };
 use crate::error::AspError;
+use crate::prover::{CircuitType, ProofResult};
+use crate::relayer::PoolCalls;
+use crate::webhook::WebhookEvent;
 use crate::AppState;
 
-const TICK_OFFSET: i32 = 887272;
-
 fn validate_mint_request(req: &MintRequest) -> Result<(), AspError> {
     for (prefix, note) in [
         ("input_note_0", &req.input_note_0),
@@ -19,8 +26,8 @@ fn validate_mint_request(req: &MintRequest) -> Result<(), AspError> {
     ] {
         validate_secret(&note.secret, &format!("{prefix}.secret"))?;
         validate_secret(&note.nullifier, &format!("{prefix}.nullifier"))?;
-        validate_decimal(&note.balance_low, &format!("{prefix}.balance_low"))?;
-        validate_decimal(&note.balance_high, &format!("{prefix}.balance_high"))?;
+        validate_u128_limb(&note.balance_low, &format!("{prefix}.balance_low"))?;
+        validate_u128_limb(&note.balance_high, &format!("{prefix}.balance_high"))?;
         validate_address(&note.token, &format!("{prefix}.token"))?;
     }
 
@@ -28,11 +35,14 @@ fn validate_mint_request(req: &MintRequest) -> Result<(), AspError> {
     validate_secret(&req.position.nullifier, "position.nullifier")?;
     validate_decimal(&req.position.liquidity, "position.liquidity")?;
     validate_tick_range(req.position.tick_lower, req.position.tick_upper)?;
+    let tick_spacing = req.pool_key.tick_spacing;
+    validate_tick_alignment(req.position.tick_lower, tick_spacing, "tick_lower")?;
+    validate_tick_alignment(req.position.tick_upper, tick_spacing, "tick_upper")?;
 
-    validate_decimal(&req.amounts.amount0_low, "amounts.amount0_low")?;
-    validate_decimal(&req.amounts.amount0_high, "amounts.amount0_high")?;
-    validate_decimal(&req.amounts.amount1_low, "amounts.amount1_low")?;
-    validate_decimal(&req.amounts.amount1_high, "amounts.amount1_high")?;
+    validate_u128_limb(&req.amounts.amount0_low, "amounts.amount0_low")?;
+    validate_u128_limb(&req.amounts.amount0_high, "amounts.amount0_high")?;
+    validate_u128_limb(&req.amounts.amount1_low, "amounts.amount1_low")?;
+    validate_u128_limb(&req.amounts.amount1_high, "amounts.amount1_high")?;
 
     validate_secret(&req.change_note_0.secret, "change_note_0.secret")?;
     validate_secret(&req.change_note_0.nullifier, "change_note_0.nullifier")?;
@@ -45,22 +55,37 @@ fn validate_mint_request(req: &MintRequest) -> Result<(), AspError> {
     if req.liquidity == 0 {
         return Err(AspError::InvalidInput("liquidity must be > 0".into()));
     }
+    validate_liquidity_matches(&req.position.liquidity, req.liquidity, "position.liquidity")?;
+
+    validate_distinct_notes(&[
+        ("input_note_0", &req.input_note_0.secret, &req.input_note_0.nullifier),
+        ("input_note_1", &req.input_note_1.secret, &req.input_note_1.nullifier),
+        ("position", &req.position.secret, &req.position.nullifier),
+        ("change_note_0", &req.change_note_0.secret, &req.change_note_0.nullifier),
+        ("change_note_1", &req.change_note_1.secret, &req.change_note_1.nullifier),
+    ])?;
 
     Ok(())
 }
 
-pub async fn shielded_mint(
-    State(state): State<Arc<AppState>>,
-    Json(req): Json<MintRequest>,
-) -> Result<Json<MintResponse>, AspError> {
-    validate_mint_request(&req)?;
+/// Output of the "build & prove" half of a mint: everything needed to submit,
+/// produced without touching the relayer or mutating the tree/DB.
+pub(crate) struct MintProof {
+    pub nullifier_hash_0: String,
+    pub nullifier_hash_1: String,
+    pub position_commitment: String,
+    pub proof: ProofResult,
+}
 
-    tracing::info!(
-        tick_lower = req.position.tick_lower,
-        tick_upper = req.position.tick_upper,
-        "Processing shielded mint"
-    );
+/// Validate the request, check both input notes against the tree, and generate
+/// the mint proof.
+pub(crate) async fn build_mint_proof(
+    state: &AppState,
+    req: &MintRequest,
+) -> Result<MintProof, AspError> {
+    validate_mint_request(req)?;
 
+    let _slot = state.proof_slots.try_acquire()?;
     let mut worker = state.worker.lock().await;
 
     // 1. Compute input note commitments
@@ -100,11 +125,14 @@ pub async fn shielded_mint(
             }
             None => return Err(AspError::CommitmentNotFound(note.leaf_index)),
         }
-        if state.db.is_nullifier_spent(&result.nullifier_hash)? {
-            return Err(AspError::NullifierAlreadySpent(
-                result.nullifier_hash.clone(),
-            ));
-        }
+        check_note_matches_deposit(
+            state,
+            note.leaf_index,
+            &note.token,
+            &note.balance_low,
+            &note.balance_high,
+        )?;
+        ensure_unspent(state, &result.nullifier_hash)?;
     }
 
     // 3. Get Merkle proofs for both input notes
@@ -166,8 +194,67 @@ pub async fn shielded_mint(
     });
 
     // 7. Generate mint proof
-    let proof_result = worker.generate_proof("mint", inputs).await?;
+    let proof_result = worker.generate_proof(CircuitType::Mint, inputs).await?;
     drop(worker);
+    state.record_proof_time(CircuitType::Mint, proof_result.duration_ms);
+    proof_result.ensure_layout(CircuitType::Mint)?;
+
+    Ok(MintProof {
+        nullifier_hash_0: input0.nullifier_hash,
+        nullifier_hash_1: input1.nullifier_hash,
+        position_commitment: position.commitment,
+        proof: proof_result,
+    })
+}
+
+pub async fn shielded_mint(
+    State(state): State<Arc<AppState>>,
+    SignedJson { value: req, auth }: SignedJson<MintRequest>,
+) -> Result<(Extension<OperationOutcome>, Json<MintResponse>), AspError> {
+    tracing::info!(
+        tick_lower = req.position.tick_lower,
+        tick_upper = req.position.tick_upper,
+        "Processing shielded mint"
+    );
+
+    auth.verify_for_leaf(&state.db, req.input_note_0.leaf_index)?;
+    auth.verify_for_leaf(&state.db, req.input_note_1.leaf_index)?;
+
+    let MintProof {
+        nullifier_hash_0,
+        nullifier_hash_1,
+        position_commitment,
+        proof: proof_result,
+    } = state
+        .within_proof_deadline(build_mint_proof(&state, &req))
+        .await?;
+
+    // Extract circuit output signals:
+    // Mint public signal order: [changeCommitment0, changeCommitment1, root, nH0, nH1, positionCommitment, tickLower, tickUpper]
+    let ps = &proof_result.public_signals;
+    let change_commitment_0 = ps.first().cloned().unwrap_or_default();
+    let change_commitment_1 = ps.get(1).cloned().unwrap_or_default();
+
+    if req.self_submit {
+        let call = PoolCalls::from_config(&state.config)?.shielded_mint(
+            &req.pool_key,
+            &proof_result.calldata,
+            req.liquidity,
+        )?;
+        let nullifiers = [nullifier_hash_0.as_str(), nullifier_hash_1.as_str()];
+        let call = hand_off(&state, CircuitType::Mint, &nullifiers, call)?;
+        return Ok((
+            Extension(OperationOutcome::default()),
+            Json(MintResponse {
+                status: "awaiting_submission".to_string(),
+                tx_hash: String::new(),
+                position_commitment,
+                change_commitment_0,
+                change_commitment_1,
+                call: Some(call),
+            }),
+        ));
+    }
 
     // 8. Submit to pool.shielded_mint
     let tx_hash = if let Some(ref relayer) = state.relayer {
@@ -182,71 +269,85 @@ pub async fn shielded_mint(
     // 9. Record nullifiers as spent
     state
         .db
-        .insert_nullifier(&input0.nullifier_hash, "mint", Some(&tx_hash))?;
+        .insert_nullifier(&nullifier_hash_0, CircuitType::Mint, Some(&tx_hash))?;
     state
         .db
-        .insert_nullifier(&input1.nullifier_hash, "mint", Some(&tx_hash))?;
-
-    // Extract circuit output signals:
-    // Mint public signal order: [changeCommitment0, changeCommitment1, root, nH0, nH1, positionCommitment, tickLower, tickUpper]
-    let ps = &proof_result.public_signals;
-    let change_commitment_0 = ps.first().cloned().unwrap_or_default();
-    let change_commitment_1 = ps.get(1).cloned().unwrap_or_default();
-    let position_commitment = position.commitment;
-
-    // 10. Insert change commitments and position commitment into Merkle tree
-    let mut worker = state.worker.lock().await;
-
-    // Insert change commitment 0 if non-zero
-    if !change_commitment_0.is_empty() && change_commitment_0 != "0" {
-        let leaf_index = state.db.get_leaf_count()?;
-        state
-            .db
-            .insert_commitment(leaf_index, &change_commitment_0, Some(&tx_hash))?;
-        let _ = worker.insert_leaf(&change_commitment_0).await?;
-        tracing::debug!(leaf_index = leaf_index, "Inserted change_commitment_0");
-    }
-
-    // Insert change commitment 1 if non-zero
-    if !change_commitment_1.is_empty() && change_commitment_1 != "0" {
-        let leaf_index = state.db.get_leaf_count()?;
-        state
-            .db
-            .insert_commitment(leaf_index, &change_commitment_1, Some(&tx_hash))?;
-        let _ = worker.insert_leaf(&change_commitment_1).await?;
-        tracing::debug!(leaf_index = leaf_index, "Inserted change_commitment_1");
+        .insert_nullifier(&nullifier_hash_1, CircuitType::Mint, Some(&tx_hash))?;
+    state.publish_nullifier(&nullifier_hash_0, CircuitType::Mint);
+    state.publish_nullifier(&nullifier_hash_1, CircuitType::Mint);
+
+    // 10. Insert non-zero change commitments and the position commitment
+    // (always present) into the Merkle tree
+    let mut commitments = Vec::new();
+    for change in [&change_commitment_0, &change_commitment_1] {
+        if !change.is_empty() && change != "0" {
+            commitments.push(change.as_str());
+        }
     }
-
-    // Insert position commitment into tree (always present)
-    let leaf_index = state.db.get_leaf_count()?;
-    state
-        .db
-        .insert_commitment(leaf_index, &position_commitment, Some(&tx_hash))?;
-    let last_root = worker.insert_leaf(&position_commitment).await?;
-    tracing::debug!(leaf_index = leaf_index, "Inserted position_commitment");
-
-    drop(worker);
+    commitments.push(position_commitment.as_str());
+    let (leaf_indices, last_root) = state.append_leaves(&commitments, &tx_hash).await?;
+    // The position commitment is always inserted, last
+    let position_leaf_index = leaf_indices[leaf_indices.len() - 1];
 
     // 11. Store the final root in DB
     let new_count = state.db.get_leaf_count()?;
     state.db.insert_root(&last_root, new_count, Some(&tx_hash))?;
+    state.refresh_tree()?;
 
     // 12. Submit the new Merkle root to Coordinator on-chain
-    if let Some(ref relayer) = state.relayer {
+    let root_tx = if let Some(ref relayer) = state.relayer {
         let relayer = relayer.lock().await;
         let root_tx = relayer.submit_merkle_root(&last_root).await?;
         tracing::info!(tx_hash = %root_tx, "Merkle root submitted on-chain after mint");
+        Some(root_tx)
     } else {
         tracing::warn!("No relayer configured — root stored locally only");
-    }
+        None
+    };
 
     tracing::info!(tx_hash = %tx_hash, "Shielded mint confirmed");
+    state.notify(WebhookEvent::new(
+        "mint",
+        &tx_hash,
+        Some(position_leaf_index),
+        Some(&last_root),
+    ));
+
+    let outcome = OperationOutcome {
+        tx_hash: Some(tx_hash.clone()),
+        root_tx,
+        leaf_indices,
+    };
+    Ok((
+        Extension(outcome),
+        Json(MintResponse {
+            status: "confirmed".to_string(),
+            tx_hash,
+            position_commitment,
+            change_commitment_0,
+            change_commitment_1,
+            call: None,
+        }),
+    ))
+}
 
-    Ok(Json(MintResponse {
-        status: "confirmed".to_string(),
-        tx_hash,
-        position_commitment,
-        change_commitment_0,
-        change_commitment_1,
+/// Endpoint: POST /mint/prove
+/// Dry run: generate the mint proof and return it without submitting on-chain,
+/// recording nullifiers, or inserting leaves. Signed like `/mint`, since the
+/// proof could be submitted by anyone.
+pub async fn prove_mint(
+    State(state): State<Arc<AppState>>,
+    SignedJson { value: req, auth }: SignedJson<MintRequest>,
+) -> Result<Json<DryRunResponse>, AspError> {
+    auth.verify_for_leaf(&state.db, req.input_note_0.leaf_index)?;
+    auth.verify_for_leaf(&state.db, req.input_note_1.leaf_index)?;
+    let built = build_mint_proof(&state, &req).await?;
+
+    Ok(Json(DryRunResponse {
+        status: "proved".to_string(),
+        circuit: CircuitType::Mint,
+        nullifier_hashes: vec![built.nullifier_hash_0, built.nullifier_hash_1],
+        calldata: built.proof.calldata,
+        public_signals: built.proof.public_signals,
     }))
 }
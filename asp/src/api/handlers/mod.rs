@@ -1,8 +1,14 @@
+pub mod admin;
 pub mod burn;
+pub mod commitment;
 pub mod deposit;
 pub mod mint;
+pub mod notes;
 pub mod nullifier;
+pub mod openapi;
+pub mod operations;
 pub mod status;
+pub mod stream;
 pub mod swap;
 pub mod sync;
 pub mod tree;
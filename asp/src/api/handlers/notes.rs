@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::Json;
+
+use crate::api::types::{NoteScanRequest, NoteScanResponse, NoteScanResult};
+use crate::api::validation::validate_decimal;
+use crate::error::AspError;
+use crate::AppState;
+
+/// Upper bound on notes per scan request.
+const MAX_SCAN_NOTES: usize = 1000;
+
+/// Endpoint: POST /notes/scan
+/// Reconcile a batch of candidate notes in one call: for each commitment,
+/// report whether it is in the tree, its leaf index and deposit tx, and
+/// whether its nullifier (if supplied) has been spent.
+pub async fn scan_notes(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<NoteScanRequest>,
+) -> Result<Json<NoteScanResponse>, AspError> {
+    if req.notes.len() > MAX_SCAN_NOTES {
+        return Err(AspError::InvalidInput(format!(
+            "at most {MAX_SCAN_NOTES} notes per scan"
+        )));
+    }
+    for note in &req.notes {
+        validate_decimal(&note.commitment, "commitment")?;
+        if let Some(ref nullifier_hash) = note.nullifier_hash {
+            validate_decimal(nullifier_hash, "nullifier_hash")?;
+        }
+    }
+
+    let mut results = Vec::with_capacity(req.notes.len());
+    for note in req.notes {
+        let row = state.db.find_commitment(&note.commitment)?;
+        let spent = match note.nullifier_hash {
+            Some(ref hash) => Some(state.db.is_nullifier_spent(hash)?),
+            None => None,
+        };
+
+        results.push(NoteScanResult {
+            commitment: note.commitment,
+            found: row.is_some(),
+            leaf_index: row.as_ref().map(|r| r.leaf_index),
+            deposit_tx: row.and_then(|r| r.deposit_tx),
+            spent,
+        });
+    }
+
+    Ok(Json(NoteScanResponse { notes: results }))
+}
@@ -1,12 +1,57 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::Json;
+use starknet::core::types::Call;
 
-use crate::api::types::NullifierResponse;
+use crate::api::handlers::deposit::field_to_decimal;
+use crate::api::types::{
+    ContractCall, NullifierCheckRequest, NullifierCheckResponse, NullifierEntry, NullifierListQuery,
+    NullifierListResponse, NullifierResponse, NullifierStatus,
+};
+use crate::db::queries::{NullifierOrigin, NullifierRow};
 use crate::error::AspError;
+use crate::prover::CircuitType;
 use crate::AppState;
 
+/// Page size when `limit` is omitted.
+const DEFAULT_NULLIFIER_PAGE: u32 = 100;
+
+/// Largest page `GET /nullifiers` will return.
+const MAX_NULLIFIER_PAGE: u32 = 1000;
+
+/// Upper bound on hashes per `POST /nullifiers/check` request.
+const MAX_NULLIFIER_CHECK: usize = 500;
+
+/// Hand `call` to the client to send itself (`self_submit`), marking its
+/// nullifiers pending until the sync task sees them spent on-chain. Its new
+/// leaves reach the tree through the coordinator's `CommitmentAdded` events.
+pub(crate) fn hand_off(
+    state: &AppState,
+    circuit_type: CircuitType,
+    nullifier_hashes: &[&str],
+    call: Call,
+) -> Result<ContractCall, AspError> {
+    for nullifier_hash in nullifier_hashes {
+        state.db.insert_pending_nullifier(nullifier_hash, circuit_type)?;
+    }
+    tracing::info!(circuit = %circuit_type, "Proof handed to client for self-submission");
+    Ok(ContractCall::from(call))
+}
+
+/// Refuse to prove a spend of `nullifier_hash` if it is spent, or pending
+/// after a [`hand_off`] whose transaction the sync task has not seen yet.
+pub(crate) fn ensure_unspent(state: &AppState, nullifier_hash: &str) -> Result<(), AspError> {
+    if state.db.is_nullifier_spent(nullifier_hash)? {
+        return Err(AspError::NullifierAlreadySpent(nullifier_hash.to_string()));
+    }
+    if state.db.is_nullifier_pending(nullifier_hash)? {
+        return Err(AspError::NullifierPending(nullifier_hash.to_string()));
+    }
+    Ok(())
+}
+
 pub async fn get_nullifier(
     State(state): State<Arc<AppState>>,
     Path(hash): Path<String>,
@@ -17,14 +62,107 @@ pub async fn get_nullifier(
         Some(row) => Ok(Json(NullifierResponse {
             nullifier_hash: row.nullifier_hash,
             spent: true,
+            pending: false,
             circuit_type: Some(row.circuit_type),
+            origin: Some(row.origin),
             tx_hash: row.tx_hash,
+            spent_at: Some(row.spent_at),
         })),
         None => Ok(Json(NullifierResponse {
+            pending: state.db.is_nullifier_pending(&hash)?,
             nullifier_hash: hash,
             spent: false,
             circuit_type: None,
+            origin: None,
             tx_hash: None,
+            spent_at: None,
         })),
     }
 }
+
+/// Endpoint: GET /nullifiers?circuit_type=&origin=&offset=&limit=
+/// Page through spent nullifiers in spend order.
+pub async fn list_nullifiers(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<NullifierListQuery>,
+) -> Result<Json<NullifierListResponse>, AspError> {
+    let circuit_type = query
+        .circuit_type
+        .as_deref()
+        .map(str::parse::<CircuitType>)
+        .transpose()?;
+    let origin = query
+        .origin
+        .as_deref()
+        .map(str::parse::<NullifierOrigin>)
+        .transpose()?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_NULLIFIER_PAGE);
+    if limit == 0 || limit > MAX_NULLIFIER_PAGE {
+        return Err(AspError::InvalidInput(format!(
+            "limit must be between 1 and {MAX_NULLIFIER_PAGE}"
+        )));
+    }
+
+    let rows = state
+        .db
+        .get_nullifiers_paged(circuit_type, origin, query.offset, limit)?;
+
+    Ok(Json(NullifierListResponse {
+        nullifiers: rows
+            .into_iter()
+            .map(|row| NullifierEntry {
+                nullifier_hash: row.nullifier_hash,
+                circuit_type: row.circuit_type,
+                origin: row.origin,
+                tx_hash: row.tx_hash,
+                spent_at: row.spent_at,
+            })
+            .collect(),
+        offset: query.offset,
+        limit,
+    }))
+}
+
+/// Endpoint: POST /nullifiers/check
+/// Spent status of many nullifiers in one call, looked up with a single query.
+pub async fn check_nullifiers(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<NullifierCheckRequest>,
+) -> Result<Json<NullifierCheckResponse>, AspError> {
+    if req.hashes.len() > MAX_NULLIFIER_CHECK {
+        return Err(AspError::InvalidInput(format!(
+            "at most {MAX_NULLIFIER_CHECK} hashes per check"
+        )));
+    }
+    let decimals = req
+        .hashes
+        .iter()
+        .map(|hash| field_to_decimal(hash, "hashes"))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let spent: HashMap<String, NullifierRow> = state
+        .db
+        .get_nullifiers(&decimals)?
+        .into_iter()
+        .map(|row| (row.nullifier_hash.clone(), row))
+        .collect();
+
+    let nullifiers = req
+        .hashes
+        .into_iter()
+        .zip(decimals)
+        .map(|(hash, decimal)| {
+            let row = spent.get(&decimal);
+            let status = NullifierStatus {
+                spent: row.is_some(),
+                circuit_type: row.map(|r| r.circuit_type),
+                origin: row.map(|r| r.origin),
+                tx_hash: row.and_then(|r| r.tx_hash.clone()),
+            };
+            (hash, status)
+        })
+        .collect();
+
+    Ok(Json(NullifierCheckResponse { nullifiers }))
+}
@@ -0,0 +1,8 @@
+use axum::Json;
+use serde_json::Value;
+
+use crate::api::openapi;
+
+pub async fn get_openapi() -> Json<Value> {
+    Json(openapi::document())
+}
@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::Json;
+
+use crate::api::types::{
+    OperationListQuery, OperationListResponse, OperationResponse, TxCommitment,
+    TxCommitmentsResponse, TxLookupResponse,
+};
+use crate::db::queries::OperationRow;
+use crate::error::AspError;
+use crate::AppState;
+
+/// Page size when `limit` is omitted.
+const DEFAULT_OPERATION_PAGE: u32 = 50;
+
+/// Largest page `GET /operations` will return.
+const MAX_OPERATION_PAGE: u32 = 500;
+
+/// Endpoints that write to the audit log, which are the valid `kind` filters.
+const OPERATION_KINDS: &[&str] = &["deposit", "withdraw", "swap", "mint", "burn"];
+
+impl From<OperationRow> for OperationResponse {
+    fn from(row: OperationRow) -> Self {
+        OperationResponse {
+            id: row.id,
+            kind: row.kind,
+            request_hash: row.request_hash,
+            tx_hash: row.tx_hash,
+            root_tx: row.root_tx,
+            leaf_indices: row.leaf_indices,
+            status: row.status,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// Endpoint: GET /operations/{id}
+pub async fn get_operation(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<OperationResponse>, AspError> {
+    state
+        .db
+        .get_operation(id)?
+        .map(|row| Json(row.into()))
+        .ok_or(AspError::OperationNotFound(id))
+}
+
+/// Endpoint: GET /tx/{tx_hash}
+/// The audit entry a transaction belongs to, whether it is the operation's
+/// own tx or the Merkle root submission that followed it.
+pub async fn get_operation_by_tx(
+    State(state): State<Arc<AppState>>,
+    Path(tx_hash): Path<String>,
+) -> Result<Json<TxLookupResponse>, AspError> {
+    let canonical = canonical_tx_hash(&tx_hash);
+    let row = state
+        .db
+        .find_operation_by_tx(&canonical)?
+        .ok_or(AspError::TxNotFound(tx_hash))?;
+
+    let role = if row.tx_hash.as_deref() == Some(canonical.as_str()) {
+        "action"
+    } else {
+        "root"
+    };
+    Ok(Json(TxLookupResponse {
+        tx_hash: canonical,
+        role: role.to_string(),
+        operation: row.into(),
+    }))
+}
+
+/// Endpoint: GET /tx/{tx_hash}/commitments
+/// Leaves a transaction inserted: a deposit's leaf, or the outputs of a
+/// swap, mint or burn. Leaves learned from chain sync carry no tx.
+pub async fn get_tx_commitments(
+    State(state): State<Arc<AppState>>,
+    Path(tx_hash): Path<String>,
+) -> Result<Json<TxCommitmentsResponse>, AspError> {
+    let canonical = canonical_tx_hash(&tx_hash);
+    let rows = state.db.get_commitments_by_tx(&canonical)?;
+    if rows.is_empty() {
+        return Err(AspError::TxNotFound(tx_hash));
+    }
+    Ok(Json(TxCommitmentsResponse {
+        tx_hash: canonical,
+        commitments: rows
+            .into_iter()
+            .map(|row| TxCommitment {
+                leaf_index: row.leaf_index,
+                commitment: row.commitment,
+            })
+            .collect(),
+    }))
+}
+
+/// Relayed tx hashes are stored as lowercase `0x` hex without leading zeros;
+/// bring hex input to that form. Anything else is looked up as given.
+fn canonical_tx_hash(raw: &str) -> String {
+    let raw = raw.trim();
+    match raw.strip_prefix("0x").or_else(|| raw.strip_prefix("0X")) {
+        Some(digits) if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_hexdigit()) => {
+            let digits = digits.trim_start_matches('0').to_ascii_lowercase();
+            if digits.is_empty() {
+                "0x0".to_string()
+            } else {
+                format!("0x{digits}")
+            }
+        }
+        _ => raw.to_string(),
+    }
+}
+
+/// Endpoint: GET /operations?kind=&limit=
+/// Most recent mutating operations first.
+pub async fn list_operations(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<OperationListQuery>,
+) -> Result<Json<OperationListResponse>, AspError> {
+    if let Some(ref kind) = query.kind {
+        if !OPERATION_KINDS.contains(&kind.as_str()) {
+            return Err(AspError::InvalidInput(format!(
+                "kind must be one of: {}",
+                OPERATION_KINDS.join(", ")
+            )));
+        }
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_OPERATION_PAGE);
+    if limit == 0 || limit > MAX_OPERATION_PAGE {
+        return Err(AspError::InvalidInput(format!(
+            "limit must be between 1 and {MAX_OPERATION_PAGE}"
+        )));
+    }
+
+    let rows = state.db.list_operations(query.kind.as_deref(), limit)?;
+
+    Ok(Json(OperationListResponse {
+        operations: rows.into_iter().map(Into::into).collect(),
+        limit,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_tx_hash_matches_relayer_format() {
+        assert_eq!(canonical_tx_hash("0x00ABcd"), "0xabcd");
+        assert_eq!(canonical_tx_hash(" 0X0 "), "0x0");
+        assert_eq!(canonical_tx_hash("0xmock_deposit_tx"), "0xmock_deposit_tx");
+    }
+}
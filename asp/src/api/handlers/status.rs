@@ -1,19 +1,38 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::extract::State;
 use axum::Json;
 
 use crate::api::types::{ContractAddresses, StatusResponse, SyncStatus, TreeStatus};
 use crate::error::AspError;
+use crate::sync::events::TREE_DIVERGED_KEY;
 use crate::AppState;
 
+/// Endpoint: GET /ping
+/// Liveness only: answers from the HTTP server alone, without touching the
+/// worker or the database, so a long proof never fails a liveness probe.
+pub async fn ping() -> &'static str {
+    "pong"
+}
+
+/// Endpoint: GET /status
+/// Served from a cache for `STATUS_CACHE_MS` after each computation.
 pub async fn get_status(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<StatusResponse>, AspError> {
+    let ttl = Duration::from_millis(state.config.status_cache_ms);
+    let status = state
+        .status
+        .get_or_refresh(ttl, || compute_status(&state))
+        .await;
+    Ok(Json(status))
+}
+
+async fn compute_status(state: &AppState) -> StatusResponse {
     let db_healthy = state.db.is_healthy();
 
-    let leaf_count = state.db.get_leaf_count().unwrap_or(0);
-    let root = state.db.get_latest_root().unwrap_or(None);
+    let tree = state.tree_snapshot();
 
     let last_synced_block = state
         .db
@@ -21,24 +40,57 @@ pub async fn get_status(
         .unwrap_or(None)
         .and_then(|s| s.parse::<u64>().ok());
 
-    // Check worker health via ping
-    let worker_healthy = {
-        let mut worker = state.worker.lock().await;
-        worker.ping().await.unwrap_or(false)
+    let tree_diverged_at_leaf = state
+        .db
+        .get_sync_state(TREE_DIVERGED_KEY)
+        .unwrap_or(None)
+        .and_then(|s| s.parse::<u32>().ok());
+
+    let chain_head = state.syncer.as_ref().and_then(|s| s.chain_head());
+    let progress = state
+        .syncer
+        .as_ref()
+        .map(|s| s.progress())
+        .unwrap_or_default();
+    let lag_blocks = match (chain_head, last_synced_block) {
+        (Some(head), Some(last)) => Some(head.saturating_sub(last)),
+        _ => None,
+    };
+
+    // Ping the worker only if it is idle. A held lock means it is busy
+    // (e.g. generating a proof), which implies it is alive — don't wait on it.
+    let (worker_healthy, worker_busy) = match state.worker.try_lock() {
+        Ok(mut worker) => (worker.ping().await.unwrap_or(false), false),
+        Err(_) => (true, true),
     };
 
     let healthy = db_healthy && worker_healthy;
 
-    Ok(Json(StatusResponse {
+    StatusResponse {
         healthy,
         version: env!("CARGO_PKG_VERSION").to_string(),
-        tree: TreeStatus { leaf_count, root },
+        worker_busy,
+        worker: state.worker_info.clone(),
+        tree: TreeStatus {
+            leaf_count: tree.leaf_count,
+            root: state.root_or_empty(tree.root),
+            anonymity_set: tree.leaf_count,
+            last_deposit_at: tree.last_deposit_at,
+            accepted_root_window: state.config.root_history_size,
+        },
         sync: SyncStatus {
             last_synced_block,
+            chain_head,
+            lag_blocks,
+            tree_diverged_at_leaf,
+            sync_in_progress: progress.in_progress,
+            last_sync_completed_at: progress.last_completed_at,
+            last_sync_error: progress.last_error,
         },
         contracts: ContractAddresses {
             coordinator: state.config.coordinator_address.clone(),
             pool: state.config.pool_address.clone(),
         },
-    }))
+        operations: state.operations.snapshot(),
+    }
 }
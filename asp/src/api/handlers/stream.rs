@@ -0,0 +1,45 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+
+use crate::api::types::EventStreamQuery;
+use crate::stream::SequencedEvent;
+use crate::AppState;
+
+/// Endpoint: GET /events
+/// Server-Sent Events stream of tree changes (`commitment_added`,
+/// `nullifier_spent`). Clients resume with the standard `Last-Event-ID`
+/// header or `?last_event_id=`; buffered events after that id are sent first.
+pub async fn events(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<EventStreamQuery>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+        .or(query.last_event_id);
+
+    let (backlog, receiver) = state.events.subscribe(last_event_id);
+    // A subscriber that falls too far behind is disconnected; its reconnect
+    // (with Last-Event-ID) replays the missed events from the buffer.
+    let live = BroadcastStream::new(receiver).map_while(Result::ok);
+    let stream = tokio_stream::iter(backlog)
+        .chain(live)
+        .map(|event| Ok(sse_event(&event)));
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+fn sse_event(sequenced: &SequencedEvent) -> Event {
+    Event::default()
+        .id(sequenced.id.to_string())
+        .event(sequenced.event.name())
+        .data(serde_json::to_string(&sequenced.event).unwrap_or_default())
+}
@@ -1,21 +1,31 @@
 use std::sync::Arc;
 
 use axum::extract::State;
-use axum::Json;
+use axum::{Extension, Json};
 
-use crate::api::types::{SwapRequest, SwapResponse};
+use crate::api::handlers::deposit::{check_note_matches_deposit, hex_to_decimal};
+use crate::api::handlers::nullifier::{ensure_unspent, hand_off};
+use crate::api::middleware::OperationOutcome;
+use crate::api::signature::SignedJson;
+use crate::api::types::{
+    DryRunResponse, SimulateSwapRequest, SimulateSwapResponse, SwapRequest, SwapResponse,
+};
 use crate::api::validation::{
-    validate_address, validate_decimal, validate_hex_u256, validate_secret,
+    validate_address, validate_decimal, validate_distinct_notes, validate_secret,
+    validate_sqrt_price_limit, validate_u128_limb,
 };
 use crate::error::AspError;
+use crate::prover::{CircuitType, ProofResult};
+use crate::relayer::PoolCalls;
+use crate::webhook::WebhookEvent;
 use crate::AppState;
 
 fn validate_swap_request(req: &SwapRequest) -> Result<(), AspError> {
     // Input note
     validate_secret(&req.input_note.secret, "input_note.secret")?;
     validate_secret(&req.input_note.nullifier, "input_note.nullifier")?;
-    validate_decimal(&req.input_note.balance_low, "input_note.balance_low")?;
-    validate_decimal(&req.input_note.balance_high, "input_note.balance_high")?;
+    validate_u128_limb(&req.input_note.balance_low, "input_note.balance_low")?;
+    validate_u128_limb(&req.input_note.balance_high, "input_note.balance_high")?;
     validate_address(&req.input_note.token, "input_note.token")?;
 
     // Swap params
@@ -23,8 +33,8 @@ fn validate_swap_request(req: &SwapRequest) -> Result<(), AspError> {
     validate_address(&req.swap_params.token_out, "swap_params.token_out")?;
     validate_decimal(&req.swap_params.amount_in, "swap_params.amount_in")?;
     validate_decimal(&req.swap_params.amount_out_min, "swap_params.amount_out_min")?;
-    validate_decimal(&req.swap_params.amount_out_low, "swap_params.amount_out_low")?;
-    validate_decimal(&req.swap_params.amount_out_high, "swap_params.amount_out_high")?;
+    validate_u128_limb(&req.swap_params.amount_out_low, "swap_params.amount_out_low")?;
+    validate_u128_limb(&req.swap_params.amount_out_high, "swap_params.amount_out_high")?;
 
     // Output + change notes
     validate_secret(&req.output_note.secret, "output_note.secret")?;
@@ -37,22 +47,34 @@ fn validate_swap_request(req: &SwapRequest) -> Result<(), AspError> {
     validate_address(&req.pool_key.token_1, "pool_key.token_1")?;
 
     // Price limit
-    validate_hex_u256(&req.sqrt_price_limit, "sqrt_price_limit")?;
+    validate_sqrt_price_limit(&req.sqrt_price_limit, "sqrt_price_limit")?;
+
+    validate_distinct_notes(&[
+        ("input_note", &req.input_note.secret, &req.input_note.nullifier),
+        ("output_note", &req.output_note.secret, &req.output_note.nullifier),
+        ("change_note", &req.change_note.secret, &req.change_note.nullifier),
+    ])?;
 
     Ok(())
 }
 
-pub async fn shielded_swap(
-    State(state): State<Arc<AppState>>,
-    Json(req): Json<SwapRequest>,
-) -> Result<Json<SwapResponse>, AspError> {
-    validate_swap_request(&req)?;
+/// Output of the "build & prove" half of a swap: everything needed to submit,
+/// produced without touching the relayer or mutating the tree/DB.
+pub(crate) struct SwapProof {
+    pub nullifier_hash: String,
+    pub output_commitment: String,
+    pub proof: ProofResult,
+}
 
-    tracing::info!(
-        leaf_index = req.input_note.leaf_index,
-        "Processing shielded swap"
-    );
+/// Validate the request, check the input note against the tree, and generate
+/// the swap proof.
+pub(crate) async fn build_swap_proof(
+    state: &AppState,
+    req: &SwapRequest,
+) -> Result<SwapProof, AspError> {
+    validate_swap_request(req)?;
 
+    let _slot = state.proof_slots.try_acquire()?;
     let mut worker = state.worker.lock().await;
 
     // 1. Compute input note commitment
@@ -73,11 +95,16 @@ pub async fn shielded_swap(
         Some(_) => return Err(AspError::InvalidInput("Commitment mismatch".into())),
         None => return Err(AspError::CommitmentNotFound(req.input_note.leaf_index)),
     }
+    check_note_matches_deposit(
+        state,
+        req.input_note.leaf_index,
+        &req.input_note.token,
+        &req.input_note.balance_low,
+        &req.input_note.balance_high,
+    )?;
 
-    // 3. Check nullifier not spent
-    if state.db.is_nullifier_spent(&input_result.nullifier_hash)? {
-        return Err(AspError::NullifierAlreadySpent(input_result.nullifier_hash));
-    }
+    // 3. Check nullifier not spent, nor pending
+    ensure_unspent(state, &input_result.nullifier_hash)?;
 
     // 4. Get Merkle proof
     let proof = worker.get_proof(req.input_note.leaf_index).await?;
@@ -118,23 +145,36 @@ pub async fn shielded_swap(
     });
 
     // 7. Generate swap proof
-    let proof_result = worker.generate_proof("swap", inputs).await?;
+    let proof_result = worker.generate_proof(CircuitType::Swap, inputs).await?;
     drop(worker);
+    state.record_proof_time(CircuitType::Swap, proof_result.duration_ms);
+    proof_result.ensure_layout(CircuitType::Swap)?;
 
-    // 8. Submit to pool.shielded_swap
-    let tx_hash = if let Some(ref relayer) = state.relayer {
-        let relayer = relayer.lock().await;
-        relayer
-            .shielded_swap(&req.pool_key, &proof_result.calldata, &req.sqrt_price_limit)
-            .await?
-    } else {
-        return Err(AspError::Internal("No relayer configured".into()));
-    };
+    Ok(SwapProof {
+        nullifier_hash: input_result.nullifier_hash,
+        output_commitment: output_commitment.commitment,
+        proof: proof_result,
+    })
+}
 
-    // 9. Record nullifier as spent
-    state
-        .db
-        .insert_nullifier(&input_result.nullifier_hash, "swap", Some(&tx_hash))?;
+pub async fn shielded_swap(
+    State(state): State<Arc<AppState>>,
+    SignedJson { value: req, auth }: SignedJson<SwapRequest>,
+) -> Result<(Extension<OperationOutcome>, Json<SwapResponse>), AspError> {
+    tracing::info!(
+        leaf_index = req.input_note.leaf_index,
+        "Processing shielded swap"
+    );
+
+    auth.verify_for_leaf(&state.db, req.input_note.leaf_index)?;
+
+    let SwapProof {
+        nullifier_hash,
+        output_commitment,
+        proof: proof_result,
+    } = state
+        .within_proof_deadline(build_swap_proof(&state, &req))
+        .await?;
 
     // The changeCommitment is a circuit output computed inside the proof.
     // It's the first public signal from the swap circuit (Circom outputs come first).
@@ -143,46 +183,100 @@ pub async fn shielded_swap(
         .first()
         .cloned()
         .unwrap_or_default();
+    let (amount_out, amount_change) = swap_amounts(&req);
 
-    // 10. Insert output and change commitments into Merkle tree
-    let mut worker = state.worker.lock().await;
+    if req.self_submit {
+        let call = PoolCalls::from_config(&state.config)?.shielded_swap(
+            &req.pool_key,
+            &proof_result.calldata,
+            &req.sqrt_price_limit,
+        )?;
+        let call = hand_off(&state, CircuitType::Swap, &[&nullifier_hash], call)?;
+        return Ok((
+            Extension(OperationOutcome::default()),
+            Json(SwapResponse {
+                status: "awaiting_submission".to_string(),
+                tx_hash: String::new(),
+                new_commitment: output_commitment,
+                change_commitment,
+                amount_out,
+                amount_change,
+                call: Some(call),
+            }),
+        ));
+    }
 
-    // Insert output commitment (always present)
-    let leaf_index = state.db.get_leaf_count()?;
+    // 8. Submit to pool.shielded_swap
+    let tx_hash = if let Some(ref relayer) = state.relayer {
+        let relayer = relayer.lock().await;
+        relayer
+            .shielded_swap(&req.pool_key, &proof_result.calldata, &req.sqrt_price_limit)
+            .await?
+    } else {
+        return Err(AspError::Internal("No relayer configured".into()));
+    };
+
+    // 9. Record nullifier as spent
     state
         .db
-        .insert_commitment(leaf_index, &output_commitment.commitment, Some(&tx_hash))?;
-    let mut last_root = worker.insert_leaf(&output_commitment.commitment).await?;
-    tracing::debug!(leaf_index = leaf_index, "Inserted output_commitment");
+        .insert_nullifier(&nullifier_hash, CircuitType::Swap, Some(&tx_hash))?;
+    state.publish_nullifier(&nullifier_hash, CircuitType::Swap);
 
-    // Insert change commitment if non-zero
+    // 10. Insert the output commitment (always present) and a non-zero
+    // change commitment into the Merkle tree
+    let mut commitments = vec![output_commitment.as_str()];
     if !change_commitment.is_empty() && change_commitment != "0" {
-        let leaf_index = state.db.get_leaf_count()?;
-        state
-            .db
-            .insert_commitment(leaf_index, &change_commitment, Some(&tx_hash))?;
-        last_root = worker.insert_leaf(&change_commitment).await?;
-        tracing::debug!(leaf_index = leaf_index, "Inserted change_commitment");
+        commitments.push(change_commitment.as_str());
     }
-
-    drop(worker);
+    let (leaf_indices, last_root) = state.append_leaves(&commitments, &tx_hash).await?;
+    let output_leaf_index = leaf_indices[0];
 
     // 11. Store the final root in DB
     let new_count = state.db.get_leaf_count()?;
     state.db.insert_root(&last_root, new_count, Some(&tx_hash))?;
+    state.refresh_tree()?;
 
     // 12. Submit the new Merkle root to Coordinator on-chain
-    if let Some(ref relayer) = state.relayer {
+    let root_tx = if let Some(ref relayer) = state.relayer {
         let relayer = relayer.lock().await;
         let root_tx = relayer.submit_merkle_root(&last_root).await?;
         tracing::info!(tx_hash = %root_tx, "Merkle root submitted on-chain after swap");
+        Some(root_tx)
     } else {
         tracing::warn!("No relayer configured — root stored locally only");
-    }
+        None
+    };
 
     tracing::info!(tx_hash = %tx_hash, "Shielded swap confirmed");
+    state.notify(WebhookEvent::new(
+        "swap",
+        &tx_hash,
+        Some(output_leaf_index),
+        Some(&last_root),
+    ));
+
+    let outcome = OperationOutcome {
+        tx_hash: Some(tx_hash.clone()),
+        root_tx,
+        leaf_indices,
+    };
+    Ok((
+        Extension(outcome),
+        Json(SwapResponse {
+            status: "confirmed".to_string(),
+            tx_hash,
+            new_commitment: output_commitment.clone(),
+            change_commitment: change_commitment.clone(),
+            amount_out,
+            amount_change,
+            call: None,
+        }),
+    ))
+}
 
-    // Reconstruct actual amounts so the SDK can save notes with correct amounts.
+/// The swap's output and change amounts (decimal strings), echoed back so
+/// the SDK can save notes with the correct amounts.
+fn swap_amounts(req: &SwapRequest) -> (String, String) {
     // u256 is split into (low, high) 128-bit halves: value = low + high * 2^128.
     // For realistic token amounts both high parts are 0, so value == low.
     let amount_out_low: u128 = req.swap_params.amount_out_low.parse().unwrap_or(0);
@@ -210,12 +304,65 @@ pub async fn shielded_swap(
     };
     let amount_change = input_balance.saturating_sub(amount_in).to_string();
 
-    Ok(Json(SwapResponse {
-        status: "confirmed".to_string(),
-        tx_hash,
-        new_commitment: output_commitment.commitment.clone(),
-        change_commitment: change_commitment.clone(),
-        amount_out,
-        amount_change,
+    (amount_out, amount_change)
+}
+
+/// Endpoint: POST /swap/prove
+/// Dry run: generate the swap proof and return it without submitting on-chain,
+/// recording the nullifier, or inserting leaves. Signed like `/swap`, since
+/// the proof could be submitted by anyone.
+pub async fn prove_swap(
+    State(state): State<Arc<AppState>>,
+    SignedJson { value: req, auth }: SignedJson<SwapRequest>,
+) -> Result<Json<DryRunResponse>, AspError> {
+    auth.verify_for_leaf(&state.db, req.input_note.leaf_index)?;
+    let built = build_swap_proof(&state, &req).await?;
+
+    Ok(Json(DryRunResponse {
+        status: "proved".to_string(),
+        circuit: CircuitType::Swap,
+        nullifier_hashes: vec![built.nullifier_hash],
+        calldata: built.proof.calldata,
+        public_signals: built.proof.public_signals,
+    }))
+}
+
+/// Endpoint: POST /simulate-swap
+/// Quote a swap through the pool's read-only quoter. Nothing is proved,
+/// submitted or recorded.
+pub async fn simulate_swap(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<SimulateSwapRequest>,
+) -> Result<Json<SimulateSwapResponse>, AspError> {
+    validate_address(&req.pool_key.token_0, "pool_key.token_0")?;
+    validate_address(&req.pool_key.token_1, "pool_key.token_1")?;
+    validate_address(&req.token_in, "token_in")?;
+    validate_decimal(&req.amount_in, "amount_in")?;
+    validate_sqrt_price_limit(&req.sqrt_price_limit, "sqrt_price_limit")?;
+
+    let token_in = hex_to_decimal(&req.token_in)?;
+    let zero_for_one = if token_in == hex_to_decimal(&req.pool_key.token_0)? {
+        true
+    } else if token_in == hex_to_decimal(&req.pool_key.token_1)? {
+        false
+    } else {
+        return Err(AspError::InvalidInput(
+            "token_in must be one of the pool's tokens".into(),
+        ));
+    };
+
+    let Some(ref relayer) = state.relayer else {
+        return Err(AspError::QuoterUnavailable("no relayer configured".into()));
+    };
+    let relayer = relayer.lock().await;
+    let quote = relayer
+        .simulate_swap(&req.pool_key, zero_for_one, &req.amount_in, &req.sqrt_price_limit)
+        .await?;
+    drop(relayer);
+
+    Ok(Json(SimulateSwapResponse {
+        zero_for_one,
+        amount_out: quote.amount_out,
+        sqrt_price_after: quote.sqrt_price_after,
     }))
 }
@@ -2,23 +2,24 @@ use std::sync::Arc;
 
 use axum::extract::State;
 use axum::Json;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::error::AspError;
 use crate::AppState;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct SyncCommitmentsRequest {
     pub commitments: Vec<String>, // List of commitment hashes (decimal strings)
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct CommitmentWithIndex {
     pub commitment: String,
     pub leaf_index: Option<u32>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct SyncCommitmentsResponse {
     pub commitments: Vec<CommitmentWithIndex>,
 }
@@ -1,28 +1,117 @@
 use std::sync::Arc;
 
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::Json;
 
-use crate::api::types::{TreeProofResponse, TreeRootResponse};
+use crate::api::handlers::deposit::field_to_decimal;
+use crate::api::types::{
+    PathIndices, RecordedRoot, TreePathFormatQuery, TreePathQuery, TreeProofResponse,
+    TreeRootQuery, TreeRootResponse, TreeRootsQuery, TreeRootsResponse,
+};
 use crate::error::AspError;
+use crate::prover::MerkleProof;
 use crate::AppState;
 
+/// Endpoint: GET /tree/root?leaf_count=
+/// With `leaf_count`, the root recorded at that leaf count (404 if none was).
 pub async fn get_root(
     State(state): State<Arc<AppState>>,
+    Query(query): Query<TreeRootQuery>,
 ) -> Result<Json<TreeRootResponse>, AspError> {
-    let leaf_count = state.db.get_leaf_count()?;
-    let root = state.db.get_latest_root()?;
+    if let Some(leaf_count) = query.leaf_count {
+        let root = state.db.get_root_at_leaf_count(leaf_count)?.ok_or_else(|| {
+            AspError::UnknownRoot(format!("none recorded at leaf count {leaf_count}"))
+        })?;
+        return Ok(Json(TreeRootResponse { root, leaf_count }));
+    }
+
+    let tree = state.tree_snapshot();
 
     Ok(Json(TreeRootResponse {
-        root: root.unwrap_or_else(|| "0".to_string()),
-        leaf_count,
+        root: state.root_or_empty(tree.root),
+        leaf_count: tree.leaf_count,
+    }))
+}
+
+/// Largest page `GET /tree/roots` will return.
+const MAX_ROOTS_PAGE: u32 = 1000;
+
+/// Endpoint: GET /tree/roots?limit=
+/// Recorded roots, newest first, each marked with whether the coordinator
+/// still accepts proofs against it (it keeps the last `ROOT_HISTORY_SIZE`).
+pub async fn get_roots(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TreeRootsQuery>,
+) -> Result<Json<TreeRootsResponse>, AspError> {
+    let window = state.config.root_history_size;
+    let limit = query.limit.unwrap_or(window.min(MAX_ROOTS_PAGE));
+    if limit == 0 || limit > MAX_ROOTS_PAGE {
+        return Err(AspError::InvalidInput(format!(
+            "limit must be between 1 and {MAX_ROOTS_PAGE}"
+        )));
+    }
+
+    let roots = state
+        .db
+        .get_recent_roots(limit)?
+        .into_iter()
+        .enumerate()
+        .map(|(age, row)| RecordedRoot {
+            root: row.root,
+            leaf_count: row.leaf_count,
+            submit_tx: row.submit_tx,
+            created_at: row.created_at,
+            accepted: (age as u32) < window,
+        })
+        .collect();
+    Ok(Json(TreeRootsResponse {
+        accepted_root_window: window,
+        roots,
     }))
 }
 
+/// Endpoint: GET /tree/path/{leaf_index}?root=&bool_indices=
+/// With `root`, the proof is against that historical root (which must have
+/// been recorded) rather than the current one.
 pub async fn get_path(
     State(state): State<Arc<AppState>>,
     Path(leaf_index): Path<u32>,
+    Query(query): Query<TreePathQuery>,
 ) -> Result<Json<TreeProofResponse>, AspError> {
+    let bool_indices = query.bool_indices.unwrap_or(false);
+    match query.root {
+        Some(root) => tree_proof_at_root(&state, leaf_index, &root, bool_indices)
+            .await
+            .map(Json),
+        None => tree_proof(&state, leaf_index, bool_indices).await.map(Json),
+    }
+}
+
+/// Endpoint: GET /tree/path-by-commitment/{commitment}?bool_indices=
+/// Same as `/tree/path/{leaf_index}`, resolving the leaf from a hex (0x...)
+/// or decimal commitment.
+pub async fn get_path_by_commitment(
+    State(state): State<Arc<AppState>>,
+    Path(commitment): Path<String>,
+    Query(query): Query<TreePathFormatQuery>,
+) -> Result<Json<TreeProofResponse>, AspError> {
+    let commitment_decimal = field_to_decimal(&commitment, "commitment")?;
+
+    let leaf_index = state
+        .db
+        .find_commitment_leaf_index(&commitment_decimal)?
+        .ok_or(AspError::UnknownCommitment(commitment))?;
+
+    tree_proof(&state, leaf_index, query.bool_indices.unwrap_or(false))
+        .await
+        .map(Json)
+}
+
+async fn tree_proof(
+    state: &AppState,
+    leaf_index: u32,
+    bool_indices: bool,
+) -> Result<TreeProofResponse, AspError> {
     // Verify leaf exists
     let commitment = state
         .db
@@ -34,11 +123,54 @@ pub async fn get_path(
     let proof = worker.get_proof(leaf_index).await?;
     drop(worker);
 
-    Ok(Json(TreeProofResponse {
+    Ok(proof_response(leaf_index, commitment.commitment, proof, bool_indices))
+}
+
+async fn tree_proof_at_root(
+    state: &AppState,
+    leaf_index: u32,
+    root: &str,
+    bool_indices: bool,
+) -> Result<TreeProofResponse, AspError> {
+    let root_decimal = field_to_decimal(root, "root")?;
+    let leaf_count = state
+        .db
+        .get_root_leaf_count(&root_decimal)?
+        .ok_or_else(|| AspError::UnknownRoot(root.to_string()))?;
+    if leaf_index >= leaf_count {
+        return Err(AspError::InvalidInput(format!(
+            "Leaf {leaf_index} was not yet in the tree at root {root} ({leaf_count} leaves)"
+        )));
+    }
+
+    let commitment = state
+        .db
+        .get_commitment(leaf_index)?
+        .ok_or(AspError::CommitmentNotFound(leaf_index))?;
+
+    let mut worker = state.worker.lock().await;
+    let proof = worker.get_proof_at_root(leaf_index, &root_decimal, leaf_count).await?;
+    drop(worker);
+
+    Ok(proof_response(leaf_index, commitment.commitment, proof, bool_indices))
+}
+
+fn proof_response(
+    leaf_index: u32,
+    commitment: String,
+    proof: MerkleProof,
+    bool_indices: bool,
+) -> TreeProofResponse {
+    let path_indices = if bool_indices {
+        PathIndices::Bools(proof.path_bits())
+    } else {
+        PathIndices::Bits(proof.path_indices)
+    };
+    TreeProofResponse {
         leaf_index,
-        commitment: commitment.commitment,
+        commitment,
         path_elements: proof.path_elements,
-        path_indices: proof.path_indices,
+        path_indices,
         root: proof.root,
-    }))
+    }
 }
@@ -1,33 +1,156 @@
 use std::sync::Arc;
 
-use axum::extract::State;
-use axum::Json;
+use axum::extract::{Query, State};
+use axum::{Extension, Json};
 
-use crate::api::types::{WithdrawRequest, WithdrawResponse};
-use crate::api::validation::{validate_address, validate_decimal, validate_secret};
+use crate::api::handlers::deposit::check_note_matches_deposit;
+use crate::api::handlers::nullifier::ensure_unspent;
+use crate::api::middleware::OperationOutcome;
+use crate::api::signature::SignedJson;
+use crate::api::types::{ProveQuery, WithdrawProveResponse, WithdrawRequest, WithdrawResponse};
+use crate::api::validation::{validate_address, validate_secret, validate_u128_limb};
 use crate::error::AspError;
+use crate::prover::{CircuitType, ProofResult};
+use crate::webhook::WebhookEvent;
 use crate::AppState;
 
 pub async fn withdraw(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<WithdrawRequest>,
-) -> Result<Json<WithdrawResponse>, AspError> {
-    // Validate
+    SignedJson { value: req, auth }: SignedJson<WithdrawRequest>,
+) -> Result<(Extension<OperationOutcome>, Json<WithdrawResponse>), AspError> {
+    validate_withdraw_request(&req)?;
+    auth.verify_for_leaf(&state.db, req.leaf_index)?;
+    check_withdraw_delay(&state, req.leaf_index)?;
+
+    tracing::info!(leaf_index = req.leaf_index, "Processing withdrawal (membership proof)");
+    let built = state
+        .within_proof_deadline(build_withdraw_proof(&state, &req))
+        .await?;
+    let nullifier_hash = built.nullifier_hash;
+
+    // Submit to pool.withdraw() (which internally calls coordinator.verify_membership)
+    let tx_hash = if let Some(ref relayer) = state.relayer {
+        let relayer = relayer.lock().await;
+        relayer.verify_membership(&built.proof.calldata).await?
+    } else {
+        return Err(AspError::Internal("No relayer configured".into()));
+    };
+
+    // Record nullifier as spent
+    state
+        .db
+        .insert_nullifier(&nullifier_hash, CircuitType::Membership, Some(&tx_hash))?;
+    state.publish_nullifier(&nullifier_hash, CircuitType::Membership);
+
+    tracing::info!(
+        tx_hash = %tx_hash,
+        "Withdrawal confirmed"
+    );
+    state.notify(WebhookEvent::new("withdraw", &tx_hash, None, None));
+
+    let outcome = OperationOutcome {
+        tx_hash: Some(tx_hash.clone()),
+        root_tx: None,
+        leaf_indices: Vec::new(),
+    };
+    Ok((
+        Extension(outcome),
+        Json(WithdrawResponse {
+            status: "confirmed".to_string(),
+            tx_hash,
+            nullifier_hash,
+        }),
+    ))
+}
+
+/// Endpoint: POST /withdraw/prove?verify=
+/// Dry run: generate the membership proof `/withdraw` would submit against the
+/// current root and return it without the on-chain call or spending the
+/// nullifier. With `verify=true` the worker also checks the Groth16 proof
+/// against the membership verification key. Signed like `/withdraw`, since
+/// the proof could be submitted by anyone.
+pub async fn prove_withdraw(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ProveQuery>,
+    SignedJson { value: req, auth }: SignedJson<WithdrawRequest>,
+) -> Result<Json<WithdrawProveResponse>, AspError> {
+    validate_withdraw_request(&req)?;
+    auth.verify_for_leaf(&state.db, req.leaf_index)?;
+    let built = build_withdraw_proof(&state, &req).await?;
+
+    let verified = match (query.verify.unwrap_or(false), &built.proof.proof) {
+        (false, _) => None,
+        (true, Some(proof)) => {
+            let mut worker = state.worker.lock().await;
+            let verified = worker
+                .verify_proof(CircuitType::Membership, proof, &built.proof.public_signals)
+                .await?;
+            Some(verified)
+        }
+        (true, None) => {
+            return Err(AspError::ProverError(
+                "worker did not return the Groth16 proof to verify".into(),
+            ))
+        }
+    };
+
+    Ok(Json(WithdrawProveResponse {
+        status: "proved".to_string(),
+        nullifier_hash: built.nullifier_hash,
+        root: built.root,
+        calldata: built.proof.calldata,
+        public_signals: built.proof.public_signals,
+        proof: built.proof.proof,
+        verified,
+    }))
+}
+
+fn validate_withdraw_request(req: &WithdrawRequest) -> Result<(), AspError> {
     validate_secret(&req.secret, "secret")?;
     validate_secret(&req.nullifier, "nullifier")?;
-    validate_decimal(&req.amount_low, "amount_low")?;
-    validate_decimal(&req.amount_high, "amount_high")?;
+    validate_u128_limb(&req.amount_low, "amount_low")?;
+    validate_u128_limb(&req.amount_high, "amount_high")?;
     validate_address(&req.token, "token")?;
     validate_address(&req.recipient, "recipient")?;
+    Ok(())
+}
 
-    tracing::info!(leaf_index = req.leaf_index, "Processing withdrawal (membership proof)");
+/// Refuse to spend a leaf inserted less than `MIN_WITHDRAW_DELAY_SECS` ago,
+/// so a withdrawal cannot be trivially linked to the deposit just before
+/// it. An unknown leaf is left for the proof step to report.
+fn check_withdraw_delay(state: &AppState, leaf_index: u32) -> Result<(), AspError> {
+    let delay = state.config.min_withdraw_delay_secs;
+    if delay == 0 {
+        return Ok(());
+    }
+    match state.db.get_commitment_age_secs(leaf_index)? {
+        Some(age) if age < delay => Err(AspError::WithdrawTooEarly {
+            remaining_secs: delay - age,
+        }),
+        _ => Ok(()),
+    }
+}
 
+/// A membership proof for a note, built against the current root.
+struct WithdrawProof {
+    nullifier_hash: String,
+    /// Merkle root (decimal) the proof is against.
+    root: String,
+    proof: ProofResult,
+}
+
+/// Check the note against the tree and the spent nullifiers, and generate
+/// its membership proof. The request must already be validated.
+async fn build_withdraw_proof(
+    state: &AppState,
+    req: &WithdrawRequest,
+) -> Result<WithdrawProof, AspError> {
     // 1. Compute commitment to verify it exists at leaf_index
+    let _slot = state.proof_slots.try_acquire()?;
     let mut worker = state.worker.lock().await;
     let commitment_result = worker
         .compute_commitment(&req.secret, &req.nullifier, &req.amount_low, &req.amount_high, &req.token)
         .await?;
-
     // 2. Verify commitment exists in our tree
     let stored = state.db.get_commitment(req.leaf_index)?;
     match &stored {
@@ -40,13 +163,16 @@ pub async fn withdraw(
         }
         None => return Err(AspError::CommitmentNotFound(req.leaf_index)),
     }
+    check_note_matches_deposit(
+        state,
+        req.leaf_index,
+        &req.token,
+        &req.amount_low,
+        &req.amount_high,
+    )?;
 
-    // 3. Check nullifier not already spent
-    if state.db.is_nullifier_spent(&commitment_result.nullifier_hash)? {
-        return Err(AspError::NullifierAlreadySpent(
-            commitment_result.nullifier_hash.clone(),
-        ));
-    }
+    // 3. Check nullifier not already spent, nor pending
+    ensure_unspent(state, &commitment_result.nullifier_hash)?;
 
     // 4. Get Merkle proof
     let proof = worker.get_proof(req.leaf_index).await?;
@@ -66,32 +192,15 @@ pub async fn withdraw(
     });
 
     // 6. Generate membership proof
-    let proof_result = worker.generate_proof("membership", inputs).await?;
+    let proof_result = worker.generate_proof(CircuitType::Membership, inputs).await?;
     drop(worker);
+    state.record_proof_time(CircuitType::Membership, proof_result.duration_ms);
+    proof_result.ensure_layout(CircuitType::Membership)?;
 
-    // 7. Submit to pool.withdraw() (which internally calls coordinator.verify_membership)
-    let tx_hash = if let Some(ref relayer) = state.relayer {
-        let relayer = relayer.lock().await;
-        relayer.verify_membership(&proof_result.calldata).await?
-    } else {
-        return Err(AspError::Internal("No relayer configured".into()));
-    };
-
-    // 8. Record nullifier as spent
-    state.db.insert_nullifier(
-        &commitment_result.nullifier_hash,
-        "membership",
-        Some(&tx_hash),
-    )?;
-
-    tracing::info!(
-        tx_hash = %tx_hash,
-        "Withdrawal confirmed"
-    );
-
-    Ok(Json(WithdrawResponse {
-        status: "confirmed".to_string(),
-        tx_hash,
+    Ok(WithdrawProof {
         nullifier_hash: commitment_result.nullifier_hash,
-    }))
+        root: proof.root,
+        proof: proof_result,
+    })
 }
+
@@ -1,26 +1,358 @@
-use axum::body::Body;
-use axum::extract::Request;
+use axum::body::{Body, HttpBody as _};
+use axum::extract::{ConnectInfo, Query, Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, Method};
 use axum::middleware::Next;
-use axum::response::Response;
-use std::time::Instant;
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::Instrument;
 
-/// Axum middleware: logs method, path, status, and duration for every request.
-pub async fn request_logger(req: Request<Body>, next: Next) -> Response {
+use crate::error::AspError;
+use crate::AppState;
+
+/// Response header carrying the request's correlation ID.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Response header carrying the audit log id of a mutating operation.
+pub const OPERATION_ID_HEADER: &str = "x-operation-id";
+
+/// Correlation ID for a single HTTP request, available to handlers as a
+/// request extension.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Axum middleware: assigns a request ID, runs the handler inside a `request`
+/// span carrying it (so worker commands and relayer txs triggered by the handler
+/// log with the same ID), and logs method, path, status, and duration.
+pub async fn request_logger(mut req: Request<Body>, next: Next) -> Response {
+    let request_id = uuid::Uuid::new_v4().to_string();
     let method = req.method().clone();
     let path = req.uri().path().to_string();
     let start = Instant::now();
 
-    let response = next.run(req).await;
+    req.extensions_mut().insert(RequestId(request_id.clone()));
+
+    let span = tracing::info_span!("request", request_id = %request_id);
+    let mut response = next.run(req).instrument(span.clone()).await;
 
     let duration = start.elapsed();
     let status = response.status().as_u16();
 
-    tracing::info!(
-        method = %method,
-        path = %path,
-        status = status,
-        duration_ms = duration.as_millis() as u64,
-    );
+    span.in_scope(|| {
+        tracing::info!(
+            method = %method,
+            path = %path,
+            status = status,
+            duration_ms = duration.as_millis() as u64,
+        );
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
 
     response
 }
+
+/// Axum middleware for `/admin/*`: requires `Authorization: Bearer <token>`
+/// matching `ADMIN_API_TOKEN`. Every request is rejected when no token is
+/// configured.
+pub async fn require_admin_token(
+    State(state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, AspError> {
+    let Some(ref expected) = state.config.admin_api_token else {
+        return Err(AspError::Unauthorized("admin API is disabled".into()));
+    };
+
+    let provided = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+
+    match provided {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => {
+            Ok(next.run(req).await)
+        }
+        _ => Err(AspError::Unauthorized("invalid or missing admin token".into())),
+    }
+}
+
+/// Axum middleware: rejects requests from banned IPs with 403 and, with
+/// `AUTO_BAN_THRESHOLD` set, bans an IP once it exceeds that many requests in
+/// `AUTO_BAN_WINDOW_SECS`. Requests with no peer address (only possible when
+/// the router is served without [`crate::server`]) pass.
+pub async fn reject_banned_source(
+    State(state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, AspError> {
+    let Some(ip) = client_ip(&req, state.config.trust_forwarded_for) else {
+        return Ok(next.run(req).await);
+    };
+    if state.bans.is_banned(&ip) {
+        return Err(AspError::Forbidden(format!("source {ip} is banned")));
+    }
+
+    let threshold = state.config.auto_ban_threshold;
+    let window_secs = state.config.auto_ban_window_secs;
+    let window = Duration::from_secs(window_secs);
+    if threshold > 0 && state.bans.record(ip, threshold, window, Instant::now()) {
+        let reason = format!("more than {threshold} requests in {window_secs}s");
+        // Ban for this process even if it cannot be persisted
+        if let Err(e) = state.db.ban_source(&ip.to_string(), &reason, true) {
+            tracing::error!(ip = %ip, error = %e, "Failed to persist automatic ban");
+        }
+        state.bans.insert(ip);
+        tracing::warn!(ip = %ip, reason = %reason, "Source banned automatically");
+        return Err(AspError::Forbidden(format!("source {ip} is banned")));
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// The client's IP. With `trust_forwarded_for`, the last `X-Forwarded-For`
+/// entry, which the proxy appended; earlier ones are written by the client.
+/// Otherwise, or when that header is missing or malformed, the connection's
+/// peer address.
+fn client_ip(req: &Request<Body>, trust_forwarded_for: bool) -> Option<IpAddr> {
+    let forwarded = req
+        .headers()
+        .get("x-forwarded-for")
+        .filter(|_| trust_forwarded_for)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.rsplit(',').next())
+        .and_then(|ip| ip.trim().parse().ok());
+    forwarded.or_else(|| {
+        req.extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+    })
+}
+
+/// Axum middleware: a POST with a body must declare `Content-Type:
+/// application/json` (or `application/x-ndjson`, for `/admin/import`).
+/// Bodiless POSTs (e.g. `/admin/resync`) pass.
+pub async fn require_json_content_type(
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, AspError> {
+    let has_body = req.body().size_hint().exact() != Some(0);
+    if req.method() == Method::POST && has_body && !is_json_content_type(req.headers()) {
+        return Err(AspError::UnsupportedMediaType(
+            "expected Content-Type: application/json".into(),
+        ));
+    }
+    Ok(next.run(req).await)
+}
+
+fn is_json_content_type(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(str::trim)
+        .is_some_and(|mime| {
+            mime.eq_ignore_ascii_case("application/json")
+                || mime.eq_ignore_ascii_case("application/x-ndjson")
+        })
+}
+
+/// `?pretty=` on any route.
+#[derive(Debug, Deserialize)]
+struct PrettyQuery {
+    pretty: Option<bool>,
+}
+
+/// Axum middleware: re-serialize `application/json` responses with
+/// indentation when `PRETTY_JSON` is set or the request passes
+/// `?pretty=true`. The body is parsed into a `serde_json::Value`, so object
+/// keys come out sorted. Other content types (the `/events` stream, NDJSON
+/// exports) pass through unbuffered.
+pub async fn pretty_json(
+    State(state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let pretty = state.config.pretty_json
+        || Query::<PrettyQuery>::try_from_uri(req.uri())
+            .ok()
+            .and_then(|Query(q)| q.pretty)
+            .unwrap_or(false);
+    let response = next.run(req).await;
+    if !pretty || !is_json_response(response.headers()) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return AspError::Internal(format!("failed to read response body: {e}"))
+                .into_response()
+        }
+    };
+    let body = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(value) => serde_json::to_string_pretty(&value)
+            .map(Body::from)
+            .unwrap_or_else(|_| Body::from(bytes)),
+        Err(_) => Body::from(bytes),
+    };
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, body)
+}
+
+fn is_json_response(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/json"))
+}
+
+/// Compare two byte strings without short-circuiting on the first mismatch.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// What a mutating handler did on-chain and in the tree, attached to its
+/// response as an extension for [`record_operation`].
+#[derive(Debug, Clone, Default)]
+pub struct OperationOutcome {
+    pub tx_hash: Option<String>,
+    /// Merkle root submission that followed `tx_hash`, if any.
+    pub root_tx: Option<String>,
+    pub leaf_indices: Vec<u32>,
+}
+
+/// Axum middleware for endpoints that submit transactions: runs the rest of
+/// the request in its own task, so neither a client disconnect nor any outer
+/// deadline can cancel it between an on-chain call and the database writes
+/// that record it.
+pub async fn run_to_completion(req: Request<Body>, next: Next) -> Response {
+    match tokio::spawn(next.run(req).in_current_span()).await {
+        Ok(response) => response,
+        Err(e) => AspError::Internal(format!("Request task failed: {e}")).into_response(),
+    }
+}
+
+/// Axum middleware for mutating endpoints: writes one `operations` row per
+/// request with the SHA-256 of the raw request body (never the body itself,
+/// which carries note secrets) and returns its id in `x-operation-id`.
+///
+/// The status is `confirmed` on success, `rejected` for client errors,
+/// `reverted` when a submitted tx reverted, and `failed` otherwise. The
+/// request is also counted in [`AppState::operations`] as `ok` or `err`.
+pub async fn record_operation(
+    State(state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, AspError> {
+    let kind = req.uri().path().trim_start_matches('/').to_string();
+    let (parts, body) = req.into_parts();
+    let limit = state.config.max_body_bytes;
+    let bytes = axum::body::to_bytes(body, limit).await.map_err(|e| {
+        AspError::PayloadTooLarge(format!("request body over {limit} bytes or unreadable: {e}"))
+    })?;
+    let request_hash = hex::encode(Sha256::digest(&bytes));
+
+    let mut response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+
+    let outcome = response
+        .extensions()
+        .get::<OperationOutcome>()
+        .cloned()
+        .unwrap_or_default();
+    let status = response.status();
+    state.operations.record(&kind, status.is_success());
+    let status = if status.is_success() {
+        "confirmed"
+    } else if status.is_client_error() {
+        "rejected"
+    } else if outcome.tx_hash.is_some() {
+        "reverted"
+    } else {
+        "failed"
+    };
+
+    // The handler's work is already done (possibly on-chain), so a failed
+    // audit write is logged rather than turned into an error response.
+    match state.db.insert_operation(
+        &kind,
+        &request_hash,
+        outcome.tx_hash.as_deref(),
+        outcome.root_tx.as_deref(),
+        &outcome.leaf_indices,
+        status,
+    ) {
+        Ok(id) => {
+            if let Ok(value) = HeaderValue::from_str(&id.to_string()) {
+                response.headers_mut().insert(OPERATION_ID_HEADER, value);
+            }
+        }
+        Err(e) => tracing::error!(kind = %kind, error = %e, "Failed to record operation"),
+    }
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use axum::routing::post;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[test]
+    fn client_ip_uses_proxy_appended_entry_or_peer() {
+        let peer: SocketAddr = "192.0.2.1:4000".parse().unwrap();
+        let request = |forwarded: Option<&str>| {
+            let mut builder = Request::get("/");
+            if let Some(forwarded) = forwarded {
+                builder = builder.header("x-forwarded-for", forwarded);
+            }
+            let mut req = builder.body(Body::empty()).unwrap();
+            req.extensions_mut().insert(ConnectInfo(peer));
+            req
+        };
+
+        let forged = request(Some("198.51.100.1, 203.0.113.7"));
+        assert_eq!(client_ip(&forged, true), Some("203.0.113.7".parse().unwrap()));
+        assert_eq!(client_ip(&forged, false), Some(peer.ip()));
+        assert_eq!(client_ip(&request(None), true), Some(peer.ip()));
+        assert_eq!(client_ip(&request(Some("garbage")), true), Some(peer.ip()));
+    }
+
+    #[tokio::test]
+    async fn dropped_request_still_runs_to_completion() {
+        let finished = Arc::new(AtomicBool::new(false));
+        let flag = finished.clone();
+        let app = Router::new()
+            .route(
+                "/submit",
+                post(move || async move {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    flag.store(true, Ordering::SeqCst);
+                }),
+            )
+            .route_layer(axum::middleware::from_fn(run_to_completion));
+
+        let request = Request::post("/submit").body(Body::empty()).unwrap();
+        // Give up on the response long before the handler is done
+        let cut_short =
+            tokio::time::timeout(Duration::from_millis(5), app.oneshot(request)).await;
+        assert!(cut_short.is_err());
+        assert!(!finished.load(Ordering::SeqCst));
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(finished.load(Ordering::SeqCst));
+    }
+}
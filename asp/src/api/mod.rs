@@ -1,5 +1,11 @@
+pub mod bans;
+pub mod cors;
+pub mod counters;
 pub mod handlers;
 pub mod middleware;
+pub mod openapi;
 pub mod routes;
+pub mod signature;
+pub mod status_cache;
 pub mod types;
 pub mod validation;
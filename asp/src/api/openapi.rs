@@ -0,0 +1,601 @@
+//! OpenAPI 3 description of the HTTP API, served at `GET /openapi.json`.
+//!
+//! Request, response and query schemas are derived from the structs in
+//! [`crate::api::types`] (and the few handler-local ones), so they follow
+//! the code; only the route table in [`document`] is written by hand and
+//! must be kept in step with `routes.rs`.
+
+use schemars::generate::{SchemaGenerator, SchemaSettings};
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+
+use crate::api::handlers::sync::{SyncCommitmentsRequest, SyncCommitmentsResponse};
+use crate::api::types::*;
+use crate::error::ERROR_CODES;
+use crate::sync::events::SyncReport;
+
+/// Accumulates paths and the component schemas they reference.
+struct Spec {
+    generator: SchemaGenerator,
+    paths: Map<String, Value>,
+}
+
+/// One documented operation.
+struct Route {
+    method: &'static str,
+    path: &'static str,
+    summary: &'static str,
+    query: Vec<Value>,
+    body: Option<Value>,
+    response: Value,
+    admin: bool,
+}
+
+impl Route {
+    fn new(method: &'static str, path: &'static str, summary: &'static str) -> Self {
+        Route {
+            method,
+            path,
+            summary,
+            query: Vec::new(),
+            body: None,
+            response: json!({"description": "OK"}),
+            admin: false,
+        }
+    }
+}
+
+impl Spec {
+    fn new() -> Self {
+        Spec {
+            generator: SchemaSettings::openapi3().into_generator(),
+            paths: Map::new(),
+        }
+    }
+
+    fn schema<T: JsonSchema>(&mut self) -> Value {
+        self.generator.subschema_for::<T>().to_value()
+    }
+
+    fn body<T: JsonSchema>(&mut self) -> Option<Value> {
+        Some(json!({
+            "required": true,
+            "content": {"application/json": {"schema": self.schema::<T>()}}
+        }))
+    }
+
+    fn json<T: JsonSchema>(&mut self) -> Value {
+        json!({
+            "description": "OK",
+            "content": {"application/json": {"schema": self.schema::<T>()}}
+        })
+    }
+
+    /// Query parameters from the fields of a `Query<T>` struct.
+    fn query<T: JsonSchema>(&mut self) -> Vec<Value> {
+        let schema = self.generator.root_schema_for::<T>();
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|names| names.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+            return Vec::new();
+        };
+        properties
+            .iter()
+            .map(|(name, property)| {
+                let mut param = json!({
+                    "name": name,
+                    "in": "query",
+                    "required": required.contains(&name.as_str()),
+                    "schema": property,
+                });
+                if let Some(description) = property.get("description") {
+                    param["description"] = description.clone();
+                }
+                param
+            })
+            .collect()
+    }
+
+    fn add(&mut self, route: Route) {
+        let mut parameters = path_parameters(route.path);
+        parameters.extend(route.query);
+
+        let mut operation = json!({
+            "summary": route.summary,
+            "responses": {
+                "200": route.response,
+                "default": {
+                    "description": "Error",
+                    "content": {
+                        "application/json": {"schema": {"$ref": "#/components/schemas/Error"}}
+                    }
+                }
+            }
+        });
+        if !parameters.is_empty() {
+            operation["parameters"] = Value::Array(parameters);
+        }
+        if let Some(body) = route.body {
+            operation["requestBody"] = body;
+        }
+        if route.admin {
+            operation["security"] = json!([{"adminToken": []}]);
+        }
+
+        let item = self
+            .paths
+            .entry(route.path)
+            .or_insert_with(|| Value::Object(Map::new()));
+        item[route.method] = operation;
+    }
+
+    fn finish(mut self) -> Value {
+        let mut schemas = self.generator.take_definitions(true);
+        schemas.insert(
+            "Error".into(),
+            json!({
+                "type": "object",
+                "required": ["error", "code", "status"],
+                "properties": {
+                    "error": {"type": "string", "description": "Human-readable message"},
+                    "code": {"type": "string", "enum": ERROR_CODES},
+                    "status": {"type": "integer", "description": "HTTP status code"},
+                    "outcome": {"type": "string", "enum": ["failed", "reverted"]},
+                    "tx_hash": {"type": "string"},
+                    "revert_reason": {"type": "string"}
+                }
+            }),
+        );
+
+        json!({
+            "openapi": "3.0.3",
+            "info": {
+                "title": "Zylith ASP",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "paths": self.paths,
+            "components": {
+                "schemas": schemas,
+                "securitySchemes": {
+                    "adminToken": {"type": "http", "scheme": "bearer"}
+                }
+            }
+        })
+    }
+}
+
+/// `{name}` segments of `path` as required path parameters.
+fn path_parameters(path: &str) -> Vec<Value> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| {
+            let schema = match name {
+                "leaf_index" => json!({"type": "integer", "format": "uint32", "minimum": 0}),
+                "id" => json!({"type": "integer", "format": "int64"}),
+                _ => json!({"type": "string"}),
+            };
+            json!({"name": name, "in": "path", "required": true, "schema": schema})
+        })
+        .collect()
+}
+
+/// The OpenAPI document for every route in `routes.rs`.
+pub fn document() -> Value {
+    let mut spec = Spec::new();
+
+    let body = spec.body::<DepositRequest>();
+    let response = spec.json::<DepositResponse>();
+    spec.add(Route {
+        body,
+        response,
+        ..Route::new(
+            "post",
+            "/deposit",
+            "Register a commitment in the Merkle tree",
+        )
+    });
+    let response = spec.json::<CommitmentStatusResponse>();
+    spec.add(Route {
+        response,
+        ..Route::new(
+            "get",
+            "/commitment/{commitment}",
+            "Deposit status of a commitment",
+        )
+    });
+
+    let body = spec.body::<WithdrawRequest>();
+    let response = spec.json::<WithdrawResponse>();
+    spec.add(Route {
+        body,
+        response,
+        ..Route::new("post", "/withdraw", "Prove membership and withdraw a note")
+    });
+    let body = spec.body::<SwapRequest>();
+    let response = spec.json::<SwapResponse>();
+    spec.add(Route {
+        body,
+        response,
+        ..Route::new("post", "/swap", "Execute a shielded swap")
+    });
+    let body = spec.body::<MintRequest>();
+    let response = spec.json::<MintResponse>();
+    spec.add(Route {
+        body,
+        response,
+        ..Route::new("post", "/mint", "Provide shielded liquidity")
+    });
+    let body = spec.body::<BurnRequest>();
+    let response = spec.json::<BurnResponse>();
+    spec.add(Route {
+        body,
+        response,
+        ..Route::new("post", "/burn", "Remove shielded liquidity")
+    });
+
+    let body = spec.body::<SimulateSwapRequest>();
+    let response = spec.json::<SimulateSwapResponse>();
+    spec.add(Route {
+        body,
+        response,
+        ..Route::new(
+            "post",
+            "/simulate-swap",
+            "Quote a swap without executing it",
+        )
+    });
+    let query = spec.query::<ProveQuery>();
+    let body = spec.body::<WithdrawRequest>();
+    let response = spec.json::<WithdrawProveResponse>();
+    spec.add(Route {
+        query,
+        body,
+        response,
+        ..Route::new(
+            "post",
+            "/withdraw/prove",
+            "Generate a membership proof without submitting it",
+        )
+    });
+    let body = spec.body::<SwapRequest>();
+    let response = spec.json::<DryRunResponse>();
+    spec.add(Route {
+        body,
+        response,
+        ..Route::new(
+            "post",
+            "/swap/prove",
+            "Generate a swap proof without submitting it",
+        )
+    });
+    let body = spec.body::<MintRequest>();
+    let response = spec.json::<DryRunResponse>();
+    spec.add(Route {
+        body,
+        response,
+        ..Route::new(
+            "post",
+            "/mint/prove",
+            "Generate a mint proof without submitting it",
+        )
+    });
+    let body = spec.body::<BurnRequest>();
+    let response = spec.json::<DryRunResponse>();
+    spec.add(Route {
+        body,
+        response,
+        ..Route::new(
+            "post",
+            "/burn/prove",
+            "Generate a burn proof without submitting it",
+        )
+    });
+
+    let body = spec.body::<ComputeCommitmentRequest>();
+    let response = spec.json::<CommitmentResponse>();
+    spec.add(Route {
+        body,
+        response,
+        ..Route::new(
+            "post",
+            "/compute-commitment",
+            "Note commitment and nullifier hash",
+        )
+    });
+    let body = spec.body::<ComputePositionCommitmentRequest>();
+    let response = spec.json::<CommitmentResponse>();
+    spec.add(Route {
+        body,
+        response,
+        ..Route::new(
+            "post",
+            "/compute-position-commitment",
+            "Position commitment from signed ticks",
+        )
+    });
+
+    let query = spec.query::<TreeRootQuery>();
+    let response = spec.json::<TreeRootResponse>();
+    spec.add(Route {
+        query,
+        response,
+        ..Route::new(
+            "get",
+            "/tree/root",
+            "Current Merkle root and leaf count, or the root at a past leaf count",
+        )
+    });
+    let query = spec.query::<TreeRootsQuery>();
+    let response = spec.json::<TreeRootsResponse>();
+    spec.add(Route {
+        query,
+        response,
+        ..Route::new(
+            "get",
+            "/tree/roots",
+            "Recent roots and whether the coordinator still accepts each",
+        )
+    });
+    let query = spec.query::<TreePathQuery>();
+    let response = spec.json::<TreeProofResponse>();
+    spec.add(Route {
+        query,
+        response,
+        ..Route::new(
+            "get",
+            "/tree/path/{leaf_index}",
+            "Merkle inclusion proof for a leaf",
+        )
+    });
+    let query = spec.query::<TreePathFormatQuery>();
+    let response = spec.json::<TreeProofResponse>();
+    spec.add(Route {
+        query,
+        response,
+        ..Route::new(
+            "get",
+            "/tree/path-by-commitment/{commitment}",
+            "Merkle inclusion proof, looked up by commitment",
+        )
+    });
+
+    let response = spec.json::<NullifierResponse>();
+    spec.add(Route {
+        response,
+        ..Route::new(
+            "get",
+            "/nullifier/{hash}",
+            "Whether a nullifier has been spent",
+        )
+    });
+    let query = spec.query::<NullifierListQuery>();
+    let response = spec.json::<NullifierListResponse>();
+    spec.add(Route {
+        query,
+        response,
+        ..Route::new("get", "/nullifiers", "Page through spent nullifiers")
+    });
+    let body = spec.body::<NullifierCheckRequest>();
+    let response = spec.json::<NullifierCheckResponse>();
+    spec.add(Route {
+        body,
+        response,
+        ..Route::new("post", "/nullifiers/check", "Batch spent check")
+    });
+
+    let query = spec.query::<OperationListQuery>();
+    let response = spec.json::<OperationListResponse>();
+    spec.add(Route {
+        query,
+        response,
+        ..Route::new(
+            "get",
+            "/operations",
+            "Audit log of mutating calls, newest first",
+        )
+    });
+    let response = spec.json::<OperationResponse>();
+    spec.add(Route {
+        response,
+        ..Route::new("get", "/operations/{id}", "One audit log entry")
+    });
+    let response = spec.json::<TxLookupResponse>();
+    spec.add(Route {
+        response,
+        ..Route::new(
+            "get",
+            "/tx/{tx_hash}",
+            "Audit log entry a transaction belongs to",
+        )
+    });
+    let response = spec.json::<TxCommitmentsResponse>();
+    spec.add(Route {
+        response,
+        ..Route::new(
+            "get",
+            "/tx/{tx_hash}/commitments",
+            "Leaves a transaction inserted",
+        )
+    });
+
+    let body = spec.body::<SyncCommitmentsRequest>();
+    let response = spec.json::<SyncCommitmentsResponse>();
+    spec.add(Route {
+        body,
+        response,
+        ..Route::new(
+            "post",
+            "/sync-commitments",
+            "Leaf indices of known commitments",
+        )
+    });
+    let body = spec.body::<NoteScanRequest>();
+    let response = spec.json::<NoteScanResponse>();
+    spec.add(Route {
+        body,
+        response,
+        ..Route::new("post", "/notes/scan", "Batch lookup of notes")
+    });
+
+    let query = spec.query::<EventStreamQuery>();
+    spec.add(Route {
+        query,
+        response: json!({
+            "description": "Server-Sent Events: `commitment_added` and `nullifier_spent`",
+            "content": {"text/event-stream": {"schema": {"type": "string"}}}
+        }),
+        ..Route::new("get", "/events", "Live tree updates")
+    });
+    let response = spec.json::<StatusResponse>();
+    spec.add(Route {
+        response,
+        ..Route::new("get", "/status", "Health, tree, sync and worker status")
+    });
+    spec.add(Route {
+        response: json!({
+            "description": "`pong`",
+            "content": {"text/plain": {"schema": {"type": "string"}}}
+        }),
+        ..Route::new("get", "/ping", "Liveness probe with no dependencies")
+    });
+    spec.add(Route {
+        response: json!({"description": "This document"}),
+        ..Route::new("get", "/openapi.json", "OpenAPI description of this API")
+    });
+
+    let query = spec.query::<ResyncQuery>();
+    let response = spec.json::<ResyncResponse>();
+    spec.add(Route {
+        query,
+        response,
+        admin: true,
+        ..Route::new("post", "/admin/resync", "Rewind event sync")
+    });
+    let response = spec.json::<SyncReport>();
+    spec.add(Route {
+        response,
+        admin: true,
+        ..Route::new("post", "/admin/sync-now", "Run one event sync cycle now")
+    });
+    let response = spec.json::<VacuumResponse>();
+    spec.add(Route {
+        response,
+        admin: true,
+        ..Route::new(
+            "post",
+            "/admin/vacuum",
+            "VACUUM the database and truncate the WAL",
+        )
+    });
+
+    let query = spec.query::<VerifyTreeQuery>();
+    let response = spec.json::<VerifyTreeResponse>();
+    spec.add(Route {
+        query,
+        response,
+        admin: true,
+        ..Route::new("post", "/admin/verify-tree", "Check the worker tree against the database")
+    });
+    let response = spec.json::<ResubmitRootResponse>();
+    spec.add(Route {
+        response,
+        admin: true,
+        ..Route::new(
+            "post",
+            "/admin/resubmit-root",
+            "Submit the worker's root on-chain again",
+        )
+    });
+    let response = spec.json::<ProofStatsResponse>();
+    spec.add(Route {
+        response,
+        admin: true,
+        ..Route::new("get", "/admin/proof-stats", "Proof generation times per circuit")
+    });
+    // NDJSON bodies: one `ExportedCommitment` object per line
+    let line = spec.schema::<ExportedCommitment>();
+    spec.add(Route {
+        response: json!({
+            "description": "NDJSON, one commitment per line in leaf order",
+            "content": {"application/x-ndjson": {"schema": line.clone()}}
+        }),
+        admin: true,
+        ..Route::new("get", "/admin/export", "Stream every commitment for backup")
+    });
+    let response = spec.json::<ImportResponse>();
+    spec.add(Route {
+        body: Some(json!({
+            "required": true,
+            "content": {"application/x-ndjson": {"schema": line}}
+        })),
+        response,
+        admin: true,
+        ..Route::new("post", "/admin/import", "Load commitments from an export")
+    });
+    let body = spec.body::<BanRequest>();
+    let response = spec.json::<BanResponse>();
+    spec.add(Route {
+        body,
+        response,
+        admin: true,
+        ..Route::new("post", "/admin/ban", "Ban a client IP")
+    });
+    let response = spec.json::<UnbanResponse>();
+    spec.add(Route {
+        response,
+        admin: true,
+        ..Route::new("delete", "/admin/ban/{ip}", "Lift a ban on a client IP")
+    });
+
+    spec.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document_lists_every_route() {
+        let doc = document();
+        assert_eq!(doc["openapi"], "3.0.3");
+
+        let paths = doc["paths"].as_object().unwrap();
+        assert_eq!(paths.len(), 40);
+        for (path, item) in paths {
+            for (method, operation) in item.as_object().unwrap() {
+                assert!(
+                    ["get", "post", "delete"].contains(&method.as_str()),
+                    "{method} {path}"
+                );
+                assert!(operation["responses"]["200"].is_object(), "{method} {path}");
+            }
+        }
+        assert!(doc["paths"]["/tree/path/{leaf_index}"]["get"]["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|p| p["name"] == "root" && p["in"] == "query"));
+    }
+
+    #[test]
+    fn referenced_schemas_are_defined() {
+        let doc = document();
+        let text = doc.to_string();
+        let schemas = doc["components"]["schemas"].as_object().unwrap();
+        for reference in text.split("\"#/components/schemas/").skip(1) {
+            let name = &reference[..reference.find('"').unwrap()];
+            assert!(schemas.contains_key(name), "missing schema {name}");
+        }
+        assert!(schemas.contains_key("DepositRequest"));
+        assert_eq!(
+            schemas["Error"]["properties"]["code"]["enum"]
+                .as_array()
+                .unwrap()
+                .len(),
+            ERROR_CODES.len()
+        );
+    }
+}
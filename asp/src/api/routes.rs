@@ -1,39 +1,169 @@
 use std::sync::Arc;
+use std::time::Duration;
 
+use axum::extract::DefaultBodyLimit;
+use axum::http::StatusCode;
 use axum::middleware;
-use axum::routing::{get, post};
+use axum::routing::{delete, get, post};
 use axum::Router;
-use tower_http::cors::CorsLayer;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::timeout::TimeoutLayer;
 
 use crate::AppState;
 
+use super::cors::{cors_layer, reject_disallowed_origin};
 use super::handlers;
-use super::middleware::request_logger;
+use super::middleware::{
+    pretty_json, record_operation, reject_banned_source, request_logger, require_admin_token,
+    require_json_content_type, run_to_completion,
+};
+
+/// Responses smaller than this are sent uncompressed.
+const COMPRESSION_MIN_BYTES: u16 = 1024;
+
+/// Answer 504 once a request has run for `secs`. The handler future is
+/// dropped, which cancels any worker command it was waiting on.
+fn timeout_layer(secs: u64) -> TimeoutLayer {
+    TimeoutLayer::with_status_code(StatusCode::GATEWAY_TIMEOUT, Duration::from_secs(secs))
+}
 
 /// Core routes shared by production and test routers.
 fn base_router(state: Arc<AppState>) -> Router {
     Router::new()
-        .route("/deposit", post(handlers::deposit::deposit))
-        .route("/withdraw", post(handlers::withdraw::withdraw))
-        .route("/swap", post(handlers::swap::shielded_swap))
-        .route("/mint", post(handlers::mint::shielded_mint))
-        .route("/burn", post(handlers::burn::shielded_burn))
+        .route(
+            "/commitment/{commitment}",
+            get(handlers::deposit::get_commitment_status),
+        )
+        .route("/simulate-swap", post(handlers::swap::simulate_swap))
+        // Commitment derivation
+        .route(
+            "/compute-commitment",
+            post(handlers::commitment::compute_commitment),
+        )
+        .route(
+            "/compute-position-commitment",
+            post(handlers::commitment::compute_position_commitment),
+        )
         // Tree queries
         .route("/tree/root", get(handlers::tree::get_root))
+        .route("/tree/roots", get(handlers::tree::get_roots))
         .route("/tree/path/{leaf_index}", get(handlers::tree::get_path))
+        .route(
+            "/tree/path-by-commitment/{commitment}",
+            get(handlers::tree::get_path_by_commitment),
+        )
         // Nullifier queries
         .route(
             "/nullifier/{hash}",
             get(handlers::nullifier::get_nullifier),
         )
+        .route("/nullifiers", get(handlers::nullifier::list_nullifiers))
+        .route(
+            "/nullifiers/check",
+            post(handlers::nullifier::check_nullifiers),
+        )
+        // Audit log
+        .route("/operations", get(handlers::operations::list_operations))
+        .route(
+            "/operations/{id}",
+            get(handlers::operations::get_operation),
+        )
+        .route("/tx/{tx_hash}", get(handlers::operations::get_operation_by_tx))
+        .route(
+            "/tx/{tx_hash}/commitments",
+            get(handlers::operations::get_tx_commitments),
+        )
         // Sync
         .route("/sync-commitments", post(handlers::sync::sync_commitments))
+        .route("/notes/scan", post(handlers::notes::scan_notes))
+        // Live updates
+        .route("/events", get(handlers::stream::events))
         // Status
+        .route("/ping", get(handlers::status::ping))
         .route("/status", get(handlers::status::get_status))
+        .route("/openapi.json", get(handlers::openapi::get_openapi))
+        // Routes above only; proving routes get a longer deadline
+        .route_layer(timeout_layer(state.config.request_timeout_secs))
+        .merge(proving_router(state.clone()))
+        // Routes above only, so operators can always reach /admin
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            reject_banned_source,
+        ))
+        .nest("/admin", admin_router(state.clone()))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            reject_disallowed_origin,
+        ))
+        .layer(middleware::from_fn(require_json_content_type))
+        .layer(middleware::from_fn_with_state(state.clone(), pretty_json))
+        // Enforce MAX_BODY_BYTES in place of axum's fixed 2 MB extractor limit
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(state.config.max_body_bytes))
+        .layer(compression_layer())
         .with_state(state)
 }
 
-/// Production router with rate limiting, logging, and CORS.
+/// Routes that generate proofs or wait on-chain. The submitting ones apply
+/// `PROOF_REQUEST_TIMEOUT_SECS` to proof generation only (see
+/// [`AppState::within_proof_deadline`]) and always run to completion once
+/// they start submitting; the dry runs are cut off at the deadline as a whole.
+fn proving_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/deposit", post(handlers::deposit::deposit))
+        .route("/withdraw", post(handlers::withdraw::withdraw))
+        .route("/swap", post(handlers::swap::shielded_swap))
+        .route("/mint", post(handlers::mint::shielded_mint))
+        .route("/burn", post(handlers::burn::shielded_burn))
+        // Audit every mutating call (routes above this layer only)
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            record_operation,
+        ))
+        .route_layer(middleware::from_fn(run_to_completion))
+        // Dry-run proving (no on-chain submission, no state changes)
+        .merge(
+            Router::new()
+                .route("/withdraw/prove", post(handlers::withdraw::prove_withdraw))
+                .route("/swap/prove", post(handlers::swap::prove_swap))
+                .route("/mint/prove", post(handlers::mint::prove_mint))
+                .route("/burn/prove", post(handlers::burn::prove_burn))
+                .route_layer(timeout_layer(state.config.proof_request_timeout_secs)),
+        )
+}
+
+/// gzip/deflate per `Accept-Encoding`, for responses of at least
+/// `COMPRESSION_MIN_BYTES`. The `/events` stream is left alone so each event
+/// reaches the client as soon as it is written.
+fn compression_layer() -> CompressionLayer<impl Predicate> {
+    let predicate = SizeAbove::new(COMPRESSION_MIN_BYTES)
+        .and(NotForContentType::GRPC)
+        .and(NotForContentType::IMAGES)
+        .and(NotForContentType::SSE);
+    CompressionLayer::new().compress_when(predicate)
+}
+
+/// Operator-only routes, gated by `ADMIN_API_TOKEN`. They have no deadline:
+/// a rebuild or import cut short would leave the worker tree behind the
+/// database.
+fn admin_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/resync", post(handlers::admin::resync))
+        .route("/sync-now", post(handlers::admin::sync_now))
+        .route("/vacuum", post(handlers::admin::vacuum))
+        .route("/verify-tree", post(handlers::admin::verify_tree))
+        .route("/resubmit-root", post(handlers::admin::resubmit_root))
+        .route("/proof-stats", get(handlers::admin::proof_stats))
+        .route("/export", get(handlers::admin::export))
+        .route("/import", post(handlers::admin::import))
+        .route("/ban", post(handlers::admin::ban))
+        .route("/ban/{ip}", delete(handlers::admin::unban))
+        .route_layer(middleware::from_fn_with_state(state, require_admin_token))
+}
+
+/// Production router with rate limiting, logging, and CORS (per `ALLOWED_ORIGINS`).
 pub fn create_router(state: Arc<AppState>) -> Router {
     // TODO: Re-enable rate limiting after fixing IP extraction for proof-only mode
     // The rate limiter requires extracting client IP from socket, which may not work
@@ -46,17 +176,19 @@ pub fn create_router(state: Arc<AppState>) -> Router {
     //         .expect("Failed to build rate limiter config"),
     // );
 
+    let cors = cors_layer(&state.config.allowed_origins);
     base_router(state)
         .layer(middleware::from_fn(request_logger))
         // .layer(GovernorLayer {
         //     config: governor_conf,
         // })
-        .layer(CorsLayer::permissive())
+        .layer(cors)
 }
 
 /// Test router without rate limiting (no real socket for IP extraction).
 pub fn create_test_router(state: Arc<AppState>) -> Router {
+    let cors = cors_layer(&state.config.allowed_origins);
     base_router(state)
         .layer(middleware::from_fn(request_logger))
-        .layer(CorsLayer::permissive())
+        .layer(cors)
 }
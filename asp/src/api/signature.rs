@@ -0,0 +1,230 @@
+//! Optional per-note request signatures.
+//!
+//! A depositor may commit an Ed25519 public key alongside a note. Spends of
+//! that note (withdraw, swap, mint, burn) must then carry a `signature` field:
+//! an Ed25519 signature over the canonical JSON of the request body with the
+//! `signature` field removed. Canonical JSON sorts object keys recursively and
+//! has no insignificant whitespace. Notes deposited without a key need no
+//! signature.
+
+use axum::extract::{FromRequest, Request};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::db::Database;
+use crate::error::AspError;
+
+/// Body field carrying the hex-encoded request signature.
+pub const SIGNATURE_FIELD: &str = "signature";
+
+/// JSON extractor that splits off the optional `signature` field and keeps
+/// the canonical bytes it must sign.
+#[derive(Debug)]
+pub struct SignedJson<T> {
+    pub value: T,
+    pub auth: RequestSignature,
+}
+
+/// Signature (if any) and signed message for one request.
+#[derive(Debug, Clone)]
+pub struct RequestSignature {
+    message: Vec<u8>,
+    signature: Option<Signature>,
+}
+
+impl<T, S> FromRequest<S> for SignedJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(mut body) = Json::<Value>::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        let signature = match body.as_object_mut().and_then(|o| o.remove(SIGNATURE_FIELD)) {
+            None | Some(Value::Null) => None,
+            Some(Value::String(hex)) => Some(parse_signature(&hex).map_err(|e| e.into_response())?),
+            Some(_) => {
+                return Err(
+                    AspError::InvalidInput("signature must be a hex string".into()).into_response(),
+                )
+            }
+        };
+        let message = canonical_json(&body).into_bytes();
+
+        let value = T::deserialize(body).map_err(|e| {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Failed to deserialize the JSON body into the target type: {e}"),
+            )
+                .into_response()
+        })?;
+
+        Ok(SignedJson {
+            value,
+            auth: RequestSignature { message, signature },
+        })
+    }
+}
+
+impl RequestSignature {
+    /// Check this request against the key committed for `leaf_index`. Leaves
+    /// without a key (or unknown leaves) pass; the caller's own lookup
+    /// reports those.
+    pub fn verify_for_leaf(&self, db: &Database, leaf_index: u32) -> Result<(), AspError> {
+        let Some(pubkey) = db.get_auth_pubkey(leaf_index)? else {
+            return Ok(());
+        };
+        let key = parse_auth_pubkey(&pubkey, "auth_pubkey")
+            .map_err(|e| AspError::Internal(format!("Stored key for leaf {leaf_index}: {e}")))?;
+
+        let signature = self.signature.as_ref().ok_or_else(|| {
+            AspError::Unauthorized(format!("Leaf {leaf_index} requires a request signature"))
+        })?;
+        key.verify_strict(&self.message, signature).map_err(|_| {
+            AspError::Unauthorized(format!("Invalid request signature for leaf {leaf_index}"))
+        })
+    }
+}
+
+/// Parse a hex-encoded (optionally `0x`-prefixed) Ed25519 public key.
+pub fn parse_auth_pubkey(value: &str, field_name: &str) -> Result<VerifyingKey, AspError> {
+    let bytes: [u8; 32] = decode_hex(value)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| AspError::InvalidInput(format!("{field_name} must be 32 bytes of hex")))?;
+    VerifyingKey::from_bytes(&bytes).map_err(|_| {
+        AspError::InvalidInput(format!("{field_name} is not a valid Ed25519 public key"))
+    })
+}
+
+fn parse_signature(value: &str) -> Result<Signature, AspError> {
+    let bytes: [u8; 64] = decode_hex(value)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| AspError::InvalidInput("signature must be 64 bytes of hex".into()))?;
+    Ok(Signature::from_bytes(&bytes))
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    let stripped = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    hex::decode(stripped).ok()
+}
+
+/// Serialize `value` with object keys sorted at every level and no
+/// whitespace — the exact bytes a client signs.
+pub fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            let fields: Vec<String> = entries
+                .into_iter()
+                .map(|(k, v)| format!("{}:{}", Value::String(k.clone()), canonical_json(v)))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use serde_json::json;
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7u8; 32])
+    }
+
+    fn db_with_key(key: Option<&SigningKey>) -> Database {
+        let db = Database::new(":memory:").unwrap();
+        db.run_migrations().unwrap();
+        db.insert_commitment(0, "123", None).unwrap();
+        if let Some(key) = key {
+            let pubkey = hex::encode(key.verifying_key().to_bytes());
+            db.set_auth_pubkey(0, &format!("0x{pubkey}")).unwrap();
+        }
+        db
+    }
+
+    fn request(body: &Value, signature: Option<Signature>) -> RequestSignature {
+        RequestSignature {
+            message: canonical_json(body).into_bytes(),
+            signature,
+        }
+    }
+
+    #[test]
+    fn canonical_json_sorts_keys_recursively() {
+        let value = json!({"b": 1, "a": {"z": [true, null], "y": "s"}});
+        assert_eq!(
+            canonical_json(&value),
+            r#"{"a":{"y":"s","z":[true,null]},"b":1}"#
+        );
+    }
+
+    #[test]
+    fn valid_signature_is_accepted() {
+        let key = signing_key();
+        let db = db_with_key(Some(&key));
+        let body = json!({"leaf_index": 0, "recipient": "0x1"});
+        let signature = key.sign(canonical_json(&body).as_bytes());
+
+        request(&body, Some(signature))
+            .verify_for_leaf(&db, 0)
+            .unwrap();
+    }
+
+    #[test]
+    fn invalid_or_missing_signature_is_rejected() {
+        let key = signing_key();
+        let db = db_with_key(Some(&key));
+        let body = json!({"leaf_index": 0, "recipient": "0x1"});
+
+        // Signed over a different body
+        let other = key.sign(canonical_json(&json!({"leaf_index": 0})).as_bytes());
+        let err = request(&body, Some(other))
+            .verify_for_leaf(&db, 0)
+            .unwrap_err();
+        assert!(matches!(err, AspError::Unauthorized(_)));
+
+        // Signed by a different key
+        let stranger = SigningKey::from_bytes(&[9u8; 32]);
+        let forged = stranger.sign(canonical_json(&body).as_bytes());
+        let err = request(&body, Some(forged))
+            .verify_for_leaf(&db, 0)
+            .unwrap_err();
+        assert!(matches!(err, AspError::Unauthorized(_)));
+
+        let err = request(&body, None).verify_for_leaf(&db, 0).unwrap_err();
+        assert!(matches!(err, AspError::Unauthorized(_)));
+    }
+
+    #[test]
+    fn notes_without_key_need_no_signature() {
+        let db = db_with_key(None);
+        request(&json!({}), None).verify_for_leaf(&db, 0).unwrap();
+    }
+
+    #[test]
+    fn parse_auth_pubkey_rejects_bad_input() {
+        assert!(parse_auth_pubkey("0x1234", "auth_pubkey").is_err());
+        assert!(parse_auth_pubkey("zz", "auth_pubkey").is_err());
+        let pubkey = hex::encode(signing_key().verifying_key().to_bytes());
+        assert!(parse_auth_pubkey(&pubkey, "auth_pubkey").is_ok());
+    }
+}
@@ -0,0 +1,79 @@
+//! Short-lived cache of the `/status` payload, so load balancer probes do not
+//! each hit the database and ping the worker.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use tokio::sync::Mutex;
+
+use crate::api::types::StatusResponse;
+
+#[derive(Debug)]
+pub struct StatusCache<T = StatusResponse> {
+    cached: Mutex<Option<(Instant, T)>>,
+}
+
+impl<T> Default for StatusCache<T> {
+    fn default() -> Self {
+        StatusCache {
+            cached: Mutex::new(None),
+        }
+    }
+}
+
+impl<T: Clone> StatusCache<T> {
+    /// The cached value if it is younger than `ttl`, otherwise the result of
+    /// `refresh`, which replaces it. Callers arriving during a refresh wait
+    /// for it instead of running their own. A zero `ttl` disables caching.
+    pub async fn get_or_refresh<F, Fut>(&self, ttl: Duration, refresh: F) -> T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        if ttl.is_zero() {
+            return refresh().await;
+        }
+
+        let mut cached = self.cached.lock().await;
+        if let Some((at, ref value)) = *cached {
+            if at.elapsed() < ttl {
+                return value.clone();
+            }
+        }
+        let value = refresh().await;
+        *cached = Some((Instant::now(), value.clone()));
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn refreshes_once_per_ttl() {
+        let cache = StatusCache::<u32>::default();
+        let refreshes = AtomicU32::new(0);
+        let refresh = || async { refreshes.fetch_add(1, Ordering::SeqCst) + 1 };
+
+        let ttl = Duration::from_millis(50);
+        assert_eq!(cache.get_or_refresh(ttl, refresh).await, 1);
+        assert_eq!(cache.get_or_refresh(ttl, refresh).await, 1);
+        assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+
+        tokio::time::sleep(ttl).await;
+        assert_eq!(cache.get_or_refresh(ttl, refresh).await, 2);
+    }
+
+    #[tokio::test]
+    async fn zero_ttl_always_refreshes() {
+        let cache = StatusCache::<u32>::default();
+        let refreshes = AtomicU32::new(0);
+        let refresh = || async { refreshes.fetch_add(1, Ordering::SeqCst) + 1 };
+
+        cache.get_or_refresh(Duration::ZERO, refresh).await;
+        cache.get_or_refresh(Duration::ZERO, refresh).await;
+        assert_eq!(refreshes.load(Ordering::SeqCst), 2);
+    }
+}
@@ -1,15 +1,39 @@
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use starknet::core::types::Call;
 
+use crate::db::queries::NullifierOrigin;
+use crate::prover::{CircuitType, WorkerInfo};
 use crate::relayer::PoolKeyParams;
 
 // --- Deposit ---
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct DepositRequest {
     pub commitment: String,
+    /// Optional note details, recorded so later spends can be cross-checked.
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(default)]
+    pub amount_low: Option<String>,
+    #[serde(default)]
+    pub amount_high: Option<String>,
+    /// Optional hex Ed25519 public key; spends of this note must then be signed.
+    #[serde(default)]
+    pub auth_pubkey: Option<String>,
+    /// Note preimage, required only when `ALLOWED_DENOMINATIONS` is set so
+    /// the declared amount can be checked against the commitment. Not stored.
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub nullifier: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+/// `status` is `confirmed` once relayed on-chain, `pending` when queued, and
+/// `local` on a node without a relayer, where the leaf exists only in this tree.
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct DepositResponse {
     pub status: String,
     pub leaf_index: u32,
@@ -17,9 +41,23 @@ pub struct DepositResponse {
     pub root: String,
 }
 
+/// `status` is `pending`, `confirmed` or `failed` for queued deposits;
+/// other leaves report `confirmed` when their deposit tx is known and
+/// `local` otherwise.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CommitmentStatusResponse {
+    pub commitment: String,
+    pub leaf_index: u32,
+    pub status: String,
+    pub deposit_tx: Option<String>,
+    pub root_tx: Option<String>,
+    /// Why the last attempt to relay a still `pending` deposit failed.
+    pub error: Option<String>,
+}
+
 // --- Withdraw (membership) ---
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct WithdrawRequest {
     pub secret: String,
     pub nullifier: String,
@@ -30,16 +68,37 @@ pub struct WithdrawRequest {
     pub leaf_index: u32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct WithdrawResponse {
     pub status: String,
     pub tx_hash: String,
     pub nullifier_hash: String,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ProveQuery {
+    /// Also verify the Groth16 proof in the worker.
+    pub verify: Option<bool>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct WithdrawProveResponse {
+    pub status: String,
+    pub nullifier_hash: String,
+    /// Merkle root (decimal) the proof is against.
+    pub root: String,
+    pub calldata: Vec<String>,
+    pub public_signals: Vec<String>,
+    /// snarkjs Groth16 proof, for verifying off-chain; `None` from workers
+    /// that do not return it.
+    pub proof: Option<serde_json::Value>,
+    /// Worker verification result, with `verify=true` only.
+    pub verified: Option<bool>,
+}
+
 // --- Swap ---
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct SwapRequest {
     pub pool_key: PoolKeyParams,
     pub input_note: NoteInput,
@@ -47,9 +106,13 @@ pub struct SwapRequest {
     pub output_note: NoteSecrets,
     pub change_note: NoteSecrets,
     pub sqrt_price_limit: String,
+    /// Return the pool call for the client to send from its own account
+    /// instead of relaying it.
+    #[serde(default)]
+    pub self_submit: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct SwapParams {
     pub token_in: String,
     pub token_out: String,
@@ -59,7 +122,9 @@ pub struct SwapParams {
     pub amount_out_high: String,
 }
 
-#[derive(Debug, Serialize)]
+/// `status` is `confirmed` once relayed on-chain, or `awaiting_submission`
+/// when the client sends `call` itself.
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct SwapResponse {
     pub status: String,
     pub tx_hash: String,
@@ -67,11 +132,30 @@ pub struct SwapResponse {
     pub change_commitment: String,
     pub amount_out: String,   // actual output amount (decimal string)
     pub amount_change: String, // actual change amount (decimal string)
+    /// Pool call to send, for `self_submit` requests; `tx_hash` is then empty.
+    pub call: Option<ContractCall>,
+}
+
+// --- Swap simulation ---
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SimulateSwapRequest {
+    pub pool_key: PoolKeyParams,
+    pub token_in: String,
+    pub amount_in: String,
+    pub sqrt_price_limit: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SimulateSwapResponse {
+    pub zero_for_one: bool,
+    pub amount_out: String,       // expected output amount (decimal string)
+    pub sqrt_price_after: String, // pool sqrt price after the swap (decimal string)
 }
 
 // --- Mint ---
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct MintRequest {
     pub pool_key: PoolKeyParams,
     pub input_note_0: NoteInput,
@@ -81,9 +165,13 @@ pub struct MintRequest {
     pub change_note_0: NoteSecrets,
     pub change_note_1: NoteSecrets,
     pub liquidity: u128,
+    /// Return the pool call for the client to send from its own account
+    /// instead of relaying it.
+    #[serde(default)]
+    pub self_submit: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct PositionInput {
     pub secret: String,
     pub nullifier: String,
@@ -92,7 +180,7 @@ pub struct PositionInput {
     pub tick_upper: i32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct MintAmounts {
     pub amount0_low: String,
     pub amount0_high: String,
@@ -100,27 +188,34 @@ pub struct MintAmounts {
     pub amount1_high: String,
 }
 
-#[derive(Debug, Serialize)]
+/// `status` is `confirmed` or `awaiting_submission`, as for swaps.
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct MintResponse {
     pub status: String,
     pub tx_hash: String,
     pub position_commitment: String,
     pub change_commitment_0: String,
     pub change_commitment_1: String,
+    /// Pool call to send, for `self_submit` requests; `tx_hash` is then empty.
+    pub call: Option<ContractCall>,
 }
 
 // --- Burn ---
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct BurnRequest {
     pub pool_key: PoolKeyParams,
     pub position_note: PositionNoteInput,
     pub output_note_0: OutputNoteInput,
     pub output_note_1: OutputNoteInput,
     pub liquidity: u128,
+    /// Return the pool call for the client to send from its own account
+    /// instead of relaying it.
+    #[serde(default)]
+    pub self_submit: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct PositionNoteInput {
     pub secret: String,
     pub nullifier: String,
@@ -130,7 +225,7 @@ pub struct PositionNoteInput {
     pub leaf_index: u32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct OutputNoteInput {
     pub secret: String,
     pub nullifier: String,
@@ -139,7 +234,8 @@ pub struct OutputNoteInput {
     pub token: String,
 }
 
-#[derive(Debug, Serialize)]
+/// `status` is `confirmed` or `awaiting_submission`, as for swaps.
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct BurnResponse {
     pub status: String,
     pub tx_hash: String,
@@ -147,11 +243,43 @@ pub struct BurnResponse {
     pub new_commitment_1: String,
     pub amount_0: String,   // actual amount for token0 (decimal string)
     pub amount_1: String,   // actual amount for token1 (decimal string)
+    /// Pool call to send, for `self_submit` requests; `tx_hash` is then empty.
+    pub call: Option<ContractCall>,
+}
+
+/// A contract call for the client to send itself: the target, the entry
+/// point selector and the serialized calldata, all hex felts.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ContractCall {
+    pub contract_address: String,
+    pub selector: String,
+    pub calldata: Vec<String>,
+}
+
+impl From<Call> for ContractCall {
+    fn from(call: Call) -> Self {
+        ContractCall {
+            contract_address: format!("{:#x}", call.to),
+            selector: format!("{:#x}", call.selector),
+            calldata: call.calldata.iter().map(|felt| format!("{felt:#x}")).collect(),
+        }
+    }
+}
+
+// --- Dry-run proving (swap/mint/burn) ---
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DryRunResponse {
+    pub status: String,
+    pub circuit: CircuitType,
+    pub nullifier_hashes: Vec<String>,
+    pub calldata: Vec<String>,
+    pub public_signals: Vec<String>,
 }
 
 // --- Shared types ---
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct NoteInput {
     pub secret: String,
     pub nullifier: String,
@@ -161,62 +289,425 @@ pub struct NoteInput {
     pub leaf_index: u32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct NoteSecrets {
     pub secret: String,
     pub nullifier: String,
 }
 
+// --- Commitment derivation ---
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ComputeCommitmentRequest {
+    pub secret: String,
+    pub nullifier: String,
+    pub amount_low: String,
+    pub amount_high: String,
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ComputePositionCommitmentRequest {
+    pub secret: String,
+    pub nullifier: String,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CommitmentResponse {
+    pub commitment: String,
+    pub nullifier_hash: String,
+}
+
 // --- Tree ---
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct TreeRootResponse {
     pub root: String,
     pub leaf_count: u32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct TreeProofResponse {
     pub leaf_index: u32,
     pub commitment: String,
     pub path_elements: Vec<String>,
-    pub path_indices: Vec<u32>,
+    pub path_indices: PathIndices,
     pub root: String,
 }
 
+/// Left/right position at each level of a Merkle path: `0`/`1` by default,
+/// `false`/`true` with `bool_indices=true`.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum PathIndices {
+    Bits(Vec<u32>),
+    Bools(Vec<bool>),
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TreeRootQuery {
+    /// Return the root recorded when the tree had exactly this many leaves
+    /// instead of the current one.
+    pub leaf_count: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TreeRootsQuery {
+    /// Roots to return, newest first; defaults to the accepted window.
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TreeRootsResponse {
+    /// How many of the latest roots the coordinator accepts proofs against
+    /// (`ROOT_HISTORY_SIZE`).
+    pub accepted_root_window: u32,
+    pub roots: Vec<RecordedRoot>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RecordedRoot {
+    pub root: String,
+    pub leaf_count: u32,
+    /// Transaction that submitted the root on-chain, if known.
+    pub submit_tx: Option<String>,
+    pub created_at: String,
+    /// Among the latest `accepted_root_window` roots, so proofs against it
+    /// are still accepted.
+    pub accepted: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TreePathQuery {
+    /// Historical root (hex or decimal) to prove against instead of the
+    /// current one.
+    pub root: Option<String>,
+    /// Return `path_indices` as booleans (`true` = right) instead of 0/1.
+    pub bool_indices: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TreePathFormatQuery {
+    /// Return `path_indices` as booleans (`true` = right) instead of 0/1.
+    pub bool_indices: Option<bool>,
+}
+
 // --- Nullifier ---
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct NullifierResponse {
     pub nullifier_hash: String,
     pub spent: bool,
+    /// Handed to a client for self-submission and not yet seen on-chain.
+    pub pending: bool,
+    pub circuit_type: Option<CircuitType>,
+    pub origin: Option<NullifierOrigin>,
+    pub tx_hash: Option<String>,
+    pub spent_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct NullifierListQuery {
     pub circuit_type: Option<String>,
+    /// `local` or `chain`.
+    pub origin: Option<String>,
+    #[serde(default)]
+    pub offset: u32,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NullifierListResponse {
+    pub nullifiers: Vec<NullifierEntry>,
+    pub offset: u32,
+    pub limit: u32,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NullifierEntry {
+    pub nullifier_hash: String,
+    pub circuit_type: CircuitType,
+    pub origin: NullifierOrigin,
     pub tx_hash: Option<String>,
+    pub spent_at: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct NullifierCheckRequest {
+    /// Nullifier hashes, hex (`0x...`) or decimal.
+    pub hashes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NullifierCheckResponse {
+    /// Keyed by each hash exactly as given in the request.
+    pub nullifiers: BTreeMap<String, NullifierStatus>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NullifierStatus {
+    pub spent: bool,
+    pub circuit_type: Option<CircuitType>,
+    pub origin: Option<NullifierOrigin>,
+    pub tx_hash: Option<String>,
+}
+
+// --- Operations ---
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct OperationListQuery {
+    pub kind: Option<String>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct OperationResponse {
+    pub id: i64,
+    pub kind: String,
+    pub request_hash: String,
+    pub tx_hash: Option<String>,
+    /// Merkle root submission that followed `tx_hash`, if any.
+    pub root_tx: Option<String>,
+    pub leaf_indices: Vec<u32>,
+    pub status: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TxLookupResponse {
+    pub tx_hash: String,
+    /// `action` when the tx is the operation's own (deposit, swap, ...),
+    /// `root` when it is the Merkle root submission that followed it.
+    pub role: String,
+    pub operation: OperationResponse,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TxCommitmentsResponse {
+    pub tx_hash: String,
+    /// Leaves the tx inserted, in leaf order.
+    pub commitments: Vec<TxCommitment>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TxCommitment {
+    pub leaf_index: u32,
+    /// Commitment as a decimal string.
+    pub commitment: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct OperationListResponse {
+    pub operations: Vec<OperationResponse>,
+    pub limit: u32,
+}
+
+// --- Notes ---
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct NoteScanRequest {
+    pub notes: Vec<NoteScanEntry>,
+}
+
+/// A candidate note: its commitment and, optionally, its nullifier hash
+/// (both decimal strings, as returned by the worker).
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct NoteScanEntry {
+    pub commitment: String,
+    pub nullifier_hash: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NoteScanResponse {
+    pub notes: Vec<NoteScanResult>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NoteScanResult {
+    pub commitment: String,
+    pub found: bool,
+    pub leaf_index: Option<u32>,
+    pub deposit_tx: Option<String>,
+    /// `None` when no nullifier hash was supplied.
+    pub spent: Option<bool>,
+}
+
+// --- Admin ---
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ResyncQuery {
+    /// Defaults to the configured `SYNC_START_BLOCK`.
+    pub from_block: Option<u64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ResyncResponse {
+    pub status: String,
+    pub from_block: u64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct VacuumResponse {
+    pub status: String,
+    pub db_bytes_before: u64,
+    pub wal_bytes_before: u64,
+    pub db_bytes_after: u64,
+    pub wal_bytes_after: u64,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct VerifyTreeQuery {
+    /// Rebuild the worker tree from the database (and resubmit its root) when
+    /// the two disagree. Defaults to `false`.
+    pub repair: Option<bool>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct VerifyTreeResponse {
+    pub consistent: bool,
+    pub db_leaf_count: u32,
+    /// Root of the worker's in-memory tree (decimal), before any repair.
+    pub worker_root: String,
+    /// Root of a tree rebuilt from the database commitments (decimal).
+    pub rebuilt_root: String,
+    pub repaired: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ResubmitRootResponse {
+    /// Root of the worker's tree (decimal).
+    pub root: String,
+    pub leaf_count: u32,
+    /// `false` when the coordinator already has this root; nothing is sent.
+    pub resubmitted: bool,
+    /// The new `submit_merkle_root` transaction, when one was sent.
+    pub tx_hash: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BanRequest {
+    /// IPv4 or IPv6 address.
+    pub ip: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BanResponse {
+    pub ip: String,
+    pub reason: String,
+    /// Banned by `AUTO_BAN_THRESHOLD` rather than by an operator.
+    pub automatic: bool,
+    /// UTC, `YYYY-MM-DD HH:MM:SS`.
+    pub banned_at: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UnbanResponse {
+    pub ip: String,
+    /// Whether the IP was banned.
+    pub removed: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ProofStatsResponse {
+    /// Circuits with at least one recorded proof, by name.
+    pub circuits: Vec<CircuitProofStats>,
+}
+
+/// Wall-clock proof generation time of one circuit, in milliseconds.
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
+pub struct CircuitProofStats {
+    pub circuit: String,
+    pub count: u64,
+    pub avg_ms: u64,
+    pub p95_ms: u64,
+}
+
+/// One line of `GET /admin/export` and `POST /admin/import`.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ExportedCommitment {
+    pub leaf_index: u32,
+    /// Decimal, as stored.
+    pub commitment: String,
+    pub deposit_tx: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ImportResponse {
+    pub imported: u32,
+    /// Rows already present with the same commitment.
+    pub skipped: u32,
+    pub leaf_count: u32,
+    /// Root of the rebuilt tree (decimal); `None` when it is empty.
+    pub root: Option<String>,
+}
+
+// --- Event stream ---
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct EventStreamQuery {
+    pub last_event_id: Option<u64>,
 }
 
 // --- Status ---
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct StatusResponse {
     pub healthy: bool,
     pub version: String,
+    pub worker_busy: bool,
+    /// snarkjs version, verification key hashes and `warmed_up`; `None` if
+    /// the worker neither reported its circuits nor warmed up.
+    pub worker: Option<WorkerInfo>,
     pub tree: TreeStatus,
     pub sync: SyncStatus,
     pub contracts: ContractAddresses,
+    /// Requests per mutating operation since startup, by outcome.
+    pub operations: BTreeMap<String, OperationCount>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct OperationCount {
+    pub ok: u64,
+    pub err: u64,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct TreeStatus {
     pub leaf_count: u32,
-    pub root: Option<String>,
+    /// Latest root; the worker's empty-tree root before the first leaf.
+    pub root: String,
+    /// Notes a withdrawal hides among; every leaf is a candidate.
+    pub anonymity_set: u32,
+    /// When the newest leaf was inserted (UTC, `YYYY-MM-DD HH:MM:SS`).
+    pub last_deposit_at: Option<String>,
+    /// How many of the latest roots the coordinator accepts proofs against;
+    /// a note proven against an older root must be proven again.
+    pub accepted_root_window: u32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct SyncStatus {
     pub last_synced_block: Option<u64>,
+    /// Latest block seen by the event sync; `None` before it reaches the node.
+    pub chain_head: Option<u64>,
+    /// Blocks between `last_synced_block` and `chain_head`.
+    pub lag_blocks: Option<u64>,
+    /// Leaf index at which the local tree last disagreed with the root a
+    /// `CommitmentAdded` event reported; `None` while they agree.
+    pub tree_diverged_at_leaf: Option<u32>,
+    /// Whether a sync cycle is running right now.
+    pub sync_in_progress: bool,
+    /// Unix time the last successful sync cycle finished.
+    pub last_sync_completed_at: Option<u64>,
+    /// Error of the last sync cycle; `None` once a cycle succeeds.
+    pub last_sync_error: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct ContractAddresses {
     pub coordinator: String,
     pub pool: String,
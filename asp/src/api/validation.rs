@@ -6,6 +6,18 @@ use crate::error::AspError;
 /// Max valid tick in the CLMM (before offset).
 const MAX_TICK: i32 = 887272;
 
+/// Shift applied to signed ticks before they enter a position commitment,
+/// so circuits only ever see non-negative values.
+pub const TICK_OFFSET: i32 = MAX_TICK;
+
+/// Smallest sqrt price (Q128.128) the pool accepts, at tick -887272.
+/// Mirrors `MIN_SQRT_PRICE` in `src/clmm/math/sqrt_price.cairo`.
+pub const MIN_SQRT_PRICE: &str = "0x1000276a300000000";
+
+/// Largest sqrt price (Q128.128) the pool accepts, at tick 887272.
+/// Mirrors `MAX_SQRT_PRICE` in `src/clmm/math/sqrt_price.cairo`.
+pub const MAX_SQRT_PRICE: &str = "0xfffd8963efd1fc6a506488495d951d5263988d2600000000";
+
 /// Validate a hex string is a valid u256 (0x-prefixed, valid hex, fits in 256 bits).
 pub fn validate_hex_u256(value: &str, field_name: &str) -> Result<(), AspError> {
     if value.is_empty() {
@@ -49,6 +61,22 @@ pub fn validate_decimal(value: &str, field_name: &str) -> Result<(), AspError> {
     Ok(())
 }
 
+/// Validate a decimal string is one u128 limb of a split u256 (`< 2^128`).
+pub fn validate_u128_limb(value: &str, field_name: &str) -> Result<(), AspError> {
+    if value.is_empty() {
+        return Err(AspError::InvalidInput(format!("{field_name} is required")));
+    }
+    let big = BigUint::from_str_radix(value, 10).map_err(|_| {
+        AspError::InvalidInput(format!("{field_name} must be a valid decimal number"))
+    })?;
+    if big >= BigUint::from(1u64) << 128 {
+        return Err(AspError::InvalidInput(format!(
+            "{field_name} exceeds u128 range"
+        )));
+    }
+    Ok(())
+}
+
 /// Validate a Starknet address (hex, fits in felt252 = < 2^251 + 17*2^192 + 1).
 pub fn validate_address(value: &str, field_name: &str) -> Result<(), AspError> {
     if value.is_empty() {
@@ -99,6 +127,42 @@ pub fn validate_tick_range(tick_lower: i32, tick_upper: i32) -> Result<(), AspEr
     Ok(())
 }
 
+/// Validate a swap's sqrt price limit is a u256 within
+/// [`MIN_SQRT_PRICE`, `MAX_SQRT_PRICE`], so the pool does not revert on it.
+pub fn validate_sqrt_price_limit(value: &str, field_name: &str) -> Result<(), AspError> {
+    validate_hex_u256(value, field_name)?;
+    let bound = |hex: &str| BigUint::from_str_radix(&hex[2..], 16).unwrap_or_default();
+    let limit = bound(&value.to_ascii_lowercase());
+    if limit < bound(MIN_SQRT_PRICE) || limit > bound(MAX_SQRT_PRICE) {
+        return Err(AspError::InvalidInput(format!(
+            "{field_name} must be between {MIN_SQRT_PRICE} and {MAX_SQRT_PRICE}"
+        )));
+    }
+    Ok(())
+}
+
+/// Validate a signed tick (before the TICK_OFFSET shift) is a multiple of the
+/// pool's tick spacing.
+pub fn validate_tick_alignment(
+    tick: i32,
+    tick_spacing: u64,
+    field_name: &str,
+) -> Result<(), AspError> {
+    if tick_spacing == 0 {
+        return Err(AspError::InvalidInput(
+            "pool_key.tick_spacing must be > 0".into(),
+        ));
+    }
+    // Any spacing wider than the full tick range only admits tick 0.
+    let spacing = i64::try_from(tick_spacing).unwrap_or(i64::MAX);
+    if i64::from(tick) % spacing != 0 {
+        return Err(AspError::InvalidInput(format!(
+            "{field_name} must be a multiple of tick_spacing {tick_spacing}"
+        )));
+    }
+    Ok(())
+}
+
 /// Validate a non-empty secret field (only check presence, not content).
 pub fn validate_secret(value: &str, field_name: &str) -> Result<(), AspError> {
     if value.is_empty() {
@@ -107,6 +171,58 @@ pub fn validate_secret(value: &str, field_name: &str) -> Result<(), AspError> {
     Ok(())
 }
 
+/// Validate that no two notes of one request share a secret/nullifier pair,
+/// which would give them the same commitment. Each note is
+/// `(field_name, secret, nullifier)`; values are compared numerically, so
+/// `0x10` and `16` collide.
+pub fn validate_distinct_notes(notes: &[(&str, &str, &str)]) -> Result<(), AspError> {
+    let canonical = |value: &str| {
+        let parsed = match value.strip_prefix("0x") {
+            Some(hex) => BigUint::from_str_radix(hex, 16),
+            None => BigUint::from_str_radix(value, 10),
+        };
+        parsed.map_or_else(|_| value.to_string(), |n| n.to_string())
+    };
+    let keys: Vec<_> = notes
+        .iter()
+        .map(|(_, secret, nullifier)| (canonical(secret), canonical(nullifier)))
+        .collect();
+    for (i, key) in keys.iter().enumerate() {
+        if let Some(j) = keys[i + 1..].iter().position(|other| other == key) {
+            return Err(AspError::InvalidInput(format!(
+                "{} and {} must not share a secret and nullifier",
+                notes[i].0,
+                notes[i + 1 + j].0
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Validate that a position's decimal `liquidity` (the circuit input) is
+/// exactly the `liquidity` sent on-chain, so the proof matches the call.
+pub fn validate_liquidity_matches(
+    position_liquidity: &str,
+    liquidity: u128,
+    field_name: &str,
+) -> Result<(), AspError> {
+    let position = BigUint::from_str_radix(position_liquidity, 10).map_err(|_| {
+        AspError::InvalidInput(format!("{field_name} must be a valid decimal number"))
+    })?;
+    if position != BigUint::from(liquidity) {
+        return Err(AspError::InvalidInput(format!(
+            "{field_name} ({position}) does not match liquidity ({liquidity})"
+        )));
+    }
+    Ok(())
+}
+
+/// Full u256 note amount from its decimal u128 limbs (limbs must be valid).
+pub fn note_amount(amount_low: &str, amount_high: &str) -> BigUint {
+    let limb = |value: &str| BigUint::from_str_radix(value, 10).unwrap_or_default();
+    (limb(amount_high) << 128) | limb(amount_low)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -157,6 +273,57 @@ mod tests {
         assert!(validate_address(&too_large, "test").is_err());
     }
 
+    #[test]
+    fn validate_u128_limb_boundary() {
+        // 2^128 - 1 is the largest valid limb
+        assert!(validate_u128_limb("340282366920938463463374607431768211455", "test").is_ok());
+        assert!(validate_u128_limb("340282366920938463463374607431768211456", "test").is_err());
+        assert!(validate_u128_limb("0", "test").is_ok());
+    }
+
+    #[test]
+    fn validate_u128_limb_rejects_non_decimal() {
+        assert!(validate_u128_limb("", "test").is_err());
+        assert!(validate_u128_limb("0x10", "test").is_err());
+        assert!(validate_u128_limb("-1", "test").is_err());
+    }
+
+    #[test]
+    fn validate_liquidity_matches_exact_value() {
+        assert!(validate_liquidity_matches("1000", 1000, "position.liquidity").is_ok());
+        assert!(validate_liquidity_matches("001000", 1000, "position.liquidity").is_ok());
+        let max = u128::MAX.to_string();
+        assert!(validate_liquidity_matches(&max, u128::MAX, "position.liquidity").is_ok());
+    }
+
+    #[test]
+    fn validate_liquidity_matches_rejects_mismatch() {
+        let err = validate_liquidity_matches("999", 1000, "position.liquidity").unwrap_err();
+        assert!(matches!(err, AspError::InvalidInput(ref m) if m.contains("position.liquidity")));
+        // 2^128 is larger than any u128, so it can never match
+        let too_big = "340282366920938463463374607431768211456";
+        assert!(validate_liquidity_matches(too_big, u128::MAX, "position.liquidity").is_err());
+        assert!(validate_liquidity_matches("", 0, "position.liquidity").is_err());
+    }
+
+    #[test]
+    fn validate_distinct_notes_rejects_reused_pair() {
+        let notes = [("input_note", "111", "222"), ("change_note", "555", "666")];
+        assert!(validate_distinct_notes(&notes).is_ok());
+
+        // Same secret alone is not a collision
+        let notes = [("input_note", "111", "222"), ("change_note", "111", "666")];
+        assert!(validate_distinct_notes(&notes).is_ok());
+
+        let notes = [
+            ("input_note", "111", "222"),
+            ("output_note", "333", "444"),
+            ("change_note", "0x6f", "222"),
+        ];
+        let err = validate_distinct_notes(&notes).unwrap_err().to_string();
+        assert!(err.contains("input_note and change_note"), "{err}");
+    }
+
     #[test]
     fn validate_tick_range_valid() {
         assert!(validate_tick_range(-100, 100).is_ok());
@@ -167,4 +334,45 @@ mod tests {
         assert!(validate_tick_range(100, 100).is_err());
         assert!(validate_tick_range(200, 100).is_err());
     }
+
+    #[test]
+    fn validate_sqrt_price_limit_bounds() {
+        let one = BigUint::from(1u8);
+        let hex = |v: BigUint| format!("0x{v:x}");
+        let min = BigUint::from_str_radix(&MIN_SQRT_PRICE[2..], 16).unwrap();
+        let max = BigUint::from_str_radix(&MAX_SQRT_PRICE[2..], 16).unwrap();
+
+        assert!(validate_sqrt_price_limit(MIN_SQRT_PRICE, "limit").is_ok());
+        assert!(validate_sqrt_price_limit(MAX_SQRT_PRICE, "limit").is_ok());
+        assert!(validate_sqrt_price_limit(&MAX_SQRT_PRICE.to_uppercase(), "limit").is_ok());
+        // 1.0 in Q128.128
+        assert!(validate_sqrt_price_limit(&hex(one.clone() << 128), "limit").is_ok());
+
+        assert!(validate_sqrt_price_limit(&hex(&min - &one), "limit").is_err());
+        assert!(validate_sqrt_price_limit(&hex(&max + &one), "limit").is_err());
+        assert!(validate_sqrt_price_limit("0x0", "limit").is_err());
+        assert!(validate_sqrt_price_limit("nothex", "limit").is_err());
+    }
+
+    #[test]
+    fn validate_tick_alignment_aligned() {
+        assert!(validate_tick_alignment(0, 60, "tick").is_ok());
+        assert!(validate_tick_alignment(120, 60, "tick").is_ok());
+        assert!(validate_tick_alignment(-120, 60, "tick").is_ok());
+        assert!(validate_tick_alignment(-887220, 60, "tick").is_ok());
+    }
+
+    #[test]
+    fn validate_tick_alignment_misaligned() {
+        assert!(validate_tick_alignment(30, 60, "tick").is_err());
+        assert!(validate_tick_alignment(-30, 60, "tick").is_err());
+        assert!(validate_tick_alignment(-61, 60, "tick").is_err());
+        // MAX_TICK itself is not a multiple of 60
+        assert!(validate_tick_alignment(887272, 60, "tick").is_err());
+    }
+
+    #[test]
+    fn validate_tick_alignment_zero_spacing() {
+        assert!(validate_tick_alignment(0, 0, "tick").is_err());
+    }
 }
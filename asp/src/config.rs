@@ -1,34 +1,224 @@
+use num_bigint::BigUint;
+use num_traits::Num;
 use serde::Deserialize;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
+use starknet::core::types::Felt;
+use tracing_subscriber::EnvFilter;
+
+use crate::api::handlers::deposit::field_to_decimal;
+use crate::db::{DEFAULT_BUSY_TIMEOUT_MS, SYNCHRONOUS_MODES};
 use crate::error::AspError;
+use crate::logging::LogFormat;
+use crate::prover::{
+    DomainSeparators, DEFAULT_COMMITMENT_CACHE_SIZE, DEFAULT_MAX_PROOF_JOBS, DEFAULT_NOTE_DOMAIN,
+    DEFAULT_POSITION_DOMAIN,
+};
+use crate::relayer::PoolRoutes;
+
+/// Tree height the circuits are compiled for.
+pub const DEFAULT_TREE_HEIGHT: u32 = 20;
+
+/// Roots the coordinator accepts proofs against (its `ROOT_HISTORY_SIZE`).
+pub const DEFAULT_ROOT_HISTORY_SIZE: u32 = 100;
+
+/// Default request body limit (2 MiB).
+pub const DEFAULT_MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Default window over which `AUTO_BAN_THRESHOLD` is counted, in seconds.
+pub const DEFAULT_AUTO_BAN_WINDOW_SECS: u64 = 60;
+
+/// Default lifetime of a cached `/status` payload, in milliseconds.
+pub const DEFAULT_STATUS_CACHE_MS: u64 = 1000;
+
+/// Default HTTP/2 keep-alive ping interval, in seconds.
+pub const DEFAULT_KEEPALIVE_SECS: u64 = 60;
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+pub const DEFAULT_PROOF_REQUEST_TIMEOUT_SECS: u64 = 300;
+/// Starknet's limit on invoke calldata, in felts.
+pub const DEFAULT_MAX_CALLDATA_FELTS: usize = 4000;
+/// Default multiplier on the node's fee estimate (`FEE_MULTIPLIER`).
+pub const DEFAULT_FEE_MULTIPLIER: f64 = 1.5;
+
+/// Default cap on concurrent HTTP/2 streams per connection.
+pub const DEFAULT_MAX_CONCURRENT_STREAMS: u32 = 200;
+
+/// Chain the relayer signs for when `STARKNET_CHAIN_ID` is unset.
+pub const DEFAULT_CHAIN_ID: &str = "SN_SEPOLIA";
+
+/// Worker script, relative to the source tree or the executable's directory.
+const DEFAULT_WORKER_PATH: &str = "worker/worker.mjs";
+
+/// Leaf indices are `u32`, so the tree can never hold more than 2^32 leaves.
+const MAX_TREE_HEIGHT: u32 = 32;
 
 #[derive(Clone, Debug)]
 pub struct Config {
     // Server
     pub host: String,
     pub port: u16,
+    /// Accept HTTP/2 (prior knowledge, h2c) alongside HTTP/1.1.
+    pub http2_enabled: bool,
+    /// Interval between HTTP/2 keep-alive pings; 0 turns keep-alive off
+    /// (HTTP/1.1 connections then close after each response).
+    pub keepalive_secs: u64,
+    /// Concurrent streams a client may open on one HTTP/2 connection.
+    pub max_concurrent_streams: u32,
 
     // Starknet RPC
     pub rpc_url: String,
+    /// Chain the RPC node must be on: a short string (`SN_SEPOLIA`,
+    /// `SN_MAIN`) or a hex felt.
+    pub chain_id: String,
 
     // Admin account
     pub admin_address: String,
     pub keystore_path: String,
     pub keystore_password: String,
+    /// Public key the admin account validates signatures against, for
+    /// accounts without `get_public_key`. Overrides the on-chain lookup.
+    pub admin_public_key: Option<String>,
+    /// Accounts the relayer fails over to when the admin account cannot
+    /// send; their keys come from `BACKUP_ADMIN_PRIVATE_KEYS`.
+    pub backup_admin_addresses: Vec<String>,
 
     // Contract addresses
     pub coordinator_address: String,
     pub pool_address: String,
+    /// Extra pool contracts keyed by pool id (`token_0:token_1:fee:tick_spacing`).
+    /// Pool keys not listed here route to `pool_address`.
+    pub pools: HashMap<String, String>,
 
     // Database
     pub database_path: String,
+    pub database_pool_size: u32,
+    /// `PRAGMA synchronous` mode for every connection.
+    pub database_synchronous: String,
+    pub database_foreign_keys: bool,
+    /// `PRAGMA busy_timeout`; writes still busy after it are retried.
+    pub database_busy_timeout_ms: u64,
 
     // Worker
     pub worker_path: String,
+    /// Interpreter for the worker script (`node`, `bun`, or a path).
+    pub worker_runtime: String,
+    /// Arguments passed to the runtime before the script path.
+    pub worker_runtime_args: Vec<String>,
+    /// Expected verification key hash per circuit; a worker reporting a
+    /// different one is logged at startup.
+    pub expected_circuit_hashes: HashMap<String, String>,
+    /// Note commitments remembered by the worker handle; 0 disables the cache.
+    pub commitment_cache_size: usize,
+    /// Preload circuit artifacts in the worker before serving.
+    pub worker_warmup: bool,
+    /// Proof requests admitted at once (proving or waiting for the worker);
+    /// more are rejected with 503.
+    pub max_proof_jobs: usize,
+    /// Commitment scheme names the worker must report at spawn.
+    pub commitment_domains: DomainSeparators,
+    /// Root (decimal) the worker must compute for the fixed self-test tree at
+    /// startup; `None` only logs the root it computes.
+    pub expected_self_test_root: Option<String>,
+
+    // Merkle tree
+    pub tree_height: u32,
+    /// Rebuild the tree at startup even if stored leaf indices have gaps,
+    /// logging an error instead of refusing to start.
+    pub allow_leaf_gaps: bool,
+    /// How many of the latest roots the coordinator accepts proofs against.
+    pub root_history_size: u32,
 
     // Sync
     pub sync_poll_interval_secs: u64,
+    /// Pause between consecutive RPC requests within one sync cycle, to stay
+    /// under provider rate limits.
+    pub sync_request_delay_ms: u64,
+    /// Block to start scanning from when no sync progress is recorded yet,
+    /// typically the coordinator's deployment block.
+    pub sync_start_block: u64,
+    /// Widest block range requested in a single `starknet_getEvents` query.
+    pub max_block_range: u64,
+
+    // Relayer retries
+    pub relayer_max_attempts: u32,
+    pub relayer_retry_base_ms: u64,
+
+    // Relayer fees
+    /// Multiplier applied to the estimated gas when building v3 transactions.
+    pub fee_multiplier: f64,
+    /// Refuse to send a transaction whose estimated fee exceeds this (in FRI).
+    pub max_fee_fri: Option<u128>,
+    /// Fixed v3 resource bounds; each one left unset comes from estimation.
+    pub l1_gas_max_amount: Option<u64>,
+    pub l1_gas_max_price: Option<u128>,
+    pub l2_gas_max_amount: Option<u64>,
+    pub l2_gas_max_price: Option<u128>,
+
+    // Relayer calldata
+    /// Longest proof calldata, in felts, the relayer will submit. Longer
+    /// proofs are refused before any RPC call instead of reverting on-chain.
+    pub max_calldata_felts: usize,
+
+    // Logging
+    /// `tracing` filter directive, e.g. `info` or `zylith_asp=debug`.
+    pub log_level: String,
+    /// `text` or `json` log lines (`LOG_FORMAT`).
+    pub log_format: LogFormat,
+
+    // Webhooks
+    pub webhook_url: Option<String>,
+
+    // Admin API
+    /// Bearer token required by `/admin/*` routes. Admin routes are disabled
+    /// when unset.
+    pub admin_api_token: Option<String>,
+
+    // CORS
+    /// Origins allowed to call the API from a browser; `*` allows any.
+    /// Empty means cross-origin reads only.
+    pub allowed_origins: Vec<String>,
+
+    // Deposits
+    /// Note amounts a deposit may carry; empty allows any amount. When set,
+    /// deposits must reveal their note so the amount can be verified.
+    pub allowed_denominations: Vec<BigUint>,
+    /// Answer `/deposit` as soon as the leaf is inserted locally and relay
+    /// it on-chain in the background.
+    pub deposit_queue: bool,
+
+    // Withdrawals
+    /// Seconds a leaf must have been in the tree before `/withdraw` spends
+    /// it; 0 disables the check.
+    pub min_withdraw_delay_secs: u64,
+
+    // Request limits
+    /// Largest request body accepted, in bytes.
+    pub max_body_bytes: usize,
+    /// Deadline for a request, after which it is answered with 504.
+    pub request_timeout_secs: u64,
+    /// Deadline for the routes that prove or submit on-chain.
+    pub proof_request_timeout_secs: u64,
+
+    // Abuse protection
+    /// Identify clients by the last `X-Forwarded-For` address (the one the
+    /// proxy appended) instead of the socket peer. Enable only behind a
+    /// single proxy that appends to the header.
+    pub trust_forwarded_for: bool,
+    /// Requests one IP may make per `auto_ban_window_secs` before it is
+    /// banned; 0 disables auto-banning.
+    pub auto_ban_threshold: u32,
+    pub auto_ban_window_secs: u64,
+
+    // Status
+    /// How long a computed `/status` payload is served before it is rebuilt,
+    /// in milliseconds; 0 disables caching.
+    pub status_cache_ms: u64,
+
+    // Responses
+    /// Indent every JSON response body (also available per request with
+    /// `?pretty=true`). Off in production.
+    pub pretty_json: bool,
 }
 
 #[derive(Deserialize)]
@@ -38,19 +228,244 @@ struct DeployedAddresses {
 }
 
 impl Config {
+    /// Maximum number of leaves the Merkle tree can hold (`2^tree_height`).
+    pub fn tree_capacity(&self) -> u64 {
+        1u64.checked_shl(self.tree_height).unwrap_or(u64::MAX)
+    }
+
+    /// Reject inserting a leaf at `leaf_index` if the tree has no room for it.
+    pub fn ensure_tree_capacity(&self, leaf_index: u32) -> Result<(), AspError> {
+        if u64::from(leaf_index) >= self.tree_capacity() {
+            return Err(AspError::TreeFull);
+        }
+        Ok(())
+    }
+
+    /// Check every field that can be checked without touching the network.
+    /// Returns a single `AspError::Config` listing all problems found.
+    pub fn validate(&self) -> Result<(), AspError> {
+        let mut problems = Vec::new();
+
+        for (name, value) in [
+            ("ADMIN_ADDRESS", &self.admin_address),
+            ("COORDINATOR_ADDRESS", &self.coordinator_address),
+            ("POOL_ADDRESS", &self.pool_address),
+        ] {
+            if let Err(e) = Felt::from_hex(value) {
+                problems.push(format!("{name} '{value}' is not a valid felt: {e}"));
+            }
+        }
+
+        if let Some(ref key) = self.admin_public_key {
+            if let Err(e) = Felt::from_hex(key) {
+                problems.push(format!("ADMIN_PUBLIC_KEY '{key}' is not a valid felt: {e}"));
+            }
+        }
+
+        for address in &self.backup_admin_addresses {
+            if let Err(e) = Felt::from_hex(address) {
+                problems.push(format!(
+                    "BACKUP_ADMIN_ADDRESSES entry '{address}' is not a valid felt: {e}"
+                ));
+            }
+        }
+
+        // Pool routes are only checked once the default pool itself is valid,
+        // so a bad POOL_ADDRESS is reported once.
+        if Felt::from_hex(&self.pool_address).is_ok() {
+            if let Err(e) = PoolRoutes::new(&self.pool_address, &self.pools) {
+                problems.push(format!("POOLS: {e}"));
+            }
+        }
+
+        if let Err(e) = url::Url::parse(&self.rpc_url) {
+            problems.push(format!(
+                "STARKNET_RPC_URL '{}' is not a valid URL: {e}",
+                self.rpc_url
+            ));
+        }
+        if let Err(e) = self.chain_id_felt() {
+            problems.push(e.to_string());
+        }
+        if let Some(ref webhook_url) = self.webhook_url {
+            if let Err(e) = url::Url::parse(webhook_url) {
+                problems.push(format!("WEBHOOK_URL '{webhook_url}' is not a valid URL: {e}"));
+            }
+        }
+
+        if self.worker_runtime.trim().is_empty() {
+            problems.push("WORKER_RUNTIME must not be empty".into());
+        }
+        if self.max_proof_jobs == 0 {
+            problems.push("MAX_PROOF_JOBS must be at least 1".into());
+        }
+        let domains = &self.commitment_domains;
+        if domains.note.trim().is_empty() || domains.position.trim().is_empty() {
+            problems.push(
+                "NOTE_DOMAIN_SEPARATOR and POSITION_DOMAIN_SEPARATOR must not be empty".into(),
+            );
+        } else if domains.note == domains.position {
+            problems.push(format!(
+                "NOTE_DOMAIN_SEPARATOR and POSITION_DOMAIN_SEPARATOR must differ, both are '{}'",
+                domains.note
+            ));
+        }
+        if self.database_pool_size == 0 {
+            problems.push("DATABASE_POOL_SIZE must be at least 1".into());
+        }
+        if !SYNCHRONOUS_MODES.contains(&self.database_synchronous.as_str()) {
+            problems.push(format!(
+                "DATABASE_SYNCHRONOUS must be one of {}, got '{}'",
+                SYNCHRONOUS_MODES.join(", "),
+                self.database_synchronous
+            ));
+        }
+        if self.tree_height == 0 || self.tree_height > MAX_TREE_HEIGHT {
+            problems.push(format!(
+                "TREE_HEIGHT must be between 1 and {MAX_TREE_HEIGHT}, got {}",
+                self.tree_height
+            ));
+        }
+        if self.sync_poll_interval_secs == 0 {
+            problems.push("SYNC_POLL_INTERVAL_SECS must be at least 1".into());
+        }
+        if self.max_block_range == 0 {
+            problems.push("MAX_BLOCK_RANGE must be at least 1".into());
+        }
+        if self.relayer_max_attempts == 0 {
+            problems.push("RELAYER_MAX_ATTEMPTS must be at least 1".into());
+        }
+        if !(self.fee_multiplier.is_finite() && self.fee_multiplier >= 1.0) {
+            problems.push(format!(
+                "FEE_MULTIPLIER must be a number >= 1.0, got {}",
+                self.fee_multiplier
+            ));
+        }
+        if self.max_fee_fri == Some(0) {
+            problems.push("MAX_FEE_FRI must be greater than 0 when set".into());
+        }
+        for (name, zero) in [
+            ("L1_GAS_MAX_AMOUNT", self.l1_gas_max_amount == Some(0)),
+            ("L1_GAS_MAX_PRICE", self.l1_gas_max_price == Some(0)),
+            ("L2_GAS_MAX_AMOUNT", self.l2_gas_max_amount == Some(0)),
+            ("L2_GAS_MAX_PRICE", self.l2_gas_max_price == Some(0)),
+        ] {
+            if zero {
+                problems.push(format!("{name} must be greater than 0 when set"));
+            }
+        }
+        if self.allowed_denominations.iter().any(|d| d.bits() == 0 || d.bits() > 256) {
+            problems.push("ALLOWED_DENOMINATIONS entries must be between 1 and 2^256 - 1".into());
+        }
+        if self.max_concurrent_streams == 0 {
+            problems.push("MAX_CONCURRENT_STREAMS must be at least 1".into());
+        }
+        if self.root_history_size == 0 {
+            problems.push("ROOT_HISTORY_SIZE must be at least 1".into());
+        }
+        if self.max_calldata_felts == 0 {
+            problems.push("MAX_CALLDATA_FELTS must be at least 1".into());
+        }
+        if self.max_body_bytes == 0 {
+            problems.push("MAX_BODY_BYTES must be at least 1".into());
+        }
+        if self.request_timeout_secs == 0 {
+            problems.push("REQUEST_TIMEOUT_SECS must be at least 1".into());
+        }
+        if self.proof_request_timeout_secs == 0 {
+            problems.push("PROOF_REQUEST_TIMEOUT_SECS must be at least 1".into());
+        }
+        if self.auto_ban_threshold > 0 && self.auto_ban_window_secs == 0 {
+            problems.push("AUTO_BAN_WINDOW_SECS must be at least 1".into());
+        }
+        if self.allowed_origins.len() > 1 && self.allowed_origins.iter().any(|o| o == "*") {
+            problems.push("ALLOWED_ORIGINS must be either `*` or a list of origins".into());
+        }
+        for origin in self.allowed_origins.iter().filter(|o| *o != "*") {
+            let valid = url::Url::parse(origin)
+                .is_ok_and(|url| url.origin().ascii_serialization() == *origin);
+            if !valid {
+                problems.push(format!(
+                    "ALLOWED_ORIGINS entry '{origin}' is not an origin (scheme://host[:port])"
+                ));
+            }
+        }
+        if let Err(e) = EnvFilter::try_new(&self.log_level) {
+            problems.push(format!("RUST_LOG '{}' is not a valid filter: {e}", self.log_level));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(AspError::Config(format!(
+                "invalid configuration:\n  - {}",
+                problems.join("\n  - ")
+            )))
+        }
+    }
+
+    /// `chain_id` as the felt the RPC node reports and transactions are signed
+    /// for.
+    pub fn chain_id_felt(&self) -> Result<Felt, AspError> {
+        let id = self.chain_id.trim();
+        let felt = if id.starts_with("0x") || id.starts_with("0X") {
+            Felt::from_hex(id).ok()
+        } else if !id.is_empty() && id.len() <= 31 && id.is_ascii() {
+            // Cairo short string: the ASCII bytes read as a big-endian number
+            Felt::from_hex(&format!("0x{}", hex::encode(id))).ok()
+        } else {
+            None
+        };
+        felt.ok_or_else(|| {
+            AspError::Config(format!(
+                "STARKNET_CHAIN_ID '{}' is not a chain id (e.g. SN_SEPOLIA, SN_MAIN or 0x...)",
+                self.chain_id
+            ))
+        })
+    }
+
     pub fn load() -> Result<Self, AspError> {
         // Load .env file (optional, won't fail if missing)
         dotenvy::dotenv().ok();
+        Self::from_env()
+    }
+
+    /// Re-read configuration for a hot reload. Unlike [`Config::load`], values
+    /// in `.env` override the process environment so edits to the file apply.
+    pub fn reload() -> Result<Self, AspError> {
+        dotenvy::dotenv_override().ok();
+        Self::from_env()
+    }
 
+    fn from_env() -> Result<Self, AspError> {
         let host = std::env::var("ASP_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
         let port: u16 = std::env::var("ASP_PORT")
             .unwrap_or_else(|_| "3000".to_string())
             .parse()
             .map_err(|_| AspError::Config("ASP_PORT must be a valid port number".into()))?;
 
+        let http2_enabled: bool = match std::env::var("HTTP2_ENABLED") {
+            Ok(raw) if !raw.trim().is_empty() => raw
+                .trim()
+                .parse()
+                .map_err(|_| AspError::Config("HTTP2_ENABLED must be true or false".into()))?,
+            _ => true,
+        };
+        let keepalive_secs: u64 = std::env::var("KEEPALIVE_SECS")
+            .unwrap_or_else(|_| DEFAULT_KEEPALIVE_SECS.to_string())
+            .parse()
+            .map_err(|_| AspError::Config("KEEPALIVE_SECS must be a number".into()))?;
+        let max_concurrent_streams: u32 = std::env::var("MAX_CONCURRENT_STREAMS")
+            .unwrap_or_else(|_| DEFAULT_MAX_CONCURRENT_STREAMS.to_string())
+            .parse()
+            .map_err(|_| AspError::Config("MAX_CONCURRENT_STREAMS must be a number".into()))?;
+
         let rpc_url = std::env::var("STARKNET_RPC_URL")
             .map_err(|_| AspError::Config("STARKNET_RPC_URL is required".into()))?;
 
+        let chain_id =
+            std::env::var("STARKNET_CHAIN_ID").unwrap_or_else(|_| DEFAULT_CHAIN_ID.to_string());
+
         let admin_address = std::env::var("ADMIN_ADDRESS")
             .map_err(|_| AspError::Config("ADMIN_ADDRESS is required".into()))?;
 
@@ -60,6 +475,17 @@ impl Config {
         let keystore_password = std::env::var("KEYSTORE_PASSWORD")
             .map_err(|_| AspError::Config("KEYSTORE_PASSWORD is required".into()))?;
 
+        let admin_public_key = std::env::var("ADMIN_PUBLIC_KEY")
+            .ok()
+            .filter(|key| !key.trim().is_empty());
+
+        let backup_admin_addresses: Vec<String> = std::env::var("BACKUP_ADMIN_ADDRESSES")
+            .unwrap_or_default()
+            .split(',')
+            .map(|address| address.trim().to_string())
+            .filter(|address| !address.is_empty())
+            .collect();
+
         // Try to load deployed addresses from file
         let addresses_path = std::env::var("DEPLOYED_ADDRESSES_PATH").unwrap_or_else(|_| {
             // Default: look relative to project root
@@ -68,6 +494,50 @@ impl Config {
             path.to_string_lossy().to_string()
         });
 
+        // BUN_RUNTIME is the older name of WORKER_RUNTIME
+        let worker_runtime = std::env::var("WORKER_RUNTIME")
+            .or_else(|_| std::env::var("BUN_RUNTIME"))
+            .unwrap_or_else(|_| "node".to_string());
+        let worker_runtime_args: Vec<String> = std::env::var("WORKER_RUNTIME_ARGS")
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(String::from)
+            .collect();
+
+        let worker_warmup: bool = match std::env::var("WORKER_WARMUP") {
+            Ok(raw) if !raw.trim().is_empty() => raw
+                .trim()
+                .parse()
+                .map_err(|_| AspError::Config("WORKER_WARMUP must be true or false".into()))?,
+            _ => true,
+        };
+
+        let commitment_cache_size: usize = std::env::var("COMMITMENT_CACHE_SIZE")
+            .unwrap_or_else(|_| DEFAULT_COMMITMENT_CACHE_SIZE.to_string())
+            .parse()
+            .map_err(|_| AspError::Config("COMMITMENT_CACHE_SIZE must be a number".into()))?;
+
+        let max_proof_jobs: usize = std::env::var("MAX_PROOF_JOBS")
+            .unwrap_or_else(|_| DEFAULT_MAX_PROOF_JOBS.to_string())
+            .parse()
+            .map_err(|_| AspError::Config("MAX_PROOF_JOBS must be a number".into()))?;
+
+        let commitment_domains = DomainSeparators {
+            note: std::env::var("NOTE_DOMAIN_SEPARATOR")
+                .unwrap_or_else(|_| DEFAULT_NOTE_DOMAIN.to_string()),
+            position: std::env::var("POSITION_DOMAIN_SEPARATOR")
+                .unwrap_or_else(|_| DEFAULT_POSITION_DOMAIN.to_string()),
+        };
+
+        let expected_self_test_root = std::env::var("EXPECTED_SELF_TEST_ROOT")
+            .ok()
+            .filter(|r| !r.is_empty())
+            .map(|r| {
+                field_to_decimal(&r, "EXPECTED_SELF_TEST_ROOT")
+                    .map_err(|e| AspError::Config(e.to_string()))
+            })
+            .transpose()?;
+
         let (coordinator_address, pool_address) =
             if let Ok(content) = std::fs::read_to_string(&addresses_path) {
                 let addrs: DeployedAddresses = serde_json::from_str(&content)
@@ -82,32 +552,732 @@ impl Config {
                 (coordinator, pool)
             };
 
+        let pools: HashMap<String, String> = match std::env::var("POOLS") {
+            Ok(raw) if !raw.trim().is_empty() => serde_json::from_str(&raw)
+                .map_err(|e| AspError::Config(format!("POOLS must be a JSON object: {e}")))?,
+            _ => HashMap::new(),
+        };
+
+        let expected_circuit_hashes: HashMap<String, String> =
+            match std::env::var("EXPECTED_CIRCUIT_HASHES") {
+                Ok(raw) if !raw.trim().is_empty() => serde_json::from_str(&raw).map_err(|e| {
+                    AspError::Config(format!(
+                        "EXPECTED_CIRCUIT_HASHES must be a JSON object: {e}"
+                    ))
+                })?,
+                _ => HashMap::new(),
+            };
+
         let database_path = std::env::var("DATABASE_PATH")
             .unwrap_or_else(|_| "zylith_asp.db".to_string());
 
-        let worker_path = std::env::var("WORKER_PATH").unwrap_or_else(|_| {
-            let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-            path.push("worker/worker.mjs");
-            path.to_string_lossy().to_string()
-        });
+        let database_pool_size: u32 = std::env::var("DATABASE_POOL_SIZE")
+            .unwrap_or_else(|_| "8".to_string())
+            .parse()
+            .map_err(|_| AspError::Config("DATABASE_POOL_SIZE must be a number".into()))?;
+
+        let database_synchronous = std::env::var("DATABASE_SYNCHRONOUS")
+            .unwrap_or_else(|_| "NORMAL".to_string())
+            .to_uppercase();
+
+        let database_foreign_keys: bool = match std::env::var("DATABASE_FOREIGN_KEYS") {
+            Ok(raw) if !raw.trim().is_empty() => raw.trim().parse().map_err(|_| {
+                AspError::Config("DATABASE_FOREIGN_KEYS must be true or false".into())
+            })?,
+            _ => true,
+        };
+
+        let database_busy_timeout_ms: u64 = std::env::var("DATABASE_BUSY_TIMEOUT_MS")
+            .unwrap_or_else(|_| DEFAULT_BUSY_TIMEOUT_MS.to_string())
+            .parse()
+            .map_err(|_| AspError::Config("DATABASE_BUSY_TIMEOUT_MS must be a number".into()))?;
+
+        let deposit_queue: bool = match std::env::var("DEPOSIT_QUEUE") {
+            Ok(raw) if !raw.trim().is_empty() => raw
+                .trim()
+                .parse()
+                .map_err(|_| AspError::Config("DEPOSIT_QUEUE must be true or false".into()))?,
+            _ => false,
+        };
+
+        let min_withdraw_delay_secs: u64 = std::env::var("MIN_WITHDRAW_DELAY_SECS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse()
+            .map_err(|_| AspError::Config("MIN_WITHDRAW_DELAY_SECS must be a number".into()))?;
+
+        let worker_path = resolve_worker_path(std::env::var("WORKER_PATH").ok().as_deref())?;
+
+        let tree_height: u32 = std::env::var("TREE_HEIGHT")
+            .unwrap_or_else(|_| DEFAULT_TREE_HEIGHT.to_string())
+            .parse()
+            .map_err(|_| AspError::Config("TREE_HEIGHT must be a number".into()))?;
+
+        let root_history_size: u32 = std::env::var("ROOT_HISTORY_SIZE")
+            .unwrap_or_else(|_| DEFAULT_ROOT_HISTORY_SIZE.to_string())
+            .parse()
+            .map_err(|_| AspError::Config("ROOT_HISTORY_SIZE must be a number".into()))?;
+
+        let allow_leaf_gaps: bool = match std::env::var("ALLOW_LEAF_GAPS") {
+            Ok(raw) if !raw.trim().is_empty() => raw
+                .trim()
+                .parse()
+                .map_err(|_| AspError::Config("ALLOW_LEAF_GAPS must be true or false".into()))?,
+            _ => false,
+        };
 
         let sync_poll_interval_secs: u64 = std::env::var("SYNC_POLL_INTERVAL_SECS")
             .unwrap_or_else(|_| "5".to_string())
             .parse()
             .unwrap_or(5);
 
+        let sync_request_delay_ms: u64 = std::env::var("SYNC_REQUEST_DELAY_MS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse()
+            .map_err(|_| AspError::Config("SYNC_REQUEST_DELAY_MS must be a number".into()))?;
+
+        let sync_start_block: u64 = std::env::var("SYNC_START_BLOCK")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse()
+            .map_err(|_| AspError::Config("SYNC_START_BLOCK must be a number".into()))?;
+
+        let max_block_range: u64 = std::env::var("MAX_BLOCK_RANGE")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse()
+            .map_err(|_| AspError::Config("MAX_BLOCK_RANGE must be a number".into()))?;
+
+        let relayer_max_attempts: u32 = std::env::var("RELAYER_MAX_ATTEMPTS")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse()
+            .unwrap_or(3);
+
+        let relayer_retry_base_ms: u64 = std::env::var("RELAYER_RETRY_BASE_MS")
+            .unwrap_or_else(|_| "500".to_string())
+            .parse()
+            .unwrap_or(500);
+
+        let fee_multiplier: f64 = std::env::var("FEE_MULTIPLIER")
+            .unwrap_or_else(|_| DEFAULT_FEE_MULTIPLIER.to_string())
+            .parse()
+            .map_err(|_| AspError::Config("FEE_MULTIPLIER must be a number".into()))?;
+
+        let max_fee_fri: Option<u128> = match std::env::var("MAX_FEE_FRI") {
+            Ok(v) if !v.is_empty() => Some(
+                v.parse()
+                    .map_err(|_| AspError::Config("MAX_FEE_FRI must be an integer".into()))?,
+            ),
+            _ => None,
+        };
+
+        let l1_gas_max_amount = optional_env("L1_GAS_MAX_AMOUNT")?;
+        let l1_gas_max_price = optional_env("L1_GAS_MAX_PRICE")?;
+        let l2_gas_max_amount = optional_env("L2_GAS_MAX_AMOUNT")?;
+        let l2_gas_max_price = optional_env("L2_GAS_MAX_PRICE")?;
+
+        let max_calldata_felts: usize = std::env::var("MAX_CALLDATA_FELTS")
+            .unwrap_or_else(|_| DEFAULT_MAX_CALLDATA_FELTS.to_string())
+            .parse()
+            .map_err(|_| AspError::Config("MAX_CALLDATA_FELTS must be a number".into()))?;
+
+        let log_level = std::env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string());
+        let log_format: LogFormat = std::env::var("LOG_FORMAT").unwrap_or_default().parse()?;
+
+        let webhook_url = std::env::var("WEBHOOK_URL").ok().filter(|u| !u.is_empty());
+
+        let admin_api_token = std::env::var("ADMIN_API_TOKEN").ok().filter(|t| !t.is_empty());
+
+        let max_body_bytes: usize = std::env::var("MAX_BODY_BYTES")
+            .unwrap_or_else(|_| DEFAULT_MAX_BODY_BYTES.to_string())
+            .parse()
+            .map_err(|_| AspError::Config("MAX_BODY_BYTES must be a byte count".into()))?;
+        let request_timeout_secs: u64 = std::env::var("REQUEST_TIMEOUT_SECS")
+            .unwrap_or_else(|_| DEFAULT_REQUEST_TIMEOUT_SECS.to_string())
+            .parse()
+            .map_err(|_| AspError::Config("REQUEST_TIMEOUT_SECS must be a number".into()))?;
+        let proof_request_timeout_secs: u64 = std::env::var("PROOF_REQUEST_TIMEOUT_SECS")
+            .unwrap_or_else(|_| DEFAULT_PROOF_REQUEST_TIMEOUT_SECS.to_string())
+            .parse()
+            .map_err(|_| {
+                AspError::Config("PROOF_REQUEST_TIMEOUT_SECS must be a number".into())
+            })?;
+
+        let trust_forwarded_for: bool = match std::env::var("TRUST_FORWARDED_FOR") {
+            Ok(raw) if !raw.trim().is_empty() => raw.trim().parse().map_err(|_| {
+                AspError::Config("TRUST_FORWARDED_FOR must be true or false".into())
+            })?,
+            _ => false,
+        };
+        let auto_ban_threshold: u32 = std::env::var("AUTO_BAN_THRESHOLD")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse()
+            .map_err(|_| AspError::Config("AUTO_BAN_THRESHOLD must be a number".into()))?;
+        let auto_ban_window_secs: u64 = std::env::var("AUTO_BAN_WINDOW_SECS")
+            .unwrap_or_else(|_| DEFAULT_AUTO_BAN_WINDOW_SECS.to_string())
+            .parse()
+            .map_err(|_| AspError::Config("AUTO_BAN_WINDOW_SECS must be a number".into()))?;
+
+        let status_cache_ms: u64 = std::env::var("STATUS_CACHE_MS")
+            .unwrap_or_else(|_| DEFAULT_STATUS_CACHE_MS.to_string())
+            .parse()
+            .map_err(|_| AspError::Config("STATUS_CACHE_MS must be a number".into()))?;
+
+        let pretty_json: bool = match std::env::var("PRETTY_JSON") {
+            Ok(raw) if !raw.trim().is_empty() => raw
+                .trim()
+                .parse()
+                .map_err(|_| AspError::Config("PRETTY_JSON must be true or false".into()))?,
+            _ => false,
+        };
+
+        let allowed_origins: Vec<String> = std::env::var("ALLOWED_ORIGINS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|origin| origin.trim().to_string())
+            .filter(|origin| !origin.is_empty())
+            .collect();
+
+        let allowed_denominations: Vec<BigUint> = std::env::var("ALLOWED_DENOMINATIONS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|amount| !amount.is_empty())
+            .map(|amount| {
+                BigUint::from_str_radix(amount, 10).map_err(|_| {
+                    AspError::Config(format!(
+                        "ALLOWED_DENOMINATIONS entry '{amount}' is not a decimal amount"
+                    ))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
         Ok(Config {
             host,
             port,
+            http2_enabled,
+            keepalive_secs,
+            max_concurrent_streams,
             rpc_url,
+            chain_id,
             admin_address,
             keystore_path,
             keystore_password,
+            admin_public_key,
+            backup_admin_addresses,
             coordinator_address,
             pool_address,
+            pools,
             database_path,
+            database_pool_size,
+            database_synchronous,
+            database_foreign_keys,
+            database_busy_timeout_ms,
             worker_path,
+            worker_runtime,
+            worker_runtime_args,
+            expected_circuit_hashes,
+            commitment_cache_size,
+            worker_warmup,
+            max_proof_jobs,
+            commitment_domains,
+            expected_self_test_root,
+            tree_height,
+            allow_leaf_gaps,
+            root_history_size,
             sync_poll_interval_secs,
+            sync_request_delay_ms,
+            sync_start_block,
+            max_block_range,
+            relayer_max_attempts,
+            relayer_retry_base_ms,
+            fee_multiplier,
+            max_fee_fri,
+            l1_gas_max_amount,
+            l1_gas_max_price,
+            l2_gas_max_amount,
+            l2_gas_max_price,
+            max_calldata_felts,
+            log_level,
+            log_format,
+            webhook_url,
+            admin_api_token,
+            allowed_origins,
+            allowed_denominations,
+            deposit_queue,
+            min_withdraw_delay_secs,
+            max_body_bytes,
+            request_timeout_secs,
+            proof_request_timeout_secs,
+            trust_forwarded_for,
+            auto_ban_threshold,
+            auto_ban_window_secs,
+            status_cache_ms,
+            pretty_json,
         })
     }
 }
+
+/// An optional integer setting; unset or empty means `None`.
+fn optional_env<T: std::str::FromStr>(name: &str) -> Result<Option<T>, AspError> {
+    match std::env::var(name) {
+        Ok(v) if !v.is_empty() => v
+            .parse()
+            .map(Some)
+            .map_err(|_| AspError::Config(format!("{name} must be an integer"))),
+        _ => Ok(None),
+    }
+}
+
+/// Locate the worker script: `WORKER_PATH` if set, otherwise
+/// `worker/worker.mjs` in the source tree. A relative path that is not found
+/// there is also tried next to the executable, so a deployed binary can ship
+/// the worker beside it. Fails with every absolute path tried.
+fn resolve_worker_path(configured: Option<&str>) -> Result<String, AspError> {
+    let relative = Path::new(configured.unwrap_or(DEFAULT_WORKER_PATH));
+    let mut candidates = match configured {
+        Some(path) => vec![PathBuf::from(path)],
+        None => vec![Path::new(env!("CARGO_MANIFEST_DIR")).join(DEFAULT_WORKER_PATH)],
+    };
+    if relative.is_relative() {
+        if let Some(exe_dir) = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        {
+            candidates.push(exe_dir.join(relative));
+        }
+    }
+
+    if let Some(found) = candidates
+        .iter()
+        .find(|path| path.is_file() && std::fs::File::open(path).is_ok())
+    {
+        return Ok(found.to_string_lossy().to_string());
+    }
+
+    let tried: Vec<String> = candidates
+        .iter()
+        .map(|path| {
+            std::path::absolute(path)
+                .unwrap_or_else(|_| path.clone())
+                .display()
+                .to_string()
+        })
+        .collect();
+    Err(AspError::Config(format!(
+        "WORKER_PATH: no readable worker script at {}",
+        tried.join(" or ")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid_config() -> Config {
+        Config {
+            host: "127.0.0.1".into(),
+            port: 3000,
+            http2_enabled: true,
+            keepalive_secs: 60,
+            max_concurrent_streams: 200,
+            rpc_url: "http://localhost:5050".into(),
+            chain_id: "SN_SEPOLIA".into(),
+            admin_address: "0x1234".into(),
+            keystore_path: "/dev/null".into(),
+            keystore_password: "test".into(),
+            admin_public_key: None,
+            backup_admin_addresses: vec![],
+            coordinator_address: "0xc0".into(),
+            pool_address: "0xa0".into(),
+            pools: HashMap::new(),
+            database_path: ":memory:".into(),
+            database_pool_size: 1,
+            database_synchronous: "NORMAL".into(),
+            database_foreign_keys: true,
+            database_busy_timeout_ms: 5000,
+            worker_path: "worker/worker.mjs".into(),
+            worker_runtime: "node".into(),
+            worker_runtime_args: Vec::new(),
+            expected_circuit_hashes: Default::default(),
+            commitment_cache_size: 0,
+            worker_warmup: true,
+            max_proof_jobs: 4,
+            commitment_domains: Default::default(),
+            expected_self_test_root: None,
+            tree_height: DEFAULT_TREE_HEIGHT,
+            allow_leaf_gaps: false,
+            root_history_size: DEFAULT_ROOT_HISTORY_SIZE,
+            sync_poll_interval_secs: 5,
+            sync_request_delay_ms: 0,
+            sync_start_block: 0,
+            max_block_range: 1000,
+            relayer_max_attempts: 3,
+            relayer_retry_base_ms: 500,
+            fee_multiplier: 1.5,
+            max_fee_fri: None,
+            l1_gas_max_amount: None,
+            l1_gas_max_price: None,
+            l2_gas_max_amount: None,
+            l2_gas_max_price: None,
+            max_calldata_felts: 4000,
+            log_level: "info".into(),
+            log_format: LogFormat::Text,
+            webhook_url: None,
+            admin_api_token: None,
+            allowed_origins: Vec::new(),
+            allowed_denominations: Vec::new(),
+            deposit_queue: false,
+            min_withdraw_delay_secs: 0,
+            max_body_bytes: 2 * 1024 * 1024,
+            request_timeout_secs: 30,
+            proof_request_timeout_secs: 300,
+            trust_forwarded_for: false,
+            auto_ban_threshold: 0,
+            auto_ban_window_secs: 60,
+            status_cache_ms: 1000,
+            pretty_json: false,
+        }
+    }
+
+    fn problems(config: &Config) -> String {
+        match config.validate() {
+            Err(AspError::Config(msg)) => msg,
+            other => panic!("expected config error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn valid_config_passes() {
+        assert!(valid_config().validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_admin_address() {
+        let config = Config {
+            admin_address: "alice".into(),
+            ..valid_config()
+        };
+        assert!(problems(&config).contains("ADMIN_ADDRESS"));
+    }
+
+    #[test]
+    fn rejects_invalid_admin_public_key() {
+        let config = Config {
+            admin_public_key: Some("0xnope".into()),
+            ..valid_config()
+        };
+        assert!(problems(&config).contains("ADMIN_PUBLIC_KEY"));
+
+        let config = Config {
+            backup_admin_addresses: vec!["0x9".into(), "backup".into()],
+            ..valid_config()
+        };
+        let msg = problems(&config);
+        assert!(msg.contains("'backup'") && !msg.contains("'0x9'"), "{msg}");
+    }
+
+    #[test]
+    fn rejects_invalid_coordinator_address() {
+        let config = Config {
+            coordinator_address: "0xzz".into(),
+            ..valid_config()
+        };
+        assert!(problems(&config).contains("COORDINATOR_ADDRESS"));
+    }
+
+    #[test]
+    fn rejects_invalid_pool_address() {
+        let config = Config {
+            pool_address: "".into(),
+            ..valid_config()
+        };
+        assert!(problems(&config).contains("POOL_ADDRESS"));
+    }
+
+    #[test]
+    fn rejects_invalid_pool_route() {
+        let config = Config {
+            pools: HashMap::from([("0x1:0x2".to_string(), "0xb0".to_string())]),
+            ..valid_config()
+        };
+        assert!(problems(&config).contains("POOLS"));
+    }
+
+    #[test]
+    fn rejects_invalid_rpc_url() {
+        let config = Config {
+            rpc_url: "not a url".into(),
+            ..valid_config()
+        };
+        assert!(problems(&config).contains("STARKNET_RPC_URL"));
+    }
+
+    #[test]
+    fn rejects_invalid_webhook_url() {
+        let config = Config {
+            webhook_url: Some("::".into()),
+            ..valid_config()
+        };
+        assert!(problems(&config).contains("WEBHOOK_URL"));
+    }
+
+    #[test]
+    fn rejects_zero_pool_size() {
+        let config = Config {
+            database_pool_size: 0,
+            ..valid_config()
+        };
+        assert!(problems(&config).contains("DATABASE_POOL_SIZE"));
+    }
+
+    #[test]
+    fn rejects_out_of_range_tree_height() {
+        for tree_height in [0, MAX_TREE_HEIGHT + 1] {
+            let config = Config {
+                tree_height,
+                ..valid_config()
+            };
+            assert!(problems(&config).contains("TREE_HEIGHT"));
+        }
+    }
+
+    #[test]
+    fn rejects_zero_poll_interval() {
+        let config = Config {
+            sync_poll_interval_secs: 0,
+            ..valid_config()
+        };
+        assert!(problems(&config).contains("SYNC_POLL_INTERVAL_SECS"));
+    }
+
+    #[test]
+    fn rejects_zero_request_timeouts() {
+        let config = Config {
+            request_timeout_secs: 0,
+            proof_request_timeout_secs: 0,
+            ..valid_config()
+        };
+        let problems = problems(&config);
+        assert!(problems.contains("REQUEST_TIMEOUT_SECS"));
+        assert!(problems.contains("PROOF_REQUEST_TIMEOUT_SECS"));
+    }
+
+    #[test]
+    fn rejects_zero_block_range() {
+        let config = Config {
+            max_block_range: 0,
+            ..valid_config()
+        };
+        assert!(problems(&config).contains("MAX_BLOCK_RANGE"));
+    }
+
+    #[test]
+    fn rejects_zero_relayer_attempts() {
+        let config = Config {
+            relayer_max_attempts: 0,
+            ..valid_config()
+        };
+        assert!(problems(&config).contains("RELAYER_MAX_ATTEMPTS"));
+    }
+
+    #[test]
+    fn rejects_fee_multiplier_below_one() {
+        for fee_multiplier in [0.5, f64::NAN] {
+            let config = Config {
+                fee_multiplier,
+                ..valid_config()
+            };
+            assert!(problems(&config).contains("FEE_MULTIPLIER"));
+        }
+    }
+
+    #[test]
+    fn rejects_zero_max_fee() {
+        let config = Config {
+            max_fee_fri: Some(0),
+            ..valid_config()
+        };
+        assert!(problems(&config).contains("MAX_FEE_FRI"));
+    }
+
+    #[test]
+    fn rejects_zero_gas_bounds() {
+        let config = Config {
+            l1_gas_max_price: Some(0),
+            l2_gas_max_amount: Some(0),
+            ..valid_config()
+        };
+        let problems = problems(&config);
+        assert!(problems.contains("L1_GAS_MAX_PRICE"));
+        assert!(problems.contains("L2_GAS_MAX_AMOUNT"));
+        assert!(!problems.contains("L1_GAS_MAX_AMOUNT"));
+
+        let config = Config {
+            l1_gas_max_amount: Some(30_000),
+            l2_gas_max_price: Some(1),
+            ..valid_config()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_zero_body_limit() {
+        let config = Config {
+            max_body_bytes: 0,
+            ..valid_config()
+        };
+        assert!(problems(&config).contains("MAX_BODY_BYTES"));
+    }
+
+    #[test]
+    fn rejects_zero_auto_ban_window() {
+        let config = Config {
+            auto_ban_threshold: 100,
+            auto_ban_window_secs: 0,
+            ..valid_config()
+        };
+        assert!(problems(&config).contains("AUTO_BAN_WINDOW_SECS"));
+    }
+
+    #[test]
+    fn rejects_zero_concurrent_streams() {
+        let config = Config {
+            max_concurrent_streams: 0,
+            ..valid_config()
+        };
+        assert!(problems(&config).contains("MAX_CONCURRENT_STREAMS"));
+    }
+
+    #[test]
+    fn rejects_shared_or_empty_domain_separators() {
+        let shared = Config {
+            commitment_domains: DomainSeparators {
+                note: "zylith".into(),
+                position: "zylith".into(),
+            },
+            ..valid_config()
+        };
+        assert!(problems(&shared).contains("must differ"));
+
+        let empty = Config {
+            commitment_domains: DomainSeparators {
+                note: String::new(),
+                ..Default::default()
+            },
+            ..valid_config()
+        };
+        assert!(problems(&empty).contains("must not be empty"));
+    }
+
+    #[test]
+    fn rejects_zero_proof_jobs() {
+        let config = Config {
+            max_proof_jobs: 0,
+            ..valid_config()
+        };
+        assert!(problems(&config).contains("MAX_PROOF_JOBS"));
+    }
+
+    #[test]
+    fn validates_allowed_origins() {
+        let config = Config {
+            allowed_origins: vec!["https://app.zylith.xyz".into(), "http://localhost:5173".into()],
+            ..valid_config()
+        };
+        assert!(config.validate().is_ok());
+
+        let config = Config {
+            allowed_origins: vec!["*".into()],
+            ..valid_config()
+        };
+        assert!(config.validate().is_ok());
+
+        for bad in [vec!["*", "https://a.xyz"], vec!["https://a.xyz/"], vec!["a.xyz"]] {
+            let config = Config {
+                allowed_origins: bad.into_iter().map(String::from).collect(),
+                ..valid_config()
+            };
+            assert!(problems(&config).contains("ALLOWED_ORIGINS"));
+        }
+    }
+
+    #[test]
+    fn rejects_zero_or_oversized_denomination() {
+        let config = Config {
+            allowed_denominations: vec![BigUint::from(10u8).pow(18)],
+            ..valid_config()
+        };
+        assert!(config.validate().is_ok());
+
+        for bad in [BigUint::from(0u8), BigUint::from(1u8) << 256] {
+            let config = Config {
+                allowed_denominations: vec![BigUint::from(1u8), bad],
+                ..valid_config()
+            };
+            assert!(problems(&config).contains("ALLOWED_DENOMINATIONS"));
+        }
+    }
+
+    #[test]
+    fn parses_chain_ids() {
+        let with_id = |chain_id: &str| Config {
+            chain_id: chain_id.into(),
+            ..valid_config()
+        };
+        assert_eq!(
+            with_id("SN_SEPOLIA").chain_id_felt().unwrap(),
+            Felt::from_hex("0x534e5f5345504f4c4941").unwrap()
+        );
+        assert_eq!(
+            with_id("SN_MAIN").chain_id_felt().unwrap(),
+            with_id("0x534e5f4d41494e").chain_id_felt().unwrap()
+        );
+        for bad in ["", "0xnothex", "a_chain_id_longer_than_31_chars!"] {
+            assert!(problems(&with_id(bad)).contains("STARKNET_CHAIN_ID"), "{bad}");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_log_level() {
+        let config = Config {
+            log_level: "zylith=loud".into(),
+            ..valid_config()
+        };
+        assert!(problems(&config).contains("RUST_LOG"));
+    }
+
+    #[test]
+    fn reports_all_problems_at_once() {
+        let config = Config {
+            admin_address: "alice".into(),
+            rpc_url: "not a url".into(),
+            tree_height: 0,
+            ..valid_config()
+        };
+        let msg = problems(&config);
+        assert!(msg.contains("ADMIN_ADDRESS"));
+        assert!(msg.contains("STARKNET_RPC_URL"));
+        assert!(msg.contains("TREE_HEIGHT"));
+    }
+
+    #[test]
+    fn resolves_default_worker_path() {
+        let path = resolve_worker_path(None).unwrap();
+        assert!(path.ends_with(DEFAULT_WORKER_PATH), "{path}");
+    }
+
+    #[test]
+    fn rejects_missing_worker_path() {
+        let err = resolve_worker_path(Some("/nonexistent/worker.mjs"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("WORKER_PATH"), "{err}");
+        assert!(err.contains("/nonexistent/worker.mjs"), "{err}");
+
+        // A relative path is reported absolute, both as given and beside the binary
+        let err = resolve_worker_path(Some("missing/worker.mjs"))
+            .unwrap_err()
+            .to_string();
+        let cwd = std::env::current_dir().unwrap().join("missing/worker.mjs");
+        assert!(err.contains(&cwd.display().to_string()), "{err}");
+        assert_eq!(err.matches("missing/worker.mjs").count(), 2, "{err}");
+    }
+}
@@ -0,0 +1,356 @@
+use std::collections::HashSet;
+
+use crate::error::AspError;
+
+use super::Database;
+
+/// A numbered schema change. Migrations are applied in ascending `version`
+/// order and each version is recorded in `schema_migrations` once applied.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+/// All schema migrations, in order. Never edit an applied migration —
+/// append a new one instead.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        sql: "
+            CREATE TABLE IF NOT EXISTS commitments (
+                leaf_index INTEGER PRIMARY KEY,
+                commitment TEXT NOT NULL,
+                deposit_tx TEXT,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS merkle_roots (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                root TEXT NOT NULL,
+                leaf_count INTEGER NOT NULL,
+                submit_tx TEXT,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS nullifiers (
+                nullifier_hash TEXT PRIMARY KEY,
+                circuit_type TEXT NOT NULL,
+                tx_hash TEXT,
+                spent_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS proof_jobs (
+                id TEXT PRIMARY KEY,
+                circuit_type TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                error TEXT,
+                tx_hash TEXT,
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS sync_state (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+        ",
+    },
+    Migration {
+        version: 2,
+        description: "index commitment values and root timestamps",
+        sql: "
+            CREATE INDEX IF NOT EXISTS idx_commitments_commitment ON commitments(commitment);
+            CREATE INDEX IF NOT EXISTS idx_merkle_roots_created_at ON merkle_roots(created_at);
+        ",
+    },
+    Migration {
+        version: 3,
+        description: "index nullifiers for paged listing",
+        sql: "
+            CREATE INDEX IF NOT EXISTS idx_nullifiers_circuit_spent_at ON nullifiers(circuit_type, spent_at);
+            CREATE INDEX IF NOT EXISTS idx_nullifiers_spent_at ON nullifiers(spent_at);
+        ",
+    },
+    Migration {
+        version: 4,
+        description: "record deposit token and amount",
+        sql: "
+            ALTER TABLE commitments ADD COLUMN token TEXT;
+            ALTER TABLE commitments ADD COLUMN amount_low TEXT;
+            ALTER TABLE commitments ADD COLUMN amount_high TEXT;
+        ",
+    },
+    Migration {
+        version: 5,
+        description: "audit log of mutating operations",
+        sql: "
+            CREATE TABLE IF NOT EXISTS operations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                request_hash TEXT NOT NULL,
+                tx_hash TEXT,
+                leaf_indices TEXT NOT NULL DEFAULT '[]',
+                status TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+            CREATE INDEX IF NOT EXISTS idx_operations_kind ON operations(kind, id);
+        ",
+    },
+    Migration {
+        version: 6,
+        description: "per-note request signing key",
+        sql: "ALTER TABLE commitments ADD COLUMN auth_pubkey TEXT;",
+    },
+    Migration {
+        version: 7,
+        description: "track queued deposit submissions",
+        sql: "
+            ALTER TABLE commitments ADD COLUMN deposit_status TEXT;
+            ALTER TABLE commitments ADD COLUMN deposit_error TEXT;
+            ALTER TABLE commitments ADD COLUMN root_tx TEXT;
+        ",
+    },
+    Migration {
+        version: 8,
+        description: "record where each spent nullifier was learned",
+        sql: "
+            ALTER TABLE nullifiers ADD COLUMN origin TEXT NOT NULL DEFAULT 'local';
+            UPDATE nullifiers SET origin = 'chain' WHERE circuit_type = 'synced';
+            CREATE INDEX IF NOT EXISTS idx_nullifiers_origin_spent_at ON nullifiers(origin, spent_at);
+        ",
+    },
+    Migration {
+        version: 9,
+        description: "persist banned client IPs",
+        sql: "
+            CREATE TABLE IF NOT EXISTS banned_sources (
+                ip TEXT PRIMARY KEY,
+                reason TEXT NOT NULL,
+                automatic INTEGER NOT NULL DEFAULT 0,
+                banned_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+        ",
+    },
+    Migration {
+        version: 10,
+        description: "record the root tx of each operation and index tx lookups",
+        sql: "
+            ALTER TABLE operations ADD COLUMN root_tx TEXT;
+            CREATE INDEX IF NOT EXISTS idx_operations_tx_hash ON operations(tx_hash);
+            CREATE INDEX IF NOT EXISTS idx_operations_root_tx ON operations(root_tx);
+        ",
+    },
+    Migration {
+        version: 11,
+        description: "index commitments by the tx that inserted them",
+        sql: "
+            CREATE INDEX IF NOT EXISTS idx_commitments_deposit_tx ON commitments(deposit_tx);
+        ",
+    },
+    Migration {
+        version: 12,
+        description: "record proof generation time",
+        sql: "
+            ALTER TABLE proof_jobs ADD COLUMN duration_ms INTEGER;
+            CREATE INDEX IF NOT EXISTS idx_proof_jobs_circuit
+                ON proof_jobs(circuit_type, duration_ms);
+        ",
+    },
+    Migration {
+        version: 13,
+        description: "track nullifiers handed out for self-submission",
+        sql: "
+            CREATE TABLE IF NOT EXISTS pending_nullifiers (
+                nullifier_hash TEXT PRIMARY KEY,
+                circuit_type TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+        ",
+    },
+    Migration {
+        version: 14,
+        description: "keep auth keys of rolled-back deposits for re-ingested leaves",
+        sql: "
+            CREATE TABLE IF NOT EXISTS rolled_back_auth_pubkeys (
+                commitment TEXT PRIMARY KEY,
+                auth_pubkey TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+        ",
+    },
+];
+
+impl Database {
+    /// Apply every migration in `migrations` that has not been recorded yet.
+    /// Each migration runs in its own transaction together with its
+    /// `schema_migrations` bookkeeping row.
+    pub(crate) fn apply_migrations(&self, migrations: &[Migration]) -> Result<(), AspError> {
+        let mut conn = self.conn()?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS schema_migrations (
+                version INTEGER PRIMARY KEY,
+                description TEXT NOT NULL,
+                applied_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );",
+        )?;
+
+        let applied: HashSet<u32> = {
+            let mut stmt = conn.prepare("SELECT version FROM schema_migrations")?;
+            let versions = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<HashSet<u32>, _>>()?;
+            versions
+        };
+
+        let mut pending: Vec<&Migration> = migrations
+            .iter()
+            .filter(|m| !applied.contains(&m.version))
+            .collect();
+        pending.sort_by_key(|m| m.version);
+
+        for migration in pending {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration.sql)?;
+            tx.execute(
+                "INSERT INTO schema_migrations (version, description) VALUES (?1, ?2)",
+                rusqlite::params![migration.version, migration.description],
+            )?;
+            tx.commit()?;
+            tracing::info!(
+                version = migration.version,
+                description = migration.description,
+                "Applied database migration"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Highest applied migration version (0 for a fresh database).
+    pub fn schema_version(&self) -> Result<u32, AspError> {
+        let conn = self.conn()?;
+        let version: u32 = conn.query_row(
+            "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_idempotent() {
+        let db = Database::new(":memory:").unwrap();
+        db.run_migrations().unwrap();
+        db.insert_commitment(0, "aaa", None).unwrap();
+
+        // Second run must not re-apply or wipe anything
+        db.run_migrations().unwrap();
+        assert_eq!(db.schema_version().unwrap(), MIGRATIONS.len() as u32);
+        assert_eq!(db.get_leaf_count().unwrap(), 1);
+
+        let conn = db.conn().unwrap();
+        let recorded: u32 = conn
+            .query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(recorded, MIGRATIONS.len() as u32);
+    }
+
+    #[test]
+    fn test_new_migration_applies_on_top() {
+        let db = Database::new(":memory:").unwrap();
+        db.run_migrations().unwrap();
+        db.insert_commitment(0, "aaa", None).unwrap();
+
+        let next = MIGRATIONS.len() as u32 + 1;
+        let mut extended = MIGRATIONS.to_vec();
+        extended.push(Migration {
+            version: next,
+            description: "add note column",
+            sql: "ALTER TABLE commitments ADD COLUMN note TEXT;",
+        });
+
+        db.apply_migrations(&extended).unwrap();
+        assert_eq!(db.schema_version().unwrap(), next);
+
+        // Existing rows survive and the new column is usable
+        let conn = db.conn().unwrap();
+        conn.execute("UPDATE commitments SET note = 'x' WHERE leaf_index = 0", [])
+            .unwrap();
+        drop(conn);
+        assert_eq!(db.get_leaf_count().unwrap(), 1);
+
+        // Re-running the extended set is a no-op (ALTER would fail if repeated)
+        db.apply_migrations(&extended).unwrap();
+    }
+
+    fn query_plan(db: &Database, sql: &str) -> String {
+        let conn = db.conn().unwrap();
+        let mut stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {sql}")).unwrap();
+        let details = stmt
+            .query_map([], |row| row.get::<_, String>(3))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        details.join("\n")
+    }
+
+    #[test]
+    fn test_commitment_lookup_uses_index() {
+        let db = Database::new(":memory:").unwrap();
+        db.run_migrations().unwrap();
+        for i in 0..500u32 {
+            db.insert_commitment(i, &format!("{}", 1_000_000 + i), None)
+                .unwrap();
+        }
+
+        let plan = query_plan(
+            &db,
+            "SELECT leaf_index FROM commitments WHERE commitment = '1000250'",
+        );
+        assert!(plan.contains("idx_commitments_commitment"), "plan: {plan}");
+        assert_eq!(db.find_commitment_leaf_index("1000250").unwrap(), Some(250));
+    }
+
+    #[test]
+    fn test_commitments_by_tx_uses_index() {
+        let db = Database::new(":memory:").unwrap();
+        db.run_migrations().unwrap();
+        for i in 0..500u32 {
+            let tx = format!("0x{:x}", i / 2);
+            db.insert_commitment(i, &format!("{}", 1_000_000 + i), Some(&tx))
+                .unwrap();
+        }
+
+        let plan = query_plan(
+            &db,
+            "SELECT leaf_index FROM commitments WHERE deposit_tx = '0x7d'",
+        );
+        assert!(plan.contains("idx_commitments_deposit_tx"), "plan: {plan}");
+    }
+
+    #[test]
+    fn test_roots_by_created_at_uses_index() {
+        let db = Database::new(":memory:").unwrap();
+        db.run_migrations().unwrap();
+        for i in 0..500u32 {
+            db.insert_root(&format!("root{i}"), i + 1, None).unwrap();
+        }
+
+        let plan = query_plan(
+            &db,
+            "SELECT root FROM merkle_roots WHERE created_at >= '2000-01-01' ORDER BY created_at",
+        );
+        assert!(plan.contains("idx_merkle_roots_created_at"), "plan: {plan}");
+    }
+}
@@ -1,4 +1,10 @@
+pub mod migrations;
+pub mod nullifiers;
 pub mod queries;
 mod schema;
 
-pub use schema::Database;
+pub use nullifiers::{NullifierStore, SqliteNullifierStore};
+pub use schema::{
+    Database, DbOptions, StorageSize, DEFAULT_BUSY_TIMEOUT_MS, SYNCHRONOUS_MODES, WRITE_RETRIES,
+    WRITE_RETRY_BASE_MS,
+};
@@ -0,0 +1,195 @@
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+
+use crate::error::AspError;
+use crate::prover::CircuitType;
+
+use super::queries::{NullifierOrigin, NullifierRow};
+use super::schema::{pooled_conn, write_retrying};
+
+/// Where spent nullifiers are kept. [`Database`](super::Database) delegates
+/// its nullifier methods to one of these, so the set can live outside the
+/// main SQLite file (sharded, Redis-backed, ...) once it outgrows one table.
+pub trait NullifierStore: Send + Sync {
+    /// Record `nullifier_hash` as spent. Recording a spent nullifier again
+    /// is not an error and keeps the first record.
+    fn insert(
+        &self,
+        nullifier_hash: &str,
+        circuit_type: CircuitType,
+        origin: NullifierOrigin,
+        tx_hash: Option<&str>,
+    ) -> Result<(), AspError>;
+
+    fn is_spent(&self, nullifier_hash: &str) -> Result<bool, AspError>;
+
+    fn get(&self, nullifier_hash: &str) -> Result<Option<NullifierRow>, AspError>;
+
+    /// Rows for whichever of `hashes` have been spent.
+    fn get_many(&self, hashes: &[String]) -> Result<Vec<NullifierRow>, AspError> {
+        let mut rows = Vec::new();
+        for hash in hashes {
+            rows.extend(self.get(hash)?);
+        }
+        Ok(rows)
+    }
+}
+
+/// The default store: the `nullifiers` table of the ASP database.
+pub struct SqliteNullifierStore {
+    pool: Pool<SqliteConnectionManager>,
+}
+
+impl SqliteNullifierStore {
+    pub(super) fn new(pool: Pool<SqliteConnectionManager>) -> Self {
+        SqliteNullifierStore { pool }
+    }
+}
+
+fn nullifier_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<NullifierRow> {
+    Ok(NullifierRow {
+        nullifier_hash: row.get(0)?,
+        circuit_type: row.get(1)?,
+        origin: row.get(2)?,
+        tx_hash: row.get(3)?,
+        spent_at: row.get(4)?,
+    })
+}
+
+impl NullifierStore for SqliteNullifierStore {
+    fn insert(
+        &self,
+        nullifier_hash: &str,
+        circuit_type: CircuitType,
+        origin: NullifierOrigin,
+        tx_hash: Option<&str>,
+    ) -> Result<(), AspError> {
+        write_retrying(&self.pool, |conn| {
+            conn.execute(
+                "INSERT OR IGNORE INTO nullifiers (nullifier_hash, circuit_type, origin, tx_hash)
+                 VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![nullifier_hash, circuit_type, origin, tx_hash],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn is_spent(&self, nullifier_hash: &str) -> Result<bool, AspError> {
+        let conn = pooled_conn(&self.pool)?;
+        let count: u32 = conn.query_row(
+            "SELECT COUNT(*) FROM nullifiers WHERE nullifier_hash = ?1",
+            rusqlite::params![nullifier_hash],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    fn get(&self, nullifier_hash: &str) -> Result<Option<NullifierRow>, AspError> {
+        let conn = pooled_conn(&self.pool)?;
+        let mut stmt = conn.prepare(
+            "SELECT nullifier_hash, circuit_type, origin, tx_hash, spent_at FROM nullifiers
+             WHERE nullifier_hash = ?1",
+        )?;
+        let mut rows = stmt.query_map(rusqlite::params![nullifier_hash], nullifier_from_row)?;
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
+        }
+    }
+
+    /// One query for the whole batch.
+    fn get_many(&self, hashes: &[String]) -> Result<Vec<NullifierRow>, AspError> {
+        if hashes.is_empty() {
+            return Ok(Vec::new());
+        }
+        let placeholders = vec!["?"; hashes.len()].join(", ");
+        let conn = pooled_conn(&self.pool)?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT nullifier_hash, circuit_type, origin, tx_hash, spent_at FROM nullifiers
+             WHERE nullifier_hash IN ({placeholders})"
+        ))?;
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(hashes), nullifier_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::db::Database;
+
+    /// Nullifiers kept in a map, standing in for a non-SQLite backend.
+    #[derive(Default)]
+    struct MemoryStore {
+        rows: Mutex<HashMap<String, NullifierRow>>,
+    }
+
+    impl NullifierStore for MemoryStore {
+        fn insert(
+            &self,
+            nullifier_hash: &str,
+            circuit_type: CircuitType,
+            origin: NullifierOrigin,
+            tx_hash: Option<&str>,
+        ) -> Result<(), AspError> {
+            self.rows
+                .lock()
+                .unwrap()
+                .entry(nullifier_hash.to_string())
+                .or_insert_with(|| NullifierRow {
+                    nullifier_hash: nullifier_hash.to_string(),
+                    circuit_type,
+                    origin,
+                    tx_hash: tx_hash.map(str::to_string),
+                    spent_at: "2026-01-01 00:00:00".into(),
+                });
+            Ok(())
+        }
+
+        fn is_spent(&self, nullifier_hash: &str) -> Result<bool, AspError> {
+            Ok(self.rows.lock().unwrap().contains_key(nullifier_hash))
+        }
+
+        fn get(&self, nullifier_hash: &str) -> Result<Option<NullifierRow>, AspError> {
+            Ok(self.rows.lock().unwrap().get(nullifier_hash).cloned())
+        }
+    }
+
+    #[test]
+    fn database_delegates_to_custom_store() {
+        let store = Arc::new(MemoryStore::default());
+        let db = Database::new(":memory:")
+            .unwrap()
+            .with_nullifier_store(store.clone());
+        db.run_migrations().unwrap();
+
+        db.insert_nullifier("nul1", CircuitType::Swap, Some("0x1")).unwrap();
+        db.insert_synced_nullifier("nul2").unwrap();
+        db.insert_nullifier("nul1", CircuitType::Mint, None).unwrap();
+
+        assert!(db.is_nullifier_spent("nul1").unwrap());
+        assert!(!db.is_nullifier_spent("nul3").unwrap());
+        let row = db.get_nullifier("nul1").unwrap().unwrap();
+        assert_eq!(row.circuit_type, CircuitType::Swap);
+        assert_eq!(row.tx_hash.as_deref(), Some("0x1"));
+        let synced = db.get_nullifier("nul2").unwrap().unwrap();
+        assert_eq!(synced.origin, NullifierOrigin::Chain);
+
+        let hashes = vec!["nul2".to_string(), "missing".to_string()];
+        assert_eq!(db.get_nullifiers(&hashes).unwrap().len(), 1);
+
+        // Nothing reached the SQLite table
+        assert_eq!(store.rows.lock().unwrap().len(), 2);
+        let count: u32 = db
+            .conn()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM nullifiers", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+}
@@ -1,4 +1,14 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::OptionalExtension;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
 use crate::error::AspError;
+use crate::prover::CircuitType;
 
 use super::Database;
 
@@ -9,28 +19,228 @@ pub struct CommitmentRow {
     pub deposit_tx: Option<String>,
 }
 
+/// Most missing ranges named in a [`check_leaf_sequence`] error.
+const MAX_REPORTED_GAPS: usize = 10;
+
+/// Fail unless `commitments` (in leaf order, as from
+/// [`Database::get_all_commitments`]) hold exactly the leaves `0..n`. The
+/// tree is rebuilt by position, so after a gap every later leaf would land
+/// at the wrong index. The error names the missing indices.
+pub fn check_leaf_sequence(commitments: &[CommitmentRow]) -> Result<(), AspError> {
+    let mut gaps = Vec::new();
+    let mut expected = 0u32;
+    for row in commitments {
+        if row.leaf_index > expected {
+            gaps.push(if row.leaf_index - 1 == expected {
+                expected.to_string()
+            } else {
+                format!("{expected}..={}", row.leaf_index - 1)
+            });
+        }
+        expected = row.leaf_index.saturating_add(1);
+    }
+    if gaps.is_empty() {
+        return Ok(());
+    }
+
+    let mut missing = gaps[..gaps.len().min(MAX_REPORTED_GAPS)].join(", ");
+    if gaps.len() > MAX_REPORTED_GAPS {
+        missing.push_str(&format!(" and {} more ranges", gaps.len() - MAX_REPORTED_GAPS));
+    }
+    Err(AspError::Internal(format!(
+        "Commitment leaf indices are not contiguous, missing {missing}"
+    )))
+}
+
+/// A root recorded after a tree mutation.
+#[derive(Debug, Clone)]
+pub struct RootRow {
+    pub root: String,
+    pub leaf_count: u32,
+    pub submit_tx: Option<String>,
+    pub created_at: String,
+}
+
+/// Token and amount a depositor declared for a commitment. Either part may be
+/// absent; amounts are stored as normalized decimal u128 limbs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DepositMetadata {
+    pub token: Option<String>,
+    pub amount_low: Option<String>,
+    pub amount_high: Option<String>,
+}
+
+/// On-chain submission state of a deposited leaf. `status` is `pending`
+/// while the deposit waits in the queue, `confirmed` once relayed and
+/// `failed` if relaying gave up (with the reason in `error`). It is `None`
+/// for leaves relayed inline or learned from chain sync.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepositStatusRow {
+    pub leaf_index: u32,
+    pub commitment: String,
+    pub status: Option<String>,
+    pub deposit_tx: Option<String>,
+    pub root_tx: Option<String>,
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct NullifierRow {
     pub nullifier_hash: String,
-    pub circuit_type: String,
+    pub circuit_type: CircuitType,
+    pub origin: NullifierOrigin,
     pub tx_hash: Option<String>,
+    pub spent_at: String,
+}
+
+/// Where the ASP learned that a nullifier was spent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum NullifierOrigin {
+    /// Spent through this ASP's own withdraw/swap/mint/burn endpoints.
+    Local,
+    /// Seen in a `NullifierSpent` event by the event sync. The event does not
+    /// say which circuit spent it, so its circuit type is `synced`.
+    Chain,
+}
+
+impl NullifierOrigin {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NullifierOrigin::Local => "local",
+            NullifierOrigin::Chain => "chain",
+        }
+    }
+}
+
+impl fmt::Display for NullifierOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for NullifierOrigin {
+    type Err = AspError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "local" => Ok(NullifierOrigin::Local),
+            "chain" => Ok(NullifierOrigin::Chain),
+            _ => Err(AspError::InvalidInput("origin must be one of: local, chain".into())),
+        }
+    }
+}
+
+/// One audited call to a mutating endpoint. `leaf_indices` are the leaves
+/// the operation inserted, in insertion order.
+#[derive(Debug, Clone)]
+pub struct OperationRow {
+    pub id: i64,
+    pub kind: String,
+    pub request_hash: String,
+    pub tx_hash: Option<String>,
+    /// Merkle root submission that followed `tx_hash`, if any.
+    pub root_tx: Option<String>,
+    pub leaf_indices: Vec<u32>,
+    pub status: String,
+    pub created_at: String,
+}
+
+fn operation_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<OperationRow> {
+    let leaf_indices: String = row.get(4)?;
+    Ok(OperationRow {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        request_hash: row.get(2)?,
+        tx_hash: row.get(3)?,
+        leaf_indices: serde_json::from_str(&leaf_indices).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(4, rusqlite::types::Type::Text, e.into())
+        })?,
+        status: row.get(5)?,
+        created_at: row.get(6)?,
+        root_tx: row.get(7)?,
+    })
+}
+
+/// A client IP refused by the API, set by an operator or by auto-banning.
+#[derive(Debug, Clone)]
+pub struct BannedSourceRow {
+    pub ip: String,
+    pub reason: String,
+    /// Banned for exceeding `AUTO_BAN_THRESHOLD` rather than by an operator.
+    pub automatic: bool,
+    pub banned_at: String,
+}
+
+fn banned_source_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<BannedSourceRow> {
+    Ok(BannedSourceRow {
+        ip: row.get(0)?,
+        reason: row.get(1)?,
+        automatic: row.get(2)?,
+        banned_at: row.get(3)?,
+    })
 }
 
 impl Database {
     // --- Commitments ---
 
+    /// Store a commitment at `leaf_index` unless the leaf is taken. A
+    /// commitment whose keyed deposit was rolled back gets its auth key back,
+    /// so spends of a note that reached the chain anyway stay signed.
     pub fn insert_commitment(
         &self,
         leaf_index: u32,
         commitment: &str,
         deposit_tx: Option<&str>,
     ) -> Result<(), AspError> {
-        let conn = self.conn()?;
-        conn.execute(
-            "INSERT OR IGNORE INTO commitments (leaf_index, commitment, deposit_tx) VALUES (?1, ?2, ?3)",
-            rusqlite::params![leaf_index, commitment, deposit_tx],
-        )?;
-        Ok(())
+        self.write(|conn| {
+            let tx = conn.transaction()?;
+            let inserted = tx.execute(
+                "INSERT OR IGNORE INTO commitments (leaf_index, commitment, deposit_tx)
+                 VALUES (?1, ?2, ?3)",
+                rusqlite::params![leaf_index, commitment, deposit_tx],
+            )?;
+            if inserted > 0 {
+                tx.execute(
+                    "UPDATE commitments SET auth_pubkey = (
+                         SELECT auth_pubkey FROM rolled_back_auth_pubkeys WHERE commitment = ?2
+                     )
+                     WHERE leaf_index = ?1
+                       AND EXISTS (SELECT 1 FROM rolled_back_auth_pubkeys WHERE commitment = ?2)",
+                    rusqlite::params![leaf_index, commitment],
+                )?;
+                tx.execute(
+                    "DELETE FROM rolled_back_auth_pubkeys WHERE commitment = ?1",
+                    rusqlite::params![commitment],
+                )?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// Append `commitments` at the next free leaf indices in one transaction,
+    /// so an operation's outputs are stored together or not at all. Returns
+    /// the first leaf index used.
+    pub fn insert_commitments(
+        &self,
+        commitments: &[&str],
+        deposit_tx: Option<&str>,
+    ) -> Result<u32, AspError> {
+        self.write(|conn| {
+            let tx = conn.transaction()?;
+            let first: u32 =
+                tx.query_row("SELECT COUNT(*) FROM commitments", [], |row| row.get(0))?;
+            for (offset, commitment) in commitments.iter().enumerate() {
+                tx.execute(
+                    "INSERT INTO commitments (leaf_index, commitment, deposit_tx)
+                     VALUES (?1, ?2, ?3)",
+                    rusqlite::params![first + offset as u32, commitment, deposit_tx],
+                )?;
+            }
+            tx.commit()?;
+            Ok(first)
+        })
     }
 
     pub fn get_commitment(&self, leaf_index: u32) -> Result<Option<CommitmentRow>, AspError> {
@@ -68,6 +278,79 @@ impl Database {
         Ok(rows)
     }
 
+    /// Call `f` with every commitment in leaf order, reading rows off a single
+    /// query rather than collecting them. Stops early when `f` returns `false`.
+    pub fn for_each_commitment(
+        &self,
+        mut f: impl FnMut(CommitmentRow) -> bool,
+    ) -> Result<(), AspError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT leaf_index, commitment, deposit_tx FROM commitments ORDER BY leaf_index ASC",
+        )?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let row = CommitmentRow {
+                leaf_index: row.get(0)?,
+                commitment: row.get(1)?,
+                deposit_tx: row.get(2)?,
+            };
+            if !f(row) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert commitments from a backup in one transaction, in order. Rows
+    /// already stored with the same commitment are skipped; every other row
+    /// must take the next free leaf index. Nothing is written if any row is
+    /// rejected. Returns `(imported, skipped)`.
+    pub fn import_commitments(&self, rows: &[CommitmentRow]) -> Result<(u32, u32), AspError> {
+        self.write(|conn| {
+            let tx = conn.transaction()?;
+            let mut next: u32 =
+                tx.query_row("SELECT COUNT(*) FROM commitments", [], |row| row.get(0))?;
+            let (mut imported, mut skipped) = (0, 0);
+
+            for row in rows {
+                if row.leaf_index < next {
+                    let existing: Option<String> = tx
+                        .query_row(
+                            "SELECT commitment FROM commitments WHERE leaf_index = ?1",
+                            rusqlite::params![row.leaf_index],
+                            |r| r.get(0),
+                        )
+                        .optional()?;
+                    if existing.as_deref() != Some(row.commitment.as_str()) {
+                        return Err(AspError::InvalidInput(format!(
+                            "leaf {} already holds a different commitment",
+                            row.leaf_index
+                        )));
+                    }
+                    skipped += 1;
+                    continue;
+                }
+                if row.leaf_index != next {
+                    return Err(AspError::InvalidInput(format!(
+                        "leaf {} leaves a gap after leaf count {next}",
+                        row.leaf_index
+                    )));
+                }
+                tx.execute(
+                    "INSERT INTO commitments (leaf_index, commitment, deposit_tx)
+                     VALUES (?1, ?2, ?3)",
+                    rusqlite::params![row.leaf_index, row.commitment, row.deposit_tx],
+                )?;
+                next += 1;
+                imported += 1;
+            }
+
+            tx.commit()?;
+            Ok((imported, skipped))
+        })
+    }
+
     pub fn get_leaf_count(&self) -> Result<u32, AspError> {
         let conn = self.conn()?;
         let count: u32 =
@@ -75,6 +358,38 @@ impl Database {
         Ok(count)
     }
 
+    /// Seconds since the leaf at `leaf_index` was stored, or `None` if there
+    /// is no such leaf.
+    pub fn get_commitment_age_secs(&self, leaf_index: u32) -> Result<Option<u64>, AspError> {
+        let conn = self.conn()?;
+        let age: Option<i64> = conn
+            .query_row(
+                "SELECT CAST(strftime('%s', 'now') AS INTEGER)
+                      - CAST(strftime('%s', created_at) AS INTEGER)
+                 FROM commitments WHERE leaf_index = ?1",
+                rusqlite::params![leaf_index],
+                |row| row.get(0),
+            )
+            .optional()?;
+        // A clock stepped back can make a fresh leaf look younger than 0s
+        Ok(age.map(|secs| secs.max(0) as u64))
+    }
+
+    /// `created_at` of the newest leaf, or `None` for an empty tree.
+    pub fn get_last_commitment_time(&self) -> Result<Option<String>, AspError> {
+        let conn = self.conn()?;
+        let result: Result<String, _> = conn.query_row(
+            "SELECT created_at FROM commitments ORDER BY leaf_index DESC LIMIT 1",
+            [],
+            |row| row.get(0),
+        );
+        match result {
+            Ok(created_at) => Ok(Some(created_at)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     pub fn find_commitment_leaf_index(&self, commitment: &str) -> Result<Option<u32>, AspError> {
         let conn = self.conn()?;
         let result: Result<u32, _> = conn.query_row(
@@ -89,6 +404,245 @@ impl Database {
         }
     }
 
+    /// Look up a commitment row by its (decimal) value.
+    pub fn find_commitment(&self, commitment: &str) -> Result<Option<CommitmentRow>, AspError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT leaf_index, commitment, deposit_tx FROM commitments WHERE commitment = ?1",
+        )?;
+        let mut rows = stmt.query_map(rusqlite::params![commitment], |row| {
+            Ok(CommitmentRow {
+                leaf_index: row.get(0)?,
+                commitment: row.get(1)?,
+                deposit_tx: row.get(2)?,
+            })
+        })?;
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Commitments inserted by `tx_hash` (a deposit, or the outputs of a
+    /// swap, mint or burn), in leaf order.
+    pub fn get_commitments_by_tx(&self, tx_hash: &str) -> Result<Vec<CommitmentRow>, AspError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT leaf_index, commitment, deposit_tx FROM commitments
+             WHERE deposit_tx = ?1 ORDER BY leaf_index ASC",
+        )?;
+        let rows = stmt
+            .query_map(rusqlite::params![tx_hash], |row| {
+                Ok(CommitmentRow {
+                    leaf_index: row.get(0)?,
+                    commitment: row.get(1)?,
+                    deposit_tx: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Record the on-chain deposit tx for a leaf once it has been relayed.
+    pub fn set_deposit_tx(&self, leaf_index: u32, deposit_tx: &str) -> Result<(), AspError> {
+        self.write(|conn| {
+            conn.execute(
+                "UPDATE commitments SET deposit_tx = ?1 WHERE leaf_index = ?2",
+                rusqlite::params![deposit_tx, leaf_index],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Set the queued-submission status of a leaf (see [`DepositStatusRow`]).
+    pub fn set_deposit_status(
+        &self,
+        leaf_index: u32,
+        status: &str,
+        error: Option<&str>,
+    ) -> Result<(), AspError> {
+        self.write(|conn| {
+            conn.execute(
+                "UPDATE commitments SET deposit_status = ?1, deposit_error = ?2
+                 WHERE leaf_index = ?3",
+                rusqlite::params![status, error, leaf_index],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Mark a queued deposit as relayed, recording its deposit and root txs.
+    pub fn set_deposit_confirmed(
+        &self,
+        leaf_index: u32,
+        deposit_tx: &str,
+        root_tx: &str,
+    ) -> Result<(), AspError> {
+        self.write(|conn| {
+            conn.execute(
+                "UPDATE commitments SET deposit_status = 'confirmed', deposit_error = NULL, \
+                 deposit_tx = ?1, root_tx = ?2 WHERE leaf_index = ?3",
+                rusqlite::params![deposit_tx, root_tx, leaf_index],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Submission state of a commitment (decimal), or `None` if not in the tree.
+    pub fn get_deposit_status(
+        &self,
+        commitment: &str,
+    ) -> Result<Option<DepositStatusRow>, AspError> {
+        let conn = self.conn()?;
+        let result = conn.query_row(
+            "SELECT leaf_index, commitment, deposit_status, deposit_tx, root_tx, deposit_error \
+             FROM commitments WHERE commitment = ?1",
+            rusqlite::params![commitment],
+            |row| {
+                Ok(DepositStatusRow {
+                    leaf_index: row.get(0)?,
+                    commitment: row.get(1)?,
+                    status: row.get(2)?,
+                    deposit_tx: row.get(3)?,
+                    root_tx: row.get(4)?,
+                    error: row.get(5)?,
+                })
+            },
+        );
+        match result {
+            Ok(row) => Ok(Some(row)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Deposits still waiting for on-chain submission, oldest first.
+    pub fn get_pending_deposits(&self) -> Result<Vec<CommitmentRow>, AspError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT leaf_index, commitment, deposit_tx FROM commitments \
+             WHERE deposit_status = 'pending' ORDER BY leaf_index ASC",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(CommitmentRow {
+                    leaf_index: row.get(0)?,
+                    commitment: row.get(1)?,
+                    deposit_tx: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Store the declared token/amount for a deposited leaf.
+    pub fn set_deposit_metadata(
+        &self,
+        leaf_index: u32,
+        metadata: &DepositMetadata,
+    ) -> Result<(), AspError> {
+        self.write(|conn| {
+            conn.execute(
+                "UPDATE commitments SET token = ?1, amount_low = ?2, amount_high = ?3
+                 WHERE leaf_index = ?4",
+                rusqlite::params![
+                    metadata.token,
+                    metadata.amount_low,
+                    metadata.amount_high,
+                    leaf_index
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Declared token/amount for a leaf, or `None` if the leaf does not exist.
+    pub fn get_deposit_metadata(
+        &self,
+        leaf_index: u32,
+    ) -> Result<Option<DepositMetadata>, AspError> {
+        let conn = self.conn()?;
+        let result = conn.query_row(
+            "SELECT token, amount_low, amount_high FROM commitments WHERE leaf_index = ?1",
+            rusqlite::params![leaf_index],
+            |row| {
+                Ok(DepositMetadata {
+                    token: row.get(0)?,
+                    amount_low: row.get(1)?,
+                    amount_high: row.get(2)?,
+                })
+            },
+        );
+        match result {
+            Ok(metadata) => Ok(Some(metadata)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Store the Ed25519 public key (hex) that must sign spends of a leaf.
+    pub fn set_auth_pubkey(&self, leaf_index: u32, pubkey: &str) -> Result<(), AspError> {
+        self.write(|conn| {
+            conn.execute(
+                "UPDATE commitments SET auth_pubkey = ?1 WHERE leaf_index = ?2",
+                rusqlite::params![pubkey, leaf_index],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Request signing key committed for a leaf at deposit time, if any.
+    pub fn get_auth_pubkey(&self, leaf_index: u32) -> Result<Option<String>, AspError> {
+        let conn = self.conn()?;
+        let result = conn.query_row(
+            "SELECT auth_pubkey FROM commitments WHERE leaf_index = ?1",
+            rusqlite::params![leaf_index],
+            |row| row.get(0),
+        );
+        match result {
+            Ok(pubkey) => Ok(pubkey),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Undo a local commitment insert: removes the leaf and any roots
+    /// recorded at or after it, in a single transaction. Only the last leaf
+    /// can be removed; dropping an earlier one would shift every later leaf.
+    /// The leaf's auth key is kept for [`Database::insert_commitment`], in
+    /// case its commitment reached the chain and is synced back.
+    pub fn rollback_commitment(&self, leaf_index: u32) -> Result<(), AspError> {
+        self.write(|conn| {
+            let tx = conn.transaction()?;
+            let later: u32 = tx.query_row(
+                "SELECT COUNT(*) FROM commitments WHERE leaf_index > ?1",
+                rusqlite::params![leaf_index],
+                |row| row.get(0),
+            )?;
+            if later > 0 {
+                return Err(AspError::Internal(format!(
+                    "Cannot roll back leaf {leaf_index}: {later} later leaves depend on it"
+                )));
+            }
+            tx.execute(
+                "INSERT OR REPLACE INTO rolled_back_auth_pubkeys (commitment, auth_pubkey)
+                 SELECT commitment, auth_pubkey FROM commitments
+                 WHERE leaf_index = ?1 AND auth_pubkey IS NOT NULL",
+                rusqlite::params![leaf_index],
+            )?;
+            tx.execute(
+                "DELETE FROM commitments WHERE leaf_index = ?1",
+                rusqlite::params![leaf_index],
+            )?;
+            tx.execute(
+                "DELETE FROM merkle_roots WHERE leaf_count > ?1",
+                rusqlite::params![leaf_index],
+            )?;
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
     // --- Merkle Roots ---
 
     pub fn insert_root(
@@ -97,12 +651,13 @@ impl Database {
         leaf_count: u32,
         submit_tx: Option<&str>,
     ) -> Result<(), AspError> {
-        let conn = self.conn()?;
-        conn.execute(
-            "INSERT INTO merkle_roots (root, leaf_count, submit_tx) VALUES (?1, ?2, ?3)",
-            rusqlite::params![root, leaf_count, submit_tx],
-        )?;
-        Ok(())
+        self.write(|conn| {
+            conn.execute(
+                "INSERT INTO merkle_roots (root, leaf_count, submit_tx) VALUES (?1, ?2, ?3)",
+                rusqlite::params![root, leaf_count, submit_tx],
+            )?;
+            Ok(())
+        })
     }
 
     pub fn get_latest_root(&self) -> Result<Option<String>, AspError> {
@@ -119,50 +674,307 @@ impl Database {
         }
     }
 
+    /// The `limit` most recently recorded roots, newest first.
+    pub fn get_recent_roots(&self, limit: u32) -> Result<Vec<RootRow>, AspError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT root, leaf_count, submit_tx, created_at FROM merkle_roots
+             ORDER BY id DESC LIMIT ?1",
+        )?;
+        let rows = stmt
+            .query_map([limit], |row| {
+                Ok(RootRow {
+                    root: row.get(0)?,
+                    leaf_count: row.get(1)?,
+                    submit_tx: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Root recorded when the tree had exactly `leaf_count` leaves; the
+    /// latest one if several were recorded at that count.
+    pub fn get_root_at_leaf_count(&self, leaf_count: u32) -> Result<Option<String>, AspError> {
+        let conn = self.conn()?;
+        let root = conn
+            .query_row(
+                "SELECT root FROM merkle_roots WHERE leaf_count = ?1 ORDER BY id DESC LIMIT 1",
+                [leaf_count],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(root)
+    }
+
+    /// Leaf count the tree had when `root` was recorded, if it ever was.
+    pub fn get_root_leaf_count(&self, root: &str) -> Result<Option<u32>, AspError> {
+        let conn = self.conn()?;
+        let result = conn.query_row(
+            "SELECT leaf_count FROM merkle_roots WHERE root = ?1 ORDER BY id DESC LIMIT 1",
+            [root],
+            |row| row.get(0),
+        );
+        match result {
+            Ok(count) => Ok(Some(count)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     // --- Nullifiers ---
 
     pub fn insert_nullifier(
         &self,
         nullifier_hash: &str,
-        circuit_type: &str,
+        circuit_type: CircuitType,
         tx_hash: Option<&str>,
     ) -> Result<(), AspError> {
-        let conn = self.conn()?;
-        conn.execute(
-            "INSERT OR IGNORE INTO nullifiers (nullifier_hash, circuit_type, tx_hash) VALUES (?1, ?2, ?3)",
-            rusqlite::params![nullifier_hash, circuit_type, tx_hash],
-        )?;
-        Ok(())
+        self.nullifiers
+            .insert(nullifier_hash, circuit_type, NullifierOrigin::Local, tx_hash)?;
+        self.clear_pending_nullifier(nullifier_hash)
     }
 
-    pub fn is_nullifier_spent(&self, nullifier_hash: &str) -> Result<bool, AspError> {
+    /// Record a nullifier seen spent on-chain (origin `chain`). This is how a
+    /// self-submitted spend stops being pending once its tx lands.
+    pub fn insert_synced_nullifier(&self, nullifier_hash: &str) -> Result<(), AspError> {
+        self.nullifiers
+            .insert(nullifier_hash, CircuitType::Synced, NullifierOrigin::Chain, None)?;
+        self.clear_pending_nullifier(nullifier_hash)
+    }
+
+    /// Record a nullifier whose spend was handed to the client to submit
+    /// (`self_submit`). It stays pending until it is recorded as spent.
+    pub fn insert_pending_nullifier(
+        &self,
+        nullifier_hash: &str,
+        circuit_type: CircuitType,
+    ) -> Result<(), AspError> {
+        self.write(|conn| {
+            conn.execute(
+                "INSERT OR IGNORE INTO pending_nullifiers (nullifier_hash, circuit_type)
+                 VALUES (?1, ?2)",
+                rusqlite::params![nullifier_hash, circuit_type],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn is_nullifier_pending(&self, nullifier_hash: &str) -> Result<bool, AspError> {
         let conn = self.conn()?;
         let count: u32 = conn.query_row(
-            "SELECT COUNT(*) FROM nullifiers WHERE nullifier_hash = ?1",
+            "SELECT COUNT(*) FROM pending_nullifiers WHERE nullifier_hash = ?1",
             rusqlite::params![nullifier_hash],
             |row| row.get(0),
         )?;
         Ok(count > 0)
     }
 
+    fn clear_pending_nullifier(&self, nullifier_hash: &str) -> Result<(), AspError> {
+        self.write(|conn| {
+            conn.execute(
+                "DELETE FROM pending_nullifiers WHERE nullifier_hash = ?1",
+                rusqlite::params![nullifier_hash],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn is_nullifier_spent(&self, nullifier_hash: &str) -> Result<bool, AspError> {
+        self.nullifiers.is_spent(nullifier_hash)
+    }
+
     pub fn get_nullifier(&self, nullifier_hash: &str) -> Result<Option<NullifierRow>, AspError> {
+        self.nullifiers.get(nullifier_hash)
+    }
+
+    /// Rows for whichever of `hashes` have been spent.
+    pub fn get_nullifiers(&self, hashes: &[String]) -> Result<Vec<NullifierRow>, AspError> {
+        self.nullifiers.get_many(hashes)
+    }
+
+    /// Spent nullifiers in spend order, optionally filtered by circuit type
+    /// and origin.
+    pub fn get_nullifiers_paged(
+        &self,
+        circuit_type: Option<CircuitType>,
+        origin: Option<NullifierOrigin>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<NullifierRow>, AspError> {
         let conn = self.conn()?;
         let mut stmt = conn.prepare(
-            "SELECT nullifier_hash, circuit_type, tx_hash FROM nullifiers WHERE nullifier_hash = ?1",
+            "SELECT nullifier_hash, circuit_type, origin, tx_hash, spent_at FROM nullifiers
+             WHERE (?1 IS NULL OR circuit_type = ?1) AND (?2 IS NULL OR origin = ?2)
+             ORDER BY spent_at ASC, nullifier_hash ASC
+             LIMIT ?3 OFFSET ?4",
         )?;
-        let mut rows = stmt.query_map(rusqlite::params![nullifier_hash], |row| {
-            Ok(NullifierRow {
-                nullifier_hash: row.get(0)?,
-                circuit_type: row.get(1)?,
-                tx_hash: row.get(2)?,
-            })
-        })?;
+        let rows = stmt
+            .query_map(rusqlite::params![circuit_type, origin, limit, offset], |row| {
+                Ok(NullifierRow {
+                    nullifier_hash: row.get(0)?,
+                    circuit_type: row.get(1)?,
+                    origin: row.get(2)?,
+                    tx_hash: row.get(3)?,
+                    spent_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    // --- Operations ---
+
+    /// Record a mutating operation and return its id.
+    pub fn insert_operation(
+        &self,
+        kind: &str,
+        request_hash: &str,
+        tx_hash: Option<&str>,
+        root_tx: Option<&str>,
+        leaf_indices: &[u32],
+        status: &str,
+    ) -> Result<i64, AspError> {
+        let leaf_indices = serde_json::to_string(leaf_indices)?;
+        self.write(|conn| {
+            conn.execute(
+                "INSERT INTO operations (kind, request_hash, tx_hash, root_tx, leaf_indices, status)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![kind, request_hash, tx_hash, root_tx, leaf_indices, status],
+            )?;
+            Ok(conn.last_insert_rowid())
+        })
+    }
+
+    pub fn get_operation(&self, id: i64) -> Result<Option<OperationRow>, AspError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, kind, request_hash, tx_hash, leaf_indices, status, created_at, root_tx
+             FROM operations WHERE id = ?1",
+        )?;
+        let mut rows = stmt.query_map(rusqlite::params![id], operation_from_row)?;
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Most recent operation whose action or root submission was `tx_hash`.
+    pub fn find_operation_by_tx(&self, tx_hash: &str) -> Result<Option<OperationRow>, AspError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, kind, request_hash, tx_hash, leaf_indices, status, created_at, root_tx
+             FROM operations
+             WHERE tx_hash = ?1 OR root_tx = ?1
+             ORDER BY id DESC
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query_map(rusqlite::params![tx_hash], operation_from_row)?;
         match rows.next() {
             Some(row) => Ok(Some(row?)),
             None => Ok(None),
         }
     }
 
+    /// Most recent operations first, optionally filtered by kind.
+    pub fn list_operations(
+        &self,
+        kind: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<OperationRow>, AspError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, kind, request_hash, tx_hash, leaf_indices, status, created_at, root_tx
+             FROM operations
+             WHERE ?1 IS NULL OR kind = ?1
+             ORDER BY id DESC
+             LIMIT ?2",
+        )?;
+        let rows = stmt
+            .query_map(rusqlite::params![kind, limit], operation_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    // --- Proof jobs ---
+
+    /// Record a finished proof of `circuit_type` that took `duration_ms`.
+    pub fn record_proof_job(
+        &self,
+        circuit_type: CircuitType,
+        duration_ms: u64,
+    ) -> Result<(), AspError> {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.write(|conn| {
+            conn.execute(
+                "INSERT INTO proof_jobs (id, circuit_type, status, duration_ms)
+                 VALUES (?1, ?2, 'completed', ?3)",
+                rusqlite::params![id, circuit_type, duration_ms],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Recorded proof durations per circuit, each list in ascending order.
+    pub fn get_proof_durations(&self) -> Result<BTreeMap<String, Vec<u64>>, AspError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT circuit_type, duration_ms FROM proof_jobs
+             WHERE duration_ms IS NOT NULL
+             ORDER BY circuit_type, duration_ms",
+        )?;
+        let mut durations: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        for row in rows {
+            let (circuit, duration_ms): (String, u64) = row?;
+            durations.entry(circuit).or_default().push(duration_ms);
+        }
+        Ok(durations)
+    }
+
+    // --- Banned sources ---
+
+    /// Ban `ip`, replacing the reason and time of an existing ban.
+    pub fn ban_source(
+        &self,
+        ip: &str,
+        reason: &str,
+        automatic: bool,
+    ) -> Result<BannedSourceRow, AspError> {
+        self.write(|conn| {
+            let row = conn.query_row(
+                "INSERT OR REPLACE INTO banned_sources (ip, reason, automatic) VALUES (?1, ?2, ?3)
+                 RETURNING ip, reason, automatic, banned_at",
+                rusqlite::params![ip, reason, automatic],
+                banned_source_from_row,
+            )?;
+            Ok(row)
+        })
+    }
+
+    /// Lift the ban on `ip`. Returns whether it was banned.
+    pub fn unban_source(&self, ip: &str) -> Result<bool, AspError> {
+        self.write(|conn| {
+            let removed = conn.execute(
+                "DELETE FROM banned_sources WHERE ip = ?1",
+                rusqlite::params![ip],
+            )?;
+            Ok(removed > 0)
+        })
+    }
+
+    pub fn list_banned_sources(&self) -> Result<Vec<BannedSourceRow>, AspError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT ip, reason, automatic, banned_at FROM banned_sources ORDER BY banned_at, ip",
+        )?;
+        let rows = stmt
+            .query_map([], banned_source_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
     // --- Sync State ---
 
     pub fn get_sync_state(&self, key: &str) -> Result<Option<String>, AspError> {
@@ -180,12 +992,23 @@ impl Database {
     }
 
     pub fn set_sync_state(&self, key: &str, value: &str) -> Result<(), AspError> {
-        let conn = self.conn()?;
-        conn.execute(
-            "INSERT OR REPLACE INTO sync_state (key, value) VALUES (?1, ?2)",
-            rusqlite::params![key, value],
-        )?;
-        Ok(())
+        self.write(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO sync_state (key, value) VALUES (?1, ?2)",
+                rusqlite::params![key, value],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn delete_sync_state(&self, key: &str) -> Result<(), AspError> {
+        self.write(|conn| {
+            conn.execute(
+                "DELETE FROM sync_state WHERE key = ?1",
+                rusqlite::params![key],
+            )?;
+            Ok(())
+        })
     }
 
     /// Simple health check — verifies the database is accessible.
@@ -199,6 +1022,38 @@ impl Database {
     }
 }
 
+impl ToSql for CircuitType {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+/// Unknown values in the `circuit_type` column surface as a conversion error
+/// instead of being passed through.
+impl FromSql for CircuitType {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: AspError| FromSqlError::Other(Box::new(e)))
+    }
+}
+
+impl ToSql for NullifierOrigin {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for NullifierOrigin {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: AspError| FromSqlError::Other(Box::new(e)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -219,6 +1074,82 @@ mod tests {
         assert_eq!(row.deposit_tx.as_deref(), Some("0xabc"));
     }
 
+    #[test]
+    fn test_get_commitments_by_tx() {
+        let db = test_db();
+        db.insert_commitment(0, "aaa", Some("0xswap")).unwrap();
+        db.insert_commitment(1, "bbb", None).unwrap();
+        db.insert_commitment(2, "ccc", Some("0xswap")).unwrap();
+        db.insert_commitment(3, "ddd", Some("0xother")).unwrap();
+
+        let rows = db.get_commitments_by_tx("0xswap").unwrap();
+        let found: Vec<(u32, &str)> = rows
+            .iter()
+            .map(|r| (r.leaf_index, r.commitment.as_str()))
+            .collect();
+        assert_eq!(found, vec![(0, "aaa"), (2, "ccc")]);
+        assert!(db.get_commitments_by_tx("0xnone").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_proof_durations_grouped_by_circuit() {
+        let db = test_db();
+        db.record_proof_job(CircuitType::Swap, 900).unwrap();
+        db.record_proof_job(CircuitType::Membership, 40).unwrap();
+        db.record_proof_job(CircuitType::Swap, 300).unwrap();
+
+        let durations = db.get_proof_durations().unwrap();
+        assert_eq!(durations["swap"], vec![300, 900]);
+        assert_eq!(durations["membership"], vec![40]);
+        assert_eq!(durations.len(), 2);
+    }
+
+    #[test]
+    fn test_find_commitment_and_set_deposit_tx() {
+        let db = test_db();
+        db.insert_commitment(0, "aaa", None).unwrap();
+        db.insert_commitment(1, "bbb", None).unwrap();
+        db.set_deposit_tx(1, "0xdep").unwrap();
+
+        let row = db.find_commitment("bbb").unwrap().unwrap();
+        assert_eq!(row.leaf_index, 1);
+        assert_eq!(row.deposit_tx.as_deref(), Some("0xdep"));
+        assert!(db.find_commitment("aaa").unwrap().unwrap().deposit_tx.is_none());
+        assert!(db.find_commitment("ccc").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_deposit_metadata_roundtrip() {
+        let db = test_db();
+        db.insert_commitment(0, "aaa", None).unwrap();
+
+        // Absent until set
+        assert_eq!(
+            db.get_deposit_metadata(0).unwrap(),
+            Some(DepositMetadata::default())
+        );
+        assert!(db.get_deposit_metadata(1).unwrap().is_none());
+
+        let metadata = DepositMetadata {
+            token: Some("0x1".into()),
+            amount_low: Some("1000".into()),
+            amount_high: Some("0".into()),
+        };
+        db.set_deposit_metadata(0, &metadata).unwrap();
+        assert_eq!(db.get_deposit_metadata(0).unwrap(), Some(metadata));
+    }
+
+    #[test]
+    fn test_auth_pubkey_roundtrip() {
+        let db = test_db();
+        db.insert_commitment(0, "aaa", None).unwrap();
+        assert!(db.get_auth_pubkey(0).unwrap().is_none());
+        assert!(db.get_auth_pubkey(1).unwrap().is_none());
+
+        db.set_auth_pubkey(0, "ab".repeat(32).as_str()).unwrap();
+        assert_eq!(db.get_auth_pubkey(0).unwrap(), Some("ab".repeat(32)));
+    }
+
     #[test]
     fn test_get_leaf_count() {
         let db = test_db();
@@ -229,6 +1160,43 @@ mod tests {
         assert_eq!(db.get_leaf_count().unwrap(), 2);
     }
 
+    #[test]
+    fn test_get_last_commitment_time() {
+        let db = test_db();
+        assert_eq!(db.get_last_commitment_time().unwrap(), None);
+        db.insert_commitment(0, "aaa", None).unwrap();
+        db.conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO commitments (leaf_index, commitment, created_at)
+                 VALUES (1, 'bbb', '2030-01-01 00:00:00')",
+                [],
+            )
+            .unwrap();
+        assert_eq!(
+            db.get_last_commitment_time().unwrap().as_deref(),
+            Some("2030-01-01 00:00:00")
+        );
+    }
+
+    #[test]
+    fn test_commitment_age() {
+        let db = test_db();
+        assert_eq!(db.get_commitment_age_secs(0).unwrap(), None);
+        db.conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO commitments (leaf_index, commitment, created_at)
+                 VALUES (0, 'aaa', datetime('now', '-90 seconds')),
+                        (1, 'bbb', '2999-01-01 00:00:00')",
+                [],
+            )
+            .unwrap();
+        let age = db.get_commitment_age_secs(0).unwrap().unwrap();
+        assert!((90..95).contains(&age), "{age}");
+        assert_eq!(db.get_commitment_age_secs(1).unwrap(), Some(0));
+    }
+
     #[test]
     fn test_insert_commitment_idempotent() {
         let db = test_db();
@@ -252,14 +1220,58 @@ mod tests {
         assert!(db.get_latest_root().unwrap().is_none());
     }
 
+    #[test]
+    fn test_deposit_status_lifecycle() {
+        let db = test_db();
+        db.insert_commitment(0, "111", Some("0xsynced")).unwrap();
+        db.insert_commitment(1, "222", None).unwrap();
+        db.set_deposit_status(1, "pending", None).unwrap();
+
+        let pending = db.get_pending_deposits().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].leaf_index, 1);
+        assert_eq!(db.get_deposit_status("111").unwrap().unwrap().status, None);
+
+        db.set_deposit_status(1, "failed", Some("rpc down")).unwrap();
+        let failed = db.get_deposit_status("222").unwrap().unwrap();
+        assert_eq!(failed.status.as_deref(), Some("failed"));
+        assert_eq!(failed.error.as_deref(), Some("rpc down"));
+        assert!(db.get_pending_deposits().unwrap().is_empty());
+
+        db.set_deposit_confirmed(1, "0xdeposit", "0xroot").unwrap();
+        let confirmed = db.get_deposit_status("222").unwrap().unwrap();
+        assert_eq!(
+            confirmed,
+            DepositStatusRow {
+                leaf_index: 1,
+                commitment: "222".into(),
+                status: Some("confirmed".into()),
+                deposit_tx: Some("0xdeposit".into()),
+                root_tx: Some("0xroot".into()),
+                error: None,
+            }
+        );
+        assert!(db.get_deposit_status("333").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_get_root_leaf_count() {
+        let db = test_db();
+        db.insert_root("root1", 1, None).unwrap();
+        db.insert_root("root2", 2, None).unwrap();
+        assert_eq!(db.get_root_leaf_count("root1").unwrap(), Some(1));
+        assert_eq!(db.get_root_leaf_count("root2").unwrap(), Some(2));
+        assert_eq!(db.get_root_leaf_count("root3").unwrap(), None);
+    }
+
     #[test]
     fn test_nullifier_lifecycle() {
         let db = test_db();
-        db.insert_nullifier("nul1", "membership", Some("0x111"))
+        db.insert_nullifier("nul1", CircuitType::Membership, Some("0x111"))
             .unwrap();
         assert!(db.is_nullifier_spent("nul1").unwrap());
         let row = db.get_nullifier("nul1").unwrap().unwrap();
-        assert_eq!(row.circuit_type, "membership");
+        assert_eq!(row.circuit_type, CircuitType::Membership);
         assert_eq!(row.tx_hash.as_deref(), Some("0x111"));
     }
 
@@ -270,15 +1282,125 @@ mod tests {
         assert!(db.get_nullifier("nonexistent").unwrap().is_none());
     }
 
+    #[test]
+    fn test_get_nullifiers_batch() {
+        let db = test_db();
+        db.insert_nullifier("nul1", CircuitType::Swap, Some("0x1")).unwrap();
+        db.insert_nullifier("nul2", CircuitType::Mint, None).unwrap();
+
+        let hashes = vec!["nul1".to_string(), "missing".to_string(), "nul2".to_string()];
+        let mut rows = db.get_nullifiers(&hashes).unwrap();
+        rows.sort_by(|a, b| a.nullifier_hash.cmp(&b.nullifier_hash));
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].nullifier_hash, "nul1");
+        assert_eq!(rows[0].tx_hash.as_deref(), Some("0x1"));
+        assert_eq!(rows[1].circuit_type, CircuitType::Mint);
+
+        assert!(db.get_nullifiers(&[]).unwrap().is_empty());
+    }
+
     #[test]
     fn test_nullifier_idempotent() {
         let db = test_db();
-        db.insert_nullifier("nul1", "swap", None).unwrap();
+        db.insert_nullifier("nul1", CircuitType::Swap, None).unwrap();
         // INSERT OR IGNORE — should not error
-        db.insert_nullifier("nul1", "swap", None).unwrap();
+        db.insert_nullifier("nul1", CircuitType::Swap, None).unwrap();
         assert!(db.is_nullifier_spent("nul1").unwrap());
     }
 
+    #[test]
+    fn test_pending_nullifier_clears_once_spent() {
+        let db = test_db();
+        db.insert_pending_nullifier("nul1", CircuitType::Swap).unwrap();
+        db.insert_pending_nullifier("nul1", CircuitType::Swap).unwrap();
+        db.insert_pending_nullifier("nul2", CircuitType::Mint).unwrap();
+        assert!(db.is_nullifier_pending("nul1").unwrap());
+        assert!(!db.is_nullifier_spent("nul1").unwrap());
+
+        db.insert_synced_nullifier("nul1").unwrap();
+        db.insert_nullifier("nul2", CircuitType::Mint, Some("0x1")).unwrap();
+        assert!(!db.is_nullifier_pending("nul1").unwrap());
+        assert!(!db.is_nullifier_pending("nul2").unwrap());
+    }
+
+    #[test]
+    fn test_unknown_stored_circuit_type_is_rejected() {
+        let db = test_db();
+        db.conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO nullifiers (nullifier_hash, circuit_type) VALUES ('nul1', 'transfer')",
+                [],
+            )
+            .unwrap();
+        assert!(db.get_nullifier("nul1").is_err());
+    }
+
+    #[test]
+    fn test_nullifiers_paged_unfiltered() {
+        let db = test_db();
+        for i in 0..5 {
+            let circuit = if i % 2 == 0 { CircuitType::Swap } else { CircuitType::Mint };
+            db.insert_nullifier(&format!("nul{i}"), circuit, None).unwrap();
+        }
+
+        let first = db.get_nullifiers_paged(None, None, 0, 3).unwrap();
+        let rest = db.get_nullifiers_paged(None, None, 3, 3).unwrap();
+        assert_eq!(first.len(), 3);
+        assert_eq!(rest.len(), 2);
+
+        let all: std::collections::HashSet<&str> = first
+            .iter()
+            .chain(&rest)
+            .map(|r| r.nullifier_hash.as_str())
+            .collect();
+        assert_eq!(all.len(), 5);
+        assert!(!first[0].spent_at.is_empty());
+    }
+
+    #[test]
+    fn test_nullifiers_paged_filtered() {
+        let db = test_db();
+        for i in 0..5 {
+            let circuit = if i % 2 == 0 { CircuitType::Swap } else { CircuitType::Mint };
+            db.insert_nullifier(&format!("nul{i}"), circuit, None).unwrap();
+        }
+
+        let swaps = db.get_nullifiers_paged(Some(CircuitType::Swap), None, 0, 10).unwrap();
+        assert_eq!(swaps.len(), 3);
+        assert!(swaps.iter().all(|r| r.circuit_type == CircuitType::Swap));
+
+        let mints = db.get_nullifiers_paged(Some(CircuitType::Mint), None, 1, 10).unwrap();
+        assert_eq!(mints.len(), 1);
+
+        assert!(db.get_nullifiers_paged(Some(CircuitType::Burn), None, 0, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_synced_nullifiers_are_distinguishable() {
+        let db = test_db();
+        db.insert_nullifier("local1", CircuitType::Swap, Some("0x1")).unwrap();
+        db.insert_synced_nullifier("chain1").unwrap();
+
+        let local = db.get_nullifier("local1").unwrap().unwrap();
+        assert_eq!(local.origin, NullifierOrigin::Local);
+        let synced = db.get_nullifier("chain1").unwrap().unwrap();
+        assert_eq!(synced.origin, NullifierOrigin::Chain);
+        assert_eq!(synced.circuit_type, CircuitType::Synced);
+
+        let chain = db.get_nullifiers_paged(None, Some(NullifierOrigin::Chain), 0, 10).unwrap();
+        assert_eq!(chain.len(), 1);
+        assert_eq!(chain[0].nullifier_hash, "chain1");
+        let local = db.get_nullifiers_paged(None, Some(NullifierOrigin::Local), 0, 10).unwrap();
+        assert_eq!(local.len(), 1);
+        assert_eq!(local[0].nullifier_hash, "local1");
+
+        // A spend seen on-chain after a local one keeps the local record
+        db.insert_synced_nullifier("local1").unwrap();
+        let row = db.get_nullifier("local1").unwrap().unwrap();
+        assert_eq!(row.origin, NullifierOrigin::Local);
+    }
+
     #[test]
     fn test_sync_state() {
         let db = test_db();
@@ -294,6 +1416,8 @@ mod tests {
             db.get_sync_state("last_block").unwrap().as_deref(),
             Some("200")
         );
+        db.delete_sync_state("last_block").unwrap();
+        assert!(db.get_sync_state("last_block").unwrap().is_none());
     }
 
     #[test]
@@ -302,6 +1426,111 @@ mod tests {
         assert!(db.is_healthy());
     }
 
+    #[test]
+    fn test_root_at_leaf_count() {
+        let db = test_db();
+        for (root, leaf_count) in [("root1", 1), ("root2", 2), ("root3", 3)] {
+            db.insert_root(root, leaf_count, None).unwrap();
+        }
+        assert_eq!(db.get_root_at_leaf_count(1).unwrap().as_deref(), Some("root1"));
+        assert_eq!(db.get_root_at_leaf_count(2).unwrap().as_deref(), Some("root2"));
+        assert_eq!(db.get_root_at_leaf_count(3).unwrap().as_deref(), Some("root3"));
+        assert_eq!(db.get_root_at_leaf_count(4).unwrap(), None);
+
+        // A root recorded again at the same count (e.g. after a rebuild) wins
+        db.insert_root("root2b", 2, None).unwrap();
+        assert_eq!(db.get_root_at_leaf_count(2).unwrap().as_deref(), Some("root2b"));
+    }
+
+    #[test]
+    fn test_recent_roots_newest_first() {
+        let db = test_db();
+        for (root, leaf_count) in [("root1", 1), ("root2", 2), ("root3", 3)] {
+            db.insert_root(root, leaf_count, Some("0xtx")).unwrap();
+        }
+        let roots: Vec<String> = db
+            .get_recent_roots(2)
+            .unwrap()
+            .into_iter()
+            .map(|row| row.root)
+            .collect();
+        assert_eq!(roots, ["root3", "root2"]);
+        assert_eq!(db.get_recent_roots(10).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_rollback_commitment() {
+        let db = test_db();
+        db.insert_commitment(0, "aaa", None).unwrap();
+        db.insert_root("root1", 1, None).unwrap();
+        db.insert_commitment(1, "bbb", None).unwrap();
+        db.insert_root("root2", 2, None).unwrap();
+
+        // Removing leaf 0 would shift leaf 1 into its place
+        assert!(db.rollback_commitment(0).is_err());
+        assert_eq!(db.get_leaf_count().unwrap(), 2);
+
+        db.rollback_commitment(1).unwrap();
+        assert_eq!(db.get_leaf_count().unwrap(), 1);
+        assert!(db.get_commitment(1).unwrap().is_none());
+        assert_eq!(db.get_latest_root().unwrap().as_deref(), Some("root1"));
+    }
+
+    #[test]
+    fn test_rolled_back_auth_pubkey_returns_with_its_commitment() {
+        let db = test_db();
+        let pubkey = "ab".repeat(32);
+        db.insert_commitment(0, "aaa", None).unwrap();
+        db.set_auth_pubkey(0, &pubkey).unwrap();
+        db.rollback_commitment(0).unwrap();
+
+        // Another commitment taking the leaf does not inherit the key
+        db.insert_commitment(0, "bbb", None).unwrap();
+        assert!(db.get_auth_pubkey(0).unwrap().is_none());
+        db.insert_commitment(1, "aaa", None).unwrap();
+        assert_eq!(db.get_auth_pubkey(1).unwrap(), Some(pubkey));
+    }
+
+    #[test]
+    fn test_insert_commitments_is_all_or_nothing() {
+        let db = test_db();
+        assert_eq!(db.insert_commitments(&["aaa", "bbb"], Some("0x1")).unwrap(), 0);
+        assert_eq!(db.insert_commitments(&["ccc"], None).unwrap(), 2);
+        assert_eq!(db.get_commitment(1).unwrap().unwrap().commitment, "bbb");
+
+        // The second leaf collides with a stray row, so neither is stored
+        let db = test_db();
+        db.insert_commitment(2, "stray", None).unwrap();
+        assert!(db.insert_commitments(&["aaa", "bbb"], None).is_err());
+        assert!(db.get_commitment(1).unwrap().is_none());
+        assert_eq!(db.get_leaf_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_gapped_leaves_are_reported() {
+        let db = test_db();
+        for leaf_index in [0, 1, 2] {
+            db.insert_commitment(leaf_index, "c", None).unwrap();
+        }
+        check_leaf_sequence(&db.get_all_commitments().unwrap()).unwrap();
+
+        for leaf_index in [4, 7, 8] {
+            db.insert_commitment(leaf_index, "c", None).unwrap();
+        }
+        let err = check_leaf_sequence(&db.get_all_commitments().unwrap()).unwrap_err();
+        assert!(err.to_string().ends_with("missing 3, 5..=6"), "{err}");
+
+        // A set starting past 0 is missing its head
+        let rows = vec![CommitmentRow {
+            leaf_index: 2,
+            commitment: "c".into(),
+            deposit_tx: None,
+        }];
+        let err = check_leaf_sequence(&rows).unwrap_err();
+        assert!(err.to_string().ends_with("missing 0..=1"), "{err}");
+        assert!(check_leaf_sequence(&[]).is_ok());
+    }
+
     #[test]
     fn test_get_all_commitments_ordered() {
         let db = test_db();
@@ -315,4 +1544,112 @@ mod tests {
         assert_eq!(all[1].leaf_index, 1);
         assert_eq!(all[2].leaf_index, 2);
     }
+
+    #[test]
+    fn test_for_each_commitment_stops_early() {
+        let db = test_db();
+        db.insert_commitment(1, "bbb", None).unwrap();
+        db.insert_commitment(0, "aaa", Some("0xdep")).unwrap();
+
+        let mut seen = Vec::new();
+        db.for_each_commitment(|row| {
+            seen.push((row.leaf_index, row.deposit_tx));
+            true
+        })
+        .unwrap();
+        assert_eq!(seen, vec![(0, Some("0xdep".to_string())), (1, None)]);
+
+        let mut calls = 0;
+        db.for_each_commitment(|_| {
+            calls += 1;
+            false
+        })
+        .unwrap();
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_import_commitments() {
+        let db = test_db();
+        db.insert_commitment(0, "aaa", None).unwrap();
+        let row = |leaf_index, commitment: &str| CommitmentRow {
+            leaf_index,
+            commitment: commitment.into(),
+            deposit_tx: None,
+        };
+
+        let rows = [row(0, "aaa"), row(1, "bbb"), row(2, "ccc")];
+        assert_eq!(db.import_commitments(&rows).unwrap(), (2, 1));
+        assert_eq!(db.get_leaf_count().unwrap(), 3);
+
+        // A conflict or a gap rejects the whole batch
+        assert!(db.import_commitments(&[row(3, "ddd"), row(1, "xxx")]).is_err());
+        assert!(db.import_commitments(&[row(3, "ddd"), row(5, "fff")]).is_err());
+        assert_eq!(db.get_leaf_count().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_insert_and_get_operation() {
+        let db = test_db();
+        let id = db
+            .insert_operation("swap", "abcd", Some("0xswap"), Some("0xroot"), &[4, 5], "confirmed")
+            .unwrap();
+
+        let row = db.get_operation(id).unwrap().unwrap();
+        assert_eq!(row.kind, "swap");
+        assert_eq!(row.request_hash, "abcd");
+        assert_eq!(row.tx_hash.as_deref(), Some("0xswap"));
+        assert_eq!(row.root_tx.as_deref(), Some("0xroot"));
+        assert_eq!(row.leaf_indices, vec![4, 5]);
+        assert_eq!(row.status, "confirmed");
+        assert!(db.get_operation(id + 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_find_operation_by_action_or_root_tx() {
+        let db = test_db();
+        let deposit = db
+            .insert_operation("deposit", "a", Some("0xd1"), Some("0xr1"), &[0], "confirmed")
+            .unwrap();
+        let swap = db
+            .insert_operation("swap", "b", Some("0xs1"), Some("0xr2"), &[1, 2], "confirmed")
+            .unwrap();
+
+        assert_eq!(db.find_operation_by_tx("0xd1").unwrap().unwrap().id, deposit);
+        assert_eq!(db.find_operation_by_tx("0xr1").unwrap().unwrap().id, deposit);
+        assert_eq!(db.find_operation_by_tx("0xr2").unwrap().unwrap().id, swap);
+        assert!(db.find_operation_by_tx("0xnone").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_list_operations_newest_first_by_kind() {
+        let db = test_db();
+        let first = db.insert_operation("deposit", "a", None, None, &[0], "confirmed").unwrap();
+        db.insert_operation("withdraw", "b", None, None, &[], "rejected").unwrap();
+        let third = db.insert_operation("deposit", "c", None, None, &[], "failed").unwrap();
+
+        let deposits = db.list_operations(Some("deposit"), 10).unwrap();
+        let ids: Vec<i64> = deposits.iter().map(|op| op.id).collect();
+        assert_eq!(ids, vec![third, first]);
+
+        assert_eq!(db.list_operations(None, 10).unwrap().len(), 3);
+        assert_eq!(db.list_operations(None, 1).unwrap()[0].id, third);
+    }
+
+    #[test]
+    fn test_ban_and_unban_source() {
+        let db = test_db();
+        db.ban_source("203.0.113.7", "scraping", false).unwrap();
+        let row = db.ban_source("203.0.113.7", "too many requests", true).unwrap();
+        assert_eq!(row.reason, "too many requests");
+        assert!(row.automatic);
+
+        let banned = db.list_banned_sources().unwrap();
+        assert_eq!(banned.len(), 1);
+        assert_eq!(banned[0].ip, "203.0.113.7");
+
+        assert!(db.unban_source("203.0.113.7").unwrap());
+        assert!(!db.unban_source("203.0.113.7").unwrap());
+        assert!(db.list_banned_sources().unwrap().is_empty());
+    }
 }
@@ -1,71 +1,470 @@
-use rusqlite::Connection;
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{Connection, ErrorCode};
+
+use crate::config::Config;
 use crate::error::AspError;
 
+use super::migrations::MIGRATIONS;
+use super::nullifiers::{NullifierStore, SqliteNullifierStore};
+
+/// Default number of pooled SQLite connections.
+pub const DEFAULT_POOL_SIZE: u32 = 8;
+
+/// Default `PRAGMA busy_timeout`: how long SQLite itself waits on a lock.
+pub const DEFAULT_BUSY_TIMEOUT_MS: u64 = 5000;
+
+/// Times a write that still finds the database busy or locked after the busy
+/// timeout is retried, with a backoff starting at [`WRITE_RETRY_BASE_MS`] and
+/// doubling per attempt.
+pub const WRITE_RETRIES: u32 = 4;
+pub const WRITE_RETRY_BASE_MS: u64 = 50;
+
+/// Accepted values of `PRAGMA synchronous`.
+pub const SYNCHRONOUS_MODES: &[&str] = &["OFF", "NORMAL", "FULL", "EXTRA"];
+
+/// Settings applied when opening the pool and to every connection in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbOptions {
+    pub pool_size: u32,
+    /// `PRAGMA synchronous` mode, one of [`SYNCHRONOUS_MODES`]. `NORMAL` is
+    /// durable across application crashes in WAL mode and much cheaper
+    /// than `FULL`.
+    pub synchronous: String,
+    pub foreign_keys: bool,
+    /// `PRAGMA busy_timeout` in milliseconds.
+    pub busy_timeout_ms: u64,
+}
+
+impl Default for DbOptions {
+    fn default() -> Self {
+        DbOptions {
+            pool_size: DEFAULT_POOL_SIZE,
+            synchronous: "NORMAL".into(),
+            foreign_keys: true,
+            busy_timeout_ms: DEFAULT_BUSY_TIMEOUT_MS,
+        }
+    }
+}
+
+impl DbOptions {
+    pub fn from_config(config: &Config) -> Self {
+        DbOptions {
+            pool_size: config.database_pool_size,
+            synchronous: config.database_synchronous.clone(),
+            foreign_keys: config.database_foreign_keys,
+            busy_timeout_ms: config.database_busy_timeout_ms,
+        }
+    }
+}
+
 pub struct Database {
-    conn: Mutex<Connection>,
+    pool: Pool<SqliteConnectionManager>,
+    /// Spent nullifiers; the `nullifiers` table unless replaced with
+    /// [`Database::with_nullifier_store`].
+    pub(super) nullifiers: Arc<dyn NullifierStore>,
+}
+
+/// On-disk size of the database in bytes. Both are 0 for `:memory:`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageSize {
+    pub db_bytes: u64,
+    pub wal_bytes: u64,
+}
+
+impl StorageSize {
+    fn of(path: &str) -> Self {
+        if path.is_empty() {
+            return StorageSize::default();
+        }
+        let len = |p: &str| std::fs::metadata(p).map(|m| m.len()).unwrap_or(0);
+        StorageSize {
+            db_bytes: len(path),
+            wal_bytes: len(&format!("{path}-wal")),
+        }
+    }
 }
 
 impl Database {
     pub fn new(path: &str) -> Result<Self, AspError> {
-        let conn = Connection::open(path)?;
-        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA busy_timeout=5000;")?;
+        Self::open(path, &DbOptions::default())
+    }
+
+    pub fn with_pool_size(path: &str, pool_size: u32) -> Result<Self, AspError> {
+        Self::open(
+            path,
+            &DbOptions {
+                pool_size,
+                ..DbOptions::default()
+            },
+        )
+    }
+
+    /// Open a connection pool of up to `options.pool_size` connections. WAL
+    /// mode lets readers proceed while a writer holds the lock.
+    ///
+    /// `path` is a file path, `:memory:`, or a `file:` URI. Missing parent
+    /// directories of a file database are created. In-memory databases are
+    /// private to a single connection, so they are always served by a pool
+    /// of one.
+    pub fn open(path: &str, options: &DbOptions) -> Result<Self, AspError> {
+        let location = DbLocation::parse(path);
+        if let DbLocation::File(ref file) = location {
+            create_parent_dir(file)?;
+        }
+
+        let init = format!(
+            "PRAGMA journal_mode=WAL; PRAGMA busy_timeout={}; \
+             PRAGMA synchronous={}; PRAGMA foreign_keys={};",
+            options.busy_timeout_ms,
+            options.synchronous,
+            if options.foreign_keys { "ON" } else { "OFF" },
+        );
+        let in_memory = location == DbLocation::Memory;
+        let manager = if path == ":memory:" {
+            SqliteConnectionManager::memory()
+        } else {
+            SqliteConnectionManager::file(path)
+        }
+        .with_init(move |c| c.execute_batch(&init));
+
+        let max_size = if in_memory { 1 } else { options.pool_size.max(1) };
+        let pool = Pool::builder()
+            .max_size(max_size)
+            // Never recycle connections: an in-memory database lives and dies with its connection
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .build(manager)
+            .map_err(|e| AspError::Internal(format!("Failed to open database pool: {e}")))?;
+
         Ok(Database {
-            conn: Mutex::new(conn),
+            nullifiers: Arc::new(SqliteNullifierStore::new(pool.clone())),
+            pool,
         })
     }
 
+    /// Keep spent nullifiers in `store` instead of the `nullifiers` table.
+    /// `GET /nullifiers` listing still reads the table.
+    pub fn with_nullifier_store(mut self, store: Arc<dyn NullifierStore>) -> Self {
+        self.nullifiers = store;
+        self
+    }
+
+    /// Bring the schema up to date by applying any pending migrations.
     pub fn run_migrations(&self) -> Result<(), AspError> {
-        let conn = self.conn()?;
+        self.apply_migrations(MIGRATIONS)
+    }
 
-        conn.execute_batch(
-            "
-            CREATE TABLE IF NOT EXISTS commitments (
-                leaf_index INTEGER PRIMARY KEY,
-                commitment TEXT NOT NULL,
-                deposit_tx TEXT,
-                created_at TEXT NOT NULL DEFAULT (datetime('now'))
-            );
-
-            CREATE TABLE IF NOT EXISTS merkle_roots (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                root TEXT NOT NULL,
-                leaf_count INTEGER NOT NULL,
-                submit_tx TEXT,
-                created_at TEXT NOT NULL DEFAULT (datetime('now'))
-            );
-
-            CREATE TABLE IF NOT EXISTS nullifiers (
-                nullifier_hash TEXT PRIMARY KEY,
-                circuit_type TEXT NOT NULL,
-                tx_hash TEXT,
-                spent_at TEXT NOT NULL DEFAULT (datetime('now'))
-            );
-
-            CREATE TABLE IF NOT EXISTS proof_jobs (
-                id TEXT PRIMARY KEY,
-                circuit_type TEXT NOT NULL,
-                status TEXT NOT NULL DEFAULT 'pending',
-                error TEXT,
-                tx_hash TEXT,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                updated_at TEXT NOT NULL DEFAULT (datetime('now'))
-            );
-
-            CREATE TABLE IF NOT EXISTS sync_state (
-                key TEXT PRIMARY KEY,
-                value TEXT NOT NULL
-            );
-            ",
+    /// Rebuild the database file to drop free pages, then fold the WAL back
+    /// into it and truncate it. Returns the sizes before and after.
+    ///
+    /// Everything runs on one pooled connection, so it cannot wait on itself
+    /// even with a pool of one; other connections' writes wait on SQLite's
+    /// busy timeout until it is done.
+    pub fn vacuum(&self) -> Result<(StorageSize, StorageSize), AspError> {
+        let conn = self.conn()?;
+        let path: String = conn.query_row(
+            "SELECT file FROM pragma_database_list WHERE name = 'main'",
+            [],
+            |row| row.get(0),
         )?;
 
-        Ok(())
+        let before = StorageSize::of(&path);
+        conn.execute_batch("VACUUM")?;
+        let busy: i64 =
+            conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| row.get(0))?;
+        if busy != 0 {
+            tracing::warn!("WAL checkpoint blocked by active readers; WAL not truncated");
+        }
+        let after = StorageSize::of(&path);
+
+        Ok((before, after))
+    }
+
+    pub fn conn(&self) -> Result<PooledConnection<SqliteConnectionManager>, AspError> {
+        pooled_conn(&self.pool)
+    }
+
+    /// Run a write on a pooled connection, retrying it up to [`WRITE_RETRIES`]
+    /// times when SQLite reports the database busy or locked even after its
+    /// busy timeout (e.g. a deposit burst racing the sync task). `write` must
+    /// be safe to run again: a failed attempt has written nothing, so it
+    /// should be a single statement or one transaction.
+    ///
+    /// The backoff sleeps the calling thread, like the busy timeout does.
+    pub fn write<T>(
+        &self,
+        write: impl FnMut(&mut Connection) -> Result<T, AspError>,
+    ) -> Result<T, AspError> {
+        write_retrying(&self.pool, write)
+    }
+}
+
+pub(super) fn pooled_conn(
+    pool: &Pool<SqliteConnectionManager>,
+) -> Result<PooledConnection<SqliteConnectionManager>, AspError> {
+    pool.get()
+        .map_err(|e| AspError::Internal(format!("Database pool unavailable: {e}")))
+}
+
+/// [`Database::write`] on any connection from `pool`.
+pub(super) fn write_retrying<T>(
+    pool: &Pool<SqliteConnectionManager>,
+    mut write: impl FnMut(&mut Connection) -> Result<T, AspError>,
+) -> Result<T, AspError> {
+    let mut conn = pooled_conn(pool)?;
+    let mut attempt = 0;
+    loop {
+        match write(&mut conn) {
+            Err(AspError::Database(ref e)) if is_busy(e) && attempt < WRITE_RETRIES => {
+                let backoff = Duration::from_millis(WRITE_RETRY_BASE_MS << attempt);
+                attempt += 1;
+                tracing::warn!(
+                    attempt,
+                    backoff_ms = backoff.as_millis() as u64,
+                    error = %e,
+                    "Database busy, retrying write"
+                );
+                std::thread::sleep(backoff);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether `e` is SQLite giving up on a lock held by another connection.
+fn is_busy(e: &rusqlite::Error) -> bool {
+    matches!(
+        e.sqlite_error_code(),
+        Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+    )
+}
+
+/// Where a database path points.
+#[derive(Debug, PartialEq, Eq)]
+enum DbLocation {
+    Memory,
+    File(PathBuf),
+}
+
+impl DbLocation {
+    /// Classify `:memory:`, `file:` URIs (`file:data/asp.db?mode=rwc`,
+    /// `file:///var/lib/asp.db`, `file::memory:`) and plain paths.
+    fn parse(path: &str) -> Self {
+        if path == ":memory:" {
+            return DbLocation::Memory;
+        }
+        let Some(uri) = path.strip_prefix("file:") else {
+            return DbLocation::File(PathBuf::from(path));
+        };
+
+        let (file, query) = uri.split_once('?').unwrap_or((uri, ""));
+        if file == ":memory:" || query.split('&').any(|param| param == "mode=memory") {
+            return DbLocation::Memory;
+        }
+        // `file:///abs` and `file://localhost/abs` carry an (empty) authority
+        let file = match file.strip_prefix("//") {
+            Some(rest) => rest.strip_prefix("localhost").unwrap_or(rest),
+            None => file,
+        };
+        DbLocation::File(PathBuf::from(file))
+    }
+}
+
+fn create_parent_dir(file: &Path) -> Result<(), AspError> {
+    match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => std::fs::create_dir_all(dir).map_err(|e| {
+            AspError::Config(format!(
+                "Failed to create database directory {}: {e}",
+                dir.display()
+            ))
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prover::CircuitType;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_concurrent_reads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pool.db");
+        let db = Arc::new(Database::with_pool_size(path.to_str().unwrap(), 4).unwrap());
+        db.run_migrations().unwrap();
+        db.insert_commitment(0, "aaa", None).unwrap();
+        db.insert_nullifier("nul1", CircuitType::Swap, None).unwrap();
+
+        let handles: Vec<_> = (0..32)
+            .map(|_| {
+                let db = db.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..20 {
+                        assert_eq!(db.get_leaf_count().unwrap(), 1);
+                        assert!(db.is_nullifier_spent("nul1").unwrap());
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_write_retries_while_another_writer_holds_the_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("busy.db");
+        // No SQLite-side wait, so only the retry loop can ride out the lock
+        let options = DbOptions {
+            pool_size: 2,
+            busy_timeout_ms: 0,
+            ..DbOptions::default()
+        };
+        let db = Arc::new(Database::open(path.to_str().unwrap(), &options).unwrap());
+        db.run_migrations().unwrap();
+
+        let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+        let holder = {
+            let db = db.clone();
+            std::thread::spawn(move || {
+                let mut conn = db.conn().unwrap();
+                let tx = conn
+                    .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+                    .unwrap();
+                tx.execute(
+                    "INSERT INTO commitments (leaf_index, commitment) VALUES (0, 'aaa')",
+                    [],
+                )
+                .unwrap();
+                locked_tx.send(()).unwrap();
+                std::thread::sleep(Duration::from_millis(WRITE_RETRY_BASE_MS * 3));
+                tx.commit().unwrap();
+            })
+        };
+
+        locked_rx.recv().unwrap();
+        let conn = db.conn().unwrap();
+        let direct = conn.execute(
+            "INSERT INTO commitments (leaf_index, commitment) VALUES (9, 'zzz')",
+            [],
+        );
+        assert!(matches!(direct, Err(ref e) if is_busy(e)), "{direct:?}");
+        drop(conn);
+
+        db.insert_commitment(1, "bbb", None).unwrap();
+        holder.join().unwrap();
+        assert_eq!(db.get_leaf_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_vacuum_memory_database() {
+        let db = Database::new(":memory:").unwrap();
+        db.run_migrations().unwrap();
+        db.insert_commitment(0, "aaa", None).unwrap();
+
+        let (before, after) = db.vacuum().unwrap();
+        assert_eq!(before, StorageSize::default());
+        assert_eq!(after, StorageSize::default());
+        // The pool's only connection was released
+        assert_eq!(db.get_leaf_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_vacuum_file_database_truncates_wal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vacuum.db");
+        let db = Database::with_pool_size(path.to_str().unwrap(), 2).unwrap();
+        db.run_migrations().unwrap();
+        for i in 0..200 {
+            db.insert_commitment(i, &format!("{i:064}"), None).unwrap();
+        }
+
+        let (before, after) = db.vacuum().unwrap();
+        assert!(before.db_bytes + before.wal_bytes > 0);
+        assert!(after.db_bytes > 0);
+        assert_eq!(after.wal_bytes, 0);
+        assert_eq!(db.get_leaf_count().unwrap(), 200);
+    }
+
+    #[test]
+    fn test_memory_database_shared_across_calls() {
+        let db = Database::with_pool_size(":memory:", 16).unwrap();
+        db.run_migrations().unwrap();
+        db.insert_commitment(0, "aaa", None).unwrap();
+        assert_eq!(db.get_leaf_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_creates_nested_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a/b/c/asp.db");
+        let db = Database::new(path.to_str().unwrap()).unwrap();
+        db.run_migrations().unwrap();
+        db.insert_commitment(0, "aaa", None).unwrap();
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_opens_file_uri() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/uri.db");
+        let uri = format!("file:{}?mode=rwc", path.display());
+        let db = Database::new(&uri).unwrap();
+        db.run_migrations().unwrap();
+        assert!(path.exists());
+
+        let db = Database::new("file::memory:").unwrap();
+        db.run_migrations().unwrap();
+        assert_eq!(db.get_leaf_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_location_parsing() {
+        assert_eq!(DbLocation::parse(":memory:"), DbLocation::Memory);
+        assert_eq!(DbLocation::parse("file::memory:?cache=shared"), DbLocation::Memory);
+        assert_eq!(DbLocation::parse("file:x.db?mode=memory"), DbLocation::Memory);
+        assert_eq!(
+            DbLocation::parse("file:///var/lib/asp.db?mode=rwc"),
+            DbLocation::File("/var/lib/asp.db".into())
+        );
+        assert_eq!(
+            DbLocation::parse("data/asp.db"),
+            DbLocation::File("data/asp.db".into())
+        );
     }
 
-    pub fn conn(&self) -> Result<std::sync::MutexGuard<'_, Connection>, AspError> {
-        self.conn
-            .lock()
-            .map_err(|_| AspError::Internal("Database lock poisoned".into()))
+    #[test]
+    fn test_connection_pragmas() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pragmas.db");
+        let pragma = |db: &Database, name: &str| -> i64 {
+            db.conn()
+                .unwrap()
+                .query_row(&format!("PRAGMA {name}"), [], |row| row.get(0))
+                .unwrap()
+        };
+
+        let db = Database::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(pragma(&db, "synchronous"), 1); // NORMAL
+        assert_eq!(pragma(&db, "foreign_keys"), 1);
+        drop(db);
+
+        let options = DbOptions {
+            synchronous: "FULL".into(),
+            foreign_keys: false,
+            ..DbOptions::default()
+        };
+        let db = Database::open(path.to_str().unwrap(), &options).unwrap();
+        assert_eq!(pragma(&db, "synchronous"), 2);
+        assert_eq!(pragma(&db, "foreign_keys"), 0);
     }
 }
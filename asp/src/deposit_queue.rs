@@ -0,0 +1,251 @@
+//! Background submission of deposits.
+//!
+//! With `DEPOSIT_QUEUE` enabled, `/deposit` inserts the leaf locally, marks
+//! it `pending` and returns; [`run_submitter`] then relays the commitment
+//! and the new root on-chain one deposit at a time and records the outcome
+//! on the commitment row, where `GET /commitment/{commitment}` reports it.
+//!
+//! A queued deposit cannot be rolled back like an inline one: later deposits
+//! may already have been inserted on top of it. So a failed attempt is retried
+//! with backoff, and the deposits queued after it wait, until it is relayed.
+//! The leaf never stays in a submitted root without reaching the chain. The
+//! last error is reported on the row meanwhile.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+
+use crate::error::AspError;
+use crate::relayer::{classify_deposit_error, Relayer, RetryPolicy};
+use crate::webhook::WebhookEvent;
+use crate::AppState;
+
+/// A locally inserted deposit waiting to be relayed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepositJob {
+    pub leaf_index: u32,
+    /// Decimal commitment.
+    pub commitment: String,
+    /// Decimal root to submit after the commitment.
+    pub root: String,
+}
+
+/// Channel between the deposit handler and [`run_submitter`].
+pub struct DepositQueue {
+    sender: mpsc::UnboundedSender<DepositJob>,
+    receiver: Mutex<Option<mpsc::UnboundedReceiver<DepositJob>>>,
+}
+
+impl Default for DepositQueue {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        DepositQueue {
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+        }
+    }
+}
+
+impl DepositQueue {
+    /// Hand `job` to the submitter.
+    pub fn enqueue(&self, job: DepositJob) -> Result<(), AspError> {
+        self.sender
+            .send(job)
+            .map_err(|_| AspError::Internal("Deposit submitter is not running".into()))
+    }
+
+    /// The receiving end; only the first caller gets it.
+    fn take_receiver(&self) -> Option<mpsc::UnboundedReceiver<DepositJob>> {
+        self.receiver
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take()
+    }
+}
+
+/// Relay queued deposits until the queue is dropped. Deposits left `pending`
+/// by a previous run are queued first, against the current root.
+pub async fn run_submitter(state: Arc<AppState>) {
+    let Some(mut jobs) = state.deposit_queue.take_receiver() else {
+        tracing::warn!("Deposit submitter already running");
+        return;
+    };
+
+    if let Err(e) = requeue_pending(&state) {
+        tracing::error!(error = %e, "Failed to requeue pending deposits");
+    }
+
+    while let Some(job) = jobs.recv().await {
+        submit(&state, job).await;
+    }
+}
+
+fn requeue_pending(state: &AppState) -> Result<(), AspError> {
+    let pending = state.db.get_pending_deposits()?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+    let root = state.root_or_empty(state.db.get_latest_root()?);
+    tracing::info!(count = pending.len(), "Requeueing pending deposits");
+    for row in pending {
+        state.deposit_queue.enqueue(DepositJob {
+            leaf_index: row.leaf_index,
+            commitment: row.commitment,
+            root: root.clone(),
+        })?;
+    }
+    Ok(())
+}
+
+/// Shortest wait between two attempts at the same deposit.
+const MIN_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+async fn submit(state: &AppState, job: DepositJob) {
+    let Some(ref relayer) = state.relayer else {
+        tracing::warn!(
+            leaf_index = job.leaf_index,
+            "No relayer configured — deposit left pending"
+        );
+        return;
+    };
+
+    let policy = RetryPolicy::from_config(&state.config);
+    let mut deposit = SentTx::default();
+    let mut root = SentTx::default();
+    let mut retry = 0;
+    let (deposit_tx, root_tx) = loop {
+        let relayer = relayer.lock().await;
+        let relayed = async {
+            let deposit_tx = deposit
+                .confirm(&**relayer, || relayer.deposit(&job.commitment))
+                .await
+                .map_err(classify_deposit_error)?;
+            tracing::info!(tx_hash = %deposit_tx, "Deposit commitment submitted on-chain");
+            let root_tx = root
+                .confirm(&**relayer, || relayer.submit_merkle_root(&job.root))
+                .await?;
+            tracing::info!(tx_hash = %root_tx, "Merkle root submitted on-chain");
+            Ok::<_, AspError>((deposit_tx, root_tx))
+        }
+        .await;
+        drop(relayer);
+
+        match relayed {
+            Ok(txs) => break txs,
+            Err(e) => {
+                retry += 1;
+                tracing::error!(
+                    leaf_index = job.leaf_index,
+                    error = %e,
+                    retry = retry,
+                    "Queued deposit failed; retrying before any later deposit"
+                );
+                let error = e.to_string();
+                if let Err(e) = state
+                    .db
+                    .set_deposit_status(job.leaf_index, "pending", Some(&error))
+                {
+                    tracing::error!(
+                        leaf_index = job.leaf_index,
+                        error = %e,
+                        "Failed to record deposit error"
+                    );
+                }
+                tokio::time::sleep(policy.delay_for(retry).max(MIN_RETRY_DELAY)).await;
+            }
+        }
+    };
+
+    let recorded = state
+        .db
+        .set_deposit_confirmed(job.leaf_index, &deposit_tx, &root_tx);
+    state.notify(WebhookEvent::new(
+        "deposit",
+        &deposit_tx,
+        Some(job.leaf_index),
+        Some(&job.root),
+    ));
+    state.publish_commitment(job.leaf_index, &job.commitment, &job.root);
+    tracing::info!(leaf_index = job.leaf_index, "Queued deposit confirmed");
+    if let Err(e) = recorded {
+        tracing::error!(leaf_index = job.leaf_index, error = %e, "Failed to record deposit status");
+    }
+}
+
+/// Where one transaction of a queued deposit stands across attempts, so a
+/// retry never re-sends a transaction that was already broadcast.
+#[derive(Debug, Default)]
+enum SentTx {
+    #[default]
+    Unsent,
+    /// Broadcast but not confirmed in time; polled by hash on the next attempt.
+    Pending(String),
+    Confirmed(String),
+}
+
+impl SentTx {
+    /// The confirmed transaction hash, calling `send` only if nothing was
+    /// broadcast yet.
+    async fn confirm<F, Fut>(&mut self, relayer: &dyn Relayer, send: F) -> Result<String, AspError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<String, AspError>>,
+    {
+        let result = match self {
+            SentTx::Confirmed(tx_hash) => return Ok(tx_hash.clone()),
+            SentTx::Pending(tx_hash) => {
+                relayer.wait_for_tx(tx_hash).await.map(|()| tx_hash.clone())
+            }
+            SentTx::Unsent => send().await,
+        };
+        match result {
+            Ok(tx_hash) => {
+                *self = SentTx::Confirmed(tx_hash.clone());
+                Ok(tx_hash)
+            }
+            Err(AspError::TransactionPending { tx_hash }) => {
+                *self = SentTx::Pending(tx_hash.clone());
+                Err(AspError::TransactionPending { tx_hash })
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::relayer::{MockOutcome, MockRelayer, RecordedCall, RelayerMethod};
+
+    #[tokio::test]
+    async fn broadcast_tx_is_polled_not_resent() {
+        let relayer = MockRelayer::new();
+        relayer.queue_outcome(RelayerMethod::Deposit, MockOutcome::Pending);
+        relayer.queue_outcome(RelayerMethod::WaitForTx, MockOutcome::RpcError("down".into()));
+
+        let mut sent = SentTx::default();
+        for _ in 0..2 {
+            let err = sent
+                .confirm(&relayer, || relayer.deposit("7"))
+                .await
+                .unwrap_err();
+            assert!(matches!(sent, SentTx::Pending(_)), "{err}");
+        }
+        let tx_hash = sent.confirm(&relayer, || relayer.deposit("7")).await.unwrap();
+        assert_eq!(tx_hash, "0xmock_deposit_tx");
+        assert_eq!(sent.confirm(&relayer, || relayer.deposit("7")).await.unwrap(), tx_hash);
+
+        relayer.assert_called(RelayerMethod::Deposit, 1);
+        assert_eq!(
+            relayer.calls_to(RelayerMethod::WaitForTx),
+            vec![
+                RecordedCall::WaitForTx {
+                    tx_hash: tx_hash.clone()
+                };
+                2
+            ]
+        );
+    }
+}
@@ -1,7 +1,9 @@
-use axum::http::StatusCode;
+use axum::http::{header, HeaderValue, StatusCode};
 use axum::response::{IntoResponse, Response};
 use serde_json::json;
 
+use crate::api::middleware::OperationOutcome;
+
 #[derive(thiserror::Error, Debug)]
 pub enum AspError {
     // Configuration
@@ -15,12 +17,43 @@ pub enum AspError {
     #[error("Commitment not found at leaf index {0}")]
     CommitmentNotFound(u32),
 
+    #[error("Commitment not in tree: {0}")]
+    UnknownCommitment(String),
+
+    #[error("Unknown Merkle root: {0}")]
+    UnknownRoot(String),
+
     #[error("Nullifier already spent: {0}")]
     NullifierAlreadySpent(String),
 
+    #[error("Nullifier pending: {0} has a self-submitted spend not yet seen on-chain")]
+    NullifierPending(String),
+
+    #[error("Operation not found: {0}")]
+    OperationNotFound(i64),
+
+    #[error("No operation for transaction {0}")]
+    TxNotFound(String),
+
     #[error("Merkle tree is full")]
     TreeFull,
 
+    /// The leaf has not been in the tree for `MIN_WITHDRAW_DELAY_SECS` yet.
+    #[error("Withdrawal too early: leaf can be withdrawn in {remaining_secs}s")]
+    WithdrawTooEarly { remaining_secs: u64 },
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("Unsupported media type: {0}")]
+    UnsupportedMediaType(String),
+
     // Prover
     #[error("Proof generation failed: {0}")]
     ProverError(String),
@@ -28,16 +61,47 @@ pub enum AspError {
     #[error("Worker not available: {0}")]
     WorkerUnavailable(String),
 
+    #[error("Prover busy: {0}")]
+    ProverBusy(String),
+
     // Starknet
+    /// The transaction never made it on-chain (rejected before or at submission).
     #[error("Transaction failed: {0}")]
     TransactionFailed(String),
 
-    #[error("Transaction reverted: {0}")]
-    TransactionReverted(String),
+    /// The transaction was included but its execution reverted.
+    #[error("Transaction {tx_hash} reverted: {reason}")]
+    TransactionReverted { tx_hash: String, reason: String },
+
+    /// The coordinator refused a deposit because it is paused. `tx_hash` is
+    /// set if the refusal was an on-chain revert rather than at submission.
+    #[error("Coordinator is paused: {reason}")]
+    CoordinatorPaused {
+        tx_hash: Option<String>,
+        reason: String,
+    },
+
+    /// The coordinator already holds the deposited commitment.
+    #[error("Commitment already deposited on-chain: {reason}")]
+    DuplicateCommitment {
+        tx_hash: Option<String>,
+        reason: String,
+    },
+
+    /// The transaction was accepted by the node but not confirmed in time.
+    /// It may still land, so it must be polled again rather than re-sent.
+    #[error("Transaction {tx_hash} not confirmed yet")]
+    TransactionPending { tx_hash: String },
 
     #[error("RPC error: {0}")]
     RpcError(String),
 
+    #[error("Swap quotes unavailable: {0}")]
+    QuoterUnavailable(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
     // Internal
     #[error("Database error: {0}")]
     Database(#[from] rusqlite::Error),
@@ -49,6 +113,80 @@ pub enum AspError {
     Internal(String),
 }
 
+/// `Retry-After` sent with [`AspError::ProverBusy`], in seconds.
+pub const PROVER_BUSY_RETRY_AFTER_SECS: u64 = 5;
+
+/// Every value [`AspError::code`] can return.
+pub const ERROR_CODES: [&str; 29] = [
+    "config_error",
+    "invalid_input",
+    "commitment_not_found",
+    "unknown_commitment",
+    "unknown_root",
+    "nullifier_already_spent",
+    "nullifier_pending",
+    "operation_not_found",
+    "tx_not_found",
+    "tree_full",
+    "withdraw_too_early",
+    "unauthorized",
+    "forbidden",
+    "payload_too_large",
+    "unsupported_media_type",
+    "prover_error",
+    "worker_unavailable",
+    "prover_busy",
+    "transaction_failed",
+    "transaction_reverted",
+    "coordinator_paused",
+    "duplicate_commitment",
+    "transaction_pending",
+    "rpc_error",
+    "quoter_unavailable",
+    "timeout",
+    "database_error",
+    "invalid_json",
+    "internal_error",
+];
+
+impl AspError {
+    /// Stable machine-readable identifier of the error kind, sent as `code`
+    /// in error responses so clients need not match on the message.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AspError::Config(_) => "config_error",
+            AspError::InvalidInput(_) => "invalid_input",
+            AspError::CommitmentNotFound(_) => "commitment_not_found",
+            AspError::UnknownCommitment(_) => "unknown_commitment",
+            AspError::UnknownRoot(_) => "unknown_root",
+            AspError::NullifierAlreadySpent(_) => "nullifier_already_spent",
+            AspError::NullifierPending(_) => "nullifier_pending",
+            AspError::OperationNotFound(_) => "operation_not_found",
+            AspError::TxNotFound(_) => "tx_not_found",
+            AspError::TreeFull => "tree_full",
+            AspError::WithdrawTooEarly { .. } => "withdraw_too_early",
+            AspError::Unauthorized(_) => "unauthorized",
+            AspError::Forbidden(_) => "forbidden",
+            AspError::PayloadTooLarge(_) => "payload_too_large",
+            AspError::UnsupportedMediaType(_) => "unsupported_media_type",
+            AspError::ProverError(_) => "prover_error",
+            AspError::WorkerUnavailable(_) => "worker_unavailable",
+            AspError::ProverBusy(_) => "prover_busy",
+            AspError::TransactionFailed(_) => "transaction_failed",
+            AspError::TransactionReverted { .. } => "transaction_reverted",
+            AspError::CoordinatorPaused { .. } => "coordinator_paused",
+            AspError::DuplicateCommitment { .. } => "duplicate_commitment",
+            AspError::TransactionPending { .. } => "transaction_pending",
+            AspError::RpcError(_) => "rpc_error",
+            AspError::QuoterUnavailable(_) => "quoter_unavailable",
+            AspError::Timeout(_) => "timeout",
+            AspError::Database(_) => "database_error",
+            AspError::Json(_) => "invalid_json",
+            AspError::Internal(_) => "internal_error",
+        }
+    }
+}
+
 impl IntoResponse for AspError {
     fn into_response(self) -> Response {
         let (status, message) = match &self {
@@ -57,25 +195,194 @@ impl IntoResponse for AspError {
             AspError::CommitmentNotFound(idx) => {
                 (StatusCode::NOT_FOUND, format!("Commitment not found at leaf index {idx}"))
             }
+            AspError::UnknownCommitment(c) => {
+                (StatusCode::NOT_FOUND, format!("Commitment not in tree: {c}"))
+            }
+            AspError::UnknownRoot(r) => {
+                (StatusCode::NOT_FOUND, format!("Unknown Merkle root: {r}"))
+            }
             AspError::NullifierAlreadySpent(h) => {
                 (StatusCode::CONFLICT, format!("Nullifier already spent: {h}"))
             }
+            AspError::NullifierPending(_) => (StatusCode::CONFLICT, self.to_string()),
+            AspError::OperationNotFound(id) => {
+                (StatusCode::NOT_FOUND, format!("Operation not found: {id}"))
+            }
+            AspError::TxNotFound(tx) => {
+                (StatusCode::NOT_FOUND, format!("No operation for transaction {tx}"))
+            }
             AspError::TreeFull => (StatusCode::SERVICE_UNAVAILABLE, "Merkle tree is full".into()),
+            AspError::WithdrawTooEarly { .. } => (StatusCode::FORBIDDEN, self.to_string()),
+            AspError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg.clone()),
+            AspError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg.clone()),
+            AspError::PayloadTooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, msg.clone()),
+            AspError::UnsupportedMediaType(msg) => {
+                (StatusCode::UNSUPPORTED_MEDIA_TYPE, msg.clone())
+            }
             AspError::ProverError(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg.clone()),
             AspError::WorkerUnavailable(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg.clone()),
+            AspError::ProverBusy(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg.clone()),
             AspError::TransactionFailed(msg) => (StatusCode::BAD_GATEWAY, msg.clone()),
-            AspError::TransactionReverted(msg) => (StatusCode::BAD_GATEWAY, msg.clone()),
+            AspError::TransactionReverted { .. } => (StatusCode::BAD_GATEWAY, self.to_string()),
+            AspError::CoordinatorPaused { .. } => {
+                (StatusCode::SERVICE_UNAVAILABLE, self.to_string())
+            }
+            AspError::DuplicateCommitment { .. } => (StatusCode::CONFLICT, self.to_string()),
+            AspError::TransactionPending { .. } => (StatusCode::GATEWAY_TIMEOUT, self.to_string()),
             AspError::RpcError(msg) => (StatusCode::BAD_GATEWAY, msg.clone()),
+            AspError::QuoterUnavailable(msg) => (StatusCode::NOT_IMPLEMENTED, msg.clone()),
+            AspError::Timeout(msg) => (StatusCode::GATEWAY_TIMEOUT, msg.clone()),
             AspError::Database(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
             AspError::Json(e) => (StatusCode::BAD_REQUEST, e.to_string()),
             AspError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg.clone()),
         };
 
-        let body = json!({
+        let mut body = json!({
             "error": message,
+            "code": self.code(),
             "status": status.as_u16(),
         });
+        // Let clients tell a pre-submission failure (safe to retry as-is) from
+        // an on-chain revert (the chain rejected it; retrying will not help).
+        match &self {
+            AspError::TransactionFailed(_) => body["outcome"] = json!("failed"),
+            AspError::TransactionReverted { tx_hash, reason } => {
+                body["outcome"] = json!("reverted");
+                body["tx_hash"] = json!(tx_hash);
+                body["revert_reason"] = json!(reason);
+            }
+            AspError::CoordinatorPaused { tx_hash, reason }
+            | AspError::DuplicateCommitment { tx_hash, reason } => {
+                body["outcome"] = json!(if tx_hash.is_some() { "reverted" } else { "failed" });
+                if let Some(tx_hash) = tx_hash {
+                    body["tx_hash"] = json!(tx_hash);
+                }
+                body["revert_reason"] = json!(reason);
+            }
+            AspError::TransactionPending { tx_hash } => {
+                body["outcome"] = json!("pending");
+                body["tx_hash"] = json!(tx_hash);
+            }
+            _ => {}
+        }
+
+        let mut response = (status, axum::Json(body)).into_response();
+        if let AspError::ProverBusy(_) = self {
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(PROVER_BUSY_RETRY_AFTER_SECS),
+            );
+        }
+        if let AspError::WithdrawTooEarly { remaining_secs } = self {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(remaining_secs));
+        }
+        let sent_tx = match self {
+            AspError::TransactionReverted { tx_hash, .. }
+            | AspError::TransactionPending { tx_hash } => Some(tx_hash),
+            AspError::CoordinatorPaused { tx_hash, .. }
+            | AspError::DuplicateCommitment { tx_hash, .. } => tx_hash,
+            _ => None,
+        };
+        if let Some(tx_hash) = sent_tx {
+            // The tx was sent, so the audit log should point at it.
+            response.extensions_mut().insert(OperationOutcome {
+                tx_hash: Some(tx_hash),
+                ..Default::default()
+            });
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn body_of(error: AspError) -> serde_json::Value {
+        let response = error.into_response();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn every_variant_has_its_code() {
+        let cases = [
+            (AspError::Config("x".into()), "config_error"),
+            (AspError::InvalidInput("x".into()), "invalid_input"),
+            (AspError::CommitmentNotFound(3), "commitment_not_found"),
+            (AspError::UnknownCommitment("x".into()), "unknown_commitment"),
+            (AspError::UnknownRoot("x".into()), "unknown_root"),
+            (AspError::NullifierAlreadySpent("x".into()), "nullifier_already_spent"),
+            (AspError::NullifierPending("x".into()), "nullifier_pending"),
+            (AspError::OperationNotFound(1), "operation_not_found"),
+            (AspError::TxNotFound("0x1".into()), "tx_not_found"),
+            (AspError::TreeFull, "tree_full"),
+            (
+                AspError::WithdrawTooEarly { remaining_secs: 5 },
+                "withdraw_too_early",
+            ),
+            (AspError::Unauthorized("x".into()), "unauthorized"),
+            (AspError::Forbidden("x".into()), "forbidden"),
+            (AspError::PayloadTooLarge("x".into()), "payload_too_large"),
+            (AspError::UnsupportedMediaType("x".into()), "unsupported_media_type"),
+            (AspError::ProverError("x".into()), "prover_error"),
+            (AspError::WorkerUnavailable("x".into()), "worker_unavailable"),
+            (AspError::ProverBusy("x".into()), "prover_busy"),
+            (AspError::TransactionFailed("x".into()), "transaction_failed"),
+            (
+                AspError::TransactionReverted {
+                    tx_hash: "0x1".into(),
+                    reason: "x".into(),
+                },
+                "transaction_reverted",
+            ),
+            (
+                AspError::CoordinatorPaused {
+                    tx_hash: None,
+                    reason: "x".into(),
+                },
+                "coordinator_paused",
+            ),
+            (
+                AspError::DuplicateCommitment {
+                    tx_hash: Some("0x1".into()),
+                    reason: "x".into(),
+                },
+                "duplicate_commitment",
+            ),
+            (
+                AspError::TransactionPending {
+                    tx_hash: "0x1".into(),
+                },
+                "transaction_pending",
+            ),
+            (AspError::RpcError("x".into()), "rpc_error"),
+            (AspError::QuoterUnavailable("x".into()), "quoter_unavailable"),
+            (AspError::Timeout("x".into()), "timeout"),
+            (
+                AspError::Database(rusqlite::Error::QueryReturnedNoRows),
+                "database_error",
+            ),
+            (
+                AspError::Json(serde_json::from_str::<u8>("x").unwrap_err()),
+                "invalid_json",
+            ),
+            (AspError::Internal("x".into()), "internal_error"),
+        ];
 
-        (status, axum::Json(body)).into_response()
+        assert_eq!(cases.len(), ERROR_CODES.len());
+        for (error, code) in cases {
+            assert_eq!(error.code(), code);
+            assert!(ERROR_CODES.contains(&code), "{code} missing from ERROR_CODES");
+            let message = error.to_string();
+            let body = body_of(error).await;
+            assert_eq!(body["code"], code, "{message}");
+            assert!(body["error"].is_string());
+            assert!(body["status"].is_u64());
+        }
     }
 }
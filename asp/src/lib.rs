@@ -1,21 +1,180 @@
 pub mod api;
 pub mod config;
 pub mod db;
+pub mod deposit_queue;
 pub mod error;
+pub mod logging;
 pub mod prover;
 pub mod relayer;
+pub mod reload;
+pub mod server;
+pub mod stream;
 pub mod sync;
+pub mod tree;
+pub mod webhook;
+
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use tokio::sync::Mutex;
 
+use crate::api::bans::BanList;
+use crate::api::counters::OperationCounters;
+use crate::api::status_cache::StatusCache;
 use crate::config::Config;
 use crate::db::Database;
-use crate::prover::Worker;
+use crate::deposit_queue::DepositQueue;
+use crate::error::AspError;
+use crate::prover::{CircuitType, ProofSlots, Worker, WorkerInfo};
 use crate::relayer::Relayer;
+use crate::reload::LiveConfig;
+use crate::stream::{EventBus, TreeEvent};
+use crate::sync::events::Syncer;
+use crate::webhook::{WebhookDispatcher, WebhookEvent};
+
+/// Current root and leaf count, cached so read-only endpoints never wait on
+/// the worker (which may be busy generating a proof) or hit the database.
+#[derive(Debug, Clone, Default)]
+pub struct TreeSnapshot {
+    pub root: Option<String>,
+    pub leaf_count: u32,
+    /// `created_at` of the newest leaf (UTC, `YYYY-MM-DD HH:MM:SS`).
+    pub last_deposit_at: Option<String>,
+}
 
 pub struct AppState {
+    /// Settings as loaded at startup. Reloadable fields may be stale here;
+    /// read those from `runtime`.
     pub config: Config,
+    /// Live values of the hot-reloadable settings (updated on SIGHUP).
+    pub runtime: Arc<LiveConfig>,
     pub db: Database,
     pub worker: Mutex<Worker>,
+    /// Held by every tree append until it can no longer be undone, so a
+    /// deposit rolled back after a failed relay is always the last leaf.
+    pub tree_writes: Mutex<()>,
+    /// Caps proof requests in flight at `MAX_PROOF_JOBS`.
+    pub proof_slots: ProofSlots,
+    /// Worker version and circuit hashes, queried once at startup.
+    pub worker_info: Option<WorkerInfo>,
+    /// Refreshed via [`AppState::refresh_tree`] after every tree mutation.
+    pub tree: RwLock<TreeSnapshot>,
+    /// Root the worker reports for an empty tree, queried once at startup.
+    /// Served as the current root until the first one is recorded.
+    pub empty_root: String,
     pub relayer: Option<Mutex<Box<dyn Relayer>>>,
+    /// Event sync driver; `None` when it could not be configured.
+    pub syncer: Option<Syncer>,
+    pub webhook: Option<WebhookDispatcher>,
+    /// Live tree updates served on `GET /events`.
+    pub events: EventBus,
+    /// Deposits awaiting on-chain submission (used with `DEPOSIT_QUEUE`).
+    pub deposit_queue: DepositQueue,
+    /// Outcome counts of the mutating operations since startup.
+    pub operations: OperationCounters,
+    /// Client IPs refused by the API, mirrored from `banned_sources`.
+    pub bans: BanList,
+    /// Last `/status` payload, reused for `STATUS_CACHE_MS`.
+    pub status: StatusCache,
+}
+
+impl AppState {
+    /// Latest cached tree state.
+    pub fn tree_snapshot(&self) -> TreeSnapshot {
+        self.tree
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Latest recorded root, or [`AppState::empty_root`] before the first.
+    pub fn root_or_empty(&self, root: Option<String>) -> String {
+        root.unwrap_or_else(|| self.empty_root.clone())
+    }
+
+    /// Reload the cached root and leaf count from the database. Call after
+    /// inserting commitments/roots or rolling them back.
+    pub fn refresh_tree(&self) -> Result<(), AspError> {
+        let snapshot = TreeSnapshot {
+            root: self.db.get_latest_root()?,
+            leaf_count: self.db.get_leaf_count()?,
+            last_deposit_at: self.db.get_last_commitment_time()?,
+        };
+        *self
+            .tree
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = snapshot;
+        Ok(())
+    }
+
+    /// Announce a leaf inserted into the tree to `/events` subscribers.
+    pub fn publish_commitment(&self, leaf_index: u32, commitment: &str, root: &str) {
+        self.events.publish(TreeEvent::CommitmentAdded {
+            leaf_index,
+            commitment: commitment.to_string(),
+            root: root.to_string(),
+        });
+    }
+
+    /// Append an operation's output commitments with
+    /// [`tree::append_leaves`] and announce them. Returns the leaf indices
+    /// and the root after the last one (empty if there were none).
+    pub async fn append_leaves(
+        &self,
+        commitments: &[&str],
+        tx_hash: &str,
+    ) -> Result<(Vec<u32>, String), AspError> {
+        let _appending = self.tree_writes.lock().await;
+        let mut worker = self.worker.lock().await;
+        let inserted =
+            tree::append_leaves(&self.db, &mut worker, commitments, Some(tx_hash)).await?;
+        drop(worker);
+
+        let mut last_root = String::new();
+        let mut leaf_indices = Vec::with_capacity(inserted.len());
+        for ((leaf_index, root), commitment) in inserted.into_iter().zip(commitments) {
+            self.publish_commitment(leaf_index, commitment, &root);
+            leaf_indices.push(leaf_index);
+            last_root = root;
+        }
+        Ok((leaf_indices, last_root))
+    }
+
+    /// Announce a spent nullifier to `/events` subscribers.
+    pub fn publish_nullifier(&self, nullifier_hash: &str, circuit_type: CircuitType) {
+        self.events.publish(TreeEvent::NullifierSpent {
+            nullifier_hash: nullifier_hash.to_string(),
+            circuit_type,
+        });
+    }
+
+    /// Run the proving phase of a submitting request under
+    /// `PROOF_REQUEST_TIMEOUT_SECS`. Only work done before anything is sent
+    /// on-chain belongs here: cutting off a submission would leave the
+    /// database behind the chain.
+    pub async fn within_proof_deadline<T>(
+        &self,
+        phase: impl Future<Output = Result<T, AspError>>,
+    ) -> Result<T, AspError> {
+        let secs = self.config.proof_request_timeout_secs;
+        tokio::time::timeout(Duration::from_secs(secs), phase)
+            .await
+            .map_err(|_| AspError::Timeout(format!("Proof not generated within {secs}s")))?
+    }
+
+    /// Record how long a proof of `circuit_type` took, for
+    /// `GET /admin/proof-stats`. A failed write is logged, not returned.
+    pub fn record_proof_time(&self, circuit_type: CircuitType, duration_ms: u64) {
+        if let Err(e) = self.db.record_proof_job(circuit_type, duration_ms) {
+            tracing::warn!(circuit = %circuit_type, error = %e, "Failed to record proof time");
+        }
+    }
+
+    /// Notify the configured webhook (if any) about a confirmed operation.
+    pub fn notify(&self, event: WebhookEvent) {
+        if let Some(ref webhook) = self.webhook {
+            webhook.dispatch(event);
+        }
+    }
 }
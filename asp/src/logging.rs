@@ -0,0 +1,142 @@
+use std::fmt;
+use std::str::FromStr;
+
+use tracing::Subscriber;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::error::AspError;
+
+/// How log lines are written (`LOG_FORMAT`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines.
+    #[default]
+    Text,
+    /// One JSON object per line for log aggregators. Event fields such as
+    /// `tx_hash` are top-level keys; span fields such as `request_id` are
+    /// under `span` and `spans`.
+    Json,
+}
+
+impl LogFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogFormat::Text => "text",
+            LogFormat::Json => "json",
+        }
+    }
+
+    /// `LOG_FORMAT` from the process environment, or text if it is invalid.
+    pub fn from_env() -> Self {
+        std::env::var("LOG_FORMAT")
+            .ok()
+            .and_then(|raw| raw.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = AspError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(AspError::Config(format!(
+                "LOG_FORMAT '{value}' must be text or json"
+            ))),
+        }
+    }
+}
+
+/// Formatting layer writing `format` lines to `writer`.
+pub fn fmt_layer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer().with_writer(writer);
+    match format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer.json().flatten_event(true).boxed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    use tracing_subscriber::prelude::*;
+
+    use super::*;
+
+    /// Writer collecting everything logged into a shared buffer.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'w> MakeWriter<'w> for Captured {
+        type Writer = Captured;
+
+        fn make_writer(&'w self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    fn log_line(format: LogFormat) -> String {
+        let captured = Captured::default();
+        let subscriber =
+            tracing_subscriber::registry().with(fmt_layer(format, captured.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("request", request_id = "req-1").in_scope(|| {
+                tracing::info!(tx_hash = "0xabc", "Transaction confirmed");
+            });
+        });
+        let output = captured.0.lock().unwrap().clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn json_format_keeps_fields_structured() {
+        let line = log_line(LogFormat::Json);
+        let entry: serde_json::Value = serde_json::from_str(line.trim()).unwrap();
+        assert_eq!(entry["level"], "INFO");
+        assert_eq!(entry["message"], "Transaction confirmed");
+        assert_eq!(entry["tx_hash"], "0xabc");
+        assert_eq!(entry["span"]["request_id"], "req-1");
+    }
+
+    #[test]
+    fn text_format_is_not_json() {
+        let line = log_line(LogFormat::Text);
+        assert!(line.contains("Transaction confirmed") && line.contains("tx_hash"));
+        assert!(serde_json::from_str::<serde_json::Value>(line.trim()).is_err());
+    }
+
+    #[test]
+    fn parses_log_format() {
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!(" TEXT ".parse::<LogFormat>().unwrap(), LogFormat::Text);
+        assert_eq!("".parse::<LogFormat>().unwrap(), LogFormat::Text);
+        assert!("yaml".parse::<LogFormat>().is_err());
+    }
+}
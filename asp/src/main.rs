@@ -1,26 +1,41 @@
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tracing_subscriber::EnvFilter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{reload, EnvFilter};
 
 use zylith_asp::config::Config;
-use zylith_asp::db::Database;
-use zylith_asp::prover::Worker;
-use zylith_asp::relayer::StarknetRelayer;
+use zylith_asp::db::queries::check_leaf_sequence;
+use zylith_asp::db::{Database, DbOptions};
+use zylith_asp::logging::{fmt_layer, LogFormat};
+use zylith_asp::prover::{ProofSlots, Worker, WorkerRuntime};
+use zylith_asp::relayer::{RetryPolicy, RetryingRelayer, StarknetRelayer};
+use zylith_asp::reload::{LiveConfig, RuntimeConfig};
+use zylith_asp::server::{self, ServerTuning};
+use zylith_asp::sync::events::Syncer;
+use zylith_asp::webhook::WebhookDispatcher;
 use zylith_asp::AppState;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
-        )
+    // Load .env first so RUST_LOG and LOG_FORMAT set there apply to the
+    // subscriber, as they do to the config
+    dotenvy::dotenv().ok();
+
+    // Initialize logging (filter is swappable so SIGHUP can change the level)
+    let (log_filter, log_handle) = reload::Layer::new(
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+    );
+    tracing_subscriber::registry()
+        .with(log_filter)
+        .with(fmt_layer(LogFormat::from_env(), std::io::stdout))
         .init();
 
     tracing::info!("Starting Zylith ASP server...");
 
     // Load configuration
     let config = Config::load()?;
+    config.validate()?;
+    let runtime = Arc::new(LiveConfig::new(RuntimeConfig::from_config(&config)));
     tracing::info!(
         coordinator = %config.coordinator_address,
         pool = %config.pool_address,
@@ -28,16 +43,62 @@ async fn main() -> anyhow::Result<()> {
     );
 
     // Initialize database
-    let db = Database::new(&config.database_path)?;
+    let db = Database::open(&config.database_path, &DbOptions::from_config(&config))?;
     db.run_migrations()?;
-    tracing::info!(path = %config.database_path, "Database initialized");
+    tracing::info!(
+        path = %config.database_path,
+        pool_size = config.database_pool_size,
+        "Database initialized"
+    );
 
     // Spawn Node.js worker
-    let mut worker = Worker::spawn(&config.worker_path).await?;
-    tracing::info!("Node.js worker spawned");
+    let runtime_command = WorkerRuntime::from_config(&config);
+    let mut worker = Worker::spawn(
+        &runtime_command,
+        &config.worker_path,
+        config.tree_height,
+        &config.commitment_domains,
+    )
+    .await?
+    .with_commitment_cache_size(config.commitment_cache_size);
+    tracing::info!(
+        tree_height = config.tree_height,
+        note_domain = %config.commitment_domains.note,
+        position_domain = %config.commitment_domains.position,
+        "Node.js worker spawned"
+    );
+
+    let worker_info = worker
+        .startup_info(&config.expected_circuit_hashes, config.worker_warmup)
+        .await;
+
+    // Catch a worker hashing differently from the coordinator before it
+    // builds any root
+    match config.expected_self_test_root.as_deref() {
+        Some(expected) => {
+            let root = worker.self_test(expected).await?;
+            tracing::info!(root = %root, "Poseidon self-test passed");
+        }
+        None => {
+            let root = worker.self_test_root().await?;
+            tracing::info!(
+                root = %root,
+                "Poseidon self-test root (set EXPECTED_SELF_TEST_ROOT to enforce it)"
+            );
+        }
+    }
+
+    let empty_root = worker.empty_root().await?;
+    tracing::info!(root = %empty_root, "Empty tree root");
 
     // Rebuild tree from existing commitments
     let commitments = db.get_all_commitments()?;
+    if let Err(e) = check_leaf_sequence(&commitments) {
+        if !config.allow_leaf_gaps {
+            return Err(anyhow::anyhow!("{e}; set ALLOW_LEAF_GAPS=true to start anyway"));
+        }
+        tracing::error!(error = %e, "Rebuilding the Merkle tree despite missing leaves");
+    }
     if !commitments.is_empty() {
         let leaves: Vec<String> = commitments.iter().map(|c| c.commitment.clone()).collect();
         let root = worker.build_tree(&leaves).await?;
@@ -45,10 +106,11 @@ async fn main() -> anyhow::Result<()> {
     }
 
     // Initialize relayer for on-chain transaction submission
-    let relayer = match StarknetRelayer::new(&config).await {
+    let relayer = match StarknetRelayer::new(&config, runtime.clone()).await {
         Ok(r) => {
             tracing::info!("Starknet relayer initialized (admin can submit on-chain txs)");
-            Some(Mutex::new(Box::new(r) as Box<dyn zylith_asp::relayer::Relayer>))
+            let relayer = RetryingRelayer::new(Box::new(r), RetryPolicy::from_config(&config));
+            Some(Mutex::new(Box::new(relayer) as Box<dyn zylith_asp::relayer::Relayer>))
         }
         Err(e) => {
             tracing::warn!("Relayer not available: {e} — running in proof-only mode");
@@ -56,21 +118,71 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
+    let syncer = match Syncer::from_config(&config) {
+        Ok(syncer) => Some(syncer),
+        Err(e) => {
+            tracing::error!(error = %e, "Event sync disabled");
+            None
+        }
+    };
+
+    let webhook = config.webhook_url.as_deref().map(|url| {
+        tracing::info!(url = %url, "Webhook notifications enabled");
+        WebhookDispatcher::new(url)
+    });
+
     // Build shared state
     let state = Arc::new(AppState {
         config: config.clone(),
+        runtime: runtime.clone(),
         db,
         worker: Mutex::new(worker),
+        tree_writes: Default::default(),
+        proof_slots: ProofSlots::new(config.max_proof_jobs),
+        worker_info,
+        tree: Default::default(),
+        empty_root,
         relayer,
+        syncer,
+        webhook,
+        events: Default::default(),
+        deposit_queue: Default::default(),
+        operations: Default::default(),
+        bans: Default::default(),
+        status: Default::default(),
     });
+    state.refresh_tree()?;
+    let banned = state.bans.load(&state.db)?;
+    if banned > 0 {
+        tracing::info!(banned, "Banned sources loaded");
+    }
+
+    // Relay queued deposits in the background
+    if config.deposit_queue {
+        tokio::spawn(zylith_asp::deposit_queue::run_submitter(state.clone()));
+    }
 
     // Spawn event sync background task
     let sync_state = state.clone();
-    let poll_interval = config.sync_poll_interval_secs;
     tokio::spawn(async move {
-        zylith_asp::sync::events::start_event_sync(sync_state, poll_interval).await;
+        zylith_asp::sync::events::start_event_sync(sync_state).await;
     });
 
+    // Reload the reloadable config subset on SIGHUP
+    #[cfg(unix)]
+    tokio::spawn(zylith_asp::reload::reload_on_sighup(
+        runtime.clone(),
+        config.clone(),
+        move |level| match EnvFilter::try_new(level) {
+            Ok(filter) => {
+                if let Err(e) = log_handle.reload(filter) {
+                    tracing::warn!(error = %e, "Failed to apply new log level");
+                }
+            }
+            Err(e) => tracing::warn!(error = %e, "Invalid log level, keeping current"),
+        },
+    ));
+
     // Build router
     let app = zylith_asp::api::routes::create_router(state.clone());
 
@@ -79,9 +191,14 @@ async fn main() -> anyhow::Result<()> {
     let listener = tokio::net::TcpListener::bind(&addr).await?;
     tracing::info!(addr = %addr, "Server listening");
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    let tuning = ServerTuning::from_config(&config);
+    tracing::info!(
+        http2 = tuning.http2_enabled,
+        keepalive_secs = tuning.keepalive_secs,
+        max_concurrent_streams = tuning.max_concurrent_streams,
+        "Connection settings"
+    );
+    server::serve(listener, app, tuning, shutdown_signal()).await?;
 
     tracing::info!("Server shut down gracefully");
     Ok(())
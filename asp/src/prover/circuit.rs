@@ -0,0 +1,93 @@
+use std::fmt;
+use std::str::FromStr;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AspError;
+
+/// Circuit a proof is generated for, and the circuit recorded against each
+/// spent nullifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CircuitType {
+    Membership,
+    Swap,
+    Mint,
+    Burn,
+    /// Nullifier learned from chain events rather than spent through this
+    /// ASP. Not a provable circuit.
+    Synced,
+}
+
+impl CircuitType {
+    pub const ALL: [CircuitType; 5] = [
+        CircuitType::Membership,
+        CircuitType::Swap,
+        CircuitType::Mint,
+        CircuitType::Burn,
+        CircuitType::Synced,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CircuitType::Membership => "membership",
+            CircuitType::Swap => "swap",
+            CircuitType::Mint => "mint",
+            CircuitType::Burn => "burn",
+            CircuitType::Synced => "synced",
+        }
+    }
+
+    /// Whether the worker can generate proofs for this circuit.
+    pub fn is_provable(self) -> bool {
+        self != CircuitType::Synced
+    }
+}
+
+impl fmt::Display for CircuitType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CircuitType {
+    type Err = AspError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        CircuitType::ALL
+            .into_iter()
+            .find(|circuit| circuit.as_str() == value)
+            .ok_or_else(|| {
+                let known: Vec<&str> = CircuitType::ALL.iter().map(|c| c.as_str()).collect();
+                AspError::InvalidInput(format!("circuit_type must be one of: {}", known.join(", ")))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_strings() {
+        for circuit in CircuitType::ALL {
+            assert_eq!(circuit.to_string().parse::<CircuitType>().unwrap(), circuit);
+            let json = serde_json::to_value(circuit).unwrap();
+            assert_eq!(json, circuit.as_str());
+            assert_eq!(
+                serde_json::from_value::<CircuitType>(json).unwrap(),
+                circuit
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_circuit_type() {
+        for bad in ["transfer", "", "Swap", " swap"] {
+            let err = bad.parse::<CircuitType>().unwrap_err();
+            assert!(matches!(err, AspError::InvalidInput(_)), "{bad}");
+        }
+        assert!(serde_json::from_str::<CircuitType>("\"transfer\"").is_err());
+    }
+}
@@ -1,3 +1,12 @@
+mod cache;
+mod circuit;
+mod slots;
 mod worker;
 
-pub use worker::Worker;
+pub use circuit::CircuitType;
+pub use slots::{ProofSlots, DEFAULT_MAX_PROOF_JOBS};
+pub use worker::{
+    CommitmentResult, DomainSeparators, MerkleProof, ProofResult, Worker, WorkerInfo,
+    WorkerRuntime, DEFAULT_COMMITMENT_CACHE_SIZE, DEFAULT_NOTE_DOMAIN, DEFAULT_POSITION_DOMAIN,
+    EXPECTED_CALLDATA_VERSION, SELF_TEST_LEAVES, WORKER_PROTOCOL_VERSION,
+};
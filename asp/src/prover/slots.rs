@@ -0,0 +1,59 @@
+use tokio::sync::{Semaphore, SemaphorePermit, TryAcquireError};
+
+use crate::error::AspError;
+
+/// Proof jobs admitted at once when no limit is configured.
+pub const DEFAULT_MAX_PROOF_JOBS: usize = 4;
+
+/// Bounds the proof requests in flight — proving or waiting for the worker —
+/// so a burst cannot pile up circuit inputs in memory. Requests beyond the
+/// limit are turned away with [`AspError::ProverBusy`] instead of queueing.
+#[derive(Debug)]
+pub struct ProofSlots {
+    permits: Semaphore,
+    capacity: usize,
+}
+
+impl ProofSlots {
+    pub fn new(capacity: usize) -> Self {
+        ProofSlots {
+            permits: Semaphore::new(capacity),
+            capacity,
+        }
+    }
+
+    /// Claim a slot for the lifetime of the returned permit.
+    pub fn try_acquire(&self) -> Result<SemaphorePermit<'_>, AspError> {
+        self.permits.try_acquire().map_err(|e| match e {
+            TryAcquireError::NoPermits => {
+                AspError::ProverBusy(format!("all {} proof slots are in use", self.capacity))
+            }
+            TryAcquireError::Closed => AspError::WorkerUnavailable("prover is shut down".into()),
+        })
+    }
+
+    /// Slots currently claimed.
+    pub fn in_use(&self) -> usize {
+        self.capacity - self.permits.available_permits()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_beyond_capacity_until_a_slot_frees() {
+        let slots = ProofSlots::new(2);
+        let first = slots.try_acquire().unwrap();
+        let _second = slots.try_acquire().unwrap();
+        assert_eq!(slots.in_use(), 2);
+
+        let err = slots.try_acquire().unwrap_err();
+        assert!(matches!(err, AspError::ProverBusy(_)), "{err}");
+
+        drop(first);
+        assert_eq!(slots.in_use(), 1);
+        assert!(slots.try_acquire().is_ok());
+    }
+}
@@ -1,15 +1,73 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::{Child, ChildStdin, ChildStdout};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::Child;
+use tokio::sync::mpsc;
 
+use crate::config::Config;
 use crate::error::AspError;
+use crate::prover::cache::LruCache;
+use crate::prover::CircuitType;
+
+type WorkerStdout = BufReader<Box<dyn AsyncRead + Send + Unpin>>;
+
+/// Commitments remembered when no size is configured.
+pub const DEFAULT_COMMITMENT_CACHE_SIZE: usize = 1024;
+
+/// Leaves of the fixed tree whose root the startup self-test checks.
+pub const SELF_TEST_LEAVES: [&str; 4] = ["1", "2", "3", "4"];
+
+/// `compute_commitment` inputs: secret, nullifier, amount_low, amount_high, token.
+type CommitmentKey = (String, String, String, String, String);
 
 pub struct Worker {
-    _child: Child,
-    stdin: ChildStdin,
-    stdout: BufReader<ChildStdout>,
+    _child: Option<Child>,
+    /// Lines for the worker's stdin. A background task does the writing so
+    /// that a cancel can be queued from a synchronous `Drop`.
+    stdin: mpsc::UnboundedSender<String>,
+    stdout: WorkerStdout,
+    /// Command whose caller was dropped before reading its response. That
+    /// response is discarded before the next command is sent.
+    abandoned: Option<String>,
+    /// Commitments are deterministic in their inputs, so results are kept
+    /// without invalidation.
+    commitments: LruCache<CommitmentKey, CommitmentResult>,
+}
+
+/// Sends a `cancel` for command `id` if dropped before [`CancelGuard::disarm`],
+/// i.e. when the future awaiting the worker's response is dropped (such as a
+/// handler whose client disconnected).
+struct CancelGuard<'a> {
+    id: &'a str,
+    stdin: &'a mpsc::UnboundedSender<String>,
+    armed: bool,
+}
+
+impl CancelGuard<'_> {
+    fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for CancelGuard<'_> {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        tracing::info!(command_id = %self.id, "Worker command abandoned, sending cancel");
+        let cancel = WorkerRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            command: "cancel".to_string(),
+            params: serde_json::json!({ "id": self.id }),
+        };
+        if let Ok(json) = serde_json::to_string(&cancel) {
+            let _ = self.stdin.send(json + "\n");
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -27,32 +85,49 @@ struct WorkerResponse {
     data: Value,
     #[serde(default)]
     error: Option<String>,
+    #[serde(default)]
+    kind: WorkerErrorKind,
+}
+
+/// Why a worker command failed. Input errors (e.g. circuit inputs that fail
+/// witness generation) are the caller's to fix; anything else, including an
+/// error without a kind, is treated as a worker fault worth retrying.
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum WorkerErrorKind {
+    InvalidInput,
+    #[default]
+    #[serde(other)]
+    Internal,
 }
 
 impl Worker {
-    pub async fn spawn(worker_path: &str) -> Result<Self, AspError> {
-        // Use BUN_RUNTIME env var if set, otherwise try "bun" then fall back to "node"
-        let runtime = std::env::var("BUN_RUNTIME").unwrap_or_else(|_| {
-            // Check if bun is available
-            if std::process::Command::new("bun")
-                .arg("--version")
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .status()
-                .is_ok()
-            {
-                "bun".to_string()
-            } else {
-                "node".to_string()
-            }
-        });
-        tracing::info!(runtime = %runtime, "Spawning worker process");
+    /// Spawn the worker process under `runtime`. `tree_height` is passed via
+    /// the `TREE_HEIGHT` environment variable and sizes the worker's in-memory
+    /// Merkle tree; `domains` are passed as `NOTE_DOMAIN_SEPARATOR` and
+    /// `POSITION_DOMAIN_SEPARATOR` and must match what the worker reports.
+    pub async fn spawn(
+        runtime: &WorkerRuntime,
+        worker_path: &str,
+        tree_height: u32,
+        domains: &DomainSeparators,
+    ) -> Result<Self, AspError> {
+        let program = runtime.resolve()?;
+        tracing::info!(
+            runtime = %program.display(),
+            args = ?runtime.args,
+            "Spawning worker process"
+        );
 
-        let mut child = tokio::process::Command::new(&runtime)
+        let mut child = tokio::process::Command::new(&program)
+            .args(&runtime.args)
             .arg(worker_path)
+            .env("TREE_HEIGHT", tree_height.to_string())
+            .env("NOTE_DOMAIN_SEPARATOR", &domains.note)
+            .env("POSITION_DOMAIN_SEPARATOR", &domains.position)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::inherit())
+            .stderr(Stdio::piped())
             .spawn()
             .map_err(|e| AspError::WorkerUnavailable(format!("Failed to spawn worker: {e}")))?;
 
@@ -64,25 +139,31 @@ impl Worker {
             .stdout
             .take()
             .ok_or_else(|| AspError::WorkerUnavailable("No stdout on worker".into()))?;
-
-        let stdout = BufReader::new(stdout);
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(forward_stderr(stderr));
+        }
 
         // Wait for the "ready" signal from worker
-        let mut worker = Worker {
-            _child: child,
-            stdin,
-            stdout,
-        };
+        let mut worker = Worker::from_pipes(stdout, stdin);
+        worker._child = Some(child);
+
+        worker.await_ready(domains).await?;
+        tracing::info!("Worker ready");
+        Ok(worker)
+    }
 
+    /// Read the worker's ready message and check that it speaks
+    /// [`WORKER_PROTOCOL_VERSION`] and hashes commitments under `domains`.
+    async fn await_ready(&mut self, domains: &DomainSeparators) -> Result<(), AspError> {
         let mut line = String::new();
-        worker
-            .stdout
+        self.stdout
             .read_line(&mut line)
             .await
             .map_err(|e| AspError::WorkerUnavailable(format!("Worker startup failed: {e}")))?;
 
-        let msg: Value = serde_json::from_str(line.trim())
-            .map_err(|e| AspError::WorkerUnavailable(format!("Invalid worker ready message: {e}")))?;
+        let msg: Value = serde_json::from_str(line.trim()).map_err(|e| {
+            AspError::WorkerUnavailable(format!("Invalid worker ready message: {e}"))
+        })?;
 
         if msg.get("ready").and_then(|v| v.as_bool()) != Some(true) {
             return Err(AspError::WorkerUnavailable(
@@ -90,12 +171,103 @@ impl Worker {
             ));
         }
 
-        tracing::info!("Worker ready");
-        Ok(worker)
+        // Workers from before the handshake was versioned report nothing
+        let version = msg
+            .get("protocol_version")
+            .and_then(Value::as_u64)
+            .unwrap_or(0);
+        if version != u64::from(WORKER_PROTOCOL_VERSION) {
+            return Err(AspError::WorkerUnavailable(format!(
+                "Worker speaks protocol version {version}, expected \
+                 {WORKER_PROTOCOL_VERSION}; WORKER_PATH points at a worker from another release"
+            )));
+        }
+
+        let reported: Option<DomainSeparators> = msg
+            .get("domains")
+            .and_then(|v| serde_json::from_value(v.clone()).ok());
+        match reported {
+            Some(ref reported) if reported == domains => Ok(()),
+            Some(reported) => Err(AspError::WorkerUnavailable(format!(
+                "Worker hashes commitments under domains note '{}' / position '{}', \
+                 configured note '{}' / position '{}'",
+                reported.note, reported.position, domains.note, domains.position
+            ))),
+            None => Err(AspError::WorkerUnavailable(
+                "Worker ready message reports no domain separators".into(),
+            )),
+        }
+    }
+
+    /// Wrap the worker's stdout and stdin, spawning the task that writes to
+    /// stdin.
+    pub(crate) fn from_pipes<R, W>(stdout: R, mut stdin: W) -> Self
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+        tokio::spawn(async move {
+            while let Some(line) = rx.recv().await {
+                let written = async {
+                    stdin.write_all(line.as_bytes()).await?;
+                    stdin.flush().await
+                };
+                if let Err(e) = written.await {
+                    tracing::error!(error = %e, "Failed to write to worker stdin");
+                    break;
+                }
+            }
+        });
+
+        Worker {
+            _child: None,
+            stdin: tx,
+            stdout: BufReader::new(Box::new(stdout)),
+            abandoned: None,
+            commitments: LruCache::new(DEFAULT_COMMITMENT_CACHE_SIZE),
+        }
+    }
+
+    /// Remember up to `size` computed commitments (0 disables the cache).
+    pub fn with_commitment_cache_size(mut self, size: usize) -> Self {
+        self.commitments = LruCache::new(size);
+        self
+    }
+
+    async fn read_response(stdout: &mut WorkerStdout) -> Result<WorkerResponse, AspError> {
+        let mut line = String::new();
+        let read = stdout
+            .read_line(&mut line)
+            .await
+            .map_err(|e| AspError::WorkerUnavailable(format!("Failed to read from worker: {e}")))?;
+        if read == 0 {
+            return Err(AspError::WorkerUnavailable(
+                "Worker closed its stdout".into(),
+            ));
+        }
+
+        serde_json::from_str(line.trim())
+            .map_err(|e| AspError::WorkerUnavailable(format!("Invalid worker response: {e}")))
+    }
+
+    /// Skip past the response to an abandoned command, if there is one.
+    async fn discard_abandoned(&mut self) -> Result<(), AspError> {
+        let Some(stale) = self.abandoned.clone() else {
+            return Ok(());
+        };
+        while Self::read_response(&mut self.stdout).await?.id != stale {}
+        self.abandoned = None;
+        tracing::debug!(command_id = %stale, "Discarded response to abandoned command");
+        Ok(())
     }
 
     async fn send_command(&mut self, command: &str, params: Value) -> Result<Value, AspError> {
+        self.discard_abandoned().await?;
+
         let id = uuid::Uuid::new_v4().to_string();
+        let start = std::time::Instant::now();
+        tracing::debug!(command = command, command_id = %id, "Sending worker command");
         let request = WorkerRequest {
             id: id.clone(),
             command: command.to_string(),
@@ -107,22 +279,18 @@ impl Worker {
         json.push('\n');
 
         self.stdin
-            .write_all(json.as_bytes())
-            .await
-            .map_err(|e| AspError::WorkerUnavailable(format!("Failed to write to worker: {e}")))?;
-        self.stdin
-            .flush()
-            .await
-            .map_err(|e| AspError::WorkerUnavailable(format!("Failed to flush worker stdin: {e}")))?;
-
-        let mut line = String::new();
-        self.stdout
-            .read_line(&mut line)
-            .await
-            .map_err(|e| AspError::WorkerUnavailable(format!("Failed to read from worker: {e}")))?;
+            .send(json)
+            .map_err(|_| AspError::WorkerUnavailable("Failed to write to worker".into()))?;
 
-        let response: WorkerResponse = serde_json::from_str(line.trim())
-            .map_err(|e| AspError::WorkerUnavailable(format!("Invalid worker response: {e}")))?;
+        self.abandoned = Some(id.clone());
+        let guard = CancelGuard {
+            id: &id,
+            stdin: &self.stdin,
+            armed: true,
+        };
+        let response = Self::read_response(&mut self.stdout).await?;
+        guard.disarm();
+        self.abandoned = None;
 
         if response.id != id {
             return Err(AspError::Internal(format!(
@@ -131,10 +299,22 @@ impl Worker {
             )));
         }
 
+        tracing::debug!(
+            command = command,
+            command_id = %id,
+            ok = response.ok,
+            duration_ms = start.elapsed().as_millis() as u64,
+            "Worker command finished"
+        );
+
         if !response.ok {
-            return Err(AspError::ProverError(
-                response.error.unwrap_or_else(|| "Unknown worker error".into()),
-            ));
+            let message = response
+                .error
+                .unwrap_or_else(|| "Unknown worker error".into());
+            return Err(match response.kind {
+                WorkerErrorKind::InvalidInput => AspError::InvalidInput(message),
+                WorkerErrorKind::Internal => AspError::ProverError(message),
+            });
         }
 
         Ok(response.data)
@@ -151,19 +331,74 @@ impl Worker {
             .ok_or_else(|| AspError::ProverError("Missing root in build_tree response".into()))
     }
 
+    /// Root of a tree built from `leaves` (decimal strings), computed without
+    /// replacing the worker's own tree.
+    pub async fn compute_root(&mut self, leaves: &[String]) -> Result<String, AspError> {
+        let params = serde_json::json!({ "leaves": leaves });
+        let data = self.send_command("compute_root", params).await?;
+        data["root"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| AspError::ProverError("Missing root in compute_root response".into()))
+    }
+
+    /// Root (decimal) the worker reports for a tree with no leaves.
+    pub async fn empty_root(&mut self) -> Result<String, AspError> {
+        self.compute_root(&[]).await
+    }
+
+    /// Root (decimal) of a tree holding [`SELF_TEST_LEAVES`], computed without
+    /// touching the worker's own tree.
+    pub async fn self_test_root(&mut self) -> Result<String, AspError> {
+        let leaves: Vec<String> = SELF_TEST_LEAVES.iter().map(|l| l.to_string()).collect();
+        self.compute_root(&leaves).await
+    }
+
+    /// Check the worker's Poseidon against the coordinator's before any
+    /// deposit: the [`SELF_TEST_LEAVES`] root must equal `expected` (decimal),
+    /// or the worker would build roots the coordinator rejects.
+    pub async fn self_test(&mut self, expected: &str) -> Result<String, AspError> {
+        let root = self.self_test_root().await?;
+        if root != expected {
+            return Err(AspError::Config(format!(
+                "Poseidon self-test failed: worker computed root {root} for leaves {:?}, \
+                 EXPECTED_SELF_TEST_ROOT is {expected}; worker and coordinator hash differently",
+                SELF_TEST_LEAVES
+            )));
+        }
+        Ok(root)
+    }
+
     /// Get a Merkle proof for a leaf at the given index.
-    pub async fn get_proof(
+    pub async fn get_proof(&mut self, leaf_index: u32) -> Result<MerkleProof, AspError> {
+        let params = serde_json::json!({ "leafIndex": leaf_index });
+        let data = self.send_command("get_proof", params).await?;
+        MerkleProof::from_worker(data)
+    }
+
+    /// Get a Merkle proof for a leaf against the historical `root` of the
+    /// tree's first `leaf_count` leaves. Fails if that prefix does not
+    /// hash to `root`.
+    pub async fn get_proof_at_root(
         &mut self,
         leaf_index: u32,
+        root: &str,
+        leaf_count: u32,
     ) -> Result<MerkleProof, AspError> {
-        let params = serde_json::json!({ "leafIndex": leaf_index });
+        let params = serde_json::json!({ "leafIndex": leaf_index, "leafCount": leaf_count });
         let data = self.send_command("get_proof", params).await?;
-        let proof: MerkleProof = serde_json::from_value(data)
-            .map_err(|e| AspError::ProverError(format!("Invalid proof response: {e}")))?;
+        let proof = MerkleProof::from_worker(data)?;
+        if proof.root != root {
+            return Err(AspError::ProverError(format!(
+                "First {leaf_count} leaves hash to {}, not the recorded root {root}",
+                proof.root
+            )));
+        }
         Ok(proof)
     }
 
-    /// Compute a note commitment and nullifier hash.
+    /// Compute a note commitment and nullifier hash. Repeated inputs are
+    /// answered from the cache without a worker round-trip.
     pub async fn compute_commitment(
         &mut self,
         secret: &str,
@@ -172,6 +407,17 @@ impl Worker {
         amount_high: &str,
         token: &str,
     ) -> Result<CommitmentResult, AspError> {
+        let key: CommitmentKey = (
+            secret.to_string(),
+            nullifier.to_string(),
+            amount_low.to_string(),
+            amount_high.to_string(),
+            token.to_string(),
+        );
+        if let Some(cached) = self.commitments.get(&key) {
+            return Ok(cached);
+        }
+
         let params = serde_json::json!({
             "secret": secret,
             "nullifier": nullifier,
@@ -182,6 +428,7 @@ impl Worker {
         let data = self.send_command("compute_commitment", params).await?;
         let result: CommitmentResult = serde_json::from_value(data)
             .map_err(|e| AspError::ProverError(format!("Invalid commitment response: {e}")))?;
+        self.commitments.insert(key, result.clone());
         Ok(result)
     }
 
@@ -201,28 +448,62 @@ impl Worker {
             "tickUpper": tick_upper,
             "liquidity": liquidity,
         });
-        let data = self.send_command("compute_position_commitment", params).await?;
-        let result: CommitmentResult = serde_json::from_value(data)
-            .map_err(|e| AspError::ProverError(format!("Invalid position commitment response: {e}")))?;
+        let data = self
+            .send_command("compute_position_commitment", params)
+            .await?;
+        let result: CommitmentResult = serde_json::from_value(data).map_err(|e| {
+            AspError::ProverError(format!("Invalid position commitment response: {e}"))
+        })?;
         Ok(result)
     }
 
     /// Generate a Groth16 proof and return Garaga calldata.
     pub async fn generate_proof(
         &mut self,
-        circuit: &str,
+        circuit: CircuitType,
         inputs: Value,
     ) -> Result<ProofResult, AspError> {
+        if !circuit.is_provable() {
+            return Err(AspError::ProverError(format!(
+                "'{circuit}' is not a provable circuit"
+            )));
+        }
         let params = serde_json::json!({
             "circuit": circuit,
             "inputs": inputs,
         });
+        let start = std::time::Instant::now();
         let data = self.send_command("generate_proof", params).await?;
-        let result: ProofResult = serde_json::from_value(data)
+        let mut result: ProofResult = serde_json::from_value(data)
             .map_err(|e| AspError::ProverError(format!("Invalid proof result: {e}")))?;
+        result.duration_ms = start.elapsed().as_millis() as u64;
         Ok(result)
     }
 
+    /// Verify a Groth16 proof against `circuit`'s verification key. Returns
+    /// `false` for a proof that does not verify.
+    pub async fn verify_proof(
+        &mut self,
+        circuit: CircuitType,
+        proof: &Value,
+        public_signals: &[String],
+    ) -> Result<bool, AspError> {
+        if !circuit.is_provable() {
+            return Err(AspError::ProverError(format!(
+                "'{circuit}' is not a provable circuit"
+            )));
+        }
+        let params = serde_json::json!({
+            "circuit": circuit,
+            "proof": proof,
+            "publicSignals": public_signals,
+        });
+        let data = self.send_command("verify_proof", params).await?;
+        data["verified"].as_bool().ok_or_else(|| {
+            AspError::ProverError("Missing verified in verify_proof response".into())
+        })
+    }
+
     /// Insert a single leaf and get the new root.
     pub async fn insert_leaf(&mut self, leaf: &str) -> Result<String, AspError> {
         let params = serde_json::json!({ "leaf": leaf });
@@ -235,9 +516,7 @@ impl Worker {
 
     /// Get the current tree root without modifying the tree.
     pub async fn get_root(&mut self) -> Result<String, AspError> {
-        let data = self
-            .send_command("get_root", serde_json::json!({}))
-            .await?;
+        let data = self.send_command("get_root", serde_json::json!({})).await?;
         data["root"]
             .as_str()
             .map(|s| s.to_string())
@@ -246,10 +525,76 @@ impl Worker {
 
     /// Send a ping to check if the worker process is alive.
     pub async fn ping(&mut self) -> Result<bool, AspError> {
+        let data = self.send_command("ping", serde_json::json!({})).await?;
+        Ok(data["pong"].as_bool().unwrap_or(false))
+    }
+
+    /// snarkjs version and verification key hashes of the worker's circuits.
+    pub async fn circuit_info(&mut self) -> Result<WorkerInfo, AspError> {
         let data = self
-            .send_command("ping", serde_json::json!({}))
+            .send_command("circuit_info", serde_json::json!({}))
             .await?;
-        Ok(data["pong"].as_bool().unwrap_or(false))
+        serde_json::from_value(data)
+            .map_err(|e| AspError::ProverError(format!("Failed to parse circuit info: {e}")))
+    }
+
+    /// Have the worker read every built circuit's proving artifacts into the
+    /// OS page cache. Proofs run in fresh processes, so this only spares the
+    /// first proof its disk reads. Returns the circuits it read.
+    pub async fn warmup(&mut self) -> Result<Vec<String>, AspError> {
+        let data = self.send_command("warmup", serde_json::json!({})).await?;
+        serde_json::from_value(data["circuits"].clone())
+            .map_err(|e| AspError::ProverError(format!("Invalid warmup response: {e}")))
+    }
+
+    /// Query [`WorkerInfo`], logging circuits whose hashes differ from
+    /// `expected_hashes`, and with `warmup` (`WORKER_WARMUP`) warm the worker
+    /// up. Run once before serving, so no request pays for the warmup.
+    /// Neither step failing stops startup. `warmed_up` reflects the warmup
+    /// alone: without circuit info the result is `None` only if the warmup
+    /// did not succeed either.
+    pub async fn startup_info(
+        &mut self,
+        expected_hashes: &HashMap<String, String>,
+        warmup: bool,
+    ) -> Option<WorkerInfo> {
+        let mut info = match self.circuit_info().await {
+            Ok(info) => {
+                tracing::info!(
+                    version = %info.version,
+                    circuits = ?info.circuits,
+                    "Worker circuits"
+                );
+                let mismatched = info.mismatched_circuits(expected_hashes);
+                if !mismatched.is_empty() {
+                    tracing::warn!(
+                        circuits = ?mismatched,
+                        "Worker verification keys differ from EXPECTED_CIRCUIT_HASHES"
+                    );
+                }
+                Some(info)
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Worker did not report circuit info");
+                None
+            }
+        };
+
+        if warmup {
+            let start = std::time::Instant::now();
+            match self.warmup().await {
+                Ok(circuits) => {
+                    tracing::info!(
+                        circuits = ?circuits,
+                        duration_ms = start.elapsed().as_millis() as u64,
+                        "Worker warmed up"
+                    );
+                    info.get_or_insert_with(WorkerInfo::default).warmed_up = true;
+                }
+                Err(e) => tracing::warn!(error = %e, "Worker warmup failed"),
+            }
+        }
+        info
     }
 }
 
@@ -257,20 +602,688 @@ impl Worker {
 #[serde(rename_all = "camelCase")]
 pub struct MerkleProof {
     pub path_elements: Vec<String>,
+    /// Left (0) / right (1) position at each level; guaranteed to be 0 or 1
+    /// for proofs returned by [`Worker`].
     pub path_indices: Vec<u32>,
     pub root: String,
 }
 
-#[derive(Debug, Deserialize)]
+impl MerkleProof {
+    /// Parse a `get_proof` response, rejecting path indices other than 0 or
+    /// 1 and paths whose elements and indices differ in length.
+    fn from_worker(data: Value) -> Result<Self, AspError> {
+        let proof: MerkleProof = serde_json::from_value(data)
+            .map_err(|e| AspError::ProverError(format!("Invalid proof response: {e}")))?;
+        if let Some((level, index)) = proof
+            .path_indices
+            .iter()
+            .enumerate()
+            .find(|(_, index)| **index > 1)
+        {
+            return Err(AspError::ProverError(format!(
+                "Invalid proof response: path index {index} at level {level} is not 0 or 1"
+            )));
+        }
+        if proof.path_indices.len() != proof.path_elements.len() {
+            return Err(AspError::ProverError(format!(
+                "Invalid proof response: {} path elements but {} path indices",
+                proof.path_elements.len(),
+                proof.path_indices.len()
+            )));
+        }
+        Ok(proof)
+    }
+
+    /// `path_indices` as booleans, `true` meaning right.
+    pub fn path_bits(&self) -> Vec<bool> {
+        self.path_indices.iter().map(|index| *index == 1).collect()
+    }
+}
+
+/// Interpreter the worker script runs under: `node`, `bun`, or any program
+/// name or path, plus arguments placed before the script path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerRuntime {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl WorkerRuntime {
+    pub fn from_config(config: &Config) -> Self {
+        WorkerRuntime {
+            program: config.worker_runtime.clone(),
+            args: config.worker_runtime_args.clone(),
+        }
+    }
+
+    /// Locate the runtime binary: a path is used as is, a bare name is
+    /// looked up in `PATH`.
+    fn resolve(&self) -> Result<PathBuf, AspError> {
+        let not_found = || {
+            AspError::WorkerUnavailable(format!(
+                "Worker runtime '{}' not found; set WORKER_RUNTIME to node, bun, \
+                 or the path of an interpreter",
+                self.program
+            ))
+        };
+        if self.program.is_empty() {
+            return Err(not_found());
+        }
+
+        let program = Path::new(&self.program);
+        if program.components().count() > 1 {
+            return program
+                .is_file()
+                .then(|| program.to_path_buf())
+                .ok_or_else(not_found);
+        }
+        std::env::var_os("PATH")
+            .iter()
+            .flat_map(std::env::split_paths)
+            .map(|dir| dir.join(program))
+            .find(|candidate| candidate.is_file())
+            .ok_or_else(not_found)
+    }
+}
+
+/// Log each line the worker writes to stderr. Returns once the pipe closes,
+/// i.e. when the worker exits.
+async fn forward_stderr<R: AsyncRead + Unpin>(stderr: R) {
+    let mut lines = BufReader::new(stderr).lines();
+    loop {
+        match lines.next_line().await {
+            Ok(Some(line)) => tracing::warn!(target: "worker", "{line}"),
+            Ok(None) => break,
+            Err(e) => {
+                tracing::warn!(target: "worker", error = %e, "Failed to read worker stderr");
+                break;
+            }
+        }
+    }
+    tracing::debug!(target: "worker", "Worker stderr closed");
+}
+
+#[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CommitmentResult {
     pub commitment: String,
     pub nullifier_hash: String,
 }
 
+/// What the worker is proving with, reported once at startup.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct WorkerInfo {
+    /// snarkjs version.
+    pub version: String,
+    /// SHA-256 of each circuit's verification key, `0x`-prefixed hex.
+    pub circuits: BTreeMap<String, String>,
+    /// Circuit artifacts were read into the page cache at startup
+    /// (`WORKER_WARMUP`).
+    #[serde(default)]
+    pub warmed_up: bool,
+}
+
+impl WorkerInfo {
+    /// Circuits in `expected` whose reported hash is missing or different.
+    pub fn mismatched_circuits(&self, expected: &HashMap<String, String>) -> Vec<String> {
+        let mut mismatched: Vec<String> = expected
+            .iter()
+            .filter(|(circuit, hash)| {
+                !matches!(
+                    self.circuits.get(*circuit),
+                    Some(actual) if actual.eq_ignore_ascii_case(hash)
+                )
+            })
+            .map(|(circuit, _)| circuit.clone())
+            .collect();
+        mismatched.sort();
+        mismatched
+    }
+}
+
+/// Command set the worker must implement, checked against the
+/// `protocol_version` in its ready message. Bumped in lockstep with
+/// `PROTOCOL_VERSION` in `worker/worker.mjs` whenever a command is added,
+/// removed or changes shape.
+pub const WORKER_PROTOCOL_VERSION: u32 = 4;
+
+/// Domain separator of note commitments:
+/// `Poseidon(Poseidon(secret, nullifier), amount_low, amount_high, token)`.
+pub const DEFAULT_NOTE_DOMAIN: &str = "zylith.note.v1";
+
+/// Domain separator of position commitments:
+/// `Poseidon(secret, nullifier, tick_lower, tick_upper, liquidity)`.
+pub const DEFAULT_POSITION_DOMAIN: &str = "zylith.position.v1";
+
+/// Names of the commitment schemes the worker hashes notes and positions
+/// under. The circuits fix the schemes, so these select nothing; they are
+/// checked against the worker's ready message so a worker built for another
+/// scheme is refused at spawn rather than producing commitments the pool
+/// cannot open.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DomainSeparators {
+    pub note: String,
+    pub position: String,
+}
+
+impl Default for DomainSeparators {
+    fn default() -> Self {
+        DomainSeparators {
+            note: DEFAULT_NOTE_DOMAIN.to_string(),
+            position: DEFAULT_POSITION_DOMAIN.to_string(),
+        }
+    }
+}
+
+/// Garaga calldata layout the relayer encodes transactions for. Bumped in
+/// lockstep with `CALLDATA_VERSION` in `worker/worker.mjs`.
+pub const EXPECTED_CALLDATA_VERSION: u32 = 1;
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProofResult {
     pub calldata: Vec<String>,
     pub public_signals: Vec<String>,
+    /// Circuit the worker actually proved; `None` from workers that do not
+    /// report it.
+    #[serde(default)]
+    pub circuit: Option<CircuitType>,
+    /// Calldata layout version; 0 from workers that predate versioning.
+    #[serde(default)]
+    pub calldata_version: u32,
+    /// snarkjs Groth16 proof (`pi_a`, `pi_b`, `pi_c`), as taken by
+    /// [`Worker::verify_proof`].
+    #[serde(default)]
+    pub proof: Option<Value>,
+    /// Wall-clock time of the `generate_proof` round trip, measured by the
+    /// ASP rather than reported by the worker.
+    #[serde(skip)]
+    pub duration_ms: u64,
+}
+
+impl ProofResult {
+    /// Reject calldata that was not produced for `circuit` in the layout this
+    /// ASP submits, instead of letting it fail opaquely on-chain.
+    pub fn ensure_layout(&self, circuit: CircuitType) -> Result<(), AspError> {
+        if self.circuit != Some(circuit) {
+            return Err(AspError::ProverError(format!(
+                "Worker returned a proof for circuit '{}', expected '{circuit}'",
+                self.circuit.map_or("unknown", CircuitType::as_str)
+            )));
+        }
+        if self.calldata_version != EXPECTED_CALLDATA_VERSION {
+            return Err(AspError::ProverError(format!(
+                "Unsupported {circuit} calldata version {} (expected {EXPECTED_CALLDATA_VERSION})",
+                self.calldata_version
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{DuplexStream, Lines};
+
+    /// A worker wired to in-memory pipes, plus the fake process's ends.
+    fn fake_worker() -> (Worker, Lines<BufReader<DuplexStream>>, DuplexStream) {
+        let (asp_stdin, fake_stdin) = tokio::io::duplex(4096);
+        let (fake_stdout, asp_stdout) = tokio::io::duplex(4096);
+        let worker = Worker::from_pipes(asp_stdout, asp_stdin);
+        (worker, BufReader::new(fake_stdin).lines(), fake_stdout)
+    }
+
+    async fn next_request(requests: &mut Lines<BufReader<DuplexStream>>) -> Value {
+        let line = requests.next_line().await.unwrap().unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    /// A ready line carrying the given fields.
+    fn ready_message(protocol_version: Option<u32>, domains: Option<&DomainSeparators>) -> String {
+        let mut msg = serde_json::json!({ "ready": true });
+        if let Some(version) = protocol_version {
+            msg["protocol_version"] = version.into();
+        }
+        if let Some(domains) = domains {
+            msg["domains"] = serde_json::to_value(domains).unwrap();
+        }
+        msg.to_string() + "\n"
+    }
+
+    #[tokio::test]
+    async fn ready_message_must_carry_protocol_version() {
+        let domains = DomainSeparators::default();
+        let (mut worker, _requests, mut responses) = fake_worker();
+        let ready = ready_message(Some(WORKER_PROTOCOL_VERSION), Some(&domains));
+        responses.write_all(ready.as_bytes()).await.unwrap();
+        worker.await_ready(&domains).await.unwrap();
+
+        for stale in [
+            ready_message(Some(WORKER_PROTOCOL_VERSION + 1), Some(&domains)),
+            ready_message(None, Some(&domains)),
+        ] {
+            let (mut worker, _requests, mut responses) = fake_worker();
+            responses.write_all(stale.as_bytes()).await.unwrap();
+            let err = worker.await_ready(&domains).await.unwrap_err();
+            assert!(matches!(err, AspError::WorkerUnavailable(_)), "{err}");
+            assert!(err.to_string().contains("protocol version"), "{err}");
+        }
+    }
+
+    #[tokio::test]
+    async fn ready_message_must_echo_configured_domains() {
+        let configured = DomainSeparators::default();
+        let swapped = DomainSeparators {
+            note: configured.position.clone(),
+            position: configured.note.clone(),
+        };
+        for ready in [
+            ready_message(Some(WORKER_PROTOCOL_VERSION), Some(&swapped)),
+            ready_message(Some(WORKER_PROTOCOL_VERSION), None),
+        ] {
+            let (mut worker, _requests, mut responses) = fake_worker();
+            responses.write_all(ready.as_bytes()).await.unwrap();
+            let err = worker.await_ready(&configured).await.unwrap_err();
+            assert!(matches!(err, AspError::WorkerUnavailable(_)), "{err}");
+            assert!(err.to_string().contains("domain"), "{err}");
+        }
+    }
+
+    #[tokio::test]
+    async fn dropped_command_sends_cancel_and_skips_its_response() {
+        let (mut worker, mut requests, mut responses) = fake_worker();
+
+        // Give up on the proof as soon as the worker has received it
+        let proof_request = {
+            let proving = worker.generate_proof(CircuitType::Swap, serde_json::json!({}));
+            tokio::pin!(proving);
+            tokio::select! {
+                _ = &mut proving => panic!("proof cannot complete without a response"),
+                request = next_request(&mut requests) => request,
+            }
+        };
+        assert_eq!(proof_request["command"], "generate_proof");
+
+        let cancel = next_request(&mut requests).await;
+        assert_eq!(cancel["command"], "cancel");
+        assert_eq!(cancel["params"]["id"], proof_request["id"]);
+
+        // The cancelled proof's error reply must not be taken as the answer
+        // to the next command
+        let cancelled = serde_json::json!({
+            "id": proof_request["id"], "ok": false, "error": "cancelled"
+        });
+        responses
+            .write_all(format!("{cancelled}\n").as_bytes())
+            .await
+            .unwrap();
+
+        let fake = async {
+            let ping = next_request(&mut requests).await;
+            let pong = serde_json::json!({"id": ping["id"], "ok": true, "data": {"pong": true}});
+            responses
+                .write_all(format!("{pong}\n").as_bytes())
+                .await
+                .unwrap();
+        };
+        let (alive, ()) = tokio::join!(worker.ping(), fake);
+        assert!(alive.unwrap());
+    }
+
+    #[tokio::test]
+    async fn worker_error_kind_selects_status() {
+        use axum::http::StatusCode;
+        use axum::response::IntoResponse;
+
+        let (mut worker, mut requests, mut responses) = fake_worker();
+        for (kind, status) in [
+            (Some("invalid_input"), StatusCode::BAD_REQUEST),
+            (Some("internal"), StatusCode::SERVICE_UNAVAILABLE),
+            (Some("something_new"), StatusCode::SERVICE_UNAVAILABLE),
+            (None, StatusCode::SERVICE_UNAVAILABLE),
+        ] {
+            let fake = async {
+                let request = next_request(&mut requests).await;
+                let mut reply = serde_json::json!({
+                    "id": request["id"], "ok": false, "error": "Assert Failed"
+                });
+                if let Some(kind) = kind {
+                    reply["kind"] = kind.into();
+                }
+                responses
+                    .write_all(format!("{reply}\n").as_bytes())
+                    .await
+                    .unwrap();
+            };
+            let (result, ()) = tokio::join!(
+                worker.generate_proof(CircuitType::Membership, serde_json::json!({})),
+                fake
+            );
+            let err = result.unwrap_err();
+            assert!(err.to_string().contains("Assert Failed"), "{err}");
+            assert_eq!(err.into_response().status(), status, "kind {kind:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn proof_generation_is_timed() {
+        let (mut worker, mut requests, mut responses) = fake_worker();
+        let fake = async {
+            let request = next_request(&mut requests).await;
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            let reply = serde_json::json!({
+                "id": request["id"], "ok": true,
+                "data": {"calldata": [], "publicSignals": [], "circuit": "swap"}
+            });
+            responses
+                .write_all(format!("{reply}\n").as_bytes())
+                .await
+                .unwrap();
+        };
+        let (result, ()) = tokio::join!(
+            worker.generate_proof(CircuitType::Swap, serde_json::json!({})),
+            fake
+        );
+        assert!(result.unwrap().duration_ms >= 20);
+    }
+
+    /// Answer every request with the same commitment, counting the calls.
+    fn answer_commitments(
+        mut requests: Lines<BufReader<DuplexStream>>,
+        mut responses: DuplexStream,
+    ) -> Arc<AtomicUsize> {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        tokio::spawn(async move {
+            while let Ok(Some(line)) = requests.next_line().await {
+                let request: Value = serde_json::from_str(&line).unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+                let reply = serde_json::json!({
+                    "id": request["id"], "ok": true,
+                    "data": {"commitment": "11", "nullifierHash": "22"}
+                });
+                responses
+                    .write_all(format!("{reply}\n").as_bytes())
+                    .await
+                    .unwrap();
+            }
+        });
+        calls
+    }
+
+    #[tokio::test]
+    async fn repeated_commitment_is_served_from_cache() {
+        let (mut worker, requests, responses) = fake_worker();
+        let calls = answer_commitments(requests, responses);
+
+        let first = worker
+            .compute_commitment("1", "2", "3", "0", "0x5")
+            .await
+            .unwrap();
+        let second = worker
+            .compute_commitment("1", "2", "3", "0", "0x5")
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(second.commitment, first.commitment);
+        assert_eq!(second.nullifier_hash, "22");
+
+        // Different inputs still reach the worker
+        worker
+            .compute_commitment("1", "2", "4", "0", "0x5")
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn disabled_commitment_cache_always_asks_worker() {
+        let (worker, requests, responses) = fake_worker();
+        let mut worker = worker.with_commitment_cache_size(0);
+        let calls = answer_commitments(requests, responses);
+
+        for _ in 0..2 {
+            worker
+                .compute_commitment("1", "2", "3", "0", "0x5")
+                .await
+                .unwrap();
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn unknown_runtime_fails_fast() {
+        let runtime = WorkerRuntime {
+            program: "zylith-no-such-runtime".into(),
+            args: Vec::new(),
+        };
+        let err = Worker::spawn(&runtime, "worker/worker.mjs", 20, &Default::default())
+            .await
+            .err()
+            .expect("spawn must fail");
+        match err {
+            AspError::WorkerUnavailable(msg) => {
+                assert!(msg.contains("'zylith-no-such-runtime' not found"), "{msg}")
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+
+        let missing_path = WorkerRuntime {
+            program: "/nonexistent/bin/node".into(),
+            args: Vec::new(),
+        };
+        assert!(matches!(
+            missing_path.resolve(),
+            Err(AspError::WorkerUnavailable(_))
+        ));
+    }
+
+    /// `MakeWriter` that appends formatted log output to a shared buffer.
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn worker_stderr_is_logged() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let (mut worker_stderr, asp_stderr) = tokio::io::duplex(1024);
+        worker_stderr
+            .write_all(b"snarkjs: witness too large\n")
+            .await
+            .unwrap();
+        drop(worker_stderr); // worker exits
+
+        // Returns on its own once the pipe closes
+        forward_stderr(asp_stderr).await;
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let line = output
+            .lines()
+            .find(|l| l.contains("snarkjs: witness too large"))
+            .expect("stderr line was not logged");
+        assert!(line.contains("WARN") && line.contains("worker"), "{line}");
+    }
+
+    fn proof(circuit: CircuitType, calldata_version: u32) -> ProofResult {
+        ProofResult {
+            calldata: vec!["1".into()],
+            public_signals: vec!["2".into()],
+            circuit: Some(circuit),
+            calldata_version,
+            proof: None,
+            duration_ms: 0,
+        }
+    }
+
+    #[test]
+    fn rejects_stale_calldata_version() {
+        let swap = CircuitType::Swap;
+        assert!(proof(swap, EXPECTED_CALLDATA_VERSION)
+            .ensure_layout(swap)
+            .is_ok());
+
+        let stale = proof(swap, EXPECTED_CALLDATA_VERSION - 1).ensure_layout(swap);
+        assert!(matches!(stale, Err(AspError::ProverError(_))));
+
+        // Unversioned worker output deserializes as version 0
+        let unversioned: ProofResult = serde_json::from_value(serde_json::json!({
+            "calldata": [], "publicSignals": []
+        }))
+        .unwrap();
+        assert!(unversioned.ensure_layout(swap).is_err());
+    }
+
+    #[test]
+    fn rejects_proof_for_other_circuit() {
+        let wrong =
+            proof(CircuitType::Mint, EXPECTED_CALLDATA_VERSION).ensure_layout(CircuitType::Burn);
+        assert!(matches!(wrong, Err(AspError::ProverError(_))));
+    }
+
+    #[tokio::test]
+    async fn proof_with_non_binary_path_index_is_rejected() {
+        let (mut worker, mut requests, mut responses) = fake_worker();
+
+        let fake = async {
+            for path_indices in [serde_json::json!([0, 2]), serde_json::json!([1, 0])] {
+                let request = next_request(&mut requests).await;
+                let reply = serde_json::json!({
+                    "id": request["id"], "ok": true,
+                    "data": {"pathElements": ["5", "6"], "pathIndices": path_indices, "root": "7"}
+                });
+                responses
+                    .write_all(format!("{reply}\n").as_bytes())
+                    .await
+                    .unwrap();
+            }
+        };
+        let results = async {
+            let malformed = worker.get_proof(3).await;
+            let valid = worker.get_proof(3).await;
+            (malformed, valid)
+        };
+        let ((malformed, valid), ()) = tokio::join!(results, fake);
+
+        let err = malformed.unwrap_err();
+        assert!(matches!(err, AspError::ProverError(_)), "{err}");
+        assert!(err.to_string().contains("path index 2 at level 1"), "{err}");
+        assert_eq!(valid.unwrap().path_bits(), vec![true, false]);
+    }
+
+    #[tokio::test]
+    async fn completed_command_sends_no_cancel() {
+        let (mut worker, mut requests, mut responses) = fake_worker();
+
+        let fake = async {
+            let ping = next_request(&mut requests).await;
+            let pong = serde_json::json!({"id": ping["id"], "ok": true, "data": {"pong": true}});
+            responses
+                .write_all(format!("{pong}\n").as_bytes())
+                .await
+                .unwrap();
+        };
+        let (alive, ()) = tokio::join!(worker.ping(), fake);
+        assert!(alive.unwrap());
+
+        drop(worker);
+        assert!(requests.next_line().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn startup_info_warms_up_after_circuit_info() {
+        let (mut worker, mut requests, mut responses) = fake_worker();
+
+        let fake = async {
+            let mut commands = Vec::new();
+            for data in [
+                serde_json::json!({"version": "0.7.5", "circuits": {"swap": "0xab"}}),
+                serde_json::json!({"circuits": ["swap"]}),
+            ] {
+                let request = next_request(&mut requests).await;
+                let reply = serde_json::json!({"id": request["id"], "ok": true, "data": data});
+                responses
+                    .write_all(format!("{reply}\n").as_bytes())
+                    .await
+                    .unwrap();
+                commands.push(request["command"].as_str().unwrap().to_string());
+            }
+            commands
+        };
+        let expected = HashMap::new();
+        let (info, commands) = tokio::join!(worker.startup_info(&expected, true), fake);
+
+        assert_eq!(commands, ["circuit_info", "warmup"]);
+        let info = info.unwrap();
+        assert!(info.warmed_up);
+        assert_eq!(info.version, "0.7.5");
+    }
+
+    #[tokio::test]
+    async fn warmup_is_reported_without_circuit_info() {
+        let (mut worker, mut requests, mut responses) = fake_worker();
+
+        let fake = async {
+            let info_request = next_request(&mut requests).await;
+            let failed = serde_json::json!({
+                "id": info_request["id"], "ok": false, "error": "missing verification key"
+            });
+            responses
+                .write_all(format!("{failed}\n").as_bytes())
+                .await
+                .unwrap();
+            let warmup_request = next_request(&mut requests).await;
+            let warmed = serde_json::json!({
+                "id": warmup_request["id"], "ok": true, "data": {"circuits": ["swap"]}
+            });
+            responses
+                .write_all(format!("{warmed}\n").as_bytes())
+                .await
+                .unwrap();
+        };
+        let expected = HashMap::new();
+        let (info, ()) = tokio::join!(worker.startup_info(&expected, true), fake);
+
+        let info = info.unwrap();
+        assert!(info.warmed_up);
+        assert!(info.circuits.is_empty());
+    }
+
+    #[test]
+    fn reports_mismatched_circuit_hashes() {
+        let info = WorkerInfo {
+            version: "0.7.5".into(),
+            circuits: BTreeMap::from([
+                ("swap".to_string(), "0xABCD".to_string()),
+                ("mint".to_string(), "0x1111".to_string()),
+            ]),
+            warmed_up: false,
+        };
+        let expected = HashMap::from([
+            ("swap".to_string(), "0xabcd".to_string()),
+            ("mint".to_string(), "0x2222".to_string()),
+            ("burn".to_string(), "0x3333".to_string()),
+        ]);
+        assert_eq!(info.mismatched_circuits(&expected), vec!["burn", "mint"]);
+        assert!(info.mismatched_circuits(&HashMap::new()).is_empty());
+    }
 }
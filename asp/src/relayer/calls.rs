@@ -0,0 +1,152 @@
+use starknet::core::types::{Call, Felt};
+
+use crate::config::Config;
+use crate::error::AspError;
+
+use super::starknet::{build_span_calldata, pool_key_calldata, u256_to_felts};
+use super::{PoolKeyParams, PoolRoutes};
+
+/// Builds the pool calls that spend a proof. The relayer sends them from the
+/// admin account; `self_submit` requests get them back to send themselves.
+#[derive(Debug, Clone)]
+pub struct PoolCalls {
+    pools: PoolRoutes,
+    /// Longest proof calldata accepted (`MAX_CALLDATA_FELTS`).
+    max_calldata_felts: usize,
+}
+
+impl PoolCalls {
+    pub fn new(pools: PoolRoutes, max_calldata_felts: usize) -> Self {
+        PoolCalls {
+            pools,
+            max_calldata_felts,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self, AspError> {
+        let pools = PoolRoutes::new(&config.pool_address, &config.pools)?;
+        Ok(PoolCalls::new(pools, config.max_calldata_felts))
+    }
+
+    pub fn pools(&self) -> &PoolRoutes {
+        &self.pools
+    }
+
+    /// `withdraw(proof)` on the default pool.
+    pub fn withdraw(&self, proof_calldata_hex: &[String]) -> Result<Call, AspError> {
+        let calldata = build_span_calldata(proof_calldata_hex, self.max_calldata_felts)?;
+        call(*self.pools.default_address(), "withdraw", calldata)
+    }
+
+    /// `shielded_swap(pool_key, proof, sqrt_price_limit)`.
+    pub fn shielded_swap(
+        &self,
+        pool_key: &PoolKeyParams,
+        proof_calldata_hex: &[String],
+        sqrt_price_limit: &str,
+    ) -> Result<Call, AspError> {
+        let mut calldata = pool_key_calldata(pool_key)?;
+        let span = build_span_calldata(proof_calldata_hex, self.max_calldata_felts)?;
+        calldata.extend(span);
+
+        let (low, high) = u256_to_felts(sqrt_price_limit)?;
+        calldata.push(low);
+        calldata.push(high);
+
+        call(self.pools.resolve(pool_key)?, "shielded_swap", calldata)
+    }
+
+    /// `shielded_mint(pool_key, proof, liquidity)`.
+    pub fn shielded_mint(
+        &self,
+        pool_key: &PoolKeyParams,
+        proof_calldata_hex: &[String],
+        liquidity: u128,
+    ) -> Result<Call, AspError> {
+        self.liquidity_call("shielded_mint", pool_key, proof_calldata_hex, liquidity)
+    }
+
+    /// `shielded_burn(pool_key, proof, liquidity)`.
+    pub fn shielded_burn(
+        &self,
+        pool_key: &PoolKeyParams,
+        proof_calldata_hex: &[String],
+        liquidity: u128,
+    ) -> Result<Call, AspError> {
+        self.liquidity_call("shielded_burn", pool_key, proof_calldata_hex, liquidity)
+    }
+
+    fn liquidity_call(
+        &self,
+        entry_point: &str,
+        pool_key: &PoolKeyParams,
+        proof_calldata_hex: &[String],
+        liquidity: u128,
+    ) -> Result<Call, AspError> {
+        let mut calldata = pool_key_calldata(pool_key)?;
+        let span = build_span_calldata(proof_calldata_hex, self.max_calldata_felts)?;
+        calldata.extend(span);
+        calldata.push(Felt::from(liquidity));
+
+        call(self.pools.resolve(pool_key)?, entry_point, calldata)
+    }
+}
+
+fn call(to: Felt, entry_point: &str, calldata: Vec<Felt>) -> Result<Call, AspError> {
+    Ok(Call {
+        to,
+        selector: starknet::core::utils::get_selector_from_name(entry_point)
+            .map_err(|e| AspError::Internal(format!("Selector error: {e}")))?,
+        calldata,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn pool_key() -> PoolKeyParams {
+        PoolKeyParams {
+            token_0: "0x1".into(),
+            token_1: "0x2".into(),
+            fee: 3000,
+            tick_spacing: 60,
+        }
+    }
+
+    #[test]
+    fn swap_call_targets_routed_pool() {
+        let pools = HashMap::from([("0x1:0x2:3000:60".to_string(), "0xaaa".to_string())]);
+        let calls = PoolCalls::new(PoolRoutes::new("0xdef", &pools).unwrap(), 10);
+
+        let call = calls
+            .shielded_swap(&pool_key(), &["0x5".into(), "0x6".into()], "7")
+            .unwrap();
+        assert_eq!(call.to, Felt::from(0xaaa_u64));
+        assert_eq!(
+            call.selector,
+            starknet::core::utils::get_selector_from_name("shielded_swap").unwrap()
+        );
+        let expected: Vec<Felt> = [1u64, 2, 3000, 60, 2, 5, 6, 7, 0]
+            .into_iter()
+            .map(Felt::from)
+            .collect();
+        assert_eq!(call.calldata, expected);
+    }
+
+    #[test]
+    fn liquidity_calls_end_with_liquidity() {
+        let calls = PoolCalls::new(PoolRoutes::new("0xdef", &HashMap::new()).unwrap(), 10);
+
+        let call = calls
+            .shielded_burn(&pool_key(), &["0x5".into()], 42)
+            .unwrap();
+        assert_eq!(call.to, Felt::from(0xdef_u64));
+        assert_eq!(call.calldata.last(), Some(&Felt::from(42u64)));
+        assert!(calls
+            .shielded_mint(&pool_key(), &vec!["0x1".into(); 11], 1)
+            .is_err());
+    }
+}
@@ -0,0 +1,129 @@
+use starknet::accounts::ExecutionV3;
+
+use crate::config::Config;
+
+/// Operator caps on v3 transaction resource bounds. A bound left unset is
+/// filled in from the node's fee estimate (times `FEE_MULTIPLIER`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasBounds {
+    pub l1_gas_max_amount: Option<u64>,
+    pub l1_gas_max_price: Option<u128>,
+    pub l2_gas_max_amount: Option<u64>,
+    pub l2_gas_max_price: Option<u128>,
+}
+
+impl GasBounds {
+    pub fn from_config(config: &Config) -> Self {
+        GasBounds {
+            l1_gas_max_amount: config.l1_gas_max_amount,
+            l1_gas_max_price: config.l1_gas_max_price,
+            l2_gas_max_amount: config.l2_gas_max_amount,
+            l2_gas_max_price: config.l2_gas_max_price,
+        }
+    }
+
+    /// Set every configured bound on `builder`, leaving the rest to
+    /// estimation.
+    pub(crate) fn apply<B: ResourceBoundsBuilder>(&self, mut builder: B) -> B {
+        if let Some(amount) = self.l1_gas_max_amount {
+            builder = builder.l1_gas(amount);
+        }
+        if let Some(price) = self.l1_gas_max_price {
+            builder = builder.l1_gas_price(price);
+        }
+        if let Some(amount) = self.l2_gas_max_amount {
+            builder = builder.l2_gas(amount);
+        }
+        if let Some(price) = self.l2_gas_max_price {
+            builder = builder.l2_gas_price(price);
+        }
+        builder
+    }
+}
+
+/// The resource-bound setters of a v3 execution builder.
+pub(crate) trait ResourceBoundsBuilder: Sized {
+    fn l1_gas(self, amount: u64) -> Self;
+    fn l1_gas_price(self, price: u128) -> Self;
+    fn l2_gas(self, amount: u64) -> Self;
+    fn l2_gas_price(self, price: u128) -> Self;
+}
+
+impl<A> ResourceBoundsBuilder for ExecutionV3<'_, A> {
+    fn l1_gas(self, amount: u64) -> Self {
+        ExecutionV3::l1_gas(self, amount)
+    }
+
+    fn l1_gas_price(self, price: u128) -> Self {
+        ExecutionV3::l1_gas_price(self, price)
+    }
+
+    fn l2_gas(self, amount: u64) -> Self {
+        ExecutionV3::l2_gas(self, amount)
+    }
+
+    fn l2_gas_price(self, price: u128) -> Self {
+        ExecutionV3::l2_gas_price(self, price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records which bounds were set.
+    #[derive(Default)]
+    struct Recorder(Vec<(&'static str, u128)>);
+
+    impl ResourceBoundsBuilder for Recorder {
+        fn l1_gas(mut self, amount: u64) -> Self {
+            self.0.push(("l1_gas", amount.into()));
+            self
+        }
+        fn l1_gas_price(mut self, price: u128) -> Self {
+            self.0.push(("l1_gas_price", price));
+            self
+        }
+        fn l2_gas(mut self, amount: u64) -> Self {
+            self.0.push(("l2_gas", amount.into()));
+            self
+        }
+        fn l2_gas_price(mut self, price: u128) -> Self {
+            self.0.push(("l2_gas_price", price));
+            self
+        }
+    }
+
+    #[test]
+    fn unset_bounds_leave_builder_to_estimation() {
+        let recorded = GasBounds::default().apply(Recorder::default());
+        assert!(recorded.0.is_empty());
+    }
+
+    #[test]
+    fn sets_only_configured_bounds() {
+        let bounds = GasBounds {
+            l1_gas_max_price: Some(50_000_000_000_000),
+            l2_gas_max_amount: Some(2_000_000),
+            ..GasBounds::default()
+        };
+        let recorded = bounds.apply(Recorder::default());
+        assert_eq!(
+            recorded.0,
+            vec![("l1_gas_price", 50_000_000_000_000), ("l2_gas", 2_000_000)]
+        );
+    }
+
+    #[test]
+    fn sets_all_bounds() {
+        let bounds = GasBounds {
+            l1_gas_max_amount: Some(1),
+            l1_gas_max_price: Some(2),
+            l2_gas_max_amount: Some(3),
+            l2_gas_max_price: Some(4),
+        };
+        let recorded = bounds.apply(Recorder::default());
+        let names: Vec<&str> = recorded.0.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, vec!["l1_gas", "l1_gas_price", "l2_gas", "l2_gas_price"]);
+    }
+}
@@ -0,0 +1,482 @@
+//! Scriptable in-process [`Relayer`] for tests, available to downstream
+//! crates through the `test-support` feature.
+//!
+//! Every method succeeds with a fixed `0xmock_<method>_tx` hash unless
+//! programmed to fail or revert, and every call is recorded for assertions.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use starknet::core::types::Felt;
+
+use crate::error::AspError;
+
+use super::{PoolKeyParams, Relayer, SwapQuote};
+
+/// A [`Relayer`] method, used to program and inspect a [`MockRelayer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RelayerMethod {
+    Deposit,
+    SubmitMerkleRoot,
+    WaitForTx,
+    GetOnchainRoot,
+    VerifyMembership,
+    ShieldedSwap,
+    ShieldedMint,
+    ShieldedBurn,
+    SimulateSwap,
+}
+
+impl RelayerMethod {
+    fn name(self) -> &'static str {
+        match self {
+            RelayerMethod::Deposit => "deposit",
+            RelayerMethod::SubmitMerkleRoot => "root",
+            RelayerMethod::WaitForTx => "wait",
+            RelayerMethod::GetOnchainRoot => "onchain_root",
+            RelayerMethod::VerifyMembership => "membership",
+            RelayerMethod::ShieldedSwap => "swap",
+            RelayerMethod::ShieldedMint => "mint",
+            RelayerMethod::ShieldedBurn => "burn",
+            RelayerMethod::SimulateSwap => "simulate_swap",
+        }
+    }
+
+    /// Hash returned for this method's transactions, whether they succeed
+    /// or revert.
+    pub fn tx_hash(self) -> String {
+        format!("0xmock_{}_tx", self.name())
+    }
+}
+
+/// What a programmed method does when called.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockOutcome {
+    /// Return [`RelayerMethod::tx_hash`], or the programmed quote for
+    /// `simulate_swap`.
+    Succeed,
+    /// [`AspError::TransactionFailed`] with this message.
+    Fail(String),
+    /// [`AspError::TransactionReverted`] with this reason.
+    Revert(String),
+    /// [`AspError::RpcError`] with this message; transient, so retried by
+    /// `RetryingRelayer`.
+    RpcError(String),
+    /// [`AspError::TransactionPending`]: sent but not confirmed in time, so
+    /// `RetryingRelayer` polls it with `wait_for_tx` instead of re-sending.
+    Pending,
+}
+
+/// One call received by a [`MockRelayer`], with its arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordedCall {
+    Deposit {
+        commitment: String,
+    },
+    SubmitMerkleRoot {
+        root: String,
+    },
+    WaitForTx {
+        tx_hash: String,
+    },
+    GetOnchainRoot,
+    VerifyMembership {
+        calldata: Vec<String>,
+    },
+    ShieldedSwap {
+        pool_key: PoolKeyParams,
+        calldata: Vec<String>,
+        sqrt_price_limit: String,
+    },
+    ShieldedMint {
+        pool_key: PoolKeyParams,
+        calldata: Vec<String>,
+        liquidity: u128,
+    },
+    ShieldedBurn {
+        pool_key: PoolKeyParams,
+        calldata: Vec<String>,
+        liquidity: u128,
+    },
+    SimulateSwap {
+        pool_key: PoolKeyParams,
+        zero_for_one: bool,
+        amount_in: String,
+        sqrt_price_limit: String,
+    },
+}
+
+impl RecordedCall {
+    pub fn method(&self) -> RelayerMethod {
+        match self {
+            RecordedCall::Deposit { .. } => RelayerMethod::Deposit,
+            RecordedCall::SubmitMerkleRoot { .. } => RelayerMethod::SubmitMerkleRoot,
+            RecordedCall::WaitForTx { .. } => RelayerMethod::WaitForTx,
+            RecordedCall::GetOnchainRoot => RelayerMethod::GetOnchainRoot,
+            RecordedCall::VerifyMembership { .. } => RelayerMethod::VerifyMembership,
+            RecordedCall::ShieldedSwap { .. } => RelayerMethod::ShieldedSwap,
+            RecordedCall::ShieldedMint { .. } => RelayerMethod::ShieldedMint,
+            RecordedCall::ShieldedBurn { .. } => RelayerMethod::ShieldedBurn,
+            RecordedCall::SimulateSwap { .. } => RelayerMethod::SimulateSwap,
+        }
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    outcomes: HashMap<RelayerMethod, MockOutcome>,
+    queued: HashMap<RelayerMethod, VecDeque<MockOutcome>>,
+    quote: Option<SwapQuote>,
+    onchain_root: Option<String>,
+    chain_id: Option<Felt>,
+    calls: Vec<RecordedCall>,
+}
+
+/// Programmable [`Relayer`] that never touches the network. Clones share
+/// their program and call log, so keep one clone to inspect after boxing
+/// another into the app state.
+#[derive(Clone, Default)]
+pub struct MockRelayer {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockRelayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make every call to `method` end with `outcome`.
+    pub fn with_outcome(self, method: RelayerMethod, outcome: MockOutcome) -> Self {
+        self.set_outcome(method, outcome);
+        self
+    }
+
+    /// Quote returned by a succeeding `simulate_swap`. Without one,
+    /// `simulate_swap` fails with [`AspError::QuoterUnavailable`] like a pool
+    /// that has no `quote_swap` entry point.
+    pub fn with_quote(self, quote: SwapQuote) -> Self {
+        self.lock().quote = Some(quote);
+        self
+    }
+
+    /// Root reported by a succeeding `get_onchain_root` until the next
+    /// successful `submit_merkle_root` replaces it. Starts out as `0`.
+    pub fn with_onchain_root(self, root: &str) -> Self {
+        self.lock().onchain_root = Some(root.to_string());
+        self
+    }
+
+    /// Chain id reported by `chain_id`. Defaults to `SN_SEPOLIA`.
+    pub fn with_chain_id(self, chain_id: Felt) -> Self {
+        self.lock().chain_id = Some(chain_id);
+        self
+    }
+
+    /// Like [`MockRelayer::with_outcome`], on a shared handle.
+    pub fn set_outcome(&self, method: RelayerMethod, outcome: MockOutcome) {
+        self.lock().outcomes.insert(method, outcome);
+    }
+
+    /// End only the next not-yet-scripted call to `method` with `outcome`.
+    /// Queued outcomes are used in order before the persistent one.
+    pub fn queue_outcome(&self, method: RelayerMethod, outcome: MockOutcome) {
+        self.lock().queued.entry(method).or_default().push_back(outcome);
+    }
+
+    /// Every call received so far, in order.
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        self.lock().calls.clone()
+    }
+
+    /// Calls received to `method`, in order.
+    pub fn calls_to(&self, method: RelayerMethod) -> Vec<RecordedCall> {
+        self.lock()
+            .calls
+            .iter()
+            .filter(|call| call.method() == method)
+            .cloned()
+            .collect()
+    }
+
+    /// Panic unless `method` was called exactly `times` times.
+    #[track_caller]
+    pub fn assert_called(&self, method: RelayerMethod, times: usize) {
+        let calls = self.calls_to(method);
+        assert_eq!(
+            calls.len(),
+            times,
+            "expected {times} call(s) to {method:?}, got {}: {calls:#?}",
+            calls.len()
+        );
+    }
+
+    /// Panic if `method` was called at all.
+    #[track_caller]
+    pub fn assert_not_called(&self, method: RelayerMethod) {
+        self.assert_called(method, 0);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|p| p.into_inner())
+    }
+
+    /// Record `call` and resolve its outcome.
+    fn handle(&self, call: RecordedCall) -> Result<String, AspError> {
+        let method = call.method();
+        let mut state = self.lock();
+        state.calls.push(call);
+        let queued = state.queued.get_mut(&method).and_then(VecDeque::pop_front);
+        let outcome = queued
+            .or_else(|| state.outcomes.get(&method).cloned())
+            .unwrap_or(MockOutcome::Succeed);
+
+        match outcome {
+            MockOutcome::Succeed => Ok(method.tx_hash()),
+            MockOutcome::Fail(msg) => Err(AspError::TransactionFailed(msg)),
+            MockOutcome::Revert(reason) => Err(AspError::TransactionReverted {
+                tx_hash: method.tx_hash(),
+                reason,
+            }),
+            MockOutcome::RpcError(msg) => Err(AspError::RpcError(msg)),
+            MockOutcome::Pending => Err(AspError::TransactionPending {
+                tx_hash: method.tx_hash(),
+            }),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Relayer for MockRelayer {
+    fn chain_id(&self) -> Felt {
+        self.lock()
+            .chain_id
+            .unwrap_or_else(|| Felt::from_bytes_be_slice(b"SN_SEPOLIA"))
+    }
+
+    async fn deposit(&self, commitment: &str) -> Result<String, AspError> {
+        self.handle(RecordedCall::Deposit {
+            commitment: commitment.to_string(),
+        })
+    }
+
+    async fn submit_merkle_root(&self, root: &str) -> Result<String, AspError> {
+        let tx_hash = self.handle(RecordedCall::SubmitMerkleRoot {
+            root: root.to_string(),
+        })?;
+        self.lock().onchain_root = Some(root.to_string());
+        Ok(tx_hash)
+    }
+
+    async fn wait_for_tx(&self, tx_hash: &str) -> Result<(), AspError> {
+        let waited = self.handle(RecordedCall::WaitForTx {
+            tx_hash: tx_hash.to_string(),
+        });
+        match waited {
+            Ok(_) => Ok(()),
+            Err(AspError::TransactionPending { .. }) => Err(AspError::TransactionPending {
+                tx_hash: tx_hash.to_string(),
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn get_onchain_root(&self) -> Result<String, AspError> {
+        self.handle(RecordedCall::GetOnchainRoot)?;
+        Ok(self.lock().onchain_root.clone().unwrap_or_else(|| "0".into()))
+    }
+
+    async fn verify_membership(&self, calldata: &[String]) -> Result<String, AspError> {
+        self.handle(RecordedCall::VerifyMembership {
+            calldata: calldata.to_vec(),
+        })
+    }
+
+    async fn shielded_swap(
+        &self,
+        pool_key: &PoolKeyParams,
+        calldata: &[String],
+        sqrt_price_limit: &str,
+    ) -> Result<String, AspError> {
+        self.handle(RecordedCall::ShieldedSwap {
+            pool_key: pool_key.clone(),
+            calldata: calldata.to_vec(),
+            sqrt_price_limit: sqrt_price_limit.to_string(),
+        })
+    }
+
+    async fn shielded_mint(
+        &self,
+        pool_key: &PoolKeyParams,
+        calldata: &[String],
+        liquidity: u128,
+    ) -> Result<String, AspError> {
+        self.handle(RecordedCall::ShieldedMint {
+            pool_key: pool_key.clone(),
+            calldata: calldata.to_vec(),
+            liquidity,
+        })
+    }
+
+    async fn shielded_burn(
+        &self,
+        pool_key: &PoolKeyParams,
+        calldata: &[String],
+        liquidity: u128,
+    ) -> Result<String, AspError> {
+        self.handle(RecordedCall::ShieldedBurn {
+            pool_key: pool_key.clone(),
+            calldata: calldata.to_vec(),
+            liquidity,
+        })
+    }
+
+    async fn simulate_swap(
+        &self,
+        pool_key: &PoolKeyParams,
+        zero_for_one: bool,
+        amount_in: &str,
+        sqrt_price_limit: &str,
+    ) -> Result<SwapQuote, AspError> {
+        self.handle(RecordedCall::SimulateSwap {
+            pool_key: pool_key.clone(),
+            zero_for_one,
+            amount_in: amount_in.to_string(),
+            sqrt_price_limit: sqrt_price_limit.to_string(),
+        })?;
+        self.lock().quote.clone().ok_or_else(|| {
+            AspError::QuoterUnavailable("mock pool does not implement quote_swap".into())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::relayer::{RetryPolicy, RetryingRelayer};
+    use std::time::Duration;
+
+    fn pool_key() -> PoolKeyParams {
+        PoolKeyParams {
+            token_0: "0x1".into(),
+            token_1: "0x2".into(),
+            fee: 3000,
+            tick_spacing: 60,
+        }
+    }
+
+    #[tokio::test]
+    async fn succeeds_and_records_by_default() {
+        let relayer = MockRelayer::new();
+        assert_eq!(relayer.deposit("42").await.unwrap(), "0xmock_deposit_tx");
+        assert_eq!(relayer.submit_merkle_root("7").await.unwrap(), "0xmock_root_tx");
+
+        relayer.assert_called(RelayerMethod::Deposit, 1);
+        relayer.assert_not_called(RelayerMethod::ShieldedSwap);
+        assert_eq!(
+            relayer.calls(),
+            vec![
+                RecordedCall::Deposit {
+                    commitment: "42".into()
+                },
+                RecordedCall::SubmitMerkleRoot { root: "7".into() },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn programmed_revert_and_failure() {
+        let relayer = MockRelayer::new()
+            .with_outcome(RelayerMethod::ShieldedSwap, MockOutcome::Revert("slippage".into()))
+            .with_outcome(RelayerMethod::SubmitMerkleRoot, MockOutcome::Fail("dropped".into()));
+
+        let reverted = relayer.shielded_swap(&pool_key(), &[], "0").await;
+        match reverted {
+            Err(AspError::TransactionReverted { tx_hash, reason }) => {
+                assert_eq!(tx_hash, "0xmock_swap_tx");
+                assert_eq!(reason, "slippage");
+            }
+            other => panic!("expected revert, got {other:?}"),
+        }
+        assert!(matches!(
+            relayer.submit_merkle_root("1").await,
+            Err(AspError::TransactionFailed(_))
+        ));
+        // Other methods are unaffected
+        assert!(relayer.shielded_mint(&pool_key(), &[], 5).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn queued_outcomes_apply_once_in_order() {
+        let relayer = MockRelayer::new();
+        relayer.queue_outcome(RelayerMethod::Deposit, MockOutcome::RpcError("reset".into()));
+        relayer.queue_outcome(RelayerMethod::Deposit, MockOutcome::Fail("nonce".into()));
+
+        assert!(matches!(relayer.deposit("1").await, Err(AspError::RpcError(_))));
+        assert!(matches!(
+            relayer.deposit("1").await,
+            Err(AspError::TransactionFailed(_))
+        ));
+        assert!(relayer.deposit("1").await.is_ok());
+        relayer.assert_called(RelayerMethod::Deposit, 3);
+    }
+
+    #[tokio::test]
+    async fn shared_clone_sees_calls_through_retrying_relayer() {
+        let mock = MockRelayer::new();
+        mock.queue_outcome(RelayerMethod::Deposit, MockOutcome::RpcError("reset".into()));
+        let policy = RetryPolicy {
+            max_attempts: 2,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+        };
+        let relayer = RetryingRelayer::new(Box::new(mock.clone()), policy);
+
+        assert_eq!(relayer.deposit("9").await.unwrap(), "0xmock_deposit_tx");
+        mock.assert_called(RelayerMethod::Deposit, 2);
+    }
+
+    #[tokio::test]
+    async fn onchain_root_follows_successful_submissions() {
+        let relayer = MockRelayer::new();
+        assert_eq!(relayer.get_onchain_root().await.unwrap(), "0");
+
+        let relayer = MockRelayer::new().with_onchain_root("11");
+        assert_eq!(relayer.get_onchain_root().await.unwrap(), "11");
+        relayer.submit_merkle_root("12").await.unwrap();
+        assert_eq!(relayer.get_onchain_root().await.unwrap(), "12");
+
+        relayer.set_outcome(RelayerMethod::SubmitMerkleRoot, MockOutcome::Fail("dropped".into()));
+        assert!(relayer.submit_merkle_root("13").await.is_err());
+        assert_eq!(relayer.get_onchain_root().await.unwrap(), "12");
+        relayer.assert_called(RelayerMethod::GetOnchainRoot, 3);
+    }
+
+    #[tokio::test]
+    async fn simulate_swap_needs_a_quote() {
+        let relayer = MockRelayer::new();
+        assert!(matches!(
+            relayer.simulate_swap(&pool_key(), true, "10", "0").await,
+            Err(AspError::QuoterUnavailable(_))
+        ));
+
+        let quote = SwapQuote {
+            amount_out: "9".into(),
+            sqrt_price_after: "100".into(),
+        };
+        let relayer = MockRelayer::new().with_quote(quote.clone());
+        assert_eq!(
+            relayer.simulate_swap(&pool_key(), false, "10", "0").await.unwrap(),
+            quote
+        );
+        match &relayer.calls_to(RelayerMethod::SimulateSwap)[0] {
+            RecordedCall::SimulateSwap { zero_for_one, .. } => assert!(!zero_for_one),
+            other => panic!("unexpected call {other:?}"),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 1 call(s) to Deposit, got 0")]
+    fn assert_called_reports_mismatch() {
+        MockRelayer::new().assert_called(RelayerMethod::Deposit, 1);
+    }
+}
@@ -1,16 +1,48 @@
+mod calls;
+mod gas;
+#[cfg(any(test, feature = "test-support"))]
+mod mock;
+mod pools;
+mod retry;
+mod reverts;
 mod starknet;
 
+pub use self::calls::PoolCalls;
+pub use self::gas::GasBounds;
+#[cfg(any(test, feature = "test-support"))]
+pub use self::mock::{MockOutcome, MockRelayer, RecordedCall, RelayerMethod};
+pub use self::pools::PoolRoutes;
+pub use self::retry::{RetryPolicy, RetryingRelayer};
+pub use self::reverts::classify_deposit_error;
 pub use self::starknet::PoolKeyParams;
-pub use self::starknet::StarknetRelayer;
+pub use self::starknet::{StarknetRelayer, StarknetRelayerBuilder};
+
+use ::starknet::core::types::Felt;
 
 use crate::error::AspError;
 
+/// Expected outcome of a swap, quoted by the pool without executing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapQuote {
+    /// Output token amount (decimal string).
+    pub amount_out: String,
+    /// Pool sqrt price after the swap (decimal string, Q128.128).
+    pub sqrt_price_after: String,
+}
+
 /// Trait abstracting Starknet transaction submission.
 /// Implemented by `StarknetRelayer` for production and `MockRelayer` for tests.
 #[async_trait::async_trait]
 pub trait Relayer: Send + Sync {
+    /// Chain id transactions are signed for.
+    fn chain_id(&self) -> Felt;
     async fn deposit(&self, commitment: &str) -> Result<String, AspError>;
     async fn submit_merkle_root(&self, root: &str) -> Result<String, AspError>;
+    /// Wait again for an already sent transaction that came back as
+    /// [`AspError::TransactionPending`], without re-sending it.
+    async fn wait_for_tx(&self, tx_hash: &str) -> Result<(), AspError>;
+    /// Read-only: the coordinator's current Merkle root (decimal string).
+    async fn get_onchain_root(&self) -> Result<String, AspError>;
     async fn verify_membership(&self, calldata: &[String]) -> Result<String, AspError>;
     async fn shielded_swap(
         &self,
@@ -30,4 +62,13 @@ pub trait Relayer: Send + Sync {
         calldata: &[String],
         liquidity: u128,
     ) -> Result<String, AspError>;
+    /// Read-only quote of swapping `amount_in` of token_0 (`zero_for_one`)
+    /// or token_1 in the given pool.
+    async fn simulate_swap(
+        &self,
+        pool_key: &PoolKeyParams,
+        zero_for_one: bool,
+        amount_in: &str,
+        sqrt_price_limit: &str,
+    ) -> Result<SwapQuote, AspError>;
 }
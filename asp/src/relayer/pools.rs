@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use starknet::core::types::Felt;
+
+use crate::error::AspError;
+
+use super::PoolKeyParams;
+
+/// Maps pool keys to the pool contract that serves them.
+///
+/// Keys are pool ids as produced by [`PoolKeyParams::id`]; anything not
+/// listed routes to the default pool.
+#[derive(Debug, Clone)]
+pub struct PoolRoutes {
+    default: Felt,
+    routes: HashMap<String, Felt>,
+}
+
+impl PoolRoutes {
+    /// Build routes from the default pool address and the configured
+    /// `pool id -> contract address` map. Ids are re-normalized so config
+    /// entries may use any hex casing or zero-padding.
+    pub fn new(default: &str, pools: &HashMap<String, String>) -> Result<Self, AspError> {
+        let default = Felt::from_hex(default)
+            .map_err(|e| AspError::Config(format!("Invalid pool address: {e}")))?;
+
+        let mut routes = HashMap::with_capacity(pools.len());
+        for (id, address) in pools {
+            let key = parse_pool_id(id)?
+                .id()
+                .map_err(|e| AspError::Config(format!("Invalid pool id '{id}': {e}")))?;
+            let address = Felt::from_hex(address)
+                .map_err(|e| AspError::Config(format!("Invalid address for pool '{id}': {e}")))?;
+            routes.insert(key, address);
+        }
+
+        Ok(PoolRoutes { default, routes })
+    }
+
+    /// Routes sending every pool key to `address`.
+    pub fn single(address: Felt) -> Self {
+        PoolRoutes {
+            default: address,
+            routes: HashMap::new(),
+        }
+    }
+
+    pub fn default_address(&self) -> &Felt {
+        &self.default
+    }
+
+    /// Every configured pool contract, the default first.
+    pub fn addresses(&self) -> impl Iterator<Item = Felt> + '_ {
+        std::iter::once(self.default).chain(self.routes.values().copied())
+    }
+
+    /// Contract address for `pool_key`, falling back to the default pool.
+    pub fn resolve(&self, pool_key: &PoolKeyParams) -> Result<Felt, AspError> {
+        let id = pool_key.id()?;
+        Ok(self.routes.get(&id).copied().unwrap_or(self.default))
+    }
+}
+
+/// Parse a `token_0:token_1:fee:tick_spacing` pool id from config.
+fn parse_pool_id(id: &str) -> Result<PoolKeyParams, AspError> {
+    let invalid = || {
+        AspError::Config(format!(
+            "Invalid pool id '{id}': expected token_0:token_1:fee:tick_spacing"
+        ))
+    };
+
+    let parts: Vec<&str> = id.split(':').collect();
+    let [token_0, token_1, fee, tick_spacing] = parts.as_slice() else {
+        return Err(invalid());
+    };
+
+    Ok(PoolKeyParams {
+        token_0: token_0.to_string(),
+        token_1: token_1.to_string(),
+        fee: fee.parse().map_err(|_| invalid())?,
+        tick_spacing: tick_spacing.parse().map_err(|_| invalid())?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool_key(token_0: &str, token_1: &str, fee: u64, tick_spacing: u64) -> PoolKeyParams {
+        PoolKeyParams {
+            token_0: token_0.into(),
+            token_1: token_1.into(),
+            fee,
+            tick_spacing,
+        }
+    }
+
+    #[test]
+    fn routes_pool_keys_to_distinct_addresses() {
+        let pools = HashMap::from([
+            ("0x1:0x2:3000:60".to_string(), "0xaaa".to_string()),
+            ("0x1:0x3:500:10".to_string(), "0xbbb".to_string()),
+        ]);
+        let routes = PoolRoutes::new("0xdef", &pools).unwrap();
+
+        assert_eq!(
+            routes.resolve(&pool_key("0x1", "0x2", 3000, 60)).unwrap(),
+            Felt::from_hex("0xaaa").unwrap()
+        );
+        assert_eq!(
+            routes.resolve(&pool_key("0x1", "0x3", 500, 10)).unwrap(),
+            Felt::from_hex("0xbbb").unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_pool_falls_back_to_default() {
+        let pools = HashMap::from([("0x1:0x2:3000:60".to_string(), "0xaaa".to_string())]);
+        let routes = PoolRoutes::new("0xdef", &pools).unwrap();
+
+        // Same tokens, different fee tier
+        assert_eq!(
+            routes.resolve(&pool_key("0x1", "0x2", 500, 60)).unwrap(),
+            Felt::from_hex("0xdef").unwrap()
+        );
+    }
+
+    #[test]
+    fn pool_ids_are_normalized() {
+        let pools = HashMap::from([("0x0001:0xAB:3000:60".to_string(), "0xaaa".to_string())]);
+        let routes = PoolRoutes::new("0xdef", &pools).unwrap();
+
+        assert_eq!(
+            routes.resolve(&pool_key("0x1", "0xab", 3000, 60)).unwrap(),
+            Felt::from_hex("0xaaa").unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_pool_id() {
+        let pools = HashMap::from([("0x1:0x2:3000".to_string(), "0xaaa".to_string())]);
+        assert!(matches!(
+            PoolRoutes::new("0xdef", &pools),
+            Err(AspError::Config(_))
+        ));
+    }
+}
@@ -0,0 +1,368 @@
+use std::future::Future;
+use std::time::Duration;
+
+use starknet::core::types::Felt;
+
+use crate::config::Config;
+use crate::error::AspError;
+
+use super::{PoolKeyParams, Relayer, SwapQuote};
+
+/// Exponential backoff settings for [`RetryingRelayer`].
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one.
+    pub max_attempts: u32,
+    /// Delay before the second attempt; doubled for each one after.
+    pub base_delay: Duration,
+    /// Upper bound for a single backoff delay.
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        RetryPolicy {
+            max_attempts: config.relayer_max_attempts.max(1),
+            base_delay: Duration::from_millis(config.relayer_retry_base_ms),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+
+    /// Backoff before retry number `retry` (1-based).
+    pub(crate) fn delay_for(&self, retry: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(retry.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// Errors worth retrying: the RPC hiccuped before anything was sent.
+/// Reverts, rejected sends and validation errors are final.
+fn is_transient(err: &AspError) -> bool {
+    matches!(err, AspError::RpcError(_))
+}
+
+/// `Relayer` decorator that retries `deposit`, `submit_merkle_root` and
+/// `verify_membership` on transient failures. A transaction that was sent but
+/// not confirmed in time is polled again by hash, never re-sent. Shielded
+/// swap/mint/burn are passed through untouched.
+pub struct RetryingRelayer {
+    inner: Box<dyn Relayer>,
+    policy: RetryPolicy,
+}
+
+impl RetryingRelayer {
+    pub fn new(inner: Box<dyn Relayer>, policy: RetryPolicy) -> Self {
+        RetryingRelayer { inner, policy }
+    }
+
+    async fn with_retry<F, Fut>(&self, operation: &str, mut call: F) -> Result<String, AspError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<String, AspError>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match call().await {
+                Err(AspError::TransactionPending { tx_hash }) => {
+                    return self.await_pending(operation, tx_hash, attempt).await;
+                }
+                Err(e) if is_transient(&e) && attempt < self.policy.max_attempts => {
+                    let delay = self.policy.delay_for(attempt);
+                    tracing::warn!(
+                        operation = operation,
+                        attempt = attempt,
+                        delay_ms = delay.as_millis() as u64,
+                        error = %e,
+                        "Transient relayer error, retrying"
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Keep waiting for `tx_hash` with the attempts left after `attempt`.
+    async fn await_pending(
+        &self,
+        operation: &str,
+        tx_hash: String,
+        mut attempt: u32,
+    ) -> Result<String, AspError> {
+        while attempt < self.policy.max_attempts {
+            let delay = self.policy.delay_for(attempt);
+            tracing::warn!(
+                operation = operation,
+                attempt = attempt,
+                tx_hash = %tx_hash,
+                delay_ms = delay.as_millis() as u64,
+                "Transaction not confirmed yet, waiting for it again"
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+            match self.inner.wait_for_tx(&tx_hash).await {
+                Ok(()) => return Ok(tx_hash),
+                Err(e @ (AspError::TransactionPending { .. } | AspError::RpcError(_))) => {
+                    tracing::debug!(error = %e, "Transaction still unconfirmed");
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(AspError::TransactionPending { tx_hash })
+    }
+}
+
+#[async_trait::async_trait]
+impl Relayer for RetryingRelayer {
+    fn chain_id(&self) -> Felt {
+        self.inner.chain_id()
+    }
+
+    async fn deposit(&self, commitment: &str) -> Result<String, AspError> {
+        self.with_retry("deposit", || self.inner.deposit(commitment))
+            .await
+    }
+
+    async fn submit_merkle_root(&self, root: &str) -> Result<String, AspError> {
+        self.with_retry("submit_merkle_root", || self.inner.submit_merkle_root(root))
+            .await
+    }
+
+    async fn wait_for_tx(&self, tx_hash: &str) -> Result<(), AspError> {
+        self.inner.wait_for_tx(tx_hash).await
+    }
+
+    async fn get_onchain_root(&self) -> Result<String, AspError> {
+        self.with_retry("get_onchain_root", || self.inner.get_onchain_root())
+            .await
+    }
+
+    async fn verify_membership(&self, calldata: &[String]) -> Result<String, AspError> {
+        self.with_retry("verify_membership", || {
+            self.inner.verify_membership(calldata)
+        })
+        .await
+    }
+
+    async fn shielded_swap(
+        &self,
+        pool_key: &PoolKeyParams,
+        calldata: &[String],
+        sqrt_price_limit: &str,
+    ) -> Result<String, AspError> {
+        self.inner
+            .shielded_swap(pool_key, calldata, sqrt_price_limit)
+            .await
+    }
+
+    async fn shielded_mint(
+        &self,
+        pool_key: &PoolKeyParams,
+        calldata: &[String],
+        liquidity: u128,
+    ) -> Result<String, AspError> {
+        self.inner
+            .shielded_mint(pool_key, calldata, liquidity)
+            .await
+    }
+
+    async fn shielded_burn(
+        &self,
+        pool_key: &PoolKeyParams,
+        calldata: &[String],
+        liquidity: u128,
+    ) -> Result<String, AspError> {
+        self.inner
+            .shielded_burn(pool_key, calldata, liquidity)
+            .await
+    }
+
+    async fn simulate_swap(
+        &self,
+        pool_key: &PoolKeyParams,
+        zero_for_one: bool,
+        amount_in: &str,
+        sqrt_price_limit: &str,
+    ) -> Result<SwapQuote, AspError> {
+        self.inner
+            .simulate_swap(pool_key, zero_for_one, amount_in, sqrt_price_limit)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::relayer::{MockOutcome, MockRelayer, RecordedCall, RelayerMethod};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    /// Fails `deposit` with the given error until `failures` calls have been made.
+    struct FlakyRelayer {
+        calls: Arc<AtomicU32>,
+        failures: u32,
+        error: fn() -> AspError,
+    }
+
+    #[async_trait::async_trait]
+    impl Relayer for FlakyRelayer {
+        fn chain_id(&self) -> Felt {
+            Felt::ZERO
+        }
+        async fn deposit(&self, _commitment: &str) -> Result<String, AspError> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst);
+            if n < self.failures {
+                Err((self.error)())
+            } else {
+                Ok("0xdeposit".into())
+            }
+        }
+        async fn submit_merkle_root(&self, _root: &str) -> Result<String, AspError> {
+            Ok("0xroot".into())
+        }
+        async fn wait_for_tx(&self, _tx_hash: &str) -> Result<(), AspError> {
+            Ok(())
+        }
+        async fn get_onchain_root(&self) -> Result<String, AspError> {
+            Ok("0".into())
+        }
+        async fn verify_membership(&self, _calldata: &[String]) -> Result<String, AspError> {
+            Ok("0xmembership".into())
+        }
+        async fn shielded_swap(
+            &self,
+            _pool_key: &PoolKeyParams,
+            _calldata: &[String],
+            _sqrt_price_limit: &str,
+        ) -> Result<String, AspError> {
+            Ok("0xswap".into())
+        }
+        async fn shielded_mint(
+            &self,
+            _pool_key: &PoolKeyParams,
+            _calldata: &[String],
+            _liquidity: u128,
+        ) -> Result<String, AspError> {
+            Ok("0xmint".into())
+        }
+        async fn shielded_burn(
+            &self,
+            _pool_key: &PoolKeyParams,
+            _calldata: &[String],
+            _liquidity: u128,
+        ) -> Result<String, AspError> {
+            Ok("0xburn".into())
+        }
+        async fn simulate_swap(
+            &self,
+            _pool_key: &PoolKeyParams,
+            _zero_for_one: bool,
+            _amount_in: &str,
+            _sqrt_price_limit: &str,
+        ) -> Result<SwapQuote, AspError> {
+            Err(AspError::QuoterUnavailable("flaky".into()))
+        }
+    }
+
+    fn flaky(failures: u32, error: fn() -> AspError) -> (RetryingRelayer, Arc<AtomicU32>) {
+        let calls = Arc::new(AtomicU32::new(0));
+        let inner = FlakyRelayer {
+            calls: calls.clone(),
+            failures,
+            error,
+        };
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        };
+        (RetryingRelayer::new(Box::new(inner), policy), calls)
+    }
+
+    #[tokio::test]
+    async fn retries_transient_error_then_succeeds() {
+        let (relayer, calls) = flaky(1, || AspError::RpcError("connection reset".into()));
+        assert_eq!(relayer.deposit("1").await.unwrap(), "0xdeposit");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn never_retries_revert() {
+        let (relayer, calls) = flaky(1, || AspError::TransactionReverted {
+            tx_hash: "0x1".into(),
+            reason: "nope".into(),
+        });
+        assert!(matches!(
+            relayer.deposit("1").await,
+            Err(AspError::TransactionReverted { .. })
+        ));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let (relayer, calls) = flaky(10, || AspError::RpcError("rate limited".into()));
+        assert!(matches!(
+            relayer.deposit("1").await,
+            Err(AspError::RpcError(_))
+        ));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    fn retrying(mock: &MockRelayer) -> RetryingRelayer {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        };
+        RetryingRelayer::new(Box::new(mock.clone()), policy)
+    }
+
+    #[tokio::test]
+    async fn unconfirmed_tx_is_polled_not_resent() {
+        let mock = MockRelayer::new().with_outcome(RelayerMethod::Deposit, MockOutcome::Pending);
+        mock.queue_outcome(RelayerMethod::WaitForTx, MockOutcome::Pending);
+        let relayer = retrying(&mock);
+
+        let tx_hash = relayer.deposit("1").await.unwrap();
+        assert_eq!(tx_hash, RelayerMethod::Deposit.tx_hash());
+        mock.assert_called(RelayerMethod::Deposit, 1);
+        assert_eq!(
+            mock.calls_to(RelayerMethod::WaitForTx),
+            vec![RecordedCall::WaitForTx { tx_hash: tx_hash.clone() }; 2]
+        );
+    }
+
+    #[tokio::test]
+    async fn still_unconfirmed_tx_is_reported_pending() {
+        let mock = MockRelayer::new()
+            .with_outcome(RelayerMethod::SubmitMerkleRoot, MockOutcome::Pending)
+            .with_outcome(RelayerMethod::WaitForTx, MockOutcome::Pending);
+        let relayer = retrying(&mock);
+
+        match relayer.submit_merkle_root("7").await {
+            Err(AspError::TransactionPending { tx_hash }) => {
+                assert_eq!(tx_hash, RelayerMethod::SubmitMerkleRoot.tx_hash())
+            }
+            other => panic!("expected TransactionPending, got {other:?}"),
+        }
+        mock.assert_called(RelayerMethod::SubmitMerkleRoot, 1);
+        mock.assert_called(RelayerMethod::WaitForTx, 2);
+    }
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(350),
+        };
+        assert_eq!(policy.delay_for(1), Duration::from_millis(100));
+        assert_eq!(policy.delay_for(2), Duration::from_millis(200));
+        assert_eq!(policy.delay_for(3), Duration::from_millis(350));
+        assert_eq!(policy.delay_for(40), Duration::from_millis(350));
+    }
+}
@@ -0,0 +1,108 @@
+use crate::error::AspError;
+
+/// Why the coordinator refused a deposit, as far as its revert says.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DepositRejection {
+    Paused,
+    Duplicate,
+    InvalidCommitment,
+    TreeFull,
+}
+
+/// Revert strings that identify a [`DepositRejection`]. Cairo short-string
+/// errors show up in revert reasons either as text or as their hex encoding,
+/// so both are matched. The deployed coordinator does not check for
+/// duplicates; the duplicate strings cover coordinators that do.
+const KNOWN_REASONS: [(&str, DepositRejection); 5] = [
+    ("VERIFIER: contract paused", DepositRejection::Paused),
+    (
+        "VERIFIER: duplicate commitment",
+        DepositRejection::Duplicate,
+    ),
+    ("Commitment already exists", DepositRejection::Duplicate),
+    (
+        "VERIFIER: invalid commitment",
+        DepositRejection::InvalidCommitment,
+    ),
+    ("Merkle tree is full", DepositRejection::TreeFull),
+];
+
+fn rejection(reason: &str) -> Option<DepositRejection> {
+    let lowered = reason.to_ascii_lowercase();
+    KNOWN_REASONS
+        .iter()
+        .find(|(text, _)| {
+            lowered.contains(&text.to_ascii_lowercase())
+                || lowered.contains(&short_string_hex(text))
+        })
+        .map(|(_, rejection)| *rejection)
+}
+
+/// Lowercase hex of a Cairo short string, as a felt without leading zeros.
+fn short_string_hex(text: &str) -> String {
+    text.bytes().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Turn a failed `deposit` call into a typed error when its revert reason
+/// is one the coordinator is known to raise. Reverts during fee estimation
+/// come back as [`AspError::TransactionFailed`] and are matched too; any
+/// other error is returned unchanged.
+pub fn classify_deposit_error(error: AspError) -> AspError {
+    let (tx_hash, reason) = match &error {
+        AspError::TransactionReverted { tx_hash, reason } => (Some(tx_hash.clone()), reason),
+        AspError::TransactionFailed(message) => (None, message),
+        _ => return error,
+    };
+    let Some(rejection) = rejection(reason) else {
+        return error;
+    };
+    let reason = reason.clone();
+    match rejection {
+        DepositRejection::Paused => AspError::CoordinatorPaused { tx_hash, reason },
+        DepositRejection::Duplicate => AspError::DuplicateCommitment { tx_hash, reason },
+        DepositRejection::InvalidCommitment => {
+            AspError::InvalidInput(format!("Coordinator rejected the commitment: {reason}"))
+        }
+        DepositRejection::TreeFull => AspError::TreeFull,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_reason_text_and_hex() {
+        let reverted = AspError::TransactionReverted {
+            tx_hash: "0x1".into(),
+            reason: "Error in the called contract: Failure reason: 'VERIFIER: contract paused'."
+                .into(),
+        };
+        match classify_deposit_error(reverted) {
+            AspError::CoordinatorPaused { tx_hash, .. } => {
+                assert_eq!(tx_hash.as_deref(), Some("0x1"))
+            }
+            other => panic!("expected CoordinatorPaused, got {other}"),
+        }
+
+        // 'Merkle tree is full' as a felt, in a fee estimation failure
+        let failed = AspError::TransactionFailed(
+            "execution failed: Failure reason: 0x4D65726B6C6520747265652069732066756C6C.".into(),
+        );
+        assert!(matches!(classify_deposit_error(failed), AspError::TreeFull));
+    }
+
+    #[test]
+    fn unknown_reverts_are_kept() {
+        let reverted = AspError::TransactionReverted {
+            tx_hash: "0x1".into(),
+            reason: "Out of gas".into(),
+        };
+        assert!(matches!(
+            classify_deposit_error(reverted),
+            AspError::TransactionReverted { .. }
+        ));
+        let rpc = AspError::RpcError("VERIFIER: contract paused".into());
+        assert!(matches!(classify_deposit_error(rpc), AspError::RpcError(_)));
+    }
+}
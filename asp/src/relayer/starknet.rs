@@ -1,95 +1,340 @@
 use num_bigint::BigUint;
 use num_traits::Num;
 use starknet::accounts::{Account, ConnectedAccount, ExecutionEncoding, SingleOwnerAccount};
-use starknet::core::types::{BlockId, BlockTag, Call, Felt};
+use starknet::core::types::{BlockId, BlockTag, Call, Felt, FunctionCall};
 use starknet::providers::jsonrpc::HttpTransport;
-use starknet::providers::JsonRpcClient;
+use starknet::providers::{JsonRpcClient, Provider};
 use starknet::signers::{LocalWallet, SigningKey};
 
-use crate::config::Config;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::config::{Config, DEFAULT_FEE_MULTIPLIER, DEFAULT_MAX_CALLDATA_FELTS};
 use crate::error::AspError;
+use crate::reload::{LiveConfig, RuntimeConfig};
+
+use super::{GasBounds, PoolCalls, PoolRoutes, Relayer, SwapQuote};
 
-use super::Relayer;
+type AdminAccount = SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet>;
 
 pub struct StarknetRelayer {
-    account: SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet>,
+    /// The admin account followed by the `BACKUP_ADMIN_ADDRESSES`, each
+    /// signing with its own key and nonce.
+    accounts: Vec<AdminAccount>,
+    /// Index into `accounts` of the account transactions are sent from.
+    active: AtomicUsize,
     coordinator_address: Felt,
-    pool_address: Felt,
+    calls: PoolCalls,
+    runtime: Arc<LiveConfig>,
+    chain_id: Felt,
 }
 
 impl StarknetRelayer {
-    pub async fn new(config: &Config) -> Result<Self, AspError> {
-        let provider = JsonRpcClient::new(HttpTransport::new(
-            url::Url::parse(&config.rpc_url)
-                .map_err(|e| AspError::Config(format!("Invalid RPC URL: {e}")))?,
-        ));
+    /// `runtime` supplies the live fee settings, read on every transaction.
+    /// Fails with [`AspError::Config`] if the RPC node is not on the
+    /// configured chain.
+    pub async fn new(config: &Config, runtime: Arc<LiveConfig>) -> Result<Self, AspError> {
+        let coordinator_address = Felt::from_hex(&config.coordinator_address)
+            .map_err(|e| AspError::Config(format!("Invalid coordinator address: {e}")))?;
+        let mut builder = StarknetRelayerBuilder::new()
+            .rpc_url(&config.rpc_url)
+            .chain_id(config.chain_id_felt()?)
+            .coordinator_address(coordinator_address)
+            .pools(PoolRoutes::new(&config.pool_address, &config.pools)?)
+            .max_calldata_felts(config.max_calldata_felts)
+            .runtime(runtime);
+        // A node on the wrong network is reported even without a key
+        builder.verify_chain().await?;
 
         // Resolve private key: prefer ADMIN_PRIVATE_KEY env var, fall back to keystore file
         let private_key = resolve_private_key(config)?;
-        let signer = LocalWallet::from(SigningKey::from_secret_scalar(private_key));
-
         let admin_address = Felt::from_hex(&config.admin_address)
             .map_err(|e| AspError::Config(format!("Invalid admin address: {e}")))?;
+        builder = builder.signer(admin_address, private_key);
+        if let Some(ref key) = config.admin_public_key {
+            let key = Felt::from_hex(key)
+                .map_err(|e| AspError::Config(format!("Invalid ADMIN_PUBLIC_KEY: {e}")))?;
+            builder = builder.public_key(key);
+        }
+        for (address, private_key) in resolve_backup_accounts(config)? {
+            builder = builder.backup_signer(address, private_key);
+        }
 
-        // Use Sepolia chain ID
-        let chain_id = Felt::from_hex("0x534e5f5345504f4c4941")
-            .map_err(|e| AspError::Config(format!("Invalid chain ID: {e}")))?;
+        builder.build().await
+    }
 
-        let mut account = SingleOwnerAccount::new(
-            provider,
-            signer,
-            admin_address,
-            chain_id,
-            ExecutionEncoding::New,
-        );
-        account.set_block_id(BlockId::Tag(BlockTag::Latest));
+    /// Send `calls` from the active admin account, failing over to the next
+    /// one if it cannot get the transaction accepted, then wait for it to be
+    /// confirmed. Transactions are still sent one at a time, so roots are
+    /// submitted in order whichever account sends them.
+    async fn send_transaction(&self, calls: Vec<Call>) -> Result<String, AspError> {
+        let (index, tx_hash) = failover(&self.accounts, &self.active, |account| {
+            self.submit(account, calls.clone())
+        })
+        .await?;
 
-        let coordinator_address = Felt::from_hex(&config.coordinator_address)
-            .map_err(|e| AspError::Config(format!("Invalid coordinator address: {e}")))?;
+        tracing::info!(
+            tx_hash = %format!("{tx_hash:#x}"),
+            account = index,
+            "Transaction sent, waiting for confirmation..."
+        );
 
-        let pool_address = Felt::from_hex(&config.pool_address)
-            .map_err(|e| AspError::Config(format!("Invalid pool address: {e}")))?;
+        // Wait for transaction receipt
+        watch_tx(self.provider(), tx_hash).await?;
 
-        Ok(StarknetRelayer {
-            account,
-            coordinator_address,
-            pool_address,
-        })
+        tracing::info!(tx_hash = %format!("{tx_hash:#x}"), "Transaction confirmed");
+        Ok(format!("{tx_hash:#x}"))
     }
 
-    async fn send_transaction(&self, calls: Vec<Call>) -> Result<String, AspError> {
-        let execution = self.account.execute_v3(calls);
+    /// Estimate and send `calls` from `account`, returning the tx hash once
+    /// the node has accepted it.
+    async fn submit(&self, account: &AdminAccount, calls: Vec<Call>) -> Result<Felt, AspError> {
+        let runtime = self.runtime.get();
+        let execution = account
+            .execute_v3(calls)
+            .gas_estimate_multiplier(runtime.fee_multiplier);
+        let execution = runtime.gas_bounds.apply(execution);
+
+        if let Some(max_fee) = runtime.max_fee_fri {
+            let estimate = execution
+                .estimate_fee()
+                .await
+                .map_err(|e| AspError::RpcError(format!("Fee estimation failed: {e}")))?;
+            if estimate.overall_fee > max_fee {
+                return Err(AspError::TransactionFailed(format!(
+                    "Estimated fee {} FRI exceeds MAX_FEE_FRI {max_fee}",
+                    estimate.overall_fee
+                )));
+            }
+        }
 
         let result = execution
             .send()
             .await
             .map_err(|e| AspError::TransactionFailed(format!("{e}")))?;
-
-        let tx_hash = format!("{:#x}", result.transaction_hash);
-        tracing::info!(tx_hash = %tx_hash, "Transaction sent, waiting for confirmation...");
-
-        // Wait for transaction receipt
-        watch_tx(self.account.provider(), result.transaction_hash).await?;
-
-        tracing::info!(tx_hash = %tx_hash, "Transaction confirmed");
-        Ok(tx_hash)
+        Ok(result.transaction_hash)
     }
 
     pub fn coordinator_address(&self) -> &Felt {
         &self.coordinator_address
     }
 
+    /// Default pool contract (used for withdrawals and unrouted pool keys).
     pub fn pool_address(&self) -> &Felt {
-        &self.pool_address
+        self.calls.pools().default_address()
     }
 
     pub fn provider(&self) -> &JsonRpcClient<HttpTransport> {
-        self.account.provider()
+        self.accounts[0].provider()
+    }
+}
+
+/// Builds a [`StarknetRelayer`] from explicit parts, for callers without a
+/// full [`Config`]. The RPC URL, chain id, signer, coordinator and pool are
+/// required; fees default to `FEE_MULTIPLIER`'s default with no caps.
+pub struct StarknetRelayerBuilder {
+    rpc_url: Option<String>,
+    chain_id: Option<Felt>,
+    /// Admin account address and private key.
+    signer: Option<(Felt, Felt)>,
+    public_key: Option<Felt>,
+    backup_signers: Vec<(Felt, Felt)>,
+    coordinator_address: Option<Felt>,
+    pools: Option<PoolRoutes>,
+    max_calldata_felts: usize,
+    fee_multiplier: f64,
+    max_fee_fri: Option<u128>,
+    gas_bounds: GasBounds,
+    runtime: Option<Arc<LiveConfig>>,
+    chain_verified: bool,
+}
+
+impl Default for StarknetRelayerBuilder {
+    fn default() -> Self {
+        StarknetRelayerBuilder {
+            rpc_url: None,
+            chain_id: None,
+            signer: None,
+            public_key: None,
+            backup_signers: Vec::new(),
+            coordinator_address: None,
+            pools: None,
+            max_calldata_felts: DEFAULT_MAX_CALLDATA_FELTS,
+            fee_multiplier: DEFAULT_FEE_MULTIPLIER,
+            max_fee_fri: None,
+            gas_bounds: GasBounds::default(),
+            runtime: None,
+            chain_verified: false,
+        }
     }
 }
 
+impl StarknetRelayerBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn rpc_url(mut self, rpc_url: impl Into<String>) -> Self {
+        self.rpc_url = Some(rpc_url.into());
+        self
+    }
+
+    /// Chain transactions are signed for; the node must report the same.
+    pub fn chain_id(mut self, chain_id: Felt) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// The admin account transactions are sent from.
+    pub fn signer(mut self, address: Felt, private_key: Felt) -> Self {
+        self.signer = Some((address, private_key));
+        self
+    }
+
+    /// Public key the admin account validates signatures against, instead of
+    /// asking the account's `get_public_key`.
+    pub fn public_key(mut self, public_key: Felt) -> Self {
+        self.public_key = Some(public_key);
+        self
+    }
+
+    /// An account to fail over to when the admin account cannot send; may be
+    /// called once per backup.
+    pub fn backup_signer(mut self, address: Felt, private_key: Felt) -> Self {
+        self.backup_signers.push((address, private_key));
+        self
+    }
+
+    pub fn coordinator_address(mut self, address: Felt) -> Self {
+        self.coordinator_address = Some(address);
+        self
+    }
+
+    /// Single pool contract, used for every pool key.
+    pub fn pool_address(self, address: Felt) -> Self {
+        self.pools(PoolRoutes::single(address))
+    }
+
+    /// Pool contracts by pool key.
+    pub fn pools(mut self, pools: PoolRoutes) -> Self {
+        self.pools = Some(pools);
+        self
+    }
+
+    /// Longest proof calldata submitted (`MAX_CALLDATA_FELTS`).
+    pub fn max_calldata_felts(mut self, max_calldata_felts: usize) -> Self {
+        self.max_calldata_felts = max_calldata_felts;
+        self
+    }
+
+    /// Multiplier on the node's fee estimate (`FEE_MULTIPLIER`).
+    pub fn fee_multiplier(mut self, fee_multiplier: f64) -> Self {
+        self.fee_multiplier = fee_multiplier;
+        self
+    }
+
+    /// Refuse transactions estimated above this fee (`MAX_FEE_FRI`).
+    pub fn max_fee_fri(mut self, max_fee_fri: u128) -> Self {
+        self.max_fee_fri = Some(max_fee_fri);
+        self
+    }
+
+    pub fn gas_bounds(mut self, gas_bounds: GasBounds) -> Self {
+        self.gas_bounds = gas_bounds;
+        self
+    }
+
+    /// Read fee settings from `runtime` on every transaction, so reloads
+    /// apply. Replaces the fixed fee setters.
+    pub fn runtime(mut self, runtime: Arc<LiveConfig>) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Check that the node is on the configured chain. [`Self::build`] does
+    /// this unless it was already done.
+    pub async fn verify_chain(&mut self) -> Result<(), AspError> {
+        let provider = JsonRpcClient::new(HttpTransport::new(self.parsed_rpc_url()?));
+        verify_chain_id(&provider, required(self.chain_id, "chain_id")?).await?;
+        self.chain_verified = true;
+        Ok(())
+    }
+
+    /// Connect to the node and check every signer against its account.
+    pub async fn build(mut self) -> Result<StarknetRelayer, AspError> {
+        if !self.chain_verified {
+            self.verify_chain().await?;
+        }
+        let rpc_url = self.parsed_rpc_url()?;
+        let chain_id = required(self.chain_id, "chain_id")?;
+        let coordinator_address = required(self.coordinator_address, "coordinator_address")?;
+        let pools = required(self.pools, "pool_address")?;
+        let signer = required(self.signer, "signer")?;
+
+        let provider = JsonRpcClient::new(HttpTransport::new(rpc_url.clone()));
+        let mut signers = vec![(signer, self.public_key)];
+        signers.extend(self.backup_signers.into_iter().map(|signer| (signer, None)));
+        let mut accounts = Vec::with_capacity(signers.len());
+        for ((address, private_key), public_key) in signers {
+            let declared = match public_key {
+                Some(key) => Some(key),
+                None => fetch_account_public_key(&provider, address).await?,
+            };
+            let signing_key = SigningKey::from_secret_scalar(private_key);
+            check_signer(signing_key.verifying_key().scalar(), declared)?;
+            let mut account = SingleOwnerAccount::new(
+                JsonRpcClient::new(HttpTransport::new(rpc_url.clone())),
+                LocalWallet::from(signing_key),
+                address,
+                chain_id,
+                ExecutionEncoding::New,
+            );
+            account.set_block_id(BlockId::Tag(BlockTag::Latest));
+            accounts.push(account);
+        }
+        if accounts.len() > 1 {
+            tracing::info!(backups = accounts.len() - 1, "Backup admin accounts configured");
+        }
+
+        let runtime = self.runtime.unwrap_or_else(|| {
+            // Only the fee settings are read by the relayer
+            Arc::new(LiveConfig::new(RuntimeConfig {
+                sync_poll_interval_secs: 0,
+                sync_request_delay_ms: 0,
+                fee_multiplier: self.fee_multiplier,
+                max_fee_fri: self.max_fee_fri,
+                gas_bounds: self.gas_bounds,
+                log_level: String::new(),
+            }))
+        });
+
+        Ok(StarknetRelayer {
+            accounts,
+            active: AtomicUsize::new(0),
+            coordinator_address,
+            calls: PoolCalls::new(pools, self.max_calldata_felts),
+            runtime,
+            chain_id,
+        })
+    }
+
+    fn parsed_rpc_url(&self) -> Result<url::Url, AspError> {
+        let rpc_url = required(self.rpc_url.as_deref(), "rpc_url")?;
+        url::Url::parse(rpc_url).map_err(|e| AspError::Config(format!("Invalid RPC URL: {e}")))
+    }
+}
+
+fn required<T>(value: Option<T>, name: &str) -> Result<T, AspError> {
+    value.ok_or_else(|| AspError::Config(format!("Relayer {name} is required")))
+}
+
 #[async_trait::async_trait]
 impl Relayer for StarknetRelayer {
+    fn chain_id(&self) -> Felt {
+        self.chain_id
+    }
+
     async fn deposit(&self, commitment: &str) -> Result<String, AspError> {
         let (low, high) = u256_to_felts(commitment)?;
 
@@ -116,16 +361,36 @@ impl Relayer for StarknetRelayer {
         self.send_transaction(vec![call]).await
     }
 
-    async fn verify_membership(&self, calldata_hex: &[String]) -> Result<String, AspError> {
-        let calldata = build_span_calldata(calldata_hex)?;
+    async fn wait_for_tx(&self, tx_hash: &str) -> Result<(), AspError> {
+        let tx_hash = Felt::from_hex(tx_hash)
+            .map_err(|e| AspError::InvalidInput(format!("Invalid transaction hash: {e}")))?;
+        watch_tx(self.provider(), tx_hash).await
+    }
 
-        let call = Call {
-            to: self.pool_address,
-            selector: starknet::core::utils::get_selector_from_name("withdraw")
+    async fn get_onchain_root(&self) -> Result<String, AspError> {
+        let call = FunctionCall {
+            contract_address: self.coordinator_address,
+            entry_point_selector: starknet::core::utils::get_selector_from_name("get_merkle_root")
                 .map_err(|e| AspError::Internal(format!("Selector error: {e}")))?,
-            calldata,
+            calldata: vec![],
         };
 
+        let result = self
+            .provider()
+            .call(call, BlockId::Tag(BlockTag::Latest))
+            .await
+            .map_err(|e| AspError::RpcError(format!("get_merkle_root call failed: {e}")))?;
+        match result.as_slice() {
+            [low, high] => Ok(felts_to_u256(low, high)),
+            _ => Err(AspError::RpcError(format!(
+                "get_merkle_root returned {} felts, expected 2",
+                result.len()
+            ))),
+        }
+    }
+
+    async fn verify_membership(&self, calldata_hex: &[String]) -> Result<String, AspError> {
+        let call = self.calls.withdraw(calldata_hex)?;
         self.send_transaction(vec![call]).await
     }
 
@@ -135,33 +400,9 @@ impl Relayer for StarknetRelayer {
         proof_calldata_hex: &[String],
         sqrt_price_limit: &str,
     ) -> Result<String, AspError> {
-        let mut calldata = Vec::new();
-
-        calldata.push(
-            Felt::from_hex(&pool_key.token_0)
-                .map_err(|e| AspError::InvalidInput(format!("Invalid token_0: {e}")))?,
-        );
-        calldata.push(
-            Felt::from_hex(&pool_key.token_1)
-                .map_err(|e| AspError::InvalidInput(format!("Invalid token_1: {e}")))?,
-        );
-        calldata.push(Felt::from(pool_key.fee));
-        calldata.push(Felt::from(pool_key.tick_spacing));
-
-        let span = build_span_calldata(proof_calldata_hex)?;
-        calldata.extend(span);
-
-        let (low, high) = u256_to_felts(sqrt_price_limit)?;
-        calldata.push(low);
-        calldata.push(high);
-
-        let call = Call {
-            to: self.pool_address,
-            selector: starknet::core::utils::get_selector_from_name("shielded_swap")
-                .map_err(|e| AspError::Internal(format!("Selector error: {e}")))?,
-            calldata,
-        };
-
+        let call = self
+            .calls
+            .shielded_swap(pool_key, proof_calldata_hex, sqrt_price_limit)?;
         self.send_transaction(vec![call]).await
     }
 
@@ -171,31 +412,7 @@ impl Relayer for StarknetRelayer {
         proof_calldata_hex: &[String],
         liquidity: u128,
     ) -> Result<String, AspError> {
-        let mut calldata = Vec::new();
-
-        calldata.push(
-            Felt::from_hex(&pool_key.token_0)
-                .map_err(|e| AspError::InvalidInput(format!("Invalid token_0: {e}")))?,
-        );
-        calldata.push(
-            Felt::from_hex(&pool_key.token_1)
-                .map_err(|e| AspError::InvalidInput(format!("Invalid token_1: {e}")))?,
-        );
-        calldata.push(Felt::from(pool_key.fee));
-        calldata.push(Felt::from(pool_key.tick_spacing));
-
-        let span = build_span_calldata(proof_calldata_hex)?;
-        calldata.extend(span);
-
-        calldata.push(Felt::from(liquidity));
-
-        let call = Call {
-            to: self.pool_address,
-            selector: starknet::core::utils::get_selector_from_name("shielded_mint")
-                .map_err(|e| AspError::Internal(format!("Selector error: {e}")))?,
-            calldata,
-        };
-
+        let call = self.calls.shielded_mint(pool_key, proof_calldata_hex, liquidity)?;
         self.send_transaction(vec![call]).await
     }
 
@@ -205,36 +422,54 @@ impl Relayer for StarknetRelayer {
         proof_calldata_hex: &[String],
         liquidity: u128,
     ) -> Result<String, AspError> {
-        let mut calldata = Vec::new();
-
-        calldata.push(
-            Felt::from_hex(&pool_key.token_0)
-                .map_err(|e| AspError::InvalidInput(format!("Invalid token_0: {e}")))?,
-        );
-        calldata.push(
-            Felt::from_hex(&pool_key.token_1)
-                .map_err(|e| AspError::InvalidInput(format!("Invalid token_1: {e}")))?,
-        );
-        calldata.push(Felt::from(pool_key.fee));
-        calldata.push(Felt::from(pool_key.tick_spacing));
-
-        let span = build_span_calldata(proof_calldata_hex)?;
-        calldata.extend(span);
+        let call = self.calls.shielded_burn(pool_key, proof_calldata_hex, liquidity)?;
+        self.send_transaction(vec![call]).await
+    }
 
-        calldata.push(Felt::from(liquidity));
+    async fn simulate_swap(
+        &self,
+        pool_key: &PoolKeyParams,
+        zero_for_one: bool,
+        amount_in: &str,
+        sqrt_price_limit: &str,
+    ) -> Result<SwapQuote, AspError> {
+        let pool = self.calls.pools().resolve(pool_key)?;
+
+        let mut calldata = pool_key_calldata(pool_key)?;
+        calldata.push(Felt::from(u8::from(zero_for_one)));
+        // amount_specified: i256 { mag: u256, sign: false } (exact input)
+        let (low, high) = u256_to_felts(amount_in)?;
+        calldata.extend([low, high, Felt::ZERO]);
+        let (low, high) = u256_to_felts(sqrt_price_limit)?;
+        calldata.extend([low, high]);
 
-        let call = Call {
-            to: self.pool_address,
-            selector: starknet::core::utils::get_selector_from_name("shielded_burn")
+        let call = FunctionCall {
+            contract_address: pool,
+            entry_point_selector: starknet::core::utils::get_selector_from_name(QUOTE_ENTRY_POINT)
                 .map_err(|e| AspError::Internal(format!("Selector error: {e}")))?,
             calldata,
         };
 
-        self.send_transaction(vec![call]).await
+        let result = self
+            .provider()
+            .call(call, BlockId::Tag(BlockTag::Latest))
+            .await
+            .map_err(|e| {
+                let message = e.to_string();
+                if is_missing_entry_point(&message) {
+                    AspError::QuoterUnavailable(format!(
+                        "pool {pool:#x} does not implement {QUOTE_ENTRY_POINT}"
+                    ))
+                } else {
+                    AspError::RpcError(format!("{QUOTE_ENTRY_POINT} call failed: {message}"))
+                }
+            })?;
+
+        decode_swap_quote(&result, zero_for_one)
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, schemars::JsonSchema)]
 pub struct PoolKeyParams {
     pub token_0: String,
     pub token_1: String,
@@ -242,6 +477,71 @@ pub struct PoolKeyParams {
     pub tick_spacing: u64,
 }
 
+impl PoolKeyParams {
+    /// Canonical pool identifier: `token_0:token_1:fee:tick_spacing`, with
+    /// token addresses normalized to lowercase hex without leading zeros.
+    pub fn id(&self) -> Result<String, AspError> {
+        let token_0 = Felt::from_hex(&self.token_0)
+            .map_err(|e| AspError::InvalidInput(format!("Invalid token_0: {e}")))?;
+        let token_1 = Felt::from_hex(&self.token_1)
+            .map_err(|e| AspError::InvalidInput(format!("Invalid token_1: {e}")))?;
+        Ok(format!(
+            "{token_0:#x}:{token_1:#x}:{}:{}",
+            self.fee, self.tick_spacing
+        ))
+    }
+}
+
+/// Read-only pool entry point used for swap quotes:
+/// `quote_swap(pool_key, zero_for_one, amount_specified: i256, sqrt_price_limit: u256)
+///     -> (amount_0: i256, amount_1: i256, sqrt_price_after: u256)`.
+const QUOTE_ENTRY_POINT: &str = "quote_swap";
+
+/// Serialized `PoolKey`: `[token_0, token_1, fee, tick_spacing]`.
+pub(super) fn pool_key_calldata(pool_key: &PoolKeyParams) -> Result<Vec<Felt>, AspError> {
+    Ok(vec![
+        Felt::from_hex(&pool_key.token_0)
+            .map_err(|e| AspError::InvalidInput(format!("Invalid token_0: {e}")))?,
+        Felt::from_hex(&pool_key.token_1)
+            .map_err(|e| AspError::InvalidInput(format!("Invalid token_1: {e}")))?,
+        Felt::from(pool_key.fee),
+        Felt::from(pool_key.tick_spacing),
+    ])
+}
+
+/// Whether an RPC error says the called entry point does not exist.
+fn is_missing_entry_point(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("entry_point_not_found")
+        || message.contains("entrypoint_not_found")
+        || (message.contains("entry point") && message.contains("not found"))
+}
+
+/// Decode a `quote_swap` result: two i256 deltas (`[low, high, sign]` each)
+/// followed by the u256 sqrt price. The output amount is the magnitude of the
+/// delta of the token not being sold.
+fn decode_swap_quote(result: &[Felt], zero_for_one: bool) -> Result<SwapQuote, AspError> {
+    if result.len() < 8 {
+        return Err(AspError::RpcError(format!(
+            "{QUOTE_ENTRY_POINT} returned {} felts, expected 8",
+            result.len()
+        )));
+    }
+    let out = if zero_for_one { &result[3..6] } else { &result[0..3] };
+    Ok(SwapQuote {
+        amount_out: felts_to_u256(&out[0], &out[1]),
+        sqrt_price_after: felts_to_u256(&result[6], &result[7]),
+    })
+}
+
+/// Inverse of [`u256_to_felts`]: decimal string from (low_128, high_128).
+fn felts_to_u256(low: &Felt, high: &Felt) -> String {
+    let low = BigUint::from_bytes_be(&low.to_bytes_be());
+    let high = BigUint::from_bytes_be(&high.to_bytes_be());
+    let value: BigUint = (high << 128) | low;
+    value.to_str_radix(10)
+}
+
 /// Convert a u256 (decimal or hex string) to two Felt values (low_128, high_128).
 pub fn u256_to_felts(value: &str) -> Result<(Felt, Felt), AspError> {
     let big = if value.starts_with("0x") || value.starts_with("0X") {
@@ -266,8 +566,19 @@ pub fn u256_to_felts(value: &str) -> Result<(Felt, Felt), AspError> {
     Ok((low_felt, high_felt))
 }
 
-/// Build Span<felt252> calldata: [length, elem0, elem1, ...]
-fn build_span_calldata(hex_values: &[String]) -> Result<Vec<Felt>, AspError> {
+/// Build Span<felt252> calldata: [length, elem0, elem1, ...]. Proofs longer
+/// than `max_len` felts are refused: the transaction would exceed Starknet's
+/// calldata limit and revert.
+pub(super) fn build_span_calldata(
+    hex_values: &[String],
+    max_len: usize,
+) -> Result<Vec<Felt>, AspError> {
+    if hex_values.len() > max_len {
+        return Err(AspError::ProverError(format!(
+            "Proof calldata has {} felts, more than MAX_CALLDATA_FELTS {max_len}",
+            hex_values.len()
+        )));
+    }
     let mut calldata = Vec::with_capacity(hex_values.len() + 1);
     calldata.push(Felt::from(hex_values.len()));
 
@@ -280,13 +591,86 @@ fn build_span_calldata(hex_values: &[String]) -> Result<Vec<Felt>, AspError> {
     Ok(calldata)
 }
 
+/// Fail unless `provider` reports `expected` as its chain id, so a relayer
+/// pointed at the wrong network refuses to start instead of signing
+/// transactions that can never be accepted.
+async fn verify_chain_id<P: Provider + Sync>(provider: &P, expected: Felt) -> Result<(), AspError> {
+    let actual = provider
+        .chain_id()
+        .await
+        .map_err(|e| AspError::RpcError(format!("Failed to fetch chain id: {e}")))?;
+    if actual != expected {
+        return Err(AspError::Config(format!(
+            "RPC node is on chain {}, but STARKNET_CHAIN_ID is {}",
+            chain_name(actual),
+            chain_name(expected)
+        )));
+    }
+    Ok(())
+}
+
+/// Chain id as its short-string name (`SN_SEPOLIA`) when it is printable
+/// ASCII, otherwise as hex.
+fn chain_name(chain_id: Felt) -> String {
+    let bytes = chain_id.to_bytes_be();
+    let name: Vec<u8> = bytes.iter().copied().skip_while(|b| *b == 0).collect();
+    if !name.is_empty() && name.iter().all(|b| b.is_ascii_graphic()) {
+        String::from_utf8_lossy(&name).into_owned()
+    } else {
+        format!("{chain_id:#x}")
+    }
+}
+
+/// The public key `account` declares through `get_public_key`, or `None`
+/// for accounts that do not implement it.
+async fn fetch_account_public_key<P: Provider + Sync>(
+    provider: &P,
+    account: Felt,
+) -> Result<Option<Felt>, AspError> {
+    let call = FunctionCall {
+        contract_address: account,
+        entry_point_selector: starknet::core::utils::get_selector_from_name("get_public_key")
+            .map_err(|e| AspError::Internal(format!("Selector error: {e}")))?,
+        calldata: vec![],
+    };
+    match provider.call(call, BlockId::Tag(BlockTag::Latest)).await {
+        Ok(result) => match result.as_slice() {
+            [key] => Ok(Some(*key)),
+            _ => Err(AspError::RpcError(format!(
+                "get_public_key returned {} felts, expected 1",
+                result.len()
+            ))),
+        },
+        Err(e) if is_missing_entry_point(&e.to_string()) => Ok(None),
+        Err(e) => Err(AspError::RpcError(format!("get_public_key call failed: {e}"))),
+    }
+}
+
+/// Fail unless the admin private key derives `declared`, the key the account
+/// checks signatures against; otherwise every transaction would be rejected.
+/// Without a declared key the match cannot be checked and is only warned about.
+fn check_signer(derived: Felt, declared: Option<Felt>) -> Result<(), AspError> {
+    match declared {
+        Some(declared) if declared != derived => Err(AspError::Config(format!(
+            "Admin private key has public key {derived:#x}, but the account expects \
+             {declared:#x} (set ADMIN_PUBLIC_KEY if the account does not expose get_public_key)"
+        ))),
+        Some(_) => Ok(()),
+        None => {
+            tracing::warn!(
+                public_key = %format!("{derived:#x}"),
+                "Admin account has no get_public_key; set ADMIN_PUBLIC_KEY to verify the signer"
+            );
+            Ok(())
+        }
+    }
+}
+
 /// Wait for transaction confirmation by polling.
 async fn watch_tx(
     provider: &JsonRpcClient<HttpTransport>,
     tx_hash: Felt,
 ) -> Result<(), AspError> {
-    use starknet::providers::Provider;
-
     let max_retries = 60;
     let delay = std::time::Duration::from_secs(2);
 
@@ -297,15 +681,15 @@ async fn watch_tx(
                 match receipt.receipt.execution_result().status() {
                     TransactionExecutionStatus::Succeeded => return Ok(()),
                     TransactionExecutionStatus::Reverted => {
-                        return Err(AspError::TransactionReverted(format!(
-                            "Transaction {:#x} reverted: {}",
-                            tx_hash,
-                            receipt
+                        return Err(AspError::TransactionReverted {
+                            tx_hash: format!("{tx_hash:#x}"),
+                            reason: receipt
                                 .receipt
                                 .execution_result()
                                 .revert_reason()
                                 .unwrap_or("unknown reason")
-                        )));
+                                .to_string(),
+                        });
                     }
                 }
             }
@@ -316,14 +700,81 @@ async fn watch_tx(
         }
     }
 
-    Err(AspError::TransactionFailed(format!(
-        "Transaction {:#x} not confirmed after {}s",
-        tx_hash,
-        max_retries * 2
-    )))
+    tracing::warn!(
+        tx_hash = %format!("{tx_hash:#x}"),
+        waited_secs = max_retries * 2,
+        "Transaction not confirmed yet"
+    );
+    Err(AspError::TransactionPending {
+        tx_hash: format!("{tx_hash:#x}"),
+    })
+}
+
+/// Try `attempt` with each of `accounts` in turn, starting with the `active`
+/// one, and return the index of the account that succeeded, which becomes
+/// the active one. Only errors raised before a transaction was accepted
+/// (RPC failures, a rejected send) move on to the next account; a reverted or
+/// unconfirmed transaction is returned as is so it is never sent twice.
+async fn failover<'a, A, T, F, Fut>(
+    accounts: &'a [A],
+    active: &AtomicUsize,
+    mut attempt: F,
+) -> Result<(usize, T), AspError>
+where
+    F: FnMut(&'a A) -> Fut,
+    Fut: Future<Output = Result<T, AspError>>,
+{
+    let start = active.load(Ordering::Relaxed) % accounts.len().max(1);
+    let mut last_error = None;
+    for offset in 0..accounts.len() {
+        let index = (start + offset) % accounts.len();
+        match attempt(&accounts[index]).await {
+            Ok(value) => {
+                active.store(index, Ordering::Relaxed);
+                return Ok((index, value));
+            }
+            Err(e @ (AspError::RpcError(_) | AspError::TransactionFailed(_))) => {
+                tracing::warn!(account = index, error = %e, "Admin account failed to send");
+                last_error = Some(e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| AspError::Internal("No admin account configured".into())))
+}
+
+/// `BACKUP_ADMIN_ADDRESSES` paired with the keys in the
+/// `BACKUP_ADMIN_PRIVATE_KEYS` env var (comma-separated, same order).
+fn resolve_backup_accounts(config: &Config) -> Result<Vec<(Felt, Felt)>, AspError> {
+    if config.backup_admin_addresses.is_empty() {
+        return Ok(Vec::new());
+    }
+    let keys = std::env::var("BACKUP_ADMIN_PRIVATE_KEYS").unwrap_or_default();
+    let keys: Vec<&str> = keys.split(',').map(str::trim).filter(|k| !k.is_empty()).collect();
+    if keys.len() != config.backup_admin_addresses.len() {
+        return Err(AspError::Config(format!(
+            "BACKUP_ADMIN_PRIVATE_KEYS has {} keys for {} BACKUP_ADMIN_ADDRESSES",
+            keys.len(),
+            config.backup_admin_addresses.len()
+        )));
+    }
+    config
+        .backup_admin_addresses
+        .iter()
+        .zip(keys)
+        .map(|(address, key)| {
+            let address = Felt::from_hex(address)
+                .map_err(|e| AspError::Config(format!("Invalid backup admin address: {e}")))?;
+            let key = Felt::from_hex(key).map_err(|e| {
+                AspError::Config(format!("Invalid key in BACKUP_ADMIN_PRIVATE_KEYS: {e}"))
+            })?;
+            Ok((address, key))
+        })
+        .collect()
 }
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
 
@@ -349,6 +800,130 @@ mod tests {
         assert_eq!(low, Felt::ZERO);
         assert_eq!(high, Felt::ONE);
     }
+
+    #[test]
+    fn span_calldata_is_length_prefixed() {
+        let hex: Vec<String> = vec!["0x1".into(), "0xff".into()];
+        let calldata = build_span_calldata(&hex, 2).unwrap();
+        assert_eq!(calldata, felts(&[2, 1, 255]));
+    }
+
+    #[test]
+    fn over_length_calldata_is_refused() {
+        let hex: Vec<String> = (0..5).map(|i| format!("{i:#x}")).collect();
+        let err = build_span_calldata(&hex, 4).unwrap_err();
+        assert!(matches!(err, AspError::ProverError(_)), "{err}");
+        assert!(err.to_string().contains("MAX_CALLDATA_FELTS"), "{err}");
+    }
+
+    fn felts(values: &[u64]) -> Vec<Felt> {
+        values.iter().map(|&v| Felt::from(v)).collect()
+    }
+
+    #[test]
+    fn decode_swap_quote_picks_output_token() {
+        // amount_0 = -400 (sold), amount_1 = +395 (bought), sqrt price 2^128 + 5
+        let result = felts(&[400, 0, 1, 395, 0, 0, 5, 1]);
+
+        let quote = decode_swap_quote(&result, true).unwrap();
+        assert_eq!(quote.amount_out, "395");
+        assert_eq!(
+            quote.sqrt_price_after,
+            "340282366920938463463374607431768211461"
+        );
+
+        let quote = decode_swap_quote(&result, false).unwrap();
+        assert_eq!(quote.amount_out, "400");
+    }
+
+    #[test]
+    fn decode_swap_quote_rejects_short_result() {
+        assert!(matches!(
+            decode_swap_quote(&felts(&[1, 2, 3]), true),
+            Err(AspError::RpcError(_))
+        ));
+    }
+
+    #[test]
+    fn signer_must_match_declared_public_key() {
+        let derived = Felt::from(0xabcu64);
+        assert!(check_signer(derived, Some(derived)).is_ok());
+        assert!(check_signer(derived, None).is_ok());
+
+        let err = check_signer(derived, Some(Felt::from(0xdefu64))).unwrap_err();
+        assert!(matches!(err, AspError::Config(_)), "{err}");
+        assert!(err.to_string().contains("0xabc") && err.to_string().contains("0xdef"));
+    }
+
+    /// Stand-in for an admin account that either sends or fails with `error`.
+    struct FakeAccount {
+        error: Option<fn() -> AspError>,
+        sends: AtomicUsize,
+    }
+
+    impl FakeAccount {
+        fn new(error: Option<fn() -> AspError>) -> Self {
+            FakeAccount {
+                error,
+                sends: AtomicUsize::new(0),
+            }
+        }
+
+        async fn send(&self) -> Result<&'static str, AspError> {
+            self.sends.fetch_add(1, Ordering::SeqCst);
+            match self.error {
+                Some(error) => Err(error()),
+                None => Ok("0x7"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_next_account() {
+        let accounts = [
+            FakeAccount::new(Some(|| AspError::RpcError("rate limited".into()))),
+            FakeAccount::new(None),
+        ];
+        let active = AtomicUsize::new(0);
+
+        let (index, tx) = failover(&accounts, &active, FakeAccount::send).await.unwrap();
+        assert_eq!((index, tx), (1, "0x7"));
+        assert_eq!(active.load(Ordering::SeqCst), 1);
+
+        // The working account stays active
+        failover(&accounts, &active, FakeAccount::send).await.unwrap();
+        assert_eq!(accounts[0].sends.load(Ordering::SeqCst), 1);
+        assert_eq!(accounts[1].sends.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_fail_over_after_acceptance() {
+        let reverted = || AspError::TransactionReverted {
+            tx_hash: "0x1".into(),
+            reason: "root out of order".into(),
+        };
+        let accounts = [FakeAccount::new(Some(reverted)), FakeAccount::new(None)];
+        let active = AtomicUsize::new(0);
+
+        let err = failover(&accounts, &active, FakeAccount::send).await.unwrap_err();
+        assert!(matches!(err, AspError::TransactionReverted { .. }), "{err}");
+        assert_eq!(accounts[1].sends.load(Ordering::SeqCst), 0);
+
+        // Every account failing returns the last error
+        let failing = || AspError::TransactionFailed("nonce too low".into());
+        let accounts = [FakeAccount::new(Some(failing)), FakeAccount::new(Some(failing))];
+        let err = failover(&accounts, &active, FakeAccount::send).await.unwrap_err();
+        assert!(matches!(err, AspError::TransactionFailed(_)), "{err}");
+    }
+
+    #[test]
+    fn detects_missing_entry_point() {
+        assert!(is_missing_entry_point(
+            "Contract error: Entry point EntryPointSelector(0x12) not found in contract."
+        ));
+        assert!(is_missing_entry_point("ENTRYPOINT_NOT_FOUND"));
+        assert!(!is_missing_entry_point("Contract not found"));
+    }
 }
 
 /// Resolve private key from ADMIN_PRIVATE_KEY env var or keystore file.
@@ -0,0 +1,354 @@
+//! Runtime-reloadable configuration.
+//!
+//! A safe subset of [`Config`] can be changed without restarting the server
+//! (which would drop the worker and its in-memory tree): the sync poll
+//! interval and request delay, relayer fee settings and gas bounds, and the
+//! log level. Everything else —
+//! addresses, RPC URL, keys, database and worker paths, tree height — is read
+//! once at startup and changes to it are ignored (with a warning) until restart.
+
+use std::sync::{Arc, RwLock};
+
+use crate::config::Config;
+use crate::error::AspError;
+use crate::relayer::GasBounds;
+
+/// Config fields that may change while the server is running.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeConfig {
+    pub sync_poll_interval_secs: u64,
+    pub sync_request_delay_ms: u64,
+    pub fee_multiplier: f64,
+    pub max_fee_fri: Option<u128>,
+    pub gas_bounds: GasBounds,
+    pub log_level: String,
+}
+
+impl RuntimeConfig {
+    pub fn from_config(config: &Config) -> Self {
+        RuntimeConfig {
+            sync_poll_interval_secs: config.sync_poll_interval_secs,
+            sync_request_delay_ms: config.sync_request_delay_ms,
+            fee_multiplier: config.fee_multiplier,
+            max_fee_fri: config.max_fee_fri,
+            gas_bounds: GasBounds::from_config(config),
+            log_level: config.log_level.clone(),
+        }
+    }
+}
+
+/// Shared handle to the current [`RuntimeConfig`]. Readers take a cheap
+/// snapshot; a reload swaps the whole value at once.
+#[derive(Debug)]
+pub struct LiveConfig {
+    current: RwLock<Arc<RuntimeConfig>>,
+}
+
+impl LiveConfig {
+    pub fn new(runtime: RuntimeConfig) -> Self {
+        LiveConfig {
+            current: RwLock::new(Arc::new(runtime)),
+        }
+    }
+
+    pub fn get(&self) -> Arc<RuntimeConfig> {
+        self.current
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn store(&self, runtime: RuntimeConfig) {
+        *self
+            .current
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(runtime);
+    }
+}
+
+/// Apply a freshly loaded config: validate it, swap in its reloadable subset,
+/// and return the names of changed fields that need a restart to take effect.
+pub fn apply_reload(
+    live: &LiveConfig,
+    startup: &Config,
+    reloaded: &Config,
+) -> Result<Vec<&'static str>, AspError> {
+    reloaded.validate()?;
+    live.store(RuntimeConfig::from_config(reloaded));
+    Ok(static_changes(startup, reloaded))
+}
+
+/// Non-reloadable fields that differ between `a` and `b`.
+fn static_changes(a: &Config, b: &Config) -> Vec<&'static str> {
+    let mut changed = Vec::new();
+    let mut check = |name, differs: bool| {
+        if differs {
+            changed.push(name);
+        }
+    };
+    check("ASP_HOST", a.host != b.host);
+    check("ASP_PORT", a.port != b.port);
+    check("HTTP2_ENABLED", a.http2_enabled != b.http2_enabled);
+    check("KEEPALIVE_SECS", a.keepalive_secs != b.keepalive_secs);
+    check("MAX_CONCURRENT_STREAMS", a.max_concurrent_streams != b.max_concurrent_streams);
+    check("STARKNET_RPC_URL", a.rpc_url != b.rpc_url);
+    check("STARKNET_CHAIN_ID", a.chain_id != b.chain_id);
+    check("ADMIN_ADDRESS", a.admin_address != b.admin_address);
+    check("KEYSTORE_PATH", a.keystore_path != b.keystore_path);
+    check("ADMIN_PUBLIC_KEY", a.admin_public_key != b.admin_public_key);
+    check("BACKUP_ADMIN_ADDRESSES", a.backup_admin_addresses != b.backup_admin_addresses);
+    check("LOG_FORMAT", a.log_format != b.log_format);
+    check("COORDINATOR_ADDRESS", a.coordinator_address != b.coordinator_address);
+    check("POOL_ADDRESS", a.pool_address != b.pool_address);
+    check("POOLS", a.pools != b.pools);
+    check("DATABASE_PATH", a.database_path != b.database_path);
+    check("DATABASE_POOL_SIZE", a.database_pool_size != b.database_pool_size);
+    check("DATABASE_SYNCHRONOUS", a.database_synchronous != b.database_synchronous);
+    check("DATABASE_FOREIGN_KEYS", a.database_foreign_keys != b.database_foreign_keys);
+    check(
+        "DATABASE_BUSY_TIMEOUT_MS",
+        a.database_busy_timeout_ms != b.database_busy_timeout_ms,
+    );
+    check("WORKER_PATH", a.worker_path != b.worker_path);
+    check("WORKER_RUNTIME", a.worker_runtime != b.worker_runtime);
+    check("WORKER_RUNTIME_ARGS", a.worker_runtime_args != b.worker_runtime_args);
+    check(
+        "EXPECTED_CIRCUIT_HASHES",
+        a.expected_circuit_hashes != b.expected_circuit_hashes,
+    );
+    check("COMMITMENT_CACHE_SIZE", a.commitment_cache_size != b.commitment_cache_size);
+    check("WORKER_WARMUP", a.worker_warmup != b.worker_warmup);
+    check("MAX_PROOF_JOBS", a.max_proof_jobs != b.max_proof_jobs);
+    check(
+        "NOTE_DOMAIN_SEPARATOR/POSITION_DOMAIN_SEPARATOR",
+        a.commitment_domains != b.commitment_domains,
+    );
+    check(
+        "EXPECTED_SELF_TEST_ROOT",
+        a.expected_self_test_root != b.expected_self_test_root,
+    );
+    check("TREE_HEIGHT", a.tree_height != b.tree_height);
+    check("ALLOW_LEAF_GAPS", a.allow_leaf_gaps != b.allow_leaf_gaps);
+    check("ROOT_HISTORY_SIZE", a.root_history_size != b.root_history_size);
+    check("SYNC_START_BLOCK", a.sync_start_block != b.sync_start_block);
+    check("MAX_BLOCK_RANGE", a.max_block_range != b.max_block_range);
+    check("RELAYER_MAX_ATTEMPTS", a.relayer_max_attempts != b.relayer_max_attempts);
+    check("RELAYER_RETRY_BASE_MS", a.relayer_retry_base_ms != b.relayer_retry_base_ms);
+    check("MAX_CALLDATA_FELTS", a.max_calldata_felts != b.max_calldata_felts);
+    check("WEBHOOK_URL", a.webhook_url != b.webhook_url);
+    check("ADMIN_API_TOKEN", a.admin_api_token != b.admin_api_token);
+    check("ALLOWED_ORIGINS", a.allowed_origins != b.allowed_origins);
+    check("ALLOWED_DENOMINATIONS", a.allowed_denominations != b.allowed_denominations);
+    check("DEPOSIT_QUEUE", a.deposit_queue != b.deposit_queue);
+    check(
+        "MIN_WITHDRAW_DELAY_SECS",
+        a.min_withdraw_delay_secs != b.min_withdraw_delay_secs,
+    );
+    check("MAX_BODY_BYTES", a.max_body_bytes != b.max_body_bytes);
+    check("REQUEST_TIMEOUT_SECS", a.request_timeout_secs != b.request_timeout_secs);
+    check(
+        "PROOF_REQUEST_TIMEOUT_SECS",
+        a.proof_request_timeout_secs != b.proof_request_timeout_secs,
+    );
+    check("TRUST_FORWARDED_FOR", a.trust_forwarded_for != b.trust_forwarded_for);
+    check("AUTO_BAN_THRESHOLD", a.auto_ban_threshold != b.auto_ban_threshold);
+    check("AUTO_BAN_WINDOW_SECS", a.auto_ban_window_secs != b.auto_ban_window_secs);
+    check("STATUS_CACHE_MS", a.status_cache_ms != b.status_cache_ms);
+    check("PRETTY_JSON", a.pretty_json != b.pretty_json);
+    changed
+}
+
+/// Reload config on every SIGHUP until the process exits. `set_log_level` is
+/// called with the new level after each successful reload.
+#[cfg(unix)]
+pub async fn reload_on_sighup<F>(live: Arc<LiveConfig>, startup: Config, set_log_level: F)
+where
+    F: Fn(&str) + Send + 'static,
+{
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to install SIGHUP handler; config reload disabled");
+            return;
+        }
+    };
+
+    while hangups.recv().await.is_some() {
+        tracing::info!("SIGHUP received, reloading configuration");
+
+        let reloaded = match Config::reload() {
+            Ok(c) => c,
+            Err(e) => {
+                tracing::error!(error = %e, "Config reload failed; keeping current settings");
+                continue;
+            }
+        };
+
+        match apply_reload(&live, &startup, &reloaded) {
+            Ok(ignored) => {
+                let runtime = live.get();
+                set_log_level(&runtime.log_level);
+                tracing::info!(
+                    sync_poll_interval_secs = runtime.sync_poll_interval_secs,
+                    sync_request_delay_ms = runtime.sync_request_delay_ms,
+                    fee_multiplier = runtime.fee_multiplier,
+                    max_fee_fri = ?runtime.max_fee_fri,
+                    gas_bounds = ?runtime.gas_bounds,
+                    log_level = %runtime.log_level,
+                    "Configuration reloaded"
+                );
+                if !ignored.is_empty() {
+                    tracing::warn!(
+                        fields = ?ignored,
+                        "Changed settings are not reloadable and need a restart"
+                    );
+                }
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Reloaded config is invalid; keeping current settings");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::logging::LogFormat;
+
+    fn config() -> Config {
+        Config {
+            host: "127.0.0.1".into(),
+            port: 3000,
+            http2_enabled: true,
+            keepalive_secs: 60,
+            max_concurrent_streams: 200,
+            rpc_url: "http://localhost:5050".into(),
+            chain_id: "SN_SEPOLIA".into(),
+            admin_address: "0x1234".into(),
+            keystore_path: "/dev/null".into(),
+            keystore_password: "test".into(),
+            admin_public_key: None,
+            backup_admin_addresses: vec![],
+            coordinator_address: "0xc0".into(),
+            pool_address: "0xa0".into(),
+            pools: HashMap::new(),
+            database_path: ":memory:".into(),
+            database_pool_size: 1,
+            database_synchronous: "NORMAL".into(),
+            database_foreign_keys: true,
+            database_busy_timeout_ms: 5000,
+            worker_path: "worker/worker.mjs".into(),
+            worker_runtime: "node".into(),
+            worker_runtime_args: Vec::new(),
+            expected_circuit_hashes: Default::default(),
+            commitment_cache_size: 0,
+            worker_warmup: true,
+            max_proof_jobs: 4,
+            commitment_domains: Default::default(),
+            expected_self_test_root: None,
+            tree_height: 20,
+            allow_leaf_gaps: false,
+            root_history_size: 100,
+            sync_poll_interval_secs: 5,
+            sync_request_delay_ms: 0,
+            sync_start_block: 0,
+            max_block_range: 1000,
+            relayer_max_attempts: 3,
+            relayer_retry_base_ms: 500,
+            fee_multiplier: 1.5,
+            max_fee_fri: None,
+            l1_gas_max_amount: None,
+            l1_gas_max_price: None,
+            l2_gas_max_amount: None,
+            l2_gas_max_price: None,
+            max_calldata_felts: 4000,
+            log_level: "info".into(),
+            log_format: LogFormat::Text,
+            webhook_url: None,
+            admin_api_token: None,
+            allowed_origins: Vec::new(),
+            allowed_denominations: Vec::new(),
+            deposit_queue: false,
+            min_withdraw_delay_secs: 0,
+            max_body_bytes: 2 * 1024 * 1024,
+            request_timeout_secs: 30,
+            proof_request_timeout_secs: 300,
+            trust_forwarded_for: false,
+            auto_ban_threshold: 0,
+            auto_ban_window_secs: 60,
+            status_cache_ms: 1000,
+            pretty_json: false,
+        }
+    }
+
+    #[test]
+    fn reload_toggles_poll_interval() {
+        let startup = config();
+        let live = LiveConfig::new(RuntimeConfig::from_config(&startup));
+        assert_eq!(live.get().sync_poll_interval_secs, 5);
+
+        let faster = Config {
+            sync_poll_interval_secs: 1,
+            ..config()
+        };
+        assert!(apply_reload(&live, &startup, &faster).unwrap().is_empty());
+        assert_eq!(live.get().sync_poll_interval_secs, 1);
+
+        // And back
+        apply_reload(&live, &startup, &startup).unwrap();
+        assert_eq!(live.get().sync_poll_interval_secs, 5);
+    }
+
+    #[test]
+    fn reload_applies_fee_and_log_settings() {
+        let startup = config();
+        let live = LiveConfig::new(RuntimeConfig::from_config(&startup));
+
+        let reloaded = Config {
+            fee_multiplier: 2.0,
+            max_fee_fri: Some(10u128.pow(18)),
+            l2_gas_max_price: Some(10u128.pow(10)),
+            log_level: "debug".into(),
+            ..config()
+        };
+        apply_reload(&live, &startup, &reloaded).unwrap();
+
+        let runtime = live.get();
+        assert_eq!(runtime.fee_multiplier, 2.0);
+        assert_eq!(runtime.max_fee_fri, Some(10u128.pow(18)));
+        assert_eq!(runtime.gas_bounds.l2_gas_max_price, Some(10u128.pow(10)));
+        assert_eq!(runtime.log_level, "debug");
+    }
+
+    #[test]
+    fn invalid_reload_keeps_current_settings() {
+        let startup = config();
+        let live = LiveConfig::new(RuntimeConfig::from_config(&startup));
+
+        let broken = Config {
+            sync_poll_interval_secs: 0,
+            ..config()
+        };
+        assert!(apply_reload(&live, &startup, &broken).is_err());
+        assert_eq!(live.get().sync_poll_interval_secs, 5);
+    }
+
+    #[test]
+    fn static_field_changes_are_reported() {
+        let startup = config();
+        let live = LiveConfig::new(RuntimeConfig::from_config(&startup));
+
+        let moved = Config {
+            pool_address: "0xb0".into(),
+            worker_path: "elsewhere.mjs".into(),
+            ..config()
+        };
+        let ignored = apply_reload(&live, &startup, &moved).unwrap();
+        assert_eq!(ignored, vec!["POOL_ADDRESS", "WORKER_PATH"]);
+    }
+}
@@ -0,0 +1,198 @@
+//! HTTP server loop. Serves HTTP/1.1 and, unless `HTTP2_ENABLED=false`,
+//! cleartext HTTP/2 on the same listener, with the keep-alive and stream
+//! limits from [`Config`]. Each request carries the peer address as
+//! [`ConnectInfo<SocketAddr>`].
+
+use std::future::Future;
+use std::time::Duration;
+
+use axum::extract::ConnectInfo;
+use axum::http::Request;
+use axum::Router;
+use hyper::body::Incoming;
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+use hyper_util::server::conn::auto;
+use hyper_util::server::graceful::GracefulShutdown;
+use hyper_util::service::TowerToHyperService;
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+
+use crate::config::Config;
+
+/// Connection settings taken from [`Config`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerTuning {
+    pub http2_enabled: bool,
+    pub keepalive_secs: u64,
+    pub max_concurrent_streams: u32,
+}
+
+impl ServerTuning {
+    pub fn from_config(config: &Config) -> Self {
+        ServerTuning {
+            http2_enabled: config.http2_enabled,
+            keepalive_secs: config.keepalive_secs,
+            max_concurrent_streams: config.max_concurrent_streams,
+        }
+    }
+
+    /// Per-connection builder with these settings applied.
+    pub fn connection_builder(&self) -> auto::Builder<TokioExecutor> {
+        let mut builder = auto::Builder::new(TokioExecutor::new());
+        let keepalive = (self.keepalive_secs > 0).then(|| Duration::from_secs(self.keepalive_secs));
+
+        builder.http1().keep_alive(keepalive.is_some());
+        builder
+            .http2()
+            .timer(TokioTimer::new())
+            .keep_alive_interval(keepalive)
+            .max_concurrent_streams(self.max_concurrent_streams);
+
+        if self.http2_enabled {
+            builder
+        } else {
+            builder.http1_only()
+        }
+    }
+}
+
+/// Serve `app` on `listener` until `shutdown` resolves, then wait for open
+/// connections to finish their in-flight requests.
+pub async fn serve<F>(
+    listener: TcpListener,
+    app: Router,
+    tuning: ServerTuning,
+    shutdown: F,
+) -> std::io::Result<()>
+where
+    F: Future<Output = ()>,
+{
+    let builder = tuning.connection_builder();
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(shutdown);
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to accept connection");
+                    continue;
+                }
+            },
+            _ = &mut shutdown => break,
+        };
+
+        let app = app.clone().map_request(move |mut req: Request<Incoming>| {
+            req.extensions_mut().insert(ConnectInfo(peer));
+            req
+        });
+        let service = TowerToHyperService::new(app);
+        let connection = builder
+            .serve_connection(TokioIo::new(stream), service)
+            .into_owned();
+        let connection = graceful.watch(connection);
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                tracing::debug!(error = %e, "Connection closed with an error");
+            }
+        });
+    }
+
+    drop(listener);
+    graceful.shutdown().await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use std::net::SocketAddr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    const H2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+    /// Serve a one-route app with `tuning`; returns its address.
+    async fn spawn_server(tuning: ServerTuning) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route(
+            "/",
+            get(|ConnectInfo(peer): ConnectInfo<SocketAddr>| async move { peer.ip().to_string() }),
+        );
+        tokio::spawn(serve(listener, app, tuning, std::future::pending()));
+        addr
+    }
+
+    fn tuning(http2_enabled: bool) -> ServerTuning {
+        ServerTuning {
+            http2_enabled,
+            keepalive_secs: 60,
+            max_concurrent_streams: 7,
+        }
+    }
+
+    /// Value of `id` in the server's first HTTP/2 SETTINGS frame.
+    async fn h2_setting(addr: SocketAddr, id: u16) -> Option<u32> {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(H2_PREFACE).await.unwrap();
+        // Empty client SETTINGS frame
+        stream
+            .write_all(&[0, 0, 0, 4, 0, 0, 0, 0, 0])
+            .await
+            .unwrap();
+
+        let mut header = [0u8; 9];
+        stream.read_exact(&mut header).await.unwrap();
+        assert_eq!(header[3], 4, "first server frame is SETTINGS");
+        let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+        let mut payload = vec![0u8; len];
+        stream.read_exact(&mut payload).await.unwrap();
+        payload
+            .chunks_exact(6)
+            .find(|s| u16::from_be_bytes([s[0], s[1]]) == id)
+            .map(|s| u32::from_be_bytes([s[2], s[3], s[4], s[5]]))
+    }
+
+    async fn http1_get(addr: SocketAddr) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn http2_advertises_max_concurrent_streams() {
+        let addr = spawn_server(tuning(true)).await;
+        // SETTINGS_MAX_CONCURRENT_STREAMS
+        assert_eq!(h2_setting(addr, 0x3).await, Some(7));
+        assert!(http1_get(addr).await.starts_with("HTTP/1.1 200"));
+    }
+
+    #[tokio::test]
+    async fn requests_carry_the_peer_address() {
+        let addr = spawn_server(tuning(true)).await;
+        assert!(http1_get(addr).await.ends_with("\r\n\r\n127.0.0.1"));
+    }
+
+    #[tokio::test]
+    async fn http1_only_rejects_http2() {
+        let addr = spawn_server(tuning(false)).await;
+        assert!(http1_get(addr).await.starts_with("HTTP/1.1 200"));
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(H2_PREFACE).await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        assert!(
+            response.is_empty() || response.starts_with(b"HTTP/1.1 "),
+            "expected an HTTP/1.1 error or a closed connection, got {response:?}"
+        );
+    }
+}
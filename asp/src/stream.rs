@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::prover::CircuitType;
+
+/// Recent events kept for clients resuming with a last-seen event id.
+const REPLAY_BUFFER_SIZE: usize = 1024;
+
+/// A tree change pushed to `GET /events` subscribers. Values are decimal
+/// strings, as stored in the database.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TreeEvent {
+    CommitmentAdded {
+        leaf_index: u32,
+        commitment: String,
+        /// Root after this leaf was inserted.
+        root: String,
+    },
+    NullifierSpent {
+        nullifier_hash: String,
+        circuit_type: CircuitType,
+    },
+}
+
+impl TreeEvent {
+    /// SSE event name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TreeEvent::CommitmentAdded { .. } => "commitment_added",
+            TreeEvent::NullifierSpent { .. } => "nullifier_spent",
+        }
+    }
+}
+
+/// A [`TreeEvent`] with its position in the stream. Ids start at 1 and
+/// restart when the process does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequencedEvent {
+    pub id: u64,
+    pub event: TreeEvent,
+}
+
+/// Fan-out of tree events to live subscribers, with a bounded replay buffer
+/// so a reconnecting client can catch up on what it missed.
+pub struct EventBus {
+    sender: broadcast::Sender<SequencedEvent>,
+    log: Mutex<EventLog>,
+}
+
+struct EventLog {
+    next_id: u64,
+    recent: VecDeque<SequencedEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(REPLAY_BUFFER_SIZE);
+        EventBus {
+            sender,
+            log: Mutex::new(EventLog {
+                next_id: 1,
+                recent: VecDeque::with_capacity(REPLAY_BUFFER_SIZE),
+            }),
+        }
+    }
+
+    /// Assign the next id to `event` and send it to every subscriber.
+    pub fn publish(&self, event: TreeEvent) {
+        let mut log = self.log.lock().unwrap_or_else(|p| p.into_inner());
+        let sequenced = SequencedEvent {
+            id: log.next_id,
+            event,
+        };
+        log.next_id += 1;
+        if log.recent.len() == REPLAY_BUFFER_SIZE {
+            log.recent.pop_front();
+        }
+        log.recent.push_back(sequenced.clone());
+        // Sent under the lock so a concurrent `subscribe` sees each event
+        // exactly once, either in its backlog or on its receiver.
+        let _ = self.sender.send(sequenced);
+    }
+
+    /// Subscribe to new events. Returns the buffered events after
+    /// `last_event_id` (none when `None`) and a receiver for later ones. An id
+    /// ahead of anything issued means the server restarted, so the whole
+    /// buffer is replayed.
+    pub fn subscribe(
+        &self,
+        last_event_id: Option<u64>,
+    ) -> (Vec<SequencedEvent>, broadcast::Receiver<SequencedEvent>) {
+        let log = self.log.lock().unwrap_or_else(|p| p.into_inner());
+        let backlog = match last_event_id {
+            None => Vec::new(),
+            Some(last) => {
+                let after = if last >= log.next_id { 0 } else { last };
+                log.recent.iter().filter(|e| e.id > after).cloned().collect()
+            }
+        };
+        (backlog, self.sender.subscribe())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nullifier(n: u32) -> TreeEvent {
+        TreeEvent::NullifierSpent {
+            nullifier_hash: n.to_string(),
+            circuit_type: CircuitType::Swap,
+        }
+    }
+
+    #[test]
+    fn live_subscriber_receives_published_events() {
+        let bus = EventBus::new();
+        let (backlog, mut rx) = bus.subscribe(None);
+        assert!(backlog.is_empty());
+
+        bus.publish(nullifier(7));
+        let received = rx.try_recv().unwrap();
+        assert_eq!(received.id, 1);
+        assert_eq!(received.event, nullifier(7));
+    }
+
+    #[test]
+    fn resume_replays_events_after_last_seen_id() {
+        let bus = EventBus::new();
+        for n in 1..=5 {
+            bus.publish(nullifier(n));
+        }
+
+        let (backlog, mut rx) = bus.subscribe(Some(3));
+        let ids: Vec<u64> = backlog.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![4, 5]);
+        assert!(rx.try_recv().is_err());
+
+        // An id from before a restart replays everything buffered
+        let (backlog, _) = bus.subscribe(Some(99));
+        assert_eq!(backlog.len(), 5);
+    }
+
+    #[test]
+    fn replay_buffer_is_bounded() {
+        let bus = EventBus::new();
+        for n in 0..(REPLAY_BUFFER_SIZE as u32 + 10) {
+            bus.publish(nullifier(n));
+        }
+
+        let (backlog, _) = bus.subscribe(Some(0));
+        assert_eq!(backlog.len(), REPLAY_BUFFER_SIZE);
+        assert_eq!(backlog[0].id, 11);
+    }
+}
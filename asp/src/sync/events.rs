@@ -1,12 +1,20 @@
-use std::sync::Arc;
-use std::time::Duration;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use num_bigint::BigUint;
-use starknet::core::types::{BlockId, EmittedEvent, EventFilter, Felt};
+use schemars::JsonSchema;
+use serde::Serialize;
+use starknet::core::types::{BlockId, BlockTag, EmittedEvent, EventFilter, Felt};
 use starknet::providers::jsonrpc::HttpTransport;
-use starknet::providers::{JsonRpcClient, Provider};
+use starknet::providers::JsonRpcClient;
 
+use crate::config::Config;
+use crate::db::Database;
 use crate::error::AspError;
+use crate::prover::CircuitType;
+use crate::relayer::PoolRoutes;
+use crate::sync::{EventSource, RpcEventSource};
 use crate::AppState;
 
 /// Event selectors (sn_keccak of event name)
@@ -28,10 +36,14 @@ fn felts_to_decimal(low: &Felt, high: &Felt) -> String {
     value.to_str_radix(10)
 }
 
-/// Parse a felt252 as a u32.
-fn felt_to_u32(felt: &Felt) -> u32 {
+/// Parse a felt252 as a u32, or `None` if any byte above the low 4 is set.
+fn felt_to_u32(felt: &Felt) -> Option<u32> {
     let bytes = felt.to_bytes_be();
-    u32::from_be_bytes([bytes[28], bytes[29], bytes[30], bytes[31]])
+    let (high, low) = bytes.split_at(28);
+    if high.iter().any(|&b| b != 0) {
+        return None;
+    }
+    Some(u32::from_be_bytes([low[0], low[1], low[2], low[3]]))
 }
 
 /// Parsed CommitmentAdded event.
@@ -39,6 +51,9 @@ fn felt_to_u32(felt: &Felt) -> u32 {
 struct CommitmentAddedEvent {
     commitment_decimal: String,
     leaf_index: u32,
+    /// Root the contract reports after this leaf, or `None` when it reports
+    /// zero (the coordinator leaves root computation to the ASP).
+    new_root_decimal: Option<String>,
 }
 
 /// Parsed NullifierSpent event.
@@ -58,11 +73,19 @@ fn parse_commitment_added(event: &EmittedEvent) -> Option<CommitmentAddedEvent>
         return None;
     }
     let commitment_decimal = felts_to_decimal(&event.data[0], &event.data[1]);
-    let leaf_index = felt_to_u32(&event.data[2]);
+    let Some(leaf_index) = felt_to_u32(&event.data[2]) else {
+        tracing::warn!(
+            leaf_index = %event.data[2],
+            "CommitmentAdded event leaf index does not fit in u32, skipping"
+        );
+        return None;
+    };
+    let new_root_decimal = felts_to_decimal(&event.data[3], &event.data[4]);
 
     Some(CommitmentAddedEvent {
         commitment_decimal,
         leaf_index,
+        new_root_decimal: (new_root_decimal != "0").then_some(new_root_decimal),
     })
 }
 
@@ -90,88 +113,272 @@ pub fn create_provider(rpc_url: &str) -> Result<JsonRpcClient<HttpTransport>, As
     Ok(JsonRpcClient::new(HttpTransport::new(url)))
 }
 
-/// Fetch and process events from a block range.
-/// Returns the number of new commitments and nullifiers processed.
-async fn poll_events(
-    provider: &JsonRpcClient<HttpTransport>,
-    coordinator_address: Felt,
+/// Events requested per `starknet_getEvents` page.
+const EVENTS_CHUNK_SIZE: u64 = 100;
+
+/// Monitored contracts' events from one contiguous block range.
+struct EventWindow {
     from_block: u64,
     to_block: u64,
+    events: Vec<EmittedEvent>,
+}
+
+/// Walks the monitored contracts' events from `next_from` up to the chain head in windows
+/// of at most `max_block_range` blocks, so a long outage never produces a
+/// `starknet_getEvents` range the RPC node would reject. A filter names one
+/// contract, so each window is queried once per monitored address.
+///
+/// The head is learned once per cycle. With a `head_hint` (the head seen last
+/// cycle) the probe is batched with the first address's first page; without
+/// one it costs a separate request. Every request after the first waits
+/// `request_delay`.
+struct BlockScanner<'a> {
+    source: &'a dyn EventSource,
+    addresses: &'a [Felt],
+    next_from: u64,
+    head: Option<u64>,
+    head_hint: Option<u64>,
+    max_block_range: u64,
+    request_delay: Duration,
+    requested: bool,
+}
+
+impl<'a> BlockScanner<'a> {
+    fn new(
+        source: &'a dyn EventSource,
+        addresses: &'a [Felt],
+        from_block: u64,
+        head_hint: Option<u64>,
+        max_block_range: u64,
+        request_delay: Duration,
+    ) -> Self {
+        BlockScanner {
+            source,
+            addresses,
+            next_from: from_block,
+            head: None,
+            head_hint,
+            max_block_range: max_block_range.max(1),
+            request_delay,
+            requested: false,
+        }
+    }
+
+    /// Events from `address` in the given range. Only the two events the
+    /// sync applies are requested; `process_events` still matches selectors
+    /// in case a node ignores the keys filter.
+    fn filter(&self, address: Felt, from_block: u64, to_block: BlockId) -> EventFilter {
+        EventFilter {
+            from_block: Some(BlockId::Number(from_block)),
+            to_block: Some(to_block),
+            address: Some(address),
+            keys: Some(vec![vec![
+                commitment_added_selector(),
+                nullifier_spent_selector(),
+            ]]),
+        }
+    }
+
+    async fn pace(&mut self) {
+        if self.requested && !self.request_delay.is_zero() {
+            tokio::time::sleep(self.request_delay).await;
+        }
+        self.requested = true;
+    }
+
+    /// Fetch the next window, or `None` once the head has been reached.
+    async fn next_window(&mut self) -> Result<Option<EventWindow>, AspError> {
+        let from = self.next_from;
+        let window_end = from.saturating_add(self.max_block_range - 1);
+
+        // The first address's first page of this window, with the filter it
+        // was fetched with (continuation tokens are only valid for the same
+        // filter).
+        let mut first_page = None;
+        if self.head.is_none() {
+            match (self.head_hint, self.addresses.first()) {
+                (Some(hint), Some(&address)) => {
+                    // Past the hinted head, ask up to `latest` so the query
+                    // never names a block the node has not produced yet.
+                    let to_block = if window_end < hint {
+                        BlockId::Number(window_end)
+                    } else {
+                        BlockId::Tag(BlockTag::Latest)
+                    };
+                    let filter = self.filter(address, from, to_block);
+                    self.pace().await;
+                    let (head, page) = self
+                        .source
+                        .head_and_events(filter.clone(), EVENTS_CHUNK_SIZE)
+                        .await?;
+                    self.head = Some(head);
+                    first_page = Some((filter, page));
+                }
+                _ => {
+                    self.pace().await;
+                    self.head = Some(self.source.head().await?);
+                }
+            }
+        }
+
+        let head = self.head.unwrap_or_default();
+        if from > head {
+            return Ok(None);
+        }
+        let to_block = window_end.min(head);
+
+        let mut events = Vec::new();
+        for (i, &address) in self.addresses.iter().enumerate() {
+            let (filter, mut page) = match first_page.take().filter(|_| i == 0) {
+                Some(first) => first,
+                None => {
+                    let filter = self.filter(address, from, BlockId::Number(to_block));
+                    self.pace().await;
+                    let page = self
+                        .source
+                        .events(filter.clone(), None, EVENTS_CHUNK_SIZE)
+                        .await?;
+                    (filter, page)
+                }
+            };
+            loop {
+                events.extend(page.events.into_iter().filter(|e| {
+                    e.block_number
+                        .is_some_and(|n| (from..=to_block).contains(&n))
+                        && e.from_address == address
+                }));
+                let Some(token) = page.continuation_token else {
+                    break;
+                };
+                self.pace().await;
+                page = self
+                    .source
+                    .events(filter.clone(), Some(token), EVENTS_CHUNK_SIZE)
+                    .await?;
+            }
+        }
+        // Each address's events arrive in chain order; merge them by block.
+        // The sort is stable, so one contract's events within a block keep
+        // their order.
+        events.sort_by_key(|e| e.block_number);
+
+        self.next_from = to_block + 1;
+        Ok(Some(EventWindow {
+            from_block: from,
+            to_block,
+            events,
+        }))
+    }
+}
+
+/// Apply fetched events to local state, skipping ones already known. Events
+/// are routed by selector whichever monitored contract emitted them: the
+/// coordinator emits `CommitmentAdded`, and both it and the pool emit
+/// `NullifierSpent`.
+/// Returns the number of new commitments and nullifiers processed.
+async fn process_events(
+    events: &[EmittedEvent],
     state: &Arc<AppState>,
 ) -> Result<(usize, usize), AspError> {
     let commitment_selector = commitment_added_selector();
     let nullifier_selector = nullifier_spent_selector();
 
     // Collect all new leaves and nullifiers first, then batch-process
-    let mut new_leaves: Vec<(u32, String)> = Vec::new();
+    let mut new_leaves: Vec<(u32, String, Option<String>)> = Vec::new();
     let mut new_nullifiers: Vec<String> = Vec::new();
-    let mut continuation_token: Option<String> = None;
-
-    loop {
-        let filter = EventFilter {
-            from_block: Some(BlockId::Number(from_block)),
-            to_block: Some(BlockId::Number(to_block)),
-            address: Some(coordinator_address),
-            keys: None,
-        };
-
-        let events_page = provider
-            .get_events(filter, continuation_token.clone(), 100)
-            .await
-            .map_err(|e| AspError::RpcError(format!("get_events failed: {e}")))?;
-
-        for event in &events_page.events {
-            if event.keys.is_empty() {
-                continue;
-            }
-            let selector = &event.keys[0];
 
-            if selector == &commitment_selector {
-                if let Some(parsed) = parse_commitment_added(event) {
-                    if state.db.get_commitment(parsed.leaf_index)?.is_none() {
-                        new_leaves.push((parsed.leaf_index, parsed.commitment_decimal));
-                    }
+    for event in events {
+        if event.keys.is_empty() {
+            continue;
+        }
+        let selector = &event.keys[0];
+
+        if selector == &commitment_selector {
+            if let Some(parsed) = parse_commitment_added(event) {
+                state.config.ensure_tree_capacity(parsed.leaf_index)?;
+                if state.db.get_commitment(parsed.leaf_index)?.is_none() {
+                    new_leaves.push((
+                        parsed.leaf_index,
+                        parsed.commitment_decimal,
+                        parsed.new_root_decimal,
+                    ));
                 }
-            } else if selector == &nullifier_selector {
-                if let Some(parsed) = parse_nullifier_spent(event) {
-                    if !state.db.is_nullifier_spent(&parsed.nullifier_hash_decimal)? {
-                        new_nullifiers.push(parsed.nullifier_hash_decimal);
-                    }
+            }
+        } else if selector == &nullifier_selector {
+            if let Some(parsed) = parse_nullifier_spent(event) {
+                if !state.db.is_nullifier_spent(&parsed.nullifier_hash_decimal)? {
+                    new_nullifiers.push(parsed.nullifier_hash_decimal);
                 }
             }
         }
-
-        match events_page.continuation_token {
-            Some(token) => continuation_token = Some(token),
-            None => break,
-        }
     }
 
     // Batch insert new commitments into DB and worker tree (single lock)
     if !new_leaves.is_empty() {
+        let _appending = state.tree_writes.lock().await;
         let mut worker = state.worker.lock().await;
-        for (leaf_index, commitment) in &new_leaves {
+        for (leaf_index, commitment, onchain_root) in &new_leaves {
             state
                 .db
                 .insert_commitment(*leaf_index, commitment, None)?;
-            worker.insert_leaf(commitment).await?;
+            let root = worker.insert_leaf(commitment).await?;
+            if let Some(onchain_root) = onchain_root {
+                check_synced_root(state, *leaf_index, &root, onchain_root)?;
+            }
+            state.publish_commitment(*leaf_index, commitment, &root);
             tracing::debug!(leaf_index = leaf_index, "Synced CommitmentAdded");
         }
         drop(worker);
+        state.refresh_tree()?;
     }
 
     // Batch insert nullifiers
     for nullifier in &new_nullifiers {
-        state.db.insert_nullifier(nullifier, "synced", None)?;
+        state.db.insert_synced_nullifier(nullifier)?;
+        state.publish_nullifier(nullifier, CircuitType::Synced);
         tracing::debug!(nullifier = %nullifier, "Synced NullifierSpent");
     }
 
     Ok((new_leaves.len(), new_nullifiers.len()))
 }
 
-/// Submit the current Merkle root on-chain if it differs from the last submitted root.
+/// `sync_state` key holding the leaf index at which the local tree last
+/// disagreed with a root reported by `CommitmentAdded`. Cleared once a later
+/// synced leaf agrees again.
+pub const TREE_DIVERGED_KEY: &str = "tree_diverged_at_leaf";
+
+/// Compare the worker's root after inserting a synced leaf with the root the
+/// contract reported for it, marking the tree diverged on a mismatch.
+fn check_synced_root(
+    state: &AppState,
+    leaf_index: u32,
+    local_root: &str,
+    onchain_root: &str,
+) -> Result<(), AspError> {
+    if local_root == onchain_root {
+        if state.db.get_sync_state(TREE_DIVERGED_KEY)?.is_some() {
+            tracing::info!(leaf_index, "Local tree agrees with the on-chain root again");
+            state.db.delete_sync_state(TREE_DIVERGED_KEY)?;
+        }
+        return Ok(());
+    }
+    tracing::error!(
+        leaf_index,
+        local_root = %local_root,
+        onchain_root = %onchain_root,
+        "Local tree diverged from the on-chain root"
+    );
+    state
+        .db
+        .set_sync_state(TREE_DIVERGED_KEY, &leaf_index.to_string())
+}
+
+/// Submit the current Merkle root on-chain if it differs from the last submitted root
+/// and from the coordinator's current root. The database can lag the chain (e.g. a
+/// crash between submitting and recording), so a root the coordinator already holds
+/// is only recorded locally.
 /// In proof-only mode (relayer = None), this is a no-op since users submit their own transactions.
-async fn submit_root_if_changed(state: &Arc<AppState>) -> Result<(), AspError> {
+pub async fn submit_root_if_changed(state: &Arc<AppState>) -> Result<(), AspError> {
     // In proof-only mode, we don't submit roots - users do when they submit deposits
     if state.relayer.is_none() {
         return Ok(());
@@ -193,103 +400,597 @@ async fn submit_root_if_changed(state: &Arc<AppState>) -> Result<(), AspError> {
         return Ok(());
     }
 
+    let relayer = state.relayer.as_ref().unwrap().lock().await;
+    match relayer.get_onchain_root().await {
+        Ok(onchain_root) if onchain_root == current_root => {
+            drop(relayer);
+            state.db.insert_root(&current_root, leaf_count, None)?;
+            state.refresh_tree()?;
+            tracing::info!(
+                root = %current_root,
+                leaf_count = leaf_count,
+                "Merkle root already on-chain; recorded without resubmitting"
+            );
+            return Ok(());
+        }
+        Ok(_) => {}
+        Err(e) => {
+            tracing::warn!(error = %e, "Could not read on-chain root; submitting anyway");
+        }
+    }
+
     tracing::info!(
         new_root = %current_root,
         leaf_count = leaf_count,
         "Submitting new Merkle root"
     );
 
-    let relayer = state.relayer.as_ref().unwrap().lock().await;
     let tx_hash = relayer.submit_merkle_root(&current_root).await?;
     drop(relayer);
 
     state
         .db
         .insert_root(&current_root, leaf_count, Some(&tx_hash))?;
+    state.refresh_tree()?;
 
     tracing::info!(tx_hash = %tx_hash, "Merkle root submitted");
     Ok(())
 }
 
-/// Background task: continuously polls Starknet events and syncs local state.
-pub async fn start_event_sync(state: Arc<AppState>, poll_interval_secs: u64) {
-    let provider = match create_provider(&state.config.rpc_url) {
-        Ok(p) => p,
-        Err(e) => {
-            tracing::error!(error = %e, "Failed to create provider for event sync");
-            return;
+/// What one sync cycle did: new events applied and the blocks it scanned.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct SyncReport {
+    pub new_commitments: usize,
+    pub new_nullifiers: usize,
+    pub from_block: u64,
+    pub to_block: u64,
+}
+
+/// Outcome of the sync cycles so far, for `/status`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SyncProgress {
+    /// Whether a cycle is running right now.
+    pub in_progress: bool,
+    /// Unix time the last successful cycle finished.
+    pub last_completed_at: Option<u64>,
+    /// Error of the last cycle; cleared by the next successful one.
+    pub last_error: Option<String>,
+}
+
+/// Runs sync cycles against one event source, from the background loop or on
+/// demand (`POST /admin/sync-now`).
+pub struct Syncer {
+    source: Box<dyn EventSource>,
+    /// Contracts whose events are applied: the coordinator and the pools.
+    addresses: Vec<Felt>,
+    /// Chain head seen by the last successful cycle. Held for a whole cycle,
+    /// so the background loop and on-demand syncs never interleave.
+    head_hint: tokio::sync::Mutex<Option<u64>>,
+    /// Latest chain head observed, kept across failed cycles for `/status`.
+    chain_head: Mutex<Option<u64>>,
+    progress: Mutex<SyncProgress>,
+}
+
+impl Syncer {
+    /// Sync the events emitted by any of `addresses`. Duplicates are ignored.
+    pub fn new(source: Box<dyn EventSource>, mut addresses: Vec<Felt>) -> Self {
+        let mut seen = HashSet::new();
+        addresses.retain(|a| seen.insert(*a));
+        Syncer {
+            source,
+            addresses,
+            head_hint: tokio::sync::Mutex::new(None),
+            chain_head: Mutex::new(None),
+            progress: Mutex::new(SyncProgress::default()),
         }
-    };
+    }
 
-    let coordinator_address = match Felt::from_hex(&state.config.coordinator_address) {
-        Ok(addr) => addr,
-        Err(e) => {
-            tracing::error!(error = %e, "Invalid coordinator address for event sync");
-            return;
+    /// Sync from the configured RPC node, watching the coordinator, the
+    /// default pool and every pool in `POOLS`.
+    pub fn from_config(config: &Config) -> Result<Self, AspError> {
+        let source = RpcEventSource::new(create_provider(&config.rpc_url)?);
+        let coordinator_address = Felt::from_hex(&config.coordinator_address).map_err(|e| {
+            AspError::Config(format!("Invalid coordinator address for event sync: {e}"))
+        })?;
+        let pools = PoolRoutes::new(&config.pool_address, &config.pools)?;
+        let mut addresses = vec![coordinator_address];
+        addresses.extend(pools.addresses());
+        Ok(Syncer::new(Box::new(source), addresses))
+    }
+
+    /// Latest chain head seen by any sync cycle, if one has reached the node.
+    pub fn chain_head(&self) -> Option<u64> {
+        *self.chain_head.lock().unwrap_or_else(|p| p.into_inner())
+    }
+
+    /// Whether a cycle is running, and how the last one ended.
+    pub fn progress(&self) -> SyncProgress {
+        self.progress
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .clone()
+    }
+
+    /// Run one sync cycle now, waiting for any cycle already in progress.
+    pub async fn sync_once(&self, state: &Arc<AppState>) -> Result<SyncReport, AspError> {
+        let mut head_hint = self.head_hint.lock().await;
+        self.progress
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .in_progress = true;
+        let result = sync_cycle(self.source.as_ref(), &self.addresses, state, &mut head_hint).await;
+        if let Some(head) = *head_hint {
+            *self.chain_head.lock().unwrap_or_else(|p| p.into_inner()) = Some(head);
         }
-    };
+        self.record_outcome(&result);
+        result
+    }
+
+    /// Mark the running cycle finished with `result`.
+    fn record_outcome(&self, result: &Result<SyncReport, AspError>) {
+        let mut progress = self.progress.lock().unwrap_or_else(|p| p.into_inner());
+        progress.in_progress = false;
+        match result {
+            Ok(report) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                progress.last_completed_at = Some(now);
+                progress.last_error = None;
+                tracing::debug!(
+                    from_block = report.from_block,
+                    to_block = report.to_block,
+                    new_commitments = report.new_commitments,
+                    new_nullifiers = report.new_nullifiers,
+                    "Sync cycle completed"
+                );
+            }
+            Err(e) => progress.last_error = Some(e.to_string()),
+        }
+    }
+}
 
-    let interval = Duration::from_secs(poll_interval_secs);
+/// Background task: continuously polls Starknet events and syncs local state.
+/// The poll interval is re-read from `state.runtime` every cycle, so a config
+/// reload takes effect after the current sleep.
+pub async fn start_event_sync(state: Arc<AppState>) {
+    let Some(ref syncer) = state.syncer else {
+        tracing::warn!("Event sync is not configured; not polling for events");
+        return;
+    };
 
     tracing::info!(
-        interval_secs = poll_interval_secs,
+        interval_secs = state.runtime.get().sync_poll_interval_secs,
         coordinator = %state.config.coordinator_address,
+        pool = %state.config.pool_address,
         "Event sync started"
     );
 
+    // Reconcile a root left unrecorded by a previous run before polling.
+    if let Err(e) = submit_root_if_changed(&state).await {
+        tracing::warn!(error = %e, "Failed to reconcile Merkle root on startup");
+    }
+
     loop {
-        if let Err(e) = sync_once(&provider, coordinator_address, &state).await {
+        if let Err(e) = syncer.sync_once(&state).await {
             tracing::warn!(error = %e, "Event sync cycle failed, will retry");
         }
+        let interval = Duration::from_secs(state.runtime.get().sync_poll_interval_secs);
         tokio::time::sleep(interval).await;
     }
 }
 
-/// Run a single sync cycle: fetch latest block, poll events, submit root if needed.
-async fn sync_once(
-    provider: &JsonRpcClient<HttpTransport>,
-    coordinator_address: Felt,
+/// Run a single sync cycle: scan new blocks window by window, then submit the
+/// root if needed. `last_block` is saved after every window, so progress
+/// survives a later window failing. `head_hint` carries the chain head from
+/// one cycle to the next and is cleared after a failure.
+async fn sync_cycle(
+    source: &dyn EventSource,
+    addresses: &[Felt],
     state: &Arc<AppState>,
-) -> Result<(), AspError> {
-    let latest_block = provider
-        .block_number()
-        .await
-        .map_err(|e| AspError::RpcError(format!("block_number failed: {e}")))?;
+    head_hint: &mut Option<u64>,
+) -> Result<SyncReport, AspError> {
+    let (mut last_synced, from_block) =
+        resume_point(&state.db, state.config.sync_start_block)?;
+    let request_delay = Duration::from_millis(state.runtime.get().sync_request_delay_ms);
+    let mut scanner = BlockScanner::new(
+        source,
+        addresses,
+        from_block,
+        *head_hint,
+        state.config.max_block_range,
+        request_delay,
+    );
 
-    let last_synced = state
-        .db
+    let mut report = SyncReport {
+        from_block,
+        to_block: from_block,
+        ..SyncReport::default()
+    };
+    let scanned = async {
+        while let Some(window) = scanner.next_window().await? {
+            let (commitments, nullifiers) = process_events(&window.events, state).await?;
+            report.new_commitments += commitments;
+            report.new_nullifiers += nullifiers;
+            report.to_block = window.to_block;
+
+            if commitments > 0 || nullifiers > 0 {
+                tracing::info!(
+                    new_commitments = commitments,
+                    new_nullifiers = nullifiers,
+                    from_block = window.from_block,
+                    to_block = window.to_block,
+                    "Events synced"
+                );
+            }
+
+            // A resync requested while this cycle ran takes precedence over its progress.
+            if resume_point(&state.db, state.config.sync_start_block)?.0 != last_synced {
+                tracing::info!("Sync position changed during cycle; not recording progress");
+                break;
+            }
+            state
+                .db
+                .set_sync_state("last_block", &window.to_block.to_string())?;
+            last_synced = Some(window.to_block);
+        }
+        Ok::<(), AspError>(())
+    }
+    .await;
+
+    *head_hint = match scanned {
+        Ok(()) => scanner.head,
+        Err(_) => None,
+    };
+
+    // Submit root on-chain if tree changed from external commitments, even if a
+    // later window failed.
+    if report.new_commitments > 0 {
+        if let Err(e) = submit_root_if_changed(state).await {
+            tracing::warn!(error = %e, "Failed to submit updated root");
+        }
+    }
+
+    scanned.map(|()| report)
+}
+
+/// Where the next sync cycle resumes: the last synced block, if any, and the
+/// first block to scan. The last synced block itself is scanned again: the
+/// events query is built before the head is known, and anchoring it at a
+/// block that exists keeps the batched request valid when nothing new has
+/// been produced. Events seen last cycle are skipped as already known.
+/// A fresh database starts at `start_block`.
+fn resume_point(db: &Database, start_block: u64) -> Result<(Option<u64>, u64), AspError> {
+    let last_synced = db
         .get_sync_state("last_block")?
-        .and_then(|s| s.parse::<u64>().ok())
-        .unwrap_or(0);
+        .and_then(|s| s.parse::<u64>().ok());
+    Ok((last_synced, last_synced.unwrap_or(start_block)))
+}
 
-    if latest_block <= last_synced {
-        return Ok(());
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    use starknet::core::types::EventsPage;
+
+    use super::*;
+
+    /// In-memory chain with one event per listed block. Pages hold `page_size`
+    /// events; every call counts as one RPC round trip.
+    struct MockSource {
+        head: u64,
+        event_blocks: Vec<u64>,
+        page_size: usize,
+        round_trips: AtomicUsize,
+        /// `(from_block, to_block)` of every events query, `None` for `latest`.
+        queries: Mutex<Vec<(u64, Option<u64>)>>,
+        /// `keys` of every events request, continuation pages included.
+        keys: Mutex<Vec<Option<Vec<Vec<Felt>>>>>,
+        /// `address` of every events request, continuation pages included.
+        addresses: Mutex<Vec<Option<Felt>>>,
+        /// Contract that emitted the event in a given block.
+        emitter: fn(u64) -> Felt,
     }
 
-    let from_block = last_synced + 1;
-    let (new_commitments, new_nullifiers) =
-        poll_events(provider, coordinator_address, from_block, latest_block, state).await?;
+    impl MockSource {
+        fn new(head: u64, event_blocks: &[u64], page_size: usize) -> Self {
+            MockSource {
+                head,
+                event_blocks: event_blocks.to_vec(),
+                page_size,
+                round_trips: AtomicUsize::new(0),
+                queries: Mutex::new(Vec::new()),
+                keys: Mutex::new(Vec::new()),
+                addresses: Mutex::new(Vec::new()),
+                emitter: |_| Felt::ONE,
+            }
+        }
+
+        fn round_trips(&self) -> usize {
+            self.round_trips.load(Ordering::SeqCst)
+        }
 
-    if new_commitments > 0 || new_nullifiers > 0 {
-        tracing::info!(
-            new_commitments,
-            new_nullifiers,
-            from_block,
-            to_block = latest_block,
-            "Events synced"
+        fn page(&self, filter: &EventFilter, token: Option<String>) -> EventsPage {
+            let from = match filter.from_block {
+                Some(BlockId::Number(n)) => n,
+                _ => 0,
+            };
+            let to = match filter.to_block {
+                Some(BlockId::Number(n)) => Some(n),
+                _ => None,
+            };
+            if token.is_none() {
+                self.queries.lock().unwrap().push((from, to));
+            }
+            self.keys.lock().unwrap().push(filter.keys.clone());
+            self.addresses.lock().unwrap().push(filter.address);
+            let to = to.unwrap_or(self.head);
+            assert!(to <= self.head, "query names block {to} past head {}", self.head);
+
+            let matching: Vec<u64> = self
+                .event_blocks
+                .iter()
+                .copied()
+                .filter(|b| (from..=to).contains(b))
+                .filter(|&b| !matches!(filter.address, Some(a) if a != (self.emitter)(b)))
+                .collect();
+            let offset: usize = token.map(|t| t.parse().unwrap()).unwrap_or(0);
+            let end = (offset + self.page_size).min(matching.len());
+            EventsPage {
+                events: matching[offset..end]
+                    .iter()
+                    .map(|&b| EmittedEvent {
+                        from_address: (self.emitter)(b),
+                        ..event_at(b)
+                    })
+                    .collect(),
+                continuation_token: (end < matching.len()).then(|| end.to_string()),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl EventSource for MockSource {
+        async fn head(&self) -> Result<u64, AspError> {
+            self.round_trips.fetch_add(1, Ordering::SeqCst);
+            Ok(self.head)
+        }
+
+        async fn head_and_events(
+            &self,
+            filter: EventFilter,
+            _chunk_size: u64,
+        ) -> Result<(u64, EventsPage), AspError> {
+            self.round_trips.fetch_add(1, Ordering::SeqCst);
+            Ok((self.head, self.page(&filter, None)))
+        }
+
+        async fn events(
+            &self,
+            filter: EventFilter,
+            continuation_token: Option<String>,
+            _chunk_size: u64,
+        ) -> Result<EventsPage, AspError> {
+            self.round_trips.fetch_add(1, Ordering::SeqCst);
+            Ok(self.page(&filter, continuation_token))
+        }
+    }
+
+    #[test]
+    fn felt_to_u32_rejects_high_bytes() {
+        assert_eq!(felt_to_u32(&Felt::from(7u64)), Some(7));
+        assert_eq!(felt_to_u32(&Felt::from(u32::MAX)), Some(u32::MAX));
+        assert_eq!(felt_to_u32(&Felt::from(1u64 << 32)), None);
+        // Low bytes alone would read as 5
+        let high = Felt::from((1u64 << 40) | 5);
+        assert_eq!(felt_to_u32(&high), None);
+    }
+
+    #[test]
+    fn commitment_with_oversized_leaf_index_is_skipped() {
+        let mut event = event_at(1);
+        event.data = vec![
+            Felt::from(9u64),
+            Felt::ZERO,
+            Felt::from(u64::MAX),
+            Felt::ONE,
+            Felt::ZERO,
+        ];
+        assert!(parse_commitment_added(&event).is_none());
+
+        event.data[2] = Felt::from(3u64);
+        assert_eq!(parse_commitment_added(&event).unwrap().leaf_index, 3);
+    }
+
+    #[test]
+    fn commitment_new_root_is_parsed_unless_zero() {
+        let mut event = event_at(1);
+        event.data = vec![Felt::from(9u64), Felt::ZERO, Felt::ZERO, Felt::ZERO, Felt::ZERO];
+        assert_eq!(parse_commitment_added(&event).unwrap().new_root_decimal, None);
+
+        event.data[3] = Felt::ONE;
+        event.data[4] = Felt::ONE;
+        assert_eq!(
+            parse_commitment_added(&event).unwrap().new_root_decimal.as_deref(),
+            Some("340282366920938463463374607431768211457")
         );
     }
 
-    // Submit root on-chain if tree changed from external commitments
-    if new_commitments > 0 {
-        if let Err(e) = submit_root_if_changed(state).await {
-            tracing::warn!(error = %e, "Failed to submit updated root");
+    fn event_at(block: u64) -> EmittedEvent {
+        EmittedEvent {
+            from_address: Felt::ONE,
+            keys: vec![nullifier_spent_selector()],
+            data: vec![Felt::from(block), Felt::ZERO],
+            block_hash: None,
+            block_number: Some(block),
+            transaction_hash: Felt::from(block),
         }
     }
 
-    state
-        .db
-        .set_sync_state("last_block", &latest_block.to_string())?;
+    async fn scan_all(scanner: &mut BlockScanner<'_>) -> Vec<EventWindow> {
+        let mut windows = Vec::new();
+        while let Some(window) = scanner.next_window().await.unwrap() {
+            windows.push(window);
+        }
+        windows
+    }
 
-    Ok(())
+    #[test]
+    fn fresh_database_resumes_from_start_block() {
+        let db = Database::new(":memory:").unwrap();
+        db.run_migrations().unwrap();
+        assert_eq!(resume_point(&db, 5_000).unwrap(), (None, 5_000));
+
+        db.set_sync_state("last_block", "6000").unwrap();
+        assert_eq!(resume_point(&db, 5_000).unwrap(), (Some(6_000), 6_000));
+    }
+
+    #[tokio::test]
+    async fn long_gap_is_scanned_in_bounded_windows() {
+        let source = MockSource::new(6_000, &[1_001, 2_500, 6_000], 100);
+        let mut scanner =
+            BlockScanner::new(&source, &[Felt::ONE], 1_001, None, 1_000, Duration::ZERO);
+
+        let windows = scan_all(&mut scanner).await;
+
+        let ranges: Vec<_> = windows.iter().map(|w| (w.from_block, w.to_block)).collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (1_001, 2_000),
+                (2_001, 3_000),
+                (3_001, 4_000),
+                (4_001, 5_000),
+                (5_001, 6_000),
+            ]
+        );
+        let events: usize = windows.iter().map(|w| w.events.len()).sum();
+        assert_eq!(events, 3);
+        // Head probe plus one query per window
+        assert_eq!(source.round_trips(), 6);
+    }
+
+    #[tokio::test]
+    async fn requests_only_coordinator_event_keys() {
+        // Page size 1 forces continuation requests
+        let source = MockSource::new(30, &[5, 6, 25], 1);
+        let mut scanner =
+            BlockScanner::new(&source, &[Felt::ONE], 0, Some(30), 20, Duration::ZERO);
+        scan_all(&mut scanner).await;
+
+        let expected = Some(vec![vec![
+            commitment_added_selector(),
+            nullifier_spent_selector(),
+        ]]);
+        let keys = source.keys.lock().unwrap();
+        assert!(keys.len() > 2);
+        assert!(keys.iter().all(|k| *k == expected));
+    }
+
+    #[tokio::test]
+    async fn collects_events_from_every_monitored_address() {
+        // Blocks 1x come from the coordinator, 2x from the pool, 3x elsewhere
+        let mut source = MockSource::new(40, &[10, 12, 20, 21, 30], 100);
+        source.emitter = |b| Felt::from(b / 10);
+        // The pool is queried first, yet events come back in block order
+        let addresses = [Felt::from(2u64), Felt::ONE];
+        let mut scanner =
+            BlockScanner::new(&source, &addresses, 0, Some(40), 1_000, Duration::ZERO);
+
+        let windows = scan_all(&mut scanner).await;
+
+        let blocks: Vec<u64> = windows[0]
+            .events
+            .iter()
+            .filter_map(|e| e.block_number)
+            .collect();
+        assert_eq!(blocks, vec![10, 12, 20, 21]);
+        // One node-side filtered query per address, never a chain-wide one
+        assert_eq!(
+            *source.addresses.lock().unwrap(),
+            vec![Some(Felt::from(2u64)), Some(Felt::ONE)]
+        );
+        assert_eq!(source.round_trips(), 2);
+    }
+
+    #[tokio::test]
+    async fn caught_up_cycle_is_one_round_trip() {
+        let source = MockSource::new(12, &[10, 11, 12], 100);
+        let mut scanner =
+            BlockScanner::new(&source, &[Felt::ONE], 10, Some(11), 1_000, Duration::ZERO);
+
+        let windows = scan_all(&mut scanner).await;
+
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].events.len(), 3);
+        assert_eq!(source.round_trips(), 1);
+        assert_eq!(*source.queries.lock().unwrap(), vec![(10, None)]);
+    }
+
+    #[tokio::test]
+    async fn hinted_window_stays_below_head() {
+        let source = MockSource::new(3_500, &[1_000, 1_999, 2_000], 100);
+        let mut scanner =
+            BlockScanner::new(&source, &[Felt::ONE], 1_000, Some(3_400), 1_000, Duration::ZERO);
+
+        let windows = scan_all(&mut scanner).await;
+
+        assert_eq!(windows.len(), 3);
+        assert_eq!(windows[0].events.len(), 2);
+        assert_eq!(
+            *source.queries.lock().unwrap(),
+            vec![(1_000, Some(1_999)), (2_000, Some(2_999)), (3_000, Some(3_500))]
+        );
+        // The head probe rode along with the first query
+        assert_eq!(source.round_trips(), 3);
+    }
+
+    #[tokio::test]
+    async fn requests_are_spaced_by_request_delay() {
+        let source = MockSource::new(30, &[10, 20, 30], 1);
+        let delay = Duration::from_millis(20);
+        let mut scanner = BlockScanner::new(&source, &[Felt::ONE], 10, Some(30), 1_000, delay);
+
+        let started = std::time::Instant::now();
+        let windows = scan_all(&mut scanner).await;
+
+        assert_eq!(windows[0].events.len(), 3);
+        assert_eq!(source.round_trips(), 3);
+        assert!(started.elapsed() >= delay * 2);
+    }
+
+    #[tokio::test]
+    async fn drops_events_past_window_end() {
+        let source = MockSource::new(12, &[10, 11, 12], 100);
+        let mut scanner =
+            BlockScanner::new(&source, &[Felt::ONE], 10, Some(12), 2, Duration::ZERO);
+
+        let windows = scan_all(&mut scanner).await;
+
+        let blocks: Vec<Vec<u64>> = windows
+            .iter()
+            .map(|w| w.events.iter().filter_map(|e| e.block_number).collect())
+            .collect();
+        assert_eq!(blocks, vec![vec![10, 11], vec![12]]);
+    }
+
+    #[test]
+    fn cycle_outcome_updates_progress() {
+        let syncer = Syncer::new(Box::new(MockSource::new(0, &[], 10)), vec![Felt::ONE]);
+        assert_eq!(syncer.progress(), SyncProgress::default());
+
+        syncer.progress.lock().unwrap().in_progress = true;
+        syncer.record_outcome(&Err(AspError::RpcError("node down".into())));
+        let progress = syncer.progress();
+        assert!(!progress.in_progress);
+        assert_eq!(progress.last_completed_at, None);
+        assert!(progress.last_error.unwrap().contains("node down"));
+
+        syncer.record_outcome(&Ok(SyncReport::default()));
+        let progress = syncer.progress();
+        assert!(progress.last_completed_at.is_some_and(|at| at > 0));
+        assert_eq!(progress.last_error, None);
+    }
 }
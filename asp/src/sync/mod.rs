@@ -1 +1,4 @@
 pub mod events;
+mod source;
+
+pub use self::source::{EventSource, RpcEventSource};
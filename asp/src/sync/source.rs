@@ -0,0 +1,139 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use starknet::core::types::requests::{BlockNumberRequest, GetEventsRequest};
+use starknet::core::types::{EventFilter, EventFilterWithPage, EventsPage, ResultPageRequest};
+use starknet::providers::jsonrpc::HttpTransport;
+use starknet::providers::{JsonRpcClient, Provider, ProviderRequestData, ProviderResponseData};
+
+use crate::error::AspError;
+
+/// Chain reads performed by the event sync task. Each call is normally a
+/// single RPC round trip.
+#[async_trait::async_trait]
+pub trait EventSource: Send + Sync {
+    /// The latest block number.
+    async fn head(&self) -> Result<u64, AspError>;
+
+    /// The latest block number together with the first page of events
+    /// matching `filter`.
+    async fn head_and_events(
+        &self,
+        filter: EventFilter,
+        chunk_size: u64,
+    ) -> Result<(u64, EventsPage), AspError>;
+
+    /// A page of events matching `filter`; the first page when
+    /// `continuation_token` is `None`.
+    async fn events(
+        &self,
+        filter: EventFilter,
+        continuation_token: Option<String>,
+        chunk_size: u64,
+    ) -> Result<EventsPage, AspError>;
+}
+
+/// [`EventSource`] backed by a Starknet JSON-RPC node.
+///
+/// The block number probe and the first events page are sent as one JSON-RPC
+/// batch. If the node rejects the batch but answers the same calls made
+/// separately, batching is turned off for the rest of the process lifetime.
+pub struct RpcEventSource {
+    provider: JsonRpcClient<HttpTransport>,
+    batching: AtomicBool,
+}
+
+impl RpcEventSource {
+    pub fn new(provider: JsonRpcClient<HttpTransport>) -> Self {
+        RpcEventSource {
+            provider,
+            batching: AtomicBool::new(true),
+        }
+    }
+
+    async fn batched(
+        &self,
+        filter: EventFilter,
+        chunk_size: u64,
+    ) -> Result<(u64, EventsPage), AspError> {
+        let requests = [
+            ProviderRequestData::BlockNumber(BlockNumberRequest),
+            ProviderRequestData::GetEvents(GetEventsRequest {
+                filter: EventFilterWithPage {
+                    event_filter: filter,
+                    result_page_request: ResultPageRequest {
+                        continuation_token: None,
+                        chunk_size,
+                    },
+                },
+            }),
+        ];
+        let responses = self
+            .provider
+            .batch_requests(requests)
+            .await
+            .map_err(|e| AspError::RpcError(format!("batch request failed: {e}")))?;
+
+        let mut responses = responses.into_iter();
+        match (responses.next(), responses.next()) {
+            (
+                Some(ProviderResponseData::BlockNumber(head)),
+                Some(ProviderResponseData::GetEvents(page)),
+            ) => Ok((head, page)),
+            _ => Err(AspError::RpcError("unexpected batch response".into())),
+        }
+    }
+
+    async fn separate(
+        &self,
+        filter: EventFilter,
+        chunk_size: u64,
+    ) -> Result<(u64, EventsPage), AspError> {
+        let head = self.head().await?;
+        let page = self.events(filter, None, chunk_size).await?;
+        Ok((head, page))
+    }
+}
+
+#[async_trait::async_trait]
+impl EventSource for RpcEventSource {
+    async fn head(&self) -> Result<u64, AspError> {
+        self.provider
+            .block_number()
+            .await
+            .map_err(|e| AspError::RpcError(format!("block_number failed: {e}")))
+    }
+
+    async fn head_and_events(
+        &self,
+        filter: EventFilter,
+        chunk_size: u64,
+    ) -> Result<(u64, EventsPage), AspError> {
+        if !self.batching.load(Ordering::Relaxed) {
+            return self.separate(filter, chunk_size).await;
+        }
+
+        let batch_err = match self.batched(filter.clone(), chunk_size).await {
+            Ok(result) => return Ok(result),
+            Err(e) => e,
+        };
+        let result = self.separate(filter, chunk_size).await?;
+        self.batching.store(false, Ordering::Relaxed);
+        tracing::warn!(
+            error = %batch_err,
+            "RPC node rejected a batched request; using separate calls from now on"
+        );
+        Ok(result)
+    }
+
+    async fn events(
+        &self,
+        filter: EventFilter,
+        continuation_token: Option<String>,
+        chunk_size: u64,
+    ) -> Result<EventsPage, AspError> {
+        self.provider
+            .get_events(filter, continuation_token, chunk_size)
+            .await
+            .map_err(|e| AspError::RpcError(format!("get_events failed: {e}")))
+    }
+}
@@ -0,0 +1,105 @@
+use crate::db::Database;
+use crate::error::AspError;
+use crate::prover::Worker;
+
+/// Append an operation's output `commitments` to the database and the worker
+/// tree. The rows are written in one transaction before the worker is
+/// touched; if the worker then fails partway, the transaction has already
+/// landed on-chain, so the worker tree is rebuilt from the database instead
+/// of unwinding. Returns each leaf's index with the root after it.
+pub async fn append_leaves(
+    db: &Database,
+    worker: &mut Worker,
+    commitments: &[&str],
+    tx_hash: Option<&str>,
+) -> Result<Vec<(u32, String)>, AspError> {
+    let first = db.insert_commitments(commitments, tx_hash)?;
+
+    let mut inserted = Vec::with_capacity(commitments.len());
+    for (offset, commitment) in commitments.iter().enumerate() {
+        let leaf_index = first + offset as u32;
+        match worker.insert_leaf(commitment).await {
+            Ok(root) => {
+                tracing::debug!(leaf_index, "Inserted leaf");
+                inserted.push((leaf_index, root));
+            }
+            Err(e) => {
+                tracing::error!(
+                    leaf_index,
+                    error = %e,
+                    "Worker failed to insert a stored leaf, rebuilding its tree from the database"
+                );
+                let leaves: Vec<String> = db
+                    .get_all_commitments()?
+                    .into_iter()
+                    .map(|c| c.commitment)
+                    .collect();
+                let root = worker.build_tree(&leaves).await?;
+                let end = first + commitments.len() as u32;
+                inserted.extend((leaf_index..end).map(|index| (index, root.clone())));
+                break;
+            }
+        }
+    }
+    Ok(inserted)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn failed_insert_rebuilds_tree_from_database() {
+        let db = Database::new(":memory:").unwrap();
+        db.run_migrations().unwrap();
+        db.insert_commitment(0, "100", None).unwrap();
+
+        let (asp_stdin, fake_stdin) = tokio::io::duplex(4096);
+        let (mut fake_stdout, asp_stdout) = tokio::io::duplex(4096);
+        let mut worker = Worker::from_pipes(asp_stdout, asp_stdin);
+        let mut requests = BufReader::new(fake_stdin).lines();
+
+        // Accepts the first leaf, fails the second, then answers the rebuild
+        let fake = async {
+            let mut commands = Vec::new();
+            for reply in [
+                json!({"ok": true, "data": {"root": "r1"}}),
+                json!({"ok": false, "error": "tree write failed"}),
+                json!({"ok": true, "data": {"root": "rebuilt"}}),
+            ] {
+                let line = requests.next_line().await.unwrap().unwrap();
+                let request: Value = serde_json::from_str(&line).unwrap();
+                let mut reply = reply;
+                reply["id"] = request["id"].clone();
+                fake_stdout
+                    .write_all(format!("{reply}\n").as_bytes())
+                    .await
+                    .unwrap();
+                commands.push(request);
+            }
+            commands
+        };
+        let append = append_leaves(&db, &mut worker, &["201", "202", "203"], Some("0xmint"));
+        let (inserted, commands) = tokio::join!(append, fake);
+
+        assert_eq!(
+            inserted.unwrap(),
+            vec![
+                (1, "r1".to_string()),
+                (2, "rebuilt".to_string()),
+                (3, "rebuilt".to_string()),
+            ]
+        );
+        assert_eq!(commands[2]["command"], "build_tree");
+        assert_eq!(
+            commands[2]["params"]["leaves"],
+            json!(["100", "201", "202", "203"])
+        );
+        assert_eq!(db.get_leaf_count().unwrap(), 4);
+        let row = db.get_commitment(3).unwrap().unwrap();
+        assert_eq!(row.deposit_tx.as_deref(), Some("0xmint"));
+    }
+}
@@ -0,0 +1,154 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::api::handlers::deposit::decimal_to_hex;
+
+/// Payload POSTed to the configured webhook after a confirmed operation.
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookEvent {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub tx_hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leaf_index: Option<u32>,
+    /// Tree root after the operation, `0x`-prefixed hex.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    pub timestamp: u64,
+}
+
+impl WebhookEvent {
+    /// `root` is the decimal root from the tree; it is sent as hex, like the
+    /// root in API responses.
+    pub fn new(kind: &str, tx_hash: &str, leaf_index: Option<u32>, root: Option<&str>) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        WebhookEvent {
+            kind: kind.to_string(),
+            tx_hash: tx_hash.to_string(),
+            leaf_index,
+            root: root.map(decimal_to_hex),
+            timestamp,
+        }
+    }
+}
+
+/// Fire-and-forget webhook sender. Delivery runs on a background task with a
+/// bounded number of attempts; failures are logged and never reach the caller.
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    client: reqwest::Client,
+    url: String,
+    max_attempts: u32,
+    retry_delay: Duration,
+}
+
+impl WebhookDispatcher {
+    pub fn new(url: &str) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        WebhookDispatcher {
+            client,
+            url: url.to_string(),
+            max_attempts: 3,
+            retry_delay: Duration::from_secs(1),
+        }
+    }
+
+    /// Queue `event` for delivery and return immediately.
+    pub fn dispatch(&self, event: WebhookEvent) -> tokio::task::JoinHandle<bool> {
+        let dispatcher = self.clone();
+        tokio::spawn(async move { dispatcher.deliver(&event).await })
+    }
+
+    async fn deliver(&self, event: &WebhookEvent) -> bool {
+        for attempt in 1..=self.max_attempts {
+            let result = self.client.post(&self.url).json(event).send().await;
+            match result {
+                Ok(resp) if resp.status().is_success() => {
+                    tracing::debug!(kind = %event.kind, tx_hash = %event.tx_hash, "Webhook delivered");
+                    return true;
+                }
+                Ok(resp) => tracing::warn!(
+                    attempt = attempt,
+                    status = resp.status().as_u16(),
+                    kind = %event.kind,
+                    "Webhook rejected"
+                ),
+                Err(e) => tracing::warn!(
+                    attempt = attempt,
+                    error = %e,
+                    kind = %event.kind,
+                    "Webhook delivery failed"
+                ),
+            }
+            if attempt < self.max_attempts {
+                tokio::time::sleep(self.retry_delay).await;
+            }
+        }
+        tracing::error!(kind = %event.kind, tx_hash = %event.tx_hash, "Webhook dropped after retries");
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn posts_event_payload() {
+        let (tx, mut rx) = mpsc::channel::<serde_json::Value>(1);
+        let app = Router::new().route(
+            "/hook",
+            post(move |Json(body): Json<serde_json::Value>| {
+                let tx = tx.clone();
+                async move {
+                    tx.send(body).await.unwrap();
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let dispatcher = WebhookDispatcher::new(&format!("http://{addr}/hook"));
+        let event = WebhookEvent::new("deposit", "0xabc", Some(3), Some("123"));
+        assert!(dispatcher.dispatch(event).await.unwrap());
+
+        let body = rx.recv().await.unwrap();
+        assert_eq!(body["type"], "deposit");
+        assert_eq!(body["tx_hash"], "0xabc");
+        assert_eq!(body["leaf_index"], 3);
+        assert_eq!(body["root"], "0x7b");
+        assert!(body["timestamp"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn sends_root_as_hex_for_every_operation() {
+        for kind in ["deposit", "swap", "mint", "burn"] {
+            let event = WebhookEvent::new(kind, "0xabc", Some(3), Some("255"));
+            let value = serde_json::to_value(&event).unwrap();
+            assert_eq!(value["root"], "0xff", "{kind}");
+        }
+    }
+
+    #[tokio::test]
+    async fn omits_absent_fields_and_gives_up_quietly() {
+        let event = WebhookEvent::new("withdraw", "0xdef", None, None);
+        let value = serde_json::to_value(&event).unwrap();
+        assert!(value.get("leaf_index").is_none());
+        assert!(value.get("root").is_none());
+
+        let mut dispatcher = WebhookDispatcher::new("http://127.0.0.1:1/unreachable");
+        dispatcher.retry_delay = Duration::from_millis(1);
+        assert!(!dispatcher.dispatch(event).await.unwrap());
+    }
+}
@@ -1,64 +1,58 @@
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum_test::TestServer;
 use serde_json::json;
+use sha2::Digest;
+use starknet::core::types::{EmittedEvent, EventFilter, EventsPage, Felt};
+use starknet::core::utils::get_selector_from_name;
+use starknet::signers::SigningKey;
 use tokio::sync::Mutex;
 
+use zylith_asp::api::handlers::deposit::decimal_to_hex;
 use zylith_asp::api::routes::create_test_router;
 use zylith_asp::config::Config;
 use zylith_asp::db::Database;
 use zylith_asp::error::AspError;
-use zylith_asp::prover::Worker;
-use zylith_asp::relayer::{PoolKeyParams, Relayer};
+use zylith_asp::logging::LogFormat;
+use zylith_asp::prover::{
+    CircuitType, DomainSeparators, ProofSlots, Worker, WorkerRuntime, SELF_TEST_LEAVES,
+};
+use zylith_asp::relayer::{
+    MockOutcome, MockRelayer, RecordedCall, Relayer, RelayerMethod, StarknetRelayer,
+    StarknetRelayerBuilder, SwapQuote,
+};
+use zylith_asp::reload::{LiveConfig, RuntimeConfig};
+use zylith_asp::stream::TreeEvent;
+use zylith_asp::sync::events::{submit_root_if_changed, Syncer, TREE_DIVERGED_KEY};
+use zylith_asp::sync::EventSource;
 use zylith_asp::AppState;
 
 // ---------------------------------------------------------------------------
-// MockRelayer — returns configurable tx hashes, no real Starknet calls
+// Relayers — scripted `MockRelayer`s, no real Starknet calls
 // ---------------------------------------------------------------------------
 
-struct MockRelayer;
-
-#[async_trait::async_trait]
-impl Relayer for MockRelayer {
-    async fn deposit(&self, _commitment: &str) -> Result<String, AspError> {
-        Ok("0xmock_deposit_tx".into())
-    }
-
-    async fn submit_merkle_root(&self, _root: &str) -> Result<String, AspError> {
-        Ok("0xmock_root_tx".into())
-    }
-
-    async fn verify_membership(&self, _calldata: &[String]) -> Result<String, AspError> {
-        Ok("0xmock_membership_tx".into())
-    }
-
-    async fn shielded_swap(
-        &self,
-        _pool_key: &PoolKeyParams,
-        _calldata: &[String],
-        _sqrt_price_limit: &str,
-    ) -> Result<String, AspError> {
-        Ok("0xmock_swap_tx".into())
-    }
-
-    async fn shielded_mint(
-        &self,
-        _pool_key: &PoolKeyParams,
-        _calldata: &[String],
-        _liquidity: u128,
-    ) -> Result<String, AspError> {
-        Ok("0xmock_mint_tx".into())
-    }
+/// Relays deposits fine but fails every root submission.
+fn failing_root_relayer() -> MockRelayer {
+    MockRelayer::new().with_outcome(
+        RelayerMethod::SubmitMerkleRoot,
+        MockOutcome::Fail("root submission dropped".into()),
+    )
+}
 
-    async fn shielded_burn(
-        &self,
-        _pool_key: &PoolKeyParams,
-        _calldata: &[String],
-        _liquidity: u128,
-    ) -> Result<String, AspError> {
-        Ok("0xmock_burn_tx".into())
+/// Relays deposits and roots, but every spend reverts on-chain.
+fn reverting_relayer() -> MockRelayer {
+    let relayer = MockRelayer::new();
+    for method in [
+        RelayerMethod::VerifyMembership,
+        RelayerMethod::ShieldedSwap,
+        RelayerMethod::ShieldedMint,
+        RelayerMethod::ShieldedBurn,
+    ] {
+        relayer.set_outcome(method, MockOutcome::Revert("Nullifier already spent".into()));
     }
+    relayer
 }
 
 // ---------------------------------------------------------------------------
@@ -75,34 +69,133 @@ fn test_config() -> Config {
     Config {
         host: "127.0.0.1".into(),
         port: 0,
+        http2_enabled: true,
+        keepalive_secs: 60,
+        max_concurrent_streams: 200,
         rpc_url: "http://localhost:1234".into(),
+        chain_id: "SN_SEPOLIA".into(),
         admin_address: "0x1234".into(),
         keystore_path: "/dev/null".into(),
         keystore_password: "test".into(),
+        admin_public_key: None,
+        backup_admin_addresses: vec![],
         coordinator_address: "0xcoordinator".into(),
         pool_address: "0xpool".into(),
+        pools: Default::default(),
         database_path: ":memory:".into(),
+        database_pool_size: 1,
+        database_synchronous: "NORMAL".into(),
+        database_foreign_keys: true,
+        database_busy_timeout_ms: 5000,
         worker_path: worker_path(),
+        worker_runtime: std::env::var("WORKER_RUNTIME").unwrap_or_else(|_| "node".into()),
+        worker_runtime_args: Vec::new(),
+        expected_circuit_hashes: Default::default(),
+        commitment_cache_size: 0,
+        worker_warmup: false,
+        max_proof_jobs: 4,
+        commitment_domains: Default::default(),
+        expected_self_test_root: None,
+        tree_height: 20,
+        allow_leaf_gaps: false,
+        root_history_size: 100,
         sync_poll_interval_secs: 9999,
+        sync_request_delay_ms: 0,
+        sync_start_block: 0,
+        max_block_range: 1000,
+        relayer_max_attempts: 1,
+        relayer_retry_base_ms: 0,
+        fee_multiplier: 1.5,
+        max_fee_fri: None,
+        l1_gas_max_amount: None,
+        l1_gas_max_price: None,
+        l2_gas_max_amount: None,
+        l2_gas_max_price: None,
+        max_calldata_felts: 4000,
+        log_level: "info".into(),
+        log_format: LogFormat::Text,
+        webhook_url: None,
+        admin_api_token: None,
+        allowed_origins: Vec::new(),
+        allowed_denominations: Vec::new(),
+        deposit_queue: false,
+        min_withdraw_delay_secs: 0,
+        max_body_bytes: 2 * 1024 * 1024,
+        request_timeout_secs: 30,
+        proof_request_timeout_secs: 300,
+        trust_forwarded_for: false,
+        auto_ban_threshold: 0,
+        auto_ban_window_secs: 60,
+        status_cache_ms: 0,
+        pretty_json: false,
     }
 }
 
 async fn create_test_state() -> Arc<AppState> {
-    let config = test_config();
+    create_test_state_with_relayer(Box::new(MockRelayer::new())).await
+}
+
+async fn create_test_state_with_relayer(relayer: Box<dyn Relayer>) -> Arc<AppState> {
+    create_test_state_with(test_config(), relayer).await
+}
+
+async fn create_test_state_with(config: Config, relayer: Box<dyn Relayer>) -> Arc<AppState> {
+    create_test_state_with_syncer(config, Some(relayer), None).await
+}
+
+/// A proof-only node: no relayer, so nothing is ever submitted on-chain.
+async fn create_test_state_without_relayer() -> Arc<AppState> {
+    create_test_state_without_relayer_with(test_config()).await
+}
+
+async fn create_test_state_without_relayer_with(config: Config) -> Arc<AppState> {
+    create_test_state_with_syncer(config, None, None).await
+}
 
+async fn create_test_state_with_syncer(
+    config: Config,
+    relayer: Option<Box<dyn Relayer>>,
+    syncer: Option<Syncer>,
+) -> Arc<AppState> {
     let db = Database::new(":memory:").unwrap();
     db.run_migrations().unwrap();
 
-    let worker = Worker::spawn(&config.worker_path)
-        .await
-        .expect("Failed to spawn worker — is bun installed and worker/node_modules present?");
+    let runtime = WorkerRuntime::from_config(&config);
+    let mut worker = Worker::spawn(
+        &runtime,
+        &config.worker_path,
+        config.tree_height,
+        &config.commitment_domains,
+    )
+    .await
+    .expect("Failed to spawn worker — is WORKER_RUNTIME installed and worker deps present?")
+    .with_commitment_cache_size(config.commitment_cache_size);
+    let worker_info = worker
+        .startup_info(&config.expected_circuit_hashes, config.worker_warmup)
+        .await;
+    let empty_root = worker.empty_root().await.unwrap();
 
-    Arc::new(AppState {
+    let state = Arc::new(AppState {
+        runtime: Arc::new(LiveConfig::new(RuntimeConfig::from_config(&config))),
+        proof_slots: ProofSlots::new(config.max_proof_jobs),
         config,
         db,
         worker: Mutex::new(worker),
-        relayer: Some(Mutex::new(Box::new(MockRelayer) as Box<dyn Relayer>)),
-    })
+        tree_writes: Default::default(),
+        worker_info,
+        tree: Default::default(),
+        empty_root,
+        relayer: relayer.map(Mutex::new),
+        syncer,
+        webhook: None,
+        events: Default::default(),
+        deposit_queue: Default::default(),
+        operations: Default::default(),
+        bans: Default::default(),
+        status: Default::default(),
+    });
+    state.refresh_tree().unwrap();
+    state
 }
 
 async fn create_test_server() -> TestServer {
@@ -132,6 +225,28 @@ async fn test_deposit_success() {
     assert!(body["root"].as_str().unwrap().starts_with("0x"));
 }
 
+#[tokio::test]
+async fn test_deposit_without_relayer_is_local_only() {
+    let state = create_test_state_without_relayer().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let resp = server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x1234"}))
+        .await;
+
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["status"], "local");
+    assert_eq!(body["leaf_index"], 0);
+    assert_eq!(state.db.get_leaf_count().unwrap(), 1);
+
+    let status: serde_json::Value = server.get("/commitment/0x1234").await.json();
+    assert_eq!(status["status"], "local");
+    assert!(status["deposit_tx"].is_null());
+    assert!(status["root_tx"].is_null());
+}
+
 #[tokio::test]
 async fn test_deposit_invalid_hex() {
     let server = create_test_server().await;
@@ -183,122 +298,3022 @@ async fn test_deposit_two_sequential() {
     assert_ne!(root1, root2);
 }
 
-// ---------------------------------------------------------------------------
-// Tree tests
-// ---------------------------------------------------------------------------
+#[tokio::test]
+async fn test_deposit_rolled_back_when_root_submission_fails() {
+    let state = create_test_state_with_relayer(Box::new(failing_root_relayer())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let resp = server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x1234"}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::BAD_GATEWAY);
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["outcome"], "failed");
+
+    // Neither the DB nor the tree keeps the orphaned leaf
+    assert_eq!(state.db.get_leaf_count().unwrap(), 0);
+    assert!(state.db.get_latest_root().unwrap().is_none());
+    let body: serde_json::Value = server.get("/tree/root").await.json();
+    assert_eq!(body["leaf_count"], 0);
+}
 
 #[tokio::test]
-async fn test_tree_root_empty() {
-    let server = create_test_server().await;
+async fn test_deposit_retry_after_failed_submission_reuses_leaf() {
+    let relayer = MockRelayer::new();
+    relayer.queue_outcome(RelayerMethod::Deposit, MockOutcome::Fail("nonce too old".into()));
+    let state = create_test_state_with_relayer(Box::new(relayer.clone())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
 
-    let resp = server.get("/tree/root").await;
+    let request = json!({"commitment": "0x1234"});
+    server
+        .post("/deposit")
+        .json(&request)
+        .await
+        .assert_status(axum::http::StatusCode::BAD_GATEWAY);
+    // Nothing reached the root submission
+    relayer.assert_not_called(RelayerMethod::SubmitMerkleRoot);
+
+    let resp = server.post("/deposit").json(&request).await;
     resp.assert_status_ok();
     let body: serde_json::Value = resp.json();
-    assert_eq!(body["leaf_count"], 0);
-    assert_eq!(body["root"], "0");
+    assert_eq!(body["leaf_index"], 0);
+
+    relayer.assert_called(RelayerMethod::Deposit, 2);
+    relayer.assert_called(RelayerMethod::SubmitMerkleRoot, 1);
+    assert_eq!(
+        relayer.calls_to(RelayerMethod::Deposit)[1],
+        RecordedCall::Deposit {
+            commitment: "4660".into() // 0x1234, relayed in decimal
+        }
+    );
 }
 
 #[tokio::test]
-async fn test_tree_root_after_deposit() {
+async fn test_concurrent_deposits_with_failed_relay_keep_tree_contiguous() {
+    let relayer = MockRelayer::new();
+    // Whichever deposit is relayed first fails and is rolled back
+    relayer.queue_outcome(RelayerMethod::Deposit, MockOutcome::Fail("nonce too old".into()));
+    let state = create_test_state_with_relayer(Box::new(relayer.clone())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let first = server.post("/deposit").json(&json!({"commitment": "0x1111"}));
+    let second = server.post("/deposit").json(&json!({"commitment": "0x2222"}));
+    let (first, second) = tokio::join!(first, second);
+    let (failed, succeeded) = if first.status_code().is_success() {
+        (second, first)
+    } else {
+        (first, second)
+    };
+    failed.assert_status(axum::http::StatusCode::BAD_GATEWAY);
+    succeeded.assert_status_ok();
+    let body: serde_json::Value = succeeded.json();
+    assert_eq!(body["leaf_index"], 0);
+
+    // The surviving leaf sits at index 0 and the tree matches the database
+    let leaves: Vec<String> = state
+        .db
+        .get_all_commitments()
+        .unwrap()
+        .into_iter()
+        .map(|c| c.commitment)
+        .collect();
+    assert_eq!(leaves.len(), 1);
+    assert_eq!(state.db.get_commitment(0).unwrap().unwrap().commitment, leaves[0]);
+    let mut worker = state.worker.lock().await;
+    let worker_root = worker.get_root().await.unwrap();
+    assert_eq!(worker.compute_root(&leaves).await.unwrap(), worker_root);
+    drop(worker);
+    assert_eq!(state.db.get_latest_root().unwrap(), Some(worker_root));
+    relayer.assert_called(RelayerMethod::Deposit, 2);
+    relayer.assert_called(RelayerMethod::SubmitMerkleRoot, 1);
+}
+
+#[tokio::test]
+async fn test_deposit_records_token_and_amount() {
     let state = create_test_state().await;
     let server = TestServer::new(create_test_router(state.clone())).unwrap();
 
-    // Deposit first
     server
         .post("/deposit")
-        .json(&json!({"commitment": "0x1234"}))
+        .json(&json!({
+            "commitment": "0xdead",
+            "token": "0x00AB",
+            "amount_low": "1000",
+            "amount_high": "0"
+        }))
         .await
         .assert_status_ok();
 
-    let resp = server.get("/tree/root").await;
-    resp.assert_status_ok();
-    let body: serde_json::Value = resp.json();
-    assert_eq!(body["leaf_count"], 1);
-    // Root should be a real value (stored from deposit)
-    assert_ne!(body["root"], "0");
+    let metadata = state.db.get_deposit_metadata(0).unwrap().unwrap();
+    assert_eq!(metadata.token.as_deref(), Some("0xab"));
+    assert_eq!(metadata.amount_low.as_deref(), Some("1000"));
+    assert_eq!(metadata.amount_high.as_deref(), Some("0"));
 }
 
 #[tokio::test]
-async fn test_tree_path_success() {
+async fn test_deposit_rejects_invalid_metadata() {
+    let server = create_test_server().await;
+
+    // Amount limbs must come together
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0xdead", "amount_low": "1000"}))
+        .await
+        .assert_status_bad_request();
+
+    // amount_low must fit in u128
+    server
+        .post("/deposit")
+        .json(&json!({
+            "commitment": "0xdead",
+            "amount_low": "340282366920938463463374607431768211456",
+            "amount_high": "0"
+        }))
+        .await
+        .assert_status_bad_request();
+
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0xdead", "token": "not-an-address"}))
+        .await
+        .assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_deposit_enforces_allowed_denominations() {
+    let config = Config {
+        allowed_denominations: vec![1000u32.into(), 5000u32.into()],
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let note = |amount: &str| {
+        json!({
+            "secret": "2", "nullifier": "1",
+            "amount_low": amount, "amount_high": "0", "token": "0x1"
+        })
+    };
+    let commitment_of = |amount: &str| {
+        let state = state.clone();
+        let amount = amount.to_string();
+        async move {
+            let result = state
+                .worker
+                .lock()
+                .await
+                .compute_commitment("2", "1", &amount, "0", "0x1")
+                .await
+                .unwrap();
+            decimal_to_hex(&result.commitment)
+        }
+    };
+
+    // Allowed denomination with a matching commitment
+    let mut body = note("5000");
+    body["commitment"] = json!(commitment_of("5000").await);
+    server.post("/deposit").json(&body).await.assert_status_ok();
+    assert_eq!(state.db.get_leaf_count().unwrap(), 1);
+
+    // Amount outside the allowed set
+    let mut body = note("1234");
+    body["commitment"] = json!(commitment_of("1234").await);
+    let resp = server.post("/deposit").json(&body).await;
+    resp.assert_status_bad_request();
+    let error: serde_json::Value = resp.json();
+    assert!(error["error"].as_str().unwrap().contains("denomination"));
+
+    // Declared amount allowed, but the commitment hides a different one
+    let mut body = note("1000");
+    body["commitment"] = json!(commitment_of("1234").await);
+    server.post("/deposit").json(&body).await.assert_status_bad_request();
+
+    // A bare commitment cannot prove its amount
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": commitment_of("1000").await}))
+        .await
+        .assert_status_bad_request();
+
+    assert_eq!(state.db.get_leaf_count().unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_withdraw_rejects_note_not_matching_deposit() {
     let state = create_test_state().await;
     let server = TestServer::new(create_test_router(state.clone())).unwrap();
 
-    // Deposit first
+    let note = state
+        .worker
+        .lock()
+        .await
+        .compute_commitment("111", "222", "1000", "0", "0x1")
+        .await
+        .unwrap();
+
+    // Declared balance disagrees with the committed one
     server
         .post("/deposit")
-        .json(&json!({"commitment": "0x5678"}))
+        .json(&json!({
+            "commitment": decimal_to_hex(&note.commitment),
+            "token": "0x1",
+            "amount_low": "999",
+            "amount_high": "0"
+        }))
         .await
         .assert_status_ok();
 
-    let resp = server.get("/tree/path/0").await;
-    resp.assert_status_ok();
+    let resp = server
+        .post("/withdraw")
+        .json(&json!({
+            "secret": "111", "nullifier": "222",
+            "amount_low": "1000", "amount_high": "0",
+            "token": "0x1", "recipient": "0x5", "leaf_index": 0
+        }))
+        .await;
+    resp.assert_status_bad_request();
     let body: serde_json::Value = resp.json();
-    assert_eq!(body["leaf_index"], 0);
-    // LeanIMT with height 20 gives 20 path elements
-    let path_elements = body["path_elements"].as_array().unwrap();
-    assert_eq!(path_elements.len(), 20);
-    let path_indices = body["path_indices"].as_array().unwrap();
-    assert_eq!(path_indices.len(), 20);
+    assert!(body["error"].as_str().unwrap().contains("does not match deposit"));
+    assert!(!state.db.is_nullifier_spent(&note.nullifier_hash).unwrap());
 }
 
 #[tokio::test]
-async fn test_tree_path_not_found() {
-    let server = create_test_server().await;
+async fn test_deposit_to_paused_coordinator_is_typed() {
+    let relayer = MockRelayer::new().with_outcome(
+        RelayerMethod::Deposit,
+        MockOutcome::Revert("Failure reason: 'VERIFIER: contract paused'.".into()),
+    );
+    let state = create_test_state_with_relayer(Box::new(relayer)).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
 
-    let resp = server.get("/tree/path/99").await;
-    resp.assert_status_not_found();
+    let resp = server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x1234"}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["code"], "coordinator_paused");
+    assert_eq!(body["outcome"], "reverted");
+    assert!(body["revert_reason"].as_str().unwrap().contains("contract paused"));
+    // The refused leaf was rolled back
+    assert_eq!(state.db.get_leaf_count().unwrap(), 0);
 }
 
-// ---------------------------------------------------------------------------
-// Nullifier tests
-// ---------------------------------------------------------------------------
+#[tokio::test]
+async fn test_withdraw_refused_before_min_delay() {
+    let config = Config {
+        min_withdraw_delay_secs: 3600,
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+    let (leaf_index, nullifier_hash) =
+        deposit_note(&state, &server, "111", "222", "1000", "0x1").await;
+
+    let resp = server
+        .post("/withdraw")
+        .json(&json!({
+            "secret": "111", "nullifier": "222",
+            "amount_low": "1000", "amount_high": "0",
+            "token": "0x1", "recipient": "0x5", "leaf_index": leaf_index
+        }))
+        .await;
+    resp.assert_status(axum::http::StatusCode::FORBIDDEN);
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["code"], "withdraw_too_early");
+    let retry_after: u64 = resp.header("retry-after").to_str().unwrap().parse().unwrap();
+    assert!(retry_after > 3500 && retry_after <= 3600, "{retry_after}");
+    assert!(!state.db.is_nullifier_spent(&nullifier_hash).unwrap());
+}
 
 #[tokio::test]
-async fn test_nullifier_not_spent() {
-    let server = create_test_server().await;
+async fn test_withdraw_requires_signature_for_keyed_note() {
+    use ed25519_dalek::{Signer, SigningKey};
+    use zylith_asp::api::signature::canonical_json;
 
-    let resp = server.get("/nullifier/12345").await;
-    resp.assert_status_ok();
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+    let key = SigningKey::from_bytes(&[7u8; 32]);
+
+    let note = state
+        .worker
+        .lock()
+        .await
+        .compute_commitment("111", "222", "1000", "0", "0x1")
+        .await
+        .unwrap();
+
+    // The declared amount disagrees with the note, so a request that gets past
+    // the signature check stops at the deposit cross-check instead of proving.
+    server
+        .post("/deposit")
+        .json(&json!({
+            "commitment": decimal_to_hex(&note.commitment),
+            "token": "0x1",
+            "amount_low": "999",
+            "amount_high": "0",
+            "auth_pubkey": hex::encode(key.verifying_key().to_bytes())
+        }))
+        .await
+        .assert_status_ok();
+
+    let mut request = json!({
+        "secret": "111", "nullifier": "222",
+        "amount_low": "1000", "amount_high": "0",
+        "token": "0x1", "recipient": "0x5", "leaf_index": 0
+    });
+    let signature = hex::encode(key.sign(canonical_json(&request).as_bytes()).to_bytes());
+
+    // Unsigned
+    server
+        .post("/withdraw")
+        .json(&request)
+        .await
+        .assert_status(axum::http::StatusCode::UNAUTHORIZED);
+
+    // Signed, then tampered with
+    let mut tampered = request.clone();
+    tampered["recipient"] = json!("0x6");
+    tampered["signature"] = json!(signature);
+    server
+        .post("/withdraw")
+        .json(&tampered)
+        .await
+        .assert_status(axum::http::StatusCode::UNAUTHORIZED);
+
+    request["signature"] = json!(signature);
+    let resp = server.post("/withdraw").json(&request).await;
+    resp.assert_status_bad_request();
     let body: serde_json::Value = resp.json();
-    assert_eq!(body["spent"], false);
-    assert!(body["circuit_type"].is_null());
+    assert!(body["error"].as_str().unwrap().contains("does not match deposit"));
+    assert!(!state.db.is_nullifier_spent(&note.nullifier_hash).unwrap());
 }
 
 #[tokio::test]
-async fn test_nullifier_spent() {
+async fn test_withdraw_prove_requires_signature_for_keyed_note() {
+    use ed25519_dalek::{Signer, SigningKey};
+    use zylith_asp::api::signature::canonical_json;
+
     let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+    let key = SigningKey::from_bytes(&[7u8; 32]);
 
-    // Insert nullifier directly into DB
-    state
-        .db
-        .insert_nullifier("12345", "membership", Some("0xabc"))
+    let note = state
+        .worker
+        .lock()
+        .await
+        .compute_commitment("111", "222", "1000", "0", "0x1")
+        .await
         .unwrap();
+    // As above, a mismatched amount stops a signed request before proving
+    server
+        .post("/deposit")
+        .json(&json!({
+            "commitment": decimal_to_hex(&note.commitment),
+            "token": "0x1",
+            "amount_low": "999",
+            "amount_high": "0",
+            "auth_pubkey": hex::encode(key.verifying_key().to_bytes())
+        }))
+        .await
+        .assert_status_ok();
 
-    let server = TestServer::new(create_test_router(state)).unwrap();
+    let mut request = json!({
+        "secret": "111", "nullifier": "222",
+        "amount_low": "1000", "amount_high": "0",
+        "token": "0x1", "recipient": "0x5", "leaf_index": 0
+    });
+    let resp = server.post("/withdraw/prove").json(&request).await;
+    resp.assert_status(axum::http::StatusCode::UNAUTHORIZED);
+    let body: serde_json::Value = resp.json();
+    assert!(body["proof"].is_null() && body["calldata"].is_null());
 
-    let resp = server.get("/nullifier/12345").await;
+    request["signature"] =
+        json!(hex::encode(key.sign(canonical_json(&request).as_bytes()).to_bytes()));
+    let resp = server.post("/withdraw/prove").json(&request).await;
+    resp.assert_status_bad_request();
+    let body: serde_json::Value = resp.json();
+    assert!(body["error"].as_str().unwrap().contains("does not match deposit"));
+}
+
+#[tokio::test]
+async fn test_deposit_rejects_invalid_auth_pubkey() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x1234", "auth_pubkey": "0x1234"}))
+        .await
+        .assert_status_bad_request();
+    assert_eq!(state.db.get_leaf_count().unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_deposit_rejected_when_tree_full() {
+    let config = Config {
+        tree_height: 2,
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    // Height 2 holds exactly 4 leaves
+    for i in 1..=4 {
+        server
+            .post("/deposit")
+            .json(&json!({"commitment": format!("0x{i:x}")}))
+            .await
+            .assert_status_ok();
+    }
+
+    let resp = server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x5"}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["error"], "Merkle tree is full");
+    assert_eq!(state.db.get_leaf_count().unwrap(), 4);
+}
+
+#[tokio::test]
+async fn test_queued_deposit_returns_before_relaying() {
+    let config = Config {
+        deposit_queue: true,
+        ..test_config()
+    };
+    let relayer = MockRelayer::new();
+    let state = create_test_state_with(config, Box::new(relayer.clone())).await;
+    tokio::spawn(zylith_asp::deposit_queue::run_submitter(state.clone()));
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    // Hold the relayer so nothing can reach the chain while the handler runs
+    let busy = state.relayer.as_ref().unwrap().lock().await;
+    let resp = tokio::time::timeout(
+        Duration::from_secs(5),
+        server.post("/deposit").json(&json!({"commitment": "0xdead"})),
+    )
+    .await
+    .expect("queued deposit must not wait for the relayer");
     resp.assert_status_ok();
     let body: serde_json::Value = resp.json();
-    assert_eq!(body["spent"], true);
-    assert_eq!(body["circuit_type"], "membership");
-    assert_eq!(body["tx_hash"], "0xabc");
+    assert_eq!(body["status"], "pending");
+    assert_eq!(body["leaf_index"], 0);
+
+    let status: serde_json::Value = server.get("/commitment/0xdead").await.json();
+    assert_eq!(status["status"], "pending");
+    assert!(status["deposit_tx"].is_null());
+    relayer.assert_not_called(RelayerMethod::Deposit);
+    drop(busy);
+
+    // The submitter picks it up once the relayer is free
+    let mut status = serde_json::Value::Null;
+    for _ in 0..100 {
+        status = server.get("/commitment/57005").await.json();
+        if status["status"] != "pending" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(status["status"], "confirmed");
+    assert_eq!(status["deposit_tx"], "0xmock_deposit_tx");
+    assert_eq!(status["root_tx"], "0xmock_root_tx");
+    relayer.assert_called(RelayerMethod::Deposit, 1);
+
+    server
+        .get("/commitment/0xbeef")
+        .await
+        .assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_failed_queued_deposit_is_retried_before_later_ones() {
+    let config = Config {
+        deposit_queue: true,
+        ..test_config()
+    };
+    let relayer = MockRelayer::new();
+    relayer.queue_outcome(RelayerMethod::Deposit, MockOutcome::RpcError("rpc down".into()));
+    relayer.queue_outcome(
+        RelayerMethod::SubmitMerkleRoot,
+        MockOutcome::RpcError("rpc down".into()),
+    );
+    let state = create_test_state_with(config, Box::new(relayer.clone())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    for commitment in ["0xa1", "0xa2"] {
+        server
+            .post("/deposit")
+            .json(&json!({"commitment": commitment}))
+            .await
+            .assert_status_ok();
+    }
+    tokio::spawn(zylith_asp::deposit_queue::run_submitter(state.clone()));
+
+    let mut statuses = Vec::new();
+    for _ in 0..250 {
+        statuses.clear();
+        for commitment in ["0xa1", "0xa2"] {
+            let status: serde_json::Value =
+                server.get(&format!("/commitment/{commitment}")).await.json();
+            assert_ne!(status["status"], "failed");
+            statuses.push(status["status"].as_str().unwrap().to_string());
+        }
+        if statuses.iter().all(|s| s == "confirmed") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(statuses, ["confirmed", "confirmed"]);
+
+    // 0xa1 is retried, without re-sending its landed deposit once its root
+    // fails, before 0xa2 is sent at all
+    let commitments: Vec<RecordedCall> = ["161", "161", "162"]
+        .into_iter()
+        .map(|commitment| RecordedCall::Deposit {
+            commitment: commitment.to_string(),
+        })
+        .collect();
+    assert_eq!(relayer.calls_to(RelayerMethod::Deposit), commitments);
+    let order: Vec<RelayerMethod> = relayer.calls().iter().map(RecordedCall::method).collect();
+    assert_eq!(
+        order,
+        [
+            RelayerMethod::Deposit,
+            RelayerMethod::Deposit,
+            RelayerMethod::SubmitMerkleRoot,
+            RelayerMethod::SubmitMerkleRoot,
+            RelayerMethod::Deposit,
+            RelayerMethod::SubmitMerkleRoot,
+        ]
+    );
+    assert_eq!(state.db.get_leaf_count().unwrap(), 2);
 }
 
 // ---------------------------------------------------------------------------
-// Status tests
+// Dry-run proving tests
 // ---------------------------------------------------------------------------
 
-#[tokio::test]
-async fn test_status_healthy() {
-    let server = create_test_server().await;
+/// Deposit a note with known secrets and return `(leaf_index, nullifier_hash)`.
+async fn deposit_note(
+    state: &Arc<AppState>,
+    server: &TestServer,
+    secret: &str,
+    nullifier: &str,
+    amount_low: &str,
+    token: &str,
+) -> (u32, String) {
+    let note = state
+        .worker
+        .lock()
+        .await
+        .compute_commitment(secret, nullifier, amount_low, "0", token)
+        .await
+        .unwrap();
 
-    let resp = server.get("/status").await;
+    let resp = server
+        .post("/deposit")
+        .json(&json!({"commitment": decimal_to_hex(&note.commitment)}))
+        .await;
     resp.assert_status_ok();
     let body: serde_json::Value = resp.json();
-    assert_eq!(body["healthy"], true);
-    assert!(body["version"].as_str().unwrap().len() > 0);
-    assert_eq!(body["contracts"]["coordinator"], "0xcoordinator");
-    assert_eq!(body["contracts"]["pool"], "0xpool");
+    (body["leaf_index"].as_u64().unwrap() as u32, note.nullifier_hash)
+}
+
+fn swap_request(leaf_index: u32) -> serde_json::Value {
+    json!({
+        "pool_key": {"token_0": "0x1", "token_1": "0x2", "fee": 3000, "tick_spacing": 60},
+        "input_note": {
+            "secret": "111", "nullifier": "222",
+            "balance_low": "1000", "balance_high": "0",
+            "token": "0x1", "leaf_index": leaf_index
+        },
+        "swap_params": {
+            "token_in": "0x1", "token_out": "0x2",
+            "amount_in": "400", "amount_out_min": "390",
+            "amount_out_low": "395", "amount_out_high": "0"
+        },
+        "output_note": {"secret": "333", "nullifier": "444"},
+        "change_note": {"secret": "555", "nullifier": "666"},
+        "sqrt_price_limit": "0x100000000000000000000000000000000"
+    })
+}
+
+#[tokio::test]
+async fn test_mint_and_burn_reject_mismatched_liquidity() {
+    let server = create_test_server().await;
+    let pool_key = json!({"token_0": "0x1", "token_1": "0x2", "fee": 3000, "tick_spacing": 60});
+    let note = |leaf_index: u32| {
+        json!({
+            "secret": "1", "nullifier": "2", "balance_low": "1000", "balance_high": "0",
+            "token": "0x1", "leaf_index": leaf_index
+        })
+    };
+    let output = json!({
+        "secret": "3", "nullifier": "4", "amount_low": "0", "amount_high": "0", "token": "0x1"
+    });
+
+    // The circuit would prove 5000 while the contract mints 4000
+    let mint = json!({
+        "pool_key": pool_key,
+        "input_note_0": note(0),
+        "input_note_1": note(1),
+        "position": {
+            "secret": "5", "nullifier": "6",
+            "tick_lower": -60, "tick_upper": 60, "liquidity": "5000"
+        },
+        "amounts": {
+            "amount0_low": "1", "amount0_high": "0", "amount1_low": "1", "amount1_high": "0"
+        },
+        "change_note_0": {"secret": "7", "nullifier": "8"},
+        "change_note_1": {"secret": "9", "nullifier": "10"},
+        "liquidity": 4000
+    });
+    let burn = json!({
+        "pool_key": pool_key,
+        "position_note": {
+            "secret": "5", "nullifier": "6",
+            "tick_lower": -60, "tick_upper": 60, "liquidity": "5000", "leaf_index": 0
+        },
+        "output_note_0": output,
+        "output_note_1": output,
+        "liquidity": 4000
+    });
+
+    for (path, body, field) in [
+        ("/mint/prove", &mint, "position.liquidity"),
+        ("/mint", &mint, "position.liquidity"),
+        ("/burn/prove", &burn, "position_note.liquidity"),
+        ("/burn", &burn, "position_note.liquidity"),
+    ] {
+        let resp = server.post(path).json(body).await;
+        resp.assert_status_bad_request();
+        let error: serde_json::Value = resp.json();
+        let message = error["error"].as_str().unwrap();
+        assert!(message.contains(field) && message.contains("4000"), "{path}: {message}");
+    }
+}
+
+#[tokio::test]
+async fn test_withdraw_dry_run_verifies_proof() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let (leaf_index, nullifier_hash) =
+        deposit_note(&state, &server, "111", "222", "1000", "0x1").await;
+
+    let resp = server
+        .post("/withdraw/prove?verify=true")
+        .json(&json!({
+            "secret": "111", "nullifier": "222",
+            "amount_low": "1000", "amount_high": "0",
+            "token": "0x1", "recipient": "0x5", "leaf_index": leaf_index
+        }))
+        .await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["status"], "proved");
+    assert_eq!(body["nullifier_hash"], nullifier_hash);
+    assert_eq!(body["root"], state.db.get_latest_root().unwrap().unwrap());
+    assert_eq!(body["verified"], true);
+    assert!(!state.db.is_nullifier_spent(&nullifier_hash).unwrap());
+
+    // Changing a public signal must break the proof
+    let proof = body["proof"].clone();
+    let mut public_signals: Vec<String> =
+        serde_json::from_value(body["public_signals"].clone()).unwrap();
+    let mut worker = state.worker.lock().await;
+    assert!(worker
+        .verify_proof(CircuitType::Membership, &proof, &public_signals)
+        .await
+        .unwrap());
+    public_signals[0] = "12345".into();
+    assert!(!worker
+        .verify_proof(CircuitType::Membership, &proof, &public_signals)
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn test_swap_dry_run_records_nothing() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let (leaf_index, nullifier_hash) =
+        deposit_note(&state, &server, "111", "222", "1000", "0x1").await;
+
+    let resp = server.post("/swap/prove").json(&swap_request(leaf_index)).await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["status"], "proved");
+    assert_eq!(body["circuit"], "swap");
+    assert!(!body["calldata"].as_array().unwrap().is_empty());
+
+    // No nullifier spent, no leaves added
+    assert!(!state.db.is_nullifier_spent(&nullifier_hash).unwrap());
+    assert_eq!(state.db.get_leaf_count().unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_self_submitted_swap_skips_relayer() {
+    let relayer = MockRelayer::new();
+    let config = Config {
+        pool_address: "0xb00".into(),
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(relayer.clone())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let (leaf_index, nullifier_hash) =
+        deposit_note(&state, &server, "111", "222", "1000", "0x1").await;
+    let relayed = relayer.calls().len();
+
+    let mut request = swap_request(leaf_index);
+    request["self_submit"] = json!(true);
+    let resp = server.post("/swap").json(&request).await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["status"], "awaiting_submission");
+    assert_eq!(body["tx_hash"], "");
+    assert_eq!(body["amount_out"], "395");
+    assert_eq!(body["call"]["contract_address"], "0xb00");
+    let selector = get_selector_from_name("shielded_swap").unwrap();
+    assert_eq!(body["call"]["selector"], format!("{selector:#x}"));
+    assert!(!body["call"]["calldata"].as_array().unwrap().is_empty());
+
+    // Nothing was sent, spent or inserted; the nullifier is pending
+    assert_eq!(relayer.calls().len(), relayed);
+    assert_eq!(state.db.get_leaf_count().unwrap(), 1);
+    let body: serde_json::Value = server
+        .get(&format!("/nullifier/{nullifier_hash}"))
+        .await
+        .json();
+    assert_eq!(body["spent"], false);
+    assert_eq!(body["pending"], true);
+
+    // Once the sync task sees it spent on-chain it is no longer pending
+    state.db.insert_synced_nullifier(&nullifier_hash).unwrap();
+    let body: serde_json::Value = server
+        .get(&format!("/nullifier/{nullifier_hash}"))
+        .await
+        .json();
+    assert_eq!(body["spent"], true);
+    assert_eq!(body["pending"], false);
+}
+
+#[tokio::test]
+async fn test_second_swap_refused_while_self_submitted_one_is_pending() {
+    let relayer = MockRelayer::new();
+    let state = create_test_state_with(test_config(), Box::new(relayer.clone())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let (leaf_index, nullifier_hash) =
+        deposit_note(&state, &server, "111", "222", "1000", "0x1").await;
+    let mut request = swap_request(leaf_index);
+    request["self_submit"] = json!(true);
+    server.post("/swap").json(&request).await.assert_status_ok();
+
+    // Neither another hand-off, a relayed swap nor a dry run may reuse it
+    let relayed = swap_request(leaf_index);
+    for (path, body) in [("/swap", &request), ("/swap", &relayed), ("/swap/prove", &relayed)] {
+        let resp = server.post(path).json(body).await;
+        resp.assert_status(axum::http::StatusCode::CONFLICT);
+        let body: serde_json::Value = resp.json();
+        assert_eq!(body["code"], "nullifier_pending", "{path}");
+    }
+    relayer.assert_not_called(RelayerMethod::ShieldedSwap);
+    assert!(!state.db.is_nullifier_spent(&nullifier_hash).unwrap());
+}
+
+#[tokio::test]
+async fn test_reverted_swap_persists_nothing() {
+    let relayer = reverting_relayer();
+    let state = create_test_state_with_relayer(Box::new(relayer.clone())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let (leaf_index, nullifier_hash) =
+        deposit_note(&state, &server, "111", "222", "1000", "0x1").await;
+    let root_before = state.db.get_latest_root().unwrap();
+
+    let resp = server.post("/swap").json(&swap_request(leaf_index)).await;
+    resp.assert_status(axum::http::StatusCode::BAD_GATEWAY);
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["outcome"], "reverted");
+    assert_eq!(body["tx_hash"], "0xmock_swap_tx");
+    assert_eq!(body["revert_reason"], "Nullifier already spent");
+
+    assert!(!state.db.is_nullifier_spent(&nullifier_hash).unwrap());
+    assert_eq!(state.db.get_leaf_count().unwrap(), 1);
+    assert_eq!(state.db.get_latest_root().unwrap(), root_before);
+
+    // The swap was attempted once; only the deposit's root was submitted
+    relayer.assert_called(RelayerMethod::ShieldedSwap, 1);
+    relayer.assert_called(RelayerMethod::SubmitMerkleRoot, 1);
+}
+
+#[tokio::test]
+async fn test_reverted_withdraw_persists_nothing() {
+    let state = create_test_state_with_relayer(Box::new(reverting_relayer())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let (leaf_index, nullifier_hash) =
+        deposit_note(&state, &server, "111", "222", "1000", "0x1").await;
+
+    let resp = server
+        .post("/withdraw")
+        .json(&json!({
+            "secret": "111", "nullifier": "222",
+            "amount_low": "1000", "amount_high": "0",
+            "token": "0x1", "recipient": "0x5", "leaf_index": leaf_index
+        }))
+        .await;
+    resp.assert_status(axum::http::StatusCode::BAD_GATEWAY);
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["outcome"], "reverted");
+
+    assert!(!state.db.is_nullifier_spent(&nullifier_hash).unwrap());
+    assert_eq!(state.db.get_leaf_count().unwrap(), 1);
+}
+
+// ---------------------------------------------------------------------------
+// Operation audit log tests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_tx_lookup_by_deposit_and_root_tx() {
+    let server = create_test_server().await;
+
+    let resp = server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x1234"}))
+        .await;
+    resp.assert_status_ok();
+    let id: i64 = resp.header("x-operation-id").to_str().unwrap().parse().unwrap();
+
+    let body: serde_json::Value = server.get(&format!("/operations/{id}")).await.json();
+    assert_eq!(body["root_tx"], "0xmock_root_tx");
+
+    let resp = server.get("/tx/0xmock_deposit_tx").await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["role"], "action");
+    assert_eq!(body["operation"]["id"], id);
+    assert_eq!(body["operation"]["kind"], "deposit");
+
+    let resp = server.get("/tx/0xmock_root_tx").await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["role"], "root");
+    assert_eq!(body["operation"]["id"], id);
+    assert_eq!(body["operation"]["tx_hash"], "0xmock_deposit_tx");
+
+    let resp = server.get("/tx/0xabc").await;
+    resp.assert_status_not_found();
+    assert_eq!(resp.json::<serde_json::Value>()["code"], "tx_not_found");
+}
+
+#[tokio::test]
+async fn test_tx_commitments_lists_leaves_of_one_tx() {
+    let state = create_test_state().await;
+    state.db.insert_commitment(0, "111", Some("0xabc")).unwrap();
+    state.db.insert_commitment(1, "222", None).unwrap();
+    state.db.insert_commitment(2, "333", Some("0xabc")).unwrap();
+    let server = TestServer::new(create_test_router(state)).unwrap();
+
+    // Looked up by canonical hash, whatever the input's casing and padding
+    let resp = server.get("/tx/0x0ABC/commitments").await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["tx_hash"], "0xabc");
+    assert_eq!(
+        body["commitments"],
+        json!([
+            {"leaf_index": 0, "commitment": "111"},
+            {"leaf_index": 2, "commitment": "333"},
+        ])
+    );
+
+    let resp = server.get("/tx/0xdef/commitments").await;
+    resp.assert_status_not_found();
+    assert_eq!(resp.json::<serde_json::Value>()["code"], "tx_not_found");
+}
+
+#[tokio::test]
+async fn test_deposit_records_operation() {
+    let server = create_test_server().await;
+
+    let request = json!({"commitment": "0x1234"});
+    let resp = server.post("/deposit").json(&request).await;
+    resp.assert_status_ok();
+    let id = resp.header("x-operation-id");
+    let id = id.to_str().unwrap();
+
+    let body: serde_json::Value = server.get(&format!("/operations/{id}")).await.json();
+    assert_eq!(body["kind"], "deposit");
+    assert_eq!(body["status"], "confirmed");
+    assert_eq!(body["tx_hash"], "0xmock_deposit_tx");
+    assert_eq!(body["leaf_indices"], json!([0]));
+    let expected = hex::encode(sha2::Sha256::digest(request.to_string().as_bytes()));
+    assert_eq!(body["request_hash"], expected);
+
+    // Rejected requests are logged too, and the listing filters by kind
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "not_hex"}))
+        .await
+        .assert_status_bad_request();
+    let body: serde_json::Value = server.get("/operations?kind=deposit").await.json();
+    let statuses: Vec<&str> = body["operations"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|op| op["status"].as_str().unwrap())
+        .collect();
+    assert_eq!(statuses, vec!["rejected", "confirmed"]);
+
+    let body: serde_json::Value = server.get("/operations?kind=swap").await.json();
+    assert!(body["operations"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_reverted_swap_records_operation() {
+    let state = create_test_state_with_relayer(Box::new(reverting_relayer())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let (leaf_index, _) = deposit_note(&state, &server, "111", "222", "1000", "0x1").await;
+    server
+        .post("/swap")
+        .json(&swap_request(leaf_index))
+        .await
+        .assert_status(axum::http::StatusCode::BAD_GATEWAY);
+
+    let ops = state.db.list_operations(Some("swap"), 10).unwrap();
+    assert_eq!(ops.len(), 1);
+    assert_eq!(ops[0].status, "reverted");
+    assert_eq!(ops[0].tx_hash.as_deref(), Some("0xmock_swap_tx"));
+    assert!(ops[0].leaf_indices.is_empty());
+}
+
+#[tokio::test]
+async fn test_operation_lookup_errors() {
+    let server = create_test_server().await;
+
+    server.get("/operations/42").await.assert_status_not_found();
+    server
+        .get("/operations?kind=prove")
+        .await
+        .assert_status_bad_request();
+    server
+        .get("/operations?limit=0")
+        .await
+        .assert_status_bad_request();
+}
+
+fn simulate_request(token_in: &str) -> serde_json::Value {
+    json!({
+        "pool_key": {"token_0": "0x1", "token_1": "0x2", "fee": 3000, "tick_spacing": 60},
+        "token_in": token_in,
+        "amount_in": "1000",
+        "sqrt_price_limit": "0x100000000000000000000000000000000"
+    })
+}
+
+#[tokio::test]
+async fn test_simulate_swap_returns_quote() {
+    let relayer = MockRelayer::new().with_quote(SwapQuote {
+        amount_out: "990".into(),
+        sqrt_price_after: "1000".into(),
+    });
+    let state = create_test_state_with_relayer(Box::new(relayer.clone())).await;
+    let server = TestServer::new(create_test_router(state)).unwrap();
+
+    let resp = server.post("/simulate-swap").json(&simulate_request("0x01")).await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["zero_for_one"], true);
+    assert_eq!(body["amount_out"], "990");
+    assert_eq!(body["sqrt_price_after"], "1000");
+
+    let body: serde_json::Value = server
+        .post("/simulate-swap")
+        .json(&simulate_request("0x2"))
+        .await
+        .json();
+    assert_eq!(body["zero_for_one"], false);
+
+    let directions: Vec<bool> = relayer
+        .calls_to(RelayerMethod::SimulateSwap)
+        .into_iter()
+        .map(|call| match call {
+            RecordedCall::SimulateSwap {
+                zero_for_one,
+                amount_in,
+                ..
+            } => {
+                assert_eq!(amount_in, "1000");
+                zero_for_one
+            }
+            other => panic!("unexpected call {other:?}"),
+        })
+        .collect();
+    assert_eq!(directions, vec![true, false]);
+
+    server
+        .post("/simulate-swap")
+        .json(&simulate_request("0x3"))
+        .await
+        .assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_simulate_swap_without_quoter() {
+    let state = create_test_state_with_relayer(Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state)).unwrap();
+
+    let resp = server.post("/simulate-swap").json(&simulate_request("0x1")).await;
+    resp.assert_status(axum::http::StatusCode::NOT_IMPLEMENTED);
+    let body: serde_json::Value = resp.json();
+    assert!(body["error"].as_str().unwrap().contains("quote_swap"));
+}
+
+#[tokio::test]
+async fn test_swap_dry_run_unknown_note() {
+    let server = create_test_server().await;
+
+    let resp = server.post("/swap/prove").json(&swap_request(7)).await;
+    resp.assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_swap_rejects_change_note_reusing_input_secret() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let (leaf_index, nullifier_hash) =
+        deposit_note(&state, &server, "111", "222", "1000", "0x1").await;
+
+    let mut request = swap_request(leaf_index);
+    request["change_note"] = json!({"secret": "111", "nullifier": "222"});
+    for path in ["/swap/prove", "/swap"] {
+        let resp = server.post(path).json(&request).await;
+        resp.assert_status_bad_request();
+        let error: serde_json::Value = resp.json();
+        let message = error["error"].as_str().unwrap();
+        assert!(message.contains("input_note and change_note"), "{path}: {message}");
+    }
+    assert!(!state.db.is_nullifier_spent(&nullifier_hash).unwrap());
+}
+
+#[tokio::test]
+async fn test_proof_request_beyond_limit_is_rejected() {
+    let config = Config {
+        max_proof_jobs: 1,
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    // The one slot is taken by a proof in flight, so the next is turned away.
+    let in_flight = state.proof_slots.try_acquire().unwrap();
+    let resp = server.post("/swap/prove").json(&swap_request(7)).await;
+    resp.assert_status(axum::http::StatusCode::SERVICE_UNAVAILABLE);
+    assert!(resp.headers().contains_key("retry-after"));
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["code"], "prover_busy");
+
+    drop(in_flight);
+    let resp = server.post("/swap/prove").json(&swap_request(7)).await;
+    resp.assert_status_not_found();
+}
+
+// ---------------------------------------------------------------------------
+// Commitment derivation tests
+// ---------------------------------------------------------------------------
+
+/// circomlib Poseidon([1]) — the nullifier hash of a note with nullifier = 1.
+const POSEIDON_1: &str =
+    "18586133768512220936620570745912940619677854269274689475585506675881198879027";
+
+#[tokio::test]
+async fn test_compute_commitment_known_vector() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let resp = server
+        .post("/compute-commitment")
+        .json(&json!({
+            "secret": "2", "nullifier": "1",
+            "amount_low": "1000", "amount_high": "0", "token": "0x1"
+        }))
+        .await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["nullifier_hash"], POSEIDON_1);
+
+    // Matches what the proving paths derive
+    let expected = state
+        .worker
+        .lock()
+        .await
+        .compute_commitment("2", "1", "1000", "0", "0x1")
+        .await
+        .unwrap();
+    assert_eq!(body["commitment"], expected.commitment.as_str());
+}
+
+#[tokio::test]
+async fn test_compute_position_commitment_applies_offset() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let resp = server
+        .post("/compute-position-commitment")
+        .json(&json!({
+            "secret": "2", "nullifier": "1",
+            "tick_lower": -60, "tick_upper": 60, "liquidity": "5000"
+        }))
+        .await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["nullifier_hash"], POSEIDON_1);
+
+    let expected = state
+        .worker
+        .lock()
+        .await
+        .compute_position_commitment("2", "1", 887272 - 60, 887272 + 60, "5000")
+        .await
+        .unwrap();
+    assert_eq!(body["commitment"], expected.commitment.as_str());
+}
+
+#[tokio::test]
+async fn test_compute_commitment_validates_input() {
+    let server = create_test_server().await;
+
+    server
+        .post("/compute-commitment")
+        .json(&json!({
+            "secret": "", "nullifier": "1",
+            "amount_low": "1000", "amount_high": "0", "token": "0x1"
+        }))
+        .await
+        .assert_status_bad_request();
+    server
+        .post("/compute-position-commitment")
+        .json(&json!({
+            "secret": "2", "nullifier": "1",
+            "tick_lower": 60, "tick_upper": -60, "liquidity": "5000"
+        }))
+        .await
+        .assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_worker_spawn_rejects_mismatched_domains() {
+    let config = test_config();
+    let runtime = WorkerRuntime::from_config(&config);
+    let domains = DomainSeparators {
+        note: "zylith.note.v0".into(),
+        ..Default::default()
+    };
+    let err = Worker::spawn(&runtime, &config.worker_path, config.tree_height, &domains)
+        .await
+        .err()
+        .expect("spawn must fail");
+    assert!(err.to_string().contains("zylith.note.v0"), "{err}");
+}
+
+#[tokio::test]
+async fn test_worker_poseidon_self_test() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    // The self-test tree is the one four deposits of its leaves would build
+    for leaf in SELF_TEST_LEAVES {
+        let commitment = format!("0x{leaf}");
+        server
+            .post("/deposit")
+            .json(&json!({"commitment": commitment}))
+            .await
+            .assert_status_ok();
+    }
+    let expected = state.db.get_latest_root().unwrap().unwrap();
+
+    let mut worker = state.worker.lock().await;
+    assert_eq!(worker.self_test(&expected).await.unwrap(), expected);
+    let err = worker.self_test("12345").await.unwrap_err();
+    assert!(err.to_string().contains("Poseidon self-test failed"), "{err}");
+}
+
+// ---------------------------------------------------------------------------
+// Tree tests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_worker_warmed_up_before_serving() {
+    let config = Config {
+        worker_warmup: true,
+        ..test_config()
+    };
+    // The state is only handed to the router once startup_info has returned
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    assert!(state.worker_info.as_ref().unwrap().warmed_up);
+    assert!(state.worker.try_lock().unwrap().ping().await.unwrap());
+
+    let server = TestServer::new(create_test_router(state)).unwrap();
+    let status: serde_json::Value = server.get("/status").await.json();
+    assert_eq!(status["worker"]["warmed_up"], true);
+
+    let cold = create_test_state().await;
+    assert!(!cold.worker_info.as_ref().unwrap().warmed_up);
+}
+
+#[tokio::test]
+async fn test_tree_root_empty() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+    let empty_root = state.worker.lock().await.compute_root(&[]).await.unwrap();
+
+    let resp = server.get("/tree/root").await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["leaf_count"], 0);
+    assert_eq!(body["root"], empty_root);
+    assert_eq!(state.empty_root, empty_root);
+
+    let status: serde_json::Value = server.get("/status").await.json();
+    assert_eq!(status["tree"]["root"], empty_root);
+}
+
+#[tokio::test]
+async fn test_tree_root_after_deposit() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    // Deposit first
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x1234"}))
+        .await
+        .assert_status_ok();
+
+    let resp = server.get("/tree/root").await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["leaf_count"], 1);
+    // Root should be a real value (stored from deposit)
+    assert_ne!(body["root"], "0");
+}
+
+#[tokio::test]
+async fn test_tree_root_at_leaf_count() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+    for (root, leaf_count) in [("111", 1), ("222", 2), ("333", 3)] {
+        state.db.insert_root(root, leaf_count, None).unwrap();
+    }
+
+    for (root, leaf_count) in [("111", 1), ("222", 2), ("333", 3)] {
+        let resp = server.get(&format!("/tree/root?leaf_count={leaf_count}")).await;
+        resp.assert_status_ok();
+        let body: serde_json::Value = resp.json();
+        assert_eq!(body["root"], root);
+        assert_eq!(body["leaf_count"], leaf_count);
+    }
+
+    let resp = server.get("/tree/root?leaf_count=4").await;
+    resp.assert_status_not_found();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["code"], "unknown_root");
+}
+
+#[tokio::test]
+async fn test_tree_roots_marks_accepted_window() {
+    let config = Config {
+        root_history_size: 2,
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+    for (root, leaf_count) in [("111", 1), ("222", 2), ("333", 3)] {
+        state.db.insert_root(root, leaf_count, None).unwrap();
+    }
+
+    let status: serde_json::Value = server.get("/status").await.json();
+    assert_eq!(status["tree"]["accepted_root_window"], 2);
+
+    let resp = server.get("/tree/roots?limit=3").await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["accepted_root_window"], 2);
+    let roots: Vec<(String, bool)> = body["roots"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| (r["root"].as_str().unwrap().to_string(), r["accepted"] == true))
+        .collect();
+    assert_eq!(
+        roots,
+        [
+            ("333".to_string(), true),
+            ("222".to_string(), true),
+            ("111".to_string(), false),
+        ]
+    );
+
+    // Defaults to the window
+    let body: serde_json::Value = server.get("/tree/roots").await.json();
+    assert_eq!(body["roots"].as_array().unwrap().len(), 2);
+    server
+        .get("/tree/roots?limit=0")
+        .await
+        .assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_tree_path_success() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    // Deposit first
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x5678"}))
+        .await
+        .assert_status_ok();
+
+    let resp = server.get("/tree/path/0").await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["leaf_index"], 0);
+    // LeanIMT with height 20 gives 20 path elements
+    let path_elements = body["path_elements"].as_array().unwrap();
+    assert_eq!(path_elements.len(), 20);
+    let path_indices = body["path_indices"].as_array().unwrap();
+    assert_eq!(path_indices.len(), 20);
+}
+
+#[tokio::test]
+async fn test_tree_path_bool_indices() {
+    let server = create_test_server().await;
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x5678"}))
+        .await
+        .assert_status_ok();
+
+    let body: serde_json::Value = server.get("/tree/path/0").await.json();
+    let path_indices = body["path_indices"].as_array().unwrap();
+    assert!(path_indices.iter().all(|i| i == 0 || i == 1));
+
+    let resp = server
+        .get("/tree/path/0")
+        .add_query_param("bool_indices", true)
+        .await;
+    resp.assert_status_ok();
+    let as_bools: serde_json::Value = resp.json();
+    let bools = as_bools["path_indices"].as_array().unwrap();
+    assert_eq!(bools.len(), 20);
+    for (bit, flag) in path_indices.iter().zip(bools) {
+        assert_eq!(flag.as_bool().unwrap(), bit == 1);
+    }
+    assert_eq!(as_bools["path_elements"], body["path_elements"]);
+}
+
+#[tokio::test]
+async fn test_tree_path_by_commitment() {
+    let server = create_test_server().await;
+
+    for commitment in ["0xdead", "0xbeef"] {
+        server
+            .post("/deposit")
+            .json(&json!({"commitment": commitment}))
+            .await
+            .assert_status_ok();
+    }
+
+    let by_index: serde_json::Value = server.get("/tree/path/1").await.json();
+
+    // Hex and decimal (0xbeef = 48879) resolve to the same leaf
+    for encoding in ["0xbeef", "0xBEEF", "48879", "048879"] {
+        let resp = server
+            .get(&format!("/tree/path-by-commitment/{encoding}"))
+            .await;
+        resp.assert_status_ok();
+        let body: serde_json::Value = resp.json();
+        assert_eq!(body["leaf_index"], 1);
+        assert_eq!(body, by_index);
+    }
+}
+
+#[tokio::test]
+async fn test_tree_path_by_commitment_unknown() {
+    let server = create_test_server().await;
+
+    server
+        .get("/tree/path-by-commitment/0x1234")
+        .await
+        .assert_status_not_found();
+    server
+        .get("/tree/path-by-commitment/not-a-number")
+        .await
+        .assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_tree_path_at_historical_root() {
+    let server = create_test_server().await;
+
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0xdead"}))
+        .await
+        .assert_status_ok();
+    let old_root: serde_json::Value = server.get("/tree/root").await.json();
+    let old_root = old_root["root"].as_str().unwrap().to_string();
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0xbeef"}))
+        .await
+        .assert_status_ok();
+
+    let current: serde_json::Value = server.get("/tree/path/0").await.json();
+    assert_ne!(current["root"], old_root);
+    assert_eq!(current["path_elements"][0], "48879");
+
+    // Against the old root leaf 0 had no sibling yet
+    let resp = server.get(&format!("/tree/path/0?root={old_root}")).await;
+    resp.assert_status_ok();
+    let pinned: serde_json::Value = resp.json();
+    assert_eq!(pinned["root"], old_root);
+    assert_eq!(pinned["path_elements"][0], "0");
+
+    // Leaf 1 did not exist at the old root
+    server
+        .get(&format!("/tree/path/1?root={old_root}"))
+        .await
+        .assert_status_bad_request();
+    server
+        .get("/tree/path/0?root=0x1234")
+        .await
+        .assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_tree_path_not_found() {
+    let server = create_test_server().await;
+
+    let resp = server.get("/tree/path/99").await;
+    resp.assert_status_not_found();
+}
+
+// ---------------------------------------------------------------------------
+// Nullifier tests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_nullifier_not_spent() {
+    let server = create_test_server().await;
+
+    let resp = server.get("/nullifier/12345").await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["spent"], false);
+    assert!(body["circuit_type"].is_null());
+}
+
+#[tokio::test]
+async fn test_nullifier_spent() {
+    let state = create_test_state().await;
+
+    // Insert nullifier directly into DB
+    state
+        .db
+        .insert_nullifier("12345", CircuitType::Membership, Some("0xabc"))
+        .unwrap();
+
+    let server = TestServer::new(create_test_router(state)).unwrap();
+
+    let resp = server.get("/nullifier/12345").await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["spent"], true);
+    assert_eq!(body["circuit_type"], "membership");
+    assert_eq!(body["tx_hash"], "0xabc");
+}
+
+#[tokio::test]
+async fn test_nullifier_listing() {
+    let state = create_test_state().await;
+    state.db.insert_nullifier("1", CircuitType::Swap, Some("0xa")).unwrap();
+    state.db.insert_nullifier("2", CircuitType::Mint, Some("0xb")).unwrap();
+    state.db.insert_nullifier("3", CircuitType::Swap, None).unwrap();
+    let server = TestServer::new(create_test_router(state)).unwrap();
+
+    // Unfiltered, paged
+    let resp = server.get("/nullifiers?limit=2").await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["nullifiers"].as_array().unwrap().len(), 2);
+    assert_eq!(body["limit"], 2);
+    assert!(body["nullifiers"][0]["spent_at"].is_string());
+
+    let resp = server.get("/nullifiers?offset=2&limit=2").await;
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["nullifiers"].as_array().unwrap().len(), 1);
+
+    // Filtered by circuit type
+    let resp = server.get("/nullifiers?circuit_type=swap").await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    let rows = body["nullifiers"].as_array().unwrap();
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().all(|r| r["circuit_type"] == "swap"));
+}
+
+#[tokio::test]
+async fn test_nullifier_listing_rejects_bad_params() {
+    let server = create_test_server().await;
+
+    server
+        .get("/nullifiers?circuit_type=transfer")
+        .await
+        .assert_status_bad_request();
+    server
+        .get("/nullifiers?limit=100000")
+        .await
+        .assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_slow_request_times_out_with_504() {
+    let config = Config {
+        request_timeout_secs: 1,
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    // Hold the worker so the handler cannot finish
+    let busy = state.worker.lock().await;
+    let resp = server
+        .post("/compute-commitment")
+        .json(&json!({
+            "secret": "0x1", "nullifier": "0x2", "amount_low": "0x3",
+            "amount_high": "0x0", "token": "0x5"
+        }))
+        .await;
+    resp.assert_status(axum::http::StatusCode::GATEWAY_TIMEOUT);
+    drop(busy);
+
+    // Fast requests are unaffected
+    server.get("/tree/root").await.assert_status_ok();
+}
+
+#[tokio::test]
+async fn test_ping_does_not_wait_for_worker() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    // A worker busy proving must not fail liveness
+    let _busy = state.worker.lock().await;
+    let resp = tokio::time::timeout(Duration::from_secs(1), server.get("/ping"))
+        .await
+        .expect("/ping must not wait for the worker");
+    resp.assert_status_ok();
+    resp.assert_text("pong");
+}
+
+#[tokio::test]
+async fn test_pretty_json_responses() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state)).unwrap();
+
+    // Compact by default
+    let compact = server.get("/tree/root").await.text();
+    assert!(!compact.contains('\n'));
+
+    let resp = server.get("/tree/root?pretty=true").await;
+    resp.assert_status_ok();
+    let pretty = resp.text();
+    assert!(pretty.contains('\n'));
+    let parse = |s: &str| serde_json::from_str::<serde_json::Value>(s).unwrap();
+    assert_eq!(parse(&pretty), parse(&compact));
+
+    // Error bodies too
+    let resp = server.get("/tree/path-by-commitment/xyz?pretty=true").await;
+    resp.assert_status_bad_request();
+    assert!(resp.text().contains('\n'));
+
+    // PRETTY_JSON indents every response
+    let config = Config {
+        pretty_json: true,
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state)).unwrap();
+    assert!(server.get("/tree/root").await.text().contains('\n'));
+}
+
+#[tokio::test]
+async fn test_large_listing_is_gzip_compressed() {
+    let state = create_test_state().await;
+    for i in 0..200 {
+        state
+            .db
+            .insert_nullifier(&format!("{}", 1_000_000 + i), CircuitType::Swap, Some("0xa"))
+            .unwrap();
+    }
+    let server = TestServer::new(create_test_router(state)).unwrap();
+
+    let resp = server
+        .get("/nullifiers?limit=200")
+        .add_header("accept-encoding", "gzip")
+        .await;
+    resp.assert_status_ok();
+    assert_eq!(resp.header("content-encoding"), "gzip");
+
+    let mut json = String::new();
+    std::io::Read::read_to_string(
+        &mut flate2::read::GzDecoder::new(resp.as_bytes().as_ref()),
+        &mut json,
+    )
+    .unwrap();
+    let body: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(body["nullifiers"].as_array().unwrap().len(), 200);
+
+    // Without Accept-Encoding, or below the size threshold, nothing is compressed
+    let resp = server.get("/nullifiers?limit=200").await;
+    assert!(resp.maybe_header("content-encoding").is_none());
+    let resp = server
+        .get("/tree/root")
+        .add_header("accept-encoding", "gzip")
+        .await;
+    assert!(resp.maybe_header("content-encoding").is_none());
+}
+
+#[tokio::test]
+async fn test_nullifier_batch_check_mixed() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+    state
+        .db
+        .insert_nullifier("12345", CircuitType::Swap, Some("0xabc"))
+        .unwrap();
+
+    let resp = server
+        .post("/nullifiers/check")
+        .json(&json!({"hashes": ["0x3039", "678", "012345"]}))
+        .await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    let nullifiers = &body["nullifiers"];
+
+    // 0x3039 == 12345, reported under the encoding it was asked with
+    assert_eq!(nullifiers["0x3039"]["spent"], true);
+    assert_eq!(nullifiers["0x3039"]["circuit_type"], "swap");
+    assert_eq!(nullifiers["0x3039"]["tx_hash"], "0xabc");
+    assert_eq!(nullifiers["012345"]["spent"], true);
+    assert_eq!(nullifiers["678"]["spent"], false);
+    assert!(nullifiers["678"]["circuit_type"].is_null());
+}
+
+#[tokio::test]
+async fn test_nullifier_batch_check_rejects_bad_input() {
+    let server = create_test_server().await;
+
+    let too_many: Vec<String> = (0..501).map(|i| i.to_string()).collect();
+    server
+        .post("/nullifiers/check")
+        .json(&json!({"hashes": too_many}))
+        .await
+        .assert_status_bad_request();
+    server
+        .post("/nullifiers/check")
+        .json(&json!({"hashes": ["not-a-number"]}))
+        .await
+        .assert_status_bad_request();
+}
+
+// ---------------------------------------------------------------------------
+// Note scan tests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_notes_scan_mixed() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    // 0xdead = 57005, 0xbeef = 48879
+    for commitment in ["0xdead", "0xbeef"] {
+        server
+            .post("/deposit")
+            .json(&json!({"commitment": commitment}))
+            .await
+            .assert_status_ok();
+    }
+    state.db.insert_nullifier("777", CircuitType::Swap, Some("0xspent")).unwrap();
+
+    let resp = server
+        .post("/notes/scan")
+        .json(&json!({"notes": [
+            {"commitment": "57005", "nullifier_hash": "777"},
+            {"commitment": "48879", "nullifier_hash": "888"},
+            {"commitment": "1", "nullifier_hash": "999"},
+            {"commitment": "48879"}
+        ]}))
+        .await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    let notes = body["notes"].as_array().unwrap();
+    assert_eq!(notes.len(), 4);
+
+    // Found and spent
+    assert_eq!(notes[0]["found"], true);
+    assert_eq!(notes[0]["leaf_index"], 0);
+    assert_eq!(notes[0]["deposit_tx"], "0xmock_deposit_tx");
+    assert_eq!(notes[0]["spent"], true);
+
+    // Found, unspent
+    assert_eq!(notes[1]["found"], true);
+    assert_eq!(notes[1]["leaf_index"], 1);
+    assert_eq!(notes[1]["spent"], false);
+
+    // Not found
+    assert_eq!(notes[2]["found"], false);
+    assert!(notes[2]["leaf_index"].is_null());
+    assert!(notes[2]["deposit_tx"].is_null());
+    assert_eq!(notes[2]["spent"], false);
+
+    // No nullifier supplied
+    assert_eq!(notes[3]["found"], true);
+    assert!(notes[3]["spent"].is_null());
+}
+
+#[tokio::test]
+async fn test_notes_scan_invalid_commitment() {
+    let server = create_test_server().await;
+
+    let resp = server
+        .post("/notes/scan")
+        .json(&json!({"notes": [{"commitment": "0xnot-decimal"}]}))
+        .await;
+    resp.assert_status_bad_request();
+}
+
+// ---------------------------------------------------------------------------
+// Root reconciliation tests
+// ---------------------------------------------------------------------------
+
+/// Leave a leaf in the DB and worker tree with no root recorded, as if the
+/// process died right after submitting it. Returns the tree's root.
+async fn insert_unrecorded_leaf(state: &AppState, commitment: &str) -> String {
+    let root = state.worker.lock().await.insert_leaf(commitment).await.unwrap();
+    state.db.insert_commitment(0, commitment, None).unwrap();
+    root
+}
+
+#[tokio::test]
+async fn test_root_matching_onchain_is_not_resubmitted() {
+    let relayer = MockRelayer::new();
+    let state = create_test_state_with_relayer(Box::new(relayer.clone())).await;
+    let root = insert_unrecorded_leaf(&state, "4660").await;
+    let relayer = relayer.with_onchain_root(&root);
+
+    submit_root_if_changed(&state).await.unwrap();
+
+    relayer.assert_called(RelayerMethod::GetOnchainRoot, 1);
+    relayer.assert_not_called(RelayerMethod::SubmitMerkleRoot);
+    assert_eq!(state.db.get_latest_root().unwrap(), Some(root));
+
+    // Recorded now, so the chain is not even consulted again
+    submit_root_if_changed(&state).await.unwrap();
+    relayer.assert_called(RelayerMethod::GetOnchainRoot, 1);
+}
+
+#[tokio::test]
+async fn test_root_differing_from_onchain_is_submitted() {
+    let relayer = MockRelayer::new().with_onchain_root("1");
+    let state = create_test_state_with_relayer(Box::new(relayer.clone())).await;
+    let root = insert_unrecorded_leaf(&state, "4660").await;
+
+    submit_root_if_changed(&state).await.unwrap();
+
+    assert_eq!(
+        relayer.calls_to(RelayerMethod::SubmitMerkleRoot),
+        vec![RecordedCall::SubmitMerkleRoot { root: root.clone() }]
+    );
+    assert_eq!(state.db.get_latest_root().unwrap(), Some(root));
+}
+
+// ---------------------------------------------------------------------------
+// Relayer startup tests
+// ---------------------------------------------------------------------------
+
+/// Serve a JSON-RPC endpoint that answers every request with `result` and
+/// return its URL.
+async fn spawn_rpc_stub(result: serde_json::Value) -> String {
+    let app = axum::Router::new().route(
+        "/",
+        axum::routing::post(move |axum::Json(req): axum::Json<serde_json::Value>| {
+            let result = result.clone();
+            async move { axum::Json(json!({"jsonrpc": "2.0", "id": req["id"], "result": result})) }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{addr}")
+}
+
+#[tokio::test]
+async fn test_relayer_refuses_node_on_another_chain() {
+    // The node reports SN_MAIN while the relayer is configured for SN_SEPOLIA
+    let config = Config {
+        rpc_url: spawn_rpc_stub(json!("0x534e5f4d41494e")).await,
+        ..test_config()
+    };
+    let runtime = Arc::new(LiveConfig::new(RuntimeConfig::from_config(&config)));
+
+    match StarknetRelayer::new(&config, runtime).await {
+        Err(AspError::Config(msg)) => {
+            assert!(msg.contains("SN_MAIN") && msg.contains("SN_SEPOLIA"), "{msg}");
+        }
+        Err(e) => panic!("expected a config error, got {e}"),
+        Ok(_) => panic!("relayer accepted a node on the wrong chain"),
+    }
+}
+
+#[tokio::test]
+async fn test_relayer_builder_without_config() {
+    let sepolia = Felt::from_hex("0x534e5f5345504f4c4941").unwrap();
+    let rpc_url = spawn_rpc_stub(json!("0x534e5f5345504f4c4941")).await;
+    let private_key = Felt::from(0x1234_u64);
+    let public_key = SigningKey::from_secret_scalar(private_key)
+        .verifying_key()
+        .scalar();
+    let builder = || {
+        StarknetRelayerBuilder::new()
+            .rpc_url(&rpc_url)
+            .chain_id(sepolia)
+            .coordinator_address(Felt::from(0xc0_u64))
+            .pool_address(Felt::from(0xb00_u64))
+            .fee_multiplier(2.0)
+    };
+
+    let relayer = builder()
+        .signer(Felt::from(0xad_u64), private_key)
+        .public_key(public_key)
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(relayer.chain_id(), sepolia);
+    assert_eq!(*relayer.coordinator_address(), Felt::from(0xc0_u64));
+    assert_eq!(*relayer.pool_address(), Felt::from(0xb00_u64));
+
+    // A key that does not derive the declared public key is refused
+    let err = builder()
+        .signer(Felt::from(0xad_u64), Felt::from(0x5678_u64))
+        .public_key(public_key)
+        .build()
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, AspError::Config(_)), "{err}");
+
+    match builder().build().await {
+        Err(AspError::Config(msg)) => assert!(msg.contains("signer"), "{msg}"),
+        Err(e) => panic!("expected a config error, got {e}"),
+        Ok(_) => panic!("relayer built without a signer"),
+    }
+}
+
+#[test]
+fn test_mock_relayer_reports_chain_id() {
+    let sepolia = Felt::from_hex("0x534e5f5345504f4c4941").unwrap();
+    assert_eq!(MockRelayer::new().chain_id(), sepolia);
+
+    let mainnet = Felt::from_hex("0x534e5f4d41494e").unwrap();
+    assert_eq!(MockRelayer::new().with_chain_id(mainnet).chain_id(), mainnet);
+}
+
+// ---------------------------------------------------------------------------
+// OpenAPI tests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_openapi_document_covers_every_route() {
+    let server = create_test_server().await;
+
+    let resp = server.get("/openapi.json").await;
+    resp.assert_status_ok();
+    let doc: serde_json::Value = serde_json::from_str(&resp.text()).unwrap();
+    assert_eq!(doc["openapi"], "3.0.3");
+
+    let routes = [
+        ("post", "/deposit"),
+        ("get", "/commitment/{commitment}"),
+        ("post", "/withdraw"),
+        ("post", "/swap"),
+        ("post", "/mint"),
+        ("post", "/burn"),
+        ("post", "/simulate-swap"),
+        ("post", "/withdraw/prove"),
+        ("post", "/swap/prove"),
+        ("post", "/mint/prove"),
+        ("post", "/burn/prove"),
+        ("post", "/compute-commitment"),
+        ("post", "/compute-position-commitment"),
+        ("get", "/tree/root"),
+        ("get", "/tree/roots"),
+        ("get", "/tree/path/{leaf_index}"),
+        ("get", "/tree/path-by-commitment/{commitment}"),
+        ("get", "/nullifier/{hash}"),
+        ("get", "/nullifiers"),
+        ("post", "/nullifiers/check"),
+        ("get", "/operations"),
+        ("get", "/operations/{id}"),
+        ("get", "/tx/{tx_hash}"),
+        ("get", "/tx/{tx_hash}/commitments"),
+        ("get", "/ping"),
+        ("post", "/sync-commitments"),
+        ("post", "/notes/scan"),
+        ("get", "/events"),
+        ("get", "/status"),
+        ("get", "/openapi.json"),
+        ("post", "/admin/resync"),
+        ("post", "/admin/sync-now"),
+        ("post", "/admin/vacuum"),
+        ("post", "/admin/verify-tree"),
+        ("post", "/admin/resubmit-root"),
+        ("get", "/admin/proof-stats"),
+        ("get", "/admin/export"),
+        ("post", "/admin/import"),
+        ("post", "/admin/ban"),
+        ("delete", "/admin/ban/{ip}"),
+    ];
+    for (method, path) in routes {
+        assert!(doc["paths"][path][method].is_object(), "missing {method} {path}");
+    }
+    assert_eq!(doc["paths"].as_object().unwrap().len(), routes.len());
+
+    let deposit = &doc["components"]["schemas"]["DepositRequest"];
+    assert!(deposit["properties"]["commitment"].is_object());
+    let codes = doc["components"]["schemas"]["Error"]["properties"]["code"]["enum"]
+        .as_array()
+        .unwrap();
+    assert!(codes.iter().any(|c| c == "unknown_root"));
+}
+
+// ---------------------------------------------------------------------------
+// Status tests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_status_is_cached_within_ttl() {
+    let config = Config {
+        status_cache_ms: 60_000,
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let first: serde_json::Value = server.get("/status").await.json();
+    assert_eq!(first["tree"]["leaf_count"], 0);
+
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0xdead"}))
+        .await
+        .assert_status_ok();
+
+    // Within the TTL the earlier payload is served, so the new leaf (and
+    // the worker ping) waits for the next computation
+    let second: serde_json::Value = server.get("/status").await.json();
+    assert_eq!(second, first);
+    assert_eq!(state.tree_snapshot().leaf_count, 1);
+}
+
+#[tokio::test]
+async fn test_status_healthy() {
+    let server = create_test_server().await;
+
+    let resp = server.get("/status").await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["healthy"], true);
+    assert!(!body["version"].as_str().unwrap().is_empty());
+    assert_eq!(body["contracts"]["coordinator"], "0xcoordinator");
+    assert_eq!(body["contracts"]["pool"], "0xpool");
+}
+
+#[tokio::test]
+async fn test_status_counts_operations_by_outcome() {
+    let server = create_test_server().await;
+
+    let body: serde_json::Value = server.get("/status").await.json();
+    assert_eq!(body["operations"]["deposit"], json!({"ok": 0, "err": 0}));
+
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x1234"}))
+        .await
+        .assert_status_ok();
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "not hex"}))
+        .await
+        .assert_status_bad_request();
+
+    let body: serde_json::Value = server.get("/status").await.json();
+    assert_eq!(body["operations"]["deposit"], json!({"ok": 1, "err": 1}));
+    assert_eq!(body["operations"]["swap"], json!({"ok": 0, "err": 0}));
+}
+
+#[tokio::test]
+async fn test_status_reports_anonymity_set_and_last_deposit() {
+    let server = create_test_server().await;
+
+    let body: serde_json::Value = server.get("/status").await.json();
+    assert_eq!(body["tree"]["anonymity_set"], 0);
+    assert!(body["tree"]["last_deposit_at"].is_null());
+    assert_eq!(body["tree"]["accepted_root_window"], 100);
+
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x1234"}))
+        .await
+        .assert_status_ok();
+
+    let body: serde_json::Value = server.get("/status").await.json();
+    assert_eq!(body["tree"]["anonymity_set"], 1);
+    assert_eq!(body["tree"]["leaf_count"], 1);
+    let last_deposit_at = body["tree"]["last_deposit_at"].as_str().unwrap();
+    assert_eq!(last_deposit_at.len(), "YYYY-MM-DD HH:MM:SS".len());
+}
+
+#[tokio::test]
+async fn test_status_reports_worker_circuits() {
+    let server = create_test_server().await;
+
+    let resp = server.get("/status").await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert!(!body["worker"]["version"].as_str().unwrap().is_empty());
+    let circuits = body["worker"]["circuits"].as_object().unwrap();
+    for hash in circuits.values() {
+        let hash = hash.as_str().unwrap();
+        assert!(hash.starts_with("0x") && hash.len() == 66, "hash: {hash}");
+    }
+}
+
+#[tokio::test]
+async fn test_status_does_not_wait_for_busy_worker() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0xdead"}))
+        .await
+        .assert_status_ok();
+
+    // Hold the worker as a long-running proof would
+    let _proving = state.worker.lock().await;
+
+    let resp = tokio::time::timeout(Duration::from_secs(2), server.get("/status"))
+        .await
+        .expect("/status blocked on the worker");
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["healthy"], true);
+    assert_eq!(body["worker_busy"], true);
+    assert_eq!(body["tree"]["leaf_count"], 1);
+
+    let resp = tokio::time::timeout(Duration::from_secs(2), server.get("/tree/root"))
+        .await
+        .expect("/tree/root blocked on the worker");
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["leaf_count"], 1);
+}
+
+// ---------------------------------------------------------------------------
+// Request limit tests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_oversized_body_rejected() {
+    let config = Config {
+        max_body_bytes: 1024,
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let padding = "0".repeat(2048);
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x1234", "token": padding}))
+        .await
+        .assert_status(axum::http::StatusCode::PAYLOAD_TOO_LARGE);
+    server
+        .post("/nullifiers/check")
+        .json(&json!({"hashes": [padding]}))
+        .await
+        .assert_status(axum::http::StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(state.db.get_leaf_count().unwrap(), 0);
+
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x1234"}))
+        .await
+        .assert_status_ok();
+}
+
+#[tokio::test]
+async fn test_non_json_content_type_rejected() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let resp = server
+        .post("/deposit")
+        .text(r#"{"commitment": "0x1234"}"#)
+        .await;
+    resp.assert_status(axum::http::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let body: serde_json::Value = resp.json();
+    assert!(body["error"].as_str().unwrap().contains("application/json"));
+    assert_eq!(state.db.get_leaf_count().unwrap(), 0);
+
+    // Parameters on the media type are fine
+    server
+        .post("/deposit")
+        .content_type("application/json; charset=utf-8")
+        .bytes(r#"{"commitment": "0x1234"}"#.into())
+        .await
+        .assert_status_ok();
+}
+
+// ---------------------------------------------------------------------------
+// CORS tests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_cors_rejects_disallowed_origin() {
+    let config = Config {
+        allowed_origins: vec!["https://app.zylith.xyz".into()],
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let resp = server
+        .post("/deposit")
+        .add_header("origin", "https://evil.example")
+        .json(&json!({"commitment": "0x1234"}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::FORBIDDEN);
+    assert!(resp.maybe_header("access-control-allow-origin").is_none());
+    assert_eq!(state.db.get_leaf_count().unwrap(), 0);
+
+    // Preflight from the disallowed origin is not granted either
+    let resp = server
+        .method(axum::http::Method::OPTIONS, "/deposit")
+        .add_header("origin", "https://evil.example")
+        .add_header("access-control-request-method", "POST")
+        .await;
+    assert!(resp.maybe_header("access-control-allow-origin").is_none());
+
+    let resp = server
+        .post("/deposit")
+        .add_header("origin", "https://app.zylith.xyz")
+        .json(&json!({"commitment": "0x1234"}))
+        .await;
+    resp.assert_status_ok();
+    assert_eq!(resp.header("access-control-allow-origin"), "https://app.zylith.xyz");
+}
+
+#[tokio::test]
+async fn test_cors_without_origins_allows_reads_only() {
+    let server = create_test_server().await;
+
+    let resp = server
+        .get("/status")
+        .add_header("origin", "https://anywhere.example")
+        .await;
+    resp.assert_status_ok();
+    assert_eq!(resp.header("access-control-allow-origin"), "*");
+
+    server
+        .post("/deposit")
+        .add_header("origin", "https://anywhere.example")
+        .json(&json!({"commitment": "0x1234"}))
+        .await
+        .assert_status(axum::http::StatusCode::FORBIDDEN);
+
+    // Clients without an Origin header (SDKs, curl) are unaffected
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x1234"}))
+        .await
+        .assert_status_ok();
+}
+
+#[tokio::test]
+async fn test_admin_resync_requires_token() {
+    // Admin API disabled when no token is configured
+    let server = create_test_server().await;
+    server
+        .post("/admin/resync")
+        .await
+        .assert_status_unauthorized();
+
+    let config = Config {
+        admin_api_token: Some("s3cret".into()),
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state)).unwrap();
+
+    server
+        .post("/admin/resync")
+        .await
+        .assert_status_unauthorized();
+    server
+        .post("/admin/resync")
+        .authorization_bearer("wrong")
+        .await
+        .assert_status_unauthorized();
+}
+
+#[tokio::test]
+async fn test_admin_resync_rewinds_sync() {
+    let config = Config {
+        admin_api_token: Some("s3cret".into()),
+        sync_start_block: 700,
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    state.db.set_sync_state("last_block", "9000").unwrap();
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let resp = server
+        .post("/admin/resync")
+        .add_query_param("from_block", 1234)
+        .authorization_bearer("s3cret")
+        .await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["from_block"], 1234);
+    assert_eq!(
+        state.db.get_sync_state("last_block").unwrap().as_deref(),
+        Some("1234")
+    );
+
+    // Without from_block, rewinds to the configured start block
+    let resp = server
+        .post("/admin/resync")
+        .authorization_bearer("s3cret")
+        .await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["from_block"], 700);
+}
+
+/// Chain with a fixed head whose events all arrive in a single page.
+struct StaticChain {
+    head: u64,
+    events: Vec<EmittedEvent>,
+}
+
+#[async_trait::async_trait]
+impl EventSource for StaticChain {
+    async fn head(&self) -> Result<u64, AspError> {
+        Ok(self.head)
+    }
+
+    async fn head_and_events(
+        &self,
+        _filter: EventFilter,
+        _chunk_size: u64,
+    ) -> Result<(u64, EventsPage), AspError> {
+        Ok((self.head, self.page()))
+    }
+
+    async fn events(
+        &self,
+        _filter: EventFilter,
+        _continuation_token: Option<String>,
+        _chunk_size: u64,
+    ) -> Result<EventsPage, AspError> {
+        Ok(self.page())
+    }
+}
+
+impl StaticChain {
+    fn page(&self) -> EventsPage {
+        EventsPage {
+            events: self.events.clone(),
+            continuation_token: None,
+        }
+    }
+}
+
+fn chain_event(block: u64, name: &str, data: Vec<Felt>) -> EmittedEvent {
+    EmittedEvent {
+        from_address: Felt::ONE,
+        keys: vec![get_selector_from_name(name).unwrap()],
+        data,
+        block_hash: None,
+        block_number: Some(block),
+        transaction_hash: Felt::from(block),
+    }
+}
+
+#[tokio::test]
+async fn test_admin_sync_now_runs_one_cycle() {
+    let config = Config {
+        admin_api_token: Some("s3cret".into()),
+        sync_start_block: 100,
+        ..test_config()
+    };
+    let chain = StaticChain {
+        head: 150,
+        events: vec![
+            chain_event(
+                120,
+                "CommitmentAdded",
+                vec![Felt::from(0x1234u64), Felt::ZERO, Felt::ZERO, Felt::ZERO, Felt::ZERO],
+            ),
+            chain_event(130, "NullifierSpent", vec![Felt::from(99u64), Felt::ZERO]),
+        ],
+    };
+    let syncer = Syncer::new(Box::new(chain), vec![Felt::ONE]);
+    let relayer: Box<dyn Relayer> = Box::new(MockRelayer::new());
+    let state = create_test_state_with_syncer(config, Some(relayer), Some(syncer)).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    server.post("/admin/sync-now").await.assert_status_unauthorized();
+
+    let resp = server
+        .post("/admin/sync-now")
+        .authorization_bearer("s3cret")
+        .await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["new_commitments"], 1);
+    assert_eq!(body["new_nullifiers"], 1);
+    assert_eq!(body["from_block"], 100);
+    assert_eq!(body["to_block"], 150);
+    assert_eq!(state.db.get_leaf_count().unwrap(), 1);
+    assert!(state.db.is_nullifier_spent("99").unwrap());
+
+    // Synced spends are told apart from ones made through this ASP
+    let body: serde_json::Value = server.get("/nullifier/99").await.json();
+    assert_eq!(body["origin"], "chain");
+    let body: serde_json::Value = server.get("/nullifiers?origin=local").await.json();
+    assert!(body["nullifiers"].as_array().unwrap().is_empty());
+    let body: serde_json::Value = server.get("/nullifiers?origin=chain").await.json();
+    assert_eq!(body["nullifiers"][0]["nullifier_hash"], "99");
+
+    let status: serde_json::Value = server.get("/status").await.json();
+    assert_eq!(status["sync"]["last_synced_block"], 150);
+    assert_eq!(status["sync"]["chain_head"], 150);
+    assert_eq!(status["sync"]["lag_blocks"], 0);
+    assert_eq!(status["sync"]["sync_in_progress"], false);
+    assert!(status["sync"]["last_sync_completed_at"].as_u64().unwrap() > 0);
+    assert!(status["sync"]["last_sync_error"].is_null());
+
+    // A second run finds nothing new
+    let body: serde_json::Value = server
+        .post("/admin/sync-now")
+        .authorization_bearer("s3cret")
+        .await
+        .json();
+    assert_eq!(body["new_commitments"], 0);
+    assert_eq!(body["new_nullifiers"], 0);
+    assert_eq!(body["from_block"], 150);
+}
+
+#[tokio::test]
+async fn test_resynced_rolled_back_deposit_keeps_its_auth_key() {
+    let state = create_test_state().await;
+    let note = state
+        .worker
+        .lock()
+        .await
+        .compute_commitment("111", "222", "1000", "0", "0x1")
+        .await
+        .unwrap();
+    let commitment: num_bigint::BigUint = note.commitment.parse().unwrap();
+    let limb = |n: num_bigint::BigUint| Felt::from_dec_str(&n.to_string()).unwrap();
+    let low = limb(&commitment & ((num_bigint::BigUint::from(1u8) << 128) - 1u8));
+    let high = limb(&commitment >> 128);
+
+    // The deposit lands on-chain but its root does not, so it is rolled back
+    // locally and only comes back through sync
+    let chain = StaticChain {
+        head: 20,
+        events: vec![chain_event(
+            10,
+            "CommitmentAdded",
+            vec![low, high, Felt::ZERO, Felt::ZERO, Felt::ZERO],
+        )],
+    };
+    let syncer = Syncer::new(Box::new(chain), vec![Felt::ONE]);
+    let relayer = MockRelayer::new().with_outcome(
+        RelayerMethod::SubmitMerkleRoot,
+        MockOutcome::Fail("root submission dropped".into()),
+    );
+    let state =
+        create_test_state_with_syncer(test_config(), Some(Box::new(relayer)), Some(syncer)).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let key = ed25519_dalek::SigningKey::from_bytes(&[7u8; 32]);
+    let resp = server
+        .post("/deposit")
+        .json(&json!({
+            "commitment": decimal_to_hex(&note.commitment),
+            "auth_pubkey": hex::encode(key.verifying_key().to_bytes())
+        }))
+        .await;
+    assert!(!resp.status_code().is_success());
+    assert_eq!(state.db.get_leaf_count().unwrap(), 0);
+
+    state.syncer.as_ref().unwrap().sync_once(&state).await.unwrap();
+    assert_eq!(state.db.get_leaf_count().unwrap(), 1);
+    assert!(state.db.get_auth_pubkey(0).unwrap().is_some());
+
+    let resp = server
+        .post("/withdraw/prove")
+        .json(&json!({
+            "secret": "111", "nullifier": "222",
+            "amount_low": "1000", "amount_high": "0",
+            "token": "0x1", "recipient": "0x5", "leaf_index": 0
+        }))
+        .await;
+    resp.assert_status(axum::http::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_sync_ingests_events_from_coordinator_and_pool() {
+    let (coordinator, pool, other) = (Felt::ONE, Felt::from(2u64), Felt::from(3u64));
+    let from = |address: Felt, event: EmittedEvent| EmittedEvent {
+        from_address: address,
+        ..event
+    };
+    let spent = |block, nullifier: u64| {
+        chain_event(block, "NullifierSpent", vec![Felt::from(nullifier), Felt::ZERO])
+    };
+    let chain = StaticChain {
+        head: 50,
+        events: vec![
+            from(
+                coordinator,
+                chain_event(
+                    10,
+                    "CommitmentAdded",
+                    vec![Felt::from(0x1234u64), Felt::ZERO, Felt::ZERO, Felt::ZERO, Felt::ZERO],
+                ),
+            ),
+            from(coordinator, spent(20, 7)),
+            from(pool, spent(30, 8)),
+            from(other, spent(40, 9)),
+        ],
+    };
+    let syncer = Syncer::new(Box::new(chain), vec![coordinator, pool, coordinator]);
+    let state = create_test_state_with_syncer(test_config(), None, Some(syncer)).await;
+
+    let report = state.syncer.as_ref().unwrap().sync_once(&state).await.unwrap();
+
+    assert_eq!(report.new_commitments, 1);
+    assert_eq!(report.new_nullifiers, 2);
+    assert_eq!(state.db.get_leaf_count().unwrap(), 1);
+    assert!(state.db.is_nullifier_spent("7").unwrap());
+    assert!(state.db.is_nullifier_spent("8").unwrap());
+    // Events from unmonitored contracts are ignored
+    assert!(!state.db.is_nullifier_spent("9").unwrap());
+}
+
+/// Split a decimal u256 into the `(low, high)` felts an event carries.
+fn u256_felts(decimal: &str) -> (Felt, Felt) {
+    let hex = format!("{:0>64}", decimal_to_hex(decimal).trim_start_matches("0x"));
+    let (high, low) = hex.split_at(32);
+    (
+        Felt::from_hex(&format!("0x{low}")).unwrap(),
+        Felt::from_hex(&format!("0x{high}")).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_sync_checks_commitment_root_against_worker() {
+    let leaves = ["4660".to_string(), "4661".to_string()];
+    let roots = {
+        let scratch = create_test_state().await;
+        let mut worker = scratch.worker.lock().await;
+        [
+            worker.compute_root(&leaves[..1]).await.unwrap(),
+            worker.compute_root(&leaves).await.unwrap(),
+        ]
+    };
+    let added = |leaf: u64, root: &str| {
+        let (low, high) = u256_felts(root);
+        chain_event(
+            10 + leaf,
+            "CommitmentAdded",
+            vec![Felt::from(0x1234 + leaf), Felt::ZERO, Felt::from(leaf), low, high],
+        )
+    };
+    let synced = |events: Vec<EmittedEvent>| async move {
+        let chain = StaticChain { head: 50, events };
+        let syncer = Syncer::new(Box::new(chain), vec![Felt::ONE]);
+        let state = create_test_state_with_syncer(test_config(), None, Some(syncer)).await;
+        state.syncer.as_ref().unwrap().sync_once(&state).await.unwrap();
+        state
+    };
+
+    // Roots reported by the contract match the worker's
+    let state = synced(vec![added(0, &roots[0]), added(1, &roots[1])]).await;
+    assert_eq!(state.db.get_leaf_count().unwrap(), 2);
+    assert_eq!(state.db.get_sync_state(TREE_DIVERGED_KEY).unwrap(), None);
+    let server = TestServer::new(create_test_router(state)).unwrap();
+    let status: serde_json::Value = server.get("/status").await.json();
+    assert!(status["sync"]["tree_diverged_at_leaf"].is_null());
+
+    // The second leaf's reported root disagrees: the leaf is still synced,
+    // and the tree is marked diverged at it
+    let state = synced(vec![added(0, &roots[0]), added(1, &roots[0])]).await;
+    assert_eq!(state.db.get_leaf_count().unwrap(), 2);
+    assert_eq!(
+        state.db.get_sync_state(TREE_DIVERGED_KEY).unwrap().as_deref(),
+        Some("1")
+    );
+    let server = TestServer::new(create_test_router(state)).unwrap();
+    let status: serde_json::Value = server.get("/status").await.json();
+    assert_eq!(status["sync"]["tree_diverged_at_leaf"], 1);
+}
+
+#[tokio::test]
+async fn test_admin_vacuum() {
+    let config = Config {
+        admin_api_token: Some("s3cret".into()),
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    server.post("/admin/vacuum").await.assert_status_unauthorized();
+
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x1234"}))
+        .await
+        .assert_status_ok();
+    let resp = server
+        .post("/admin/vacuum")
+        .authorization_bearer("s3cret")
+        .await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["status"], "ok");
+    assert_eq!(body["db_bytes_after"], 0); // in-memory database
+
+    // The tree is intact and the database still usable
+    assert_eq!(state.db.get_leaf_count().unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_admin_proof_stats() {
+    let config = Config {
+        admin_api_token: Some("s3cret".into()),
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    server.get("/admin/proof-stats").await.assert_status_unauthorized();
+
+    for duration_ms in [1200, 800, 1000] {
+        state.record_proof_time(CircuitType::Swap, duration_ms);
+    }
+    let resp = server
+        .get("/admin/proof-stats")
+        .authorization_bearer("s3cret")
+        .await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(
+        body["circuits"],
+        json!([{"circuit": "swap", "count": 3, "avg_ms": 1000, "p95_ms": 1200}])
+    );
+}
+
+#[tokio::test]
+async fn test_admin_verify_tree_consistent() {
+    let config = Config {
+        admin_api_token: Some("s3cret".into()),
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    server.post("/admin/verify-tree").await.assert_status_unauthorized();
+
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x1234"}))
+        .await
+        .assert_status_ok();
+    let resp = server
+        .post("/admin/verify-tree")
+        .authorization_bearer("s3cret")
+        .await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["consistent"], true);
+    assert_eq!(body["db_leaf_count"], 1);
+    assert_eq!(body["worker_root"], body["rebuilt_root"]);
+    assert_eq!(body["repaired"], false);
+    assert_eq!(
+        state.db.get_latest_root().unwrap().as_deref(),
+        body["worker_root"].as_str()
+    );
+}
+
+#[tokio::test]
+async fn test_admin_verify_tree_detects_and_repairs_mismatch() {
+    let config = Config {
+        admin_api_token: Some("s3cret".into()),
+        ..test_config()
+    };
+    let relayer = MockRelayer::new();
+    let state = create_test_state_with(config, Box::new(relayer.clone())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x1234"}))
+        .await
+        .assert_status_ok();
+    // A leaf the worker never saw, as after a manual DB edit
+    state.db.insert_commitment(1, "999", None).unwrap();
+
+    let body: serde_json::Value = server
+        .post("/admin/verify-tree")
+        .authorization_bearer("s3cret")
+        .await
+        .json();
+    assert_eq!(body["consistent"], false);
+    assert_eq!(body["db_leaf_count"], 2);
+    assert_ne!(body["worker_root"], body["rebuilt_root"]);
+    assert_eq!(body["repaired"], false);
+    let rebuilt_root = body["rebuilt_root"].as_str().unwrap().to_string();
+
+    let body: serde_json::Value = server
+        .post("/admin/verify-tree?repair=true")
+        .authorization_bearer("s3cret")
+        .await
+        .json();
+    assert_eq!(body["consistent"], false);
+    assert_eq!(body["repaired"], true);
+    assert_eq!(
+        relayer.calls_to(RelayerMethod::SubmitMerkleRoot).last(),
+        Some(&RecordedCall::SubmitMerkleRoot {
+            root: rebuilt_root.clone()
+        })
+    );
+    assert_eq!(state.db.get_latest_root().unwrap(), Some(rebuilt_root.clone()));
+
+    let body: serde_json::Value = server
+        .post("/admin/verify-tree")
+        .authorization_bearer("s3cret")
+        .await
+        .json();
+    assert_eq!(body["consistent"], true);
+    assert_eq!(body["worker_root"], rebuilt_root);
+}
+
+#[tokio::test]
+async fn test_admin_resubmit_root() {
+    let config = Config {
+        admin_api_token: Some("s3cret".into()),
+        ..test_config()
+    };
+    let relayer = MockRelayer::new();
+    let state = create_test_state_with(config, Box::new(relayer.clone())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    server
+        .post("/admin/resubmit-root")
+        .await
+        .assert_status_unauthorized();
+    server
+        .post("/admin/resubmit-root")
+        .authorization_bearer("s3cret")
+        .await
+        .assert_status_bad_request();
+
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0x1234"}))
+        .await
+        .assert_status_ok();
+    // A leaf whose root submission never landed
+    state.db.insert_commitment(1, "999", None).unwrap();
+    let root = state.worker.lock().await.insert_leaf("999").await.unwrap();
+    relayer.assert_called(RelayerMethod::SubmitMerkleRoot, 1);
+
+    let resp = server
+        .post("/admin/resubmit-root")
+        .authorization_bearer("s3cret")
+        .await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["root"], root);
+    assert_eq!(body["leaf_count"], 2);
+    assert_eq!(body["resubmitted"], true);
+    assert_eq!(body["tx_hash"], "0xmock_root_tx");
+    assert_eq!(
+        relayer.calls_to(RelayerMethod::SubmitMerkleRoot).last(),
+        Some(&RecordedCall::SubmitMerkleRoot { root: root.clone() })
+    );
+    assert_eq!(state.db.get_latest_root().unwrap(), Some(root.clone()));
+
+    // Already on-chain: nothing is sent
+    let body: serde_json::Value = server
+        .post("/admin/resubmit-root")
+        .authorization_bearer("s3cret")
+        .await
+        .json();
+    assert_eq!(body["resubmitted"], false);
+    assert!(body["tx_hash"].is_null());
+    relayer.assert_called(RelayerMethod::SubmitMerkleRoot, 2);
+}
+
+#[tokio::test]
+async fn test_admin_export_import_roundtrip() {
+    let config = Config {
+        admin_api_token: Some("s3cret".into()),
+        ..test_config()
+    };
+    let source = create_test_state_with(config.clone(), Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(source.clone())).unwrap();
+
+    server.get("/admin/export").await.assert_status_unauthorized();
+    for commitment in ["0x11", "0x22", "0x33"] {
+        server
+            .post("/deposit")
+            .json(&json!({"commitment": commitment}))
+            .await
+            .assert_status_ok();
+    }
+
+    let resp = server.get("/admin/export").authorization_bearer("s3cret").await;
+    resp.assert_status_ok();
+    assert_eq!(resp.header("content-type"), "application/x-ndjson");
+    let export = resp.text();
+    let lines: Vec<serde_json::Value> = export
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["leaf_index"], 0);
+    assert_eq!(lines[2]["commitment"], "51");
+    assert!(lines[0]["deposit_tx"].is_string());
+
+    let target = create_test_state_without_relayer_with(config).await;
+    let server = TestServer::new(create_test_router(target.clone())).unwrap();
+    let import = |body: String| {
+        server
+            .post("/admin/import")
+            .authorization_bearer("s3cret")
+            .bytes(body.into())
+            .content_type("application/x-ndjson")
+    };
+
+    let resp = import(export.clone()).await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["imported"], 3);
+    assert_eq!(body["skipped"], 0);
+    assert_eq!(body["leaf_count"], 3);
+    assert_eq!(body["root"], source.db.get_latest_root().unwrap().unwrap());
+    assert_eq!(
+        target.db.get_all_commitments().unwrap()[1].deposit_tx,
+        source.db.get_all_commitments().unwrap()[1].deposit_tx
+    );
+
+    // Re-importing is a no-op, and a conflicting row rejects the body
+    let body: serde_json::Value = import(export).await.json();
+    assert_eq!(body["imported"], 0);
+    assert_eq!(body["skipped"], 3);
+    import(r#"{"leaf_index": 0, "commitment": "99", "deposit_tx": null}"#.into())
+        .await
+        .assert_status_bad_request();
+    import("not json".into()).await.assert_status_bad_request();
+    assert_eq!(target.db.get_leaf_count().unwrap(), 3);
+}
+
+#[tokio::test]
+async fn test_admin_ban_and_unban() {
+    let config = Config {
+        admin_api_token: Some("s3cret".into()),
+        trust_forwarded_for: true,
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    let resp = server
+        .post("/admin/ban")
+        .authorization_bearer("s3cret")
+        .json(&json!({"ip": "203.0.113.7", "reason": "scraping"}))
+        .await;
+    resp.assert_status_ok();
+    let body: serde_json::Value = resp.json();
+    assert_eq!(body["ip"], "203.0.113.7");
+    assert_eq!(body["automatic"], false);
+    assert_eq!(state.db.list_banned_sources().unwrap()[0].reason, "scraping");
+
+    let resp = server
+        .get("/tree/root")
+        .add_header("x-forwarded-for", "10.0.0.1, 203.0.113.7")
+        .await;
+    resp.assert_status_forbidden();
+    assert_eq!(resp.json::<serde_json::Value>()["code"], "forbidden");
+    server
+        .get("/tree/root")
+        .add_header("x-forwarded-for", "198.51.100.1")
+        .await
+        .assert_status_ok();
+    // Admin routes stay reachable from a banned IP
+    server
+        .post("/admin/resync")
+        .authorization_bearer("s3cret")
+        .add_header("x-forwarded-for", "203.0.113.7")
+        .await
+        .assert_status_ok();
+
+    let resp = server
+        .delete("/admin/ban/203.0.113.7")
+        .authorization_bearer("s3cret")
+        .await;
+    resp.assert_status_ok();
+    assert_eq!(resp.json::<serde_json::Value>()["removed"], true);
+    assert!(state.db.list_banned_sources().unwrap().is_empty());
+    server
+        .get("/tree/root")
+        .add_header("x-forwarded-for", "203.0.113.7")
+        .await
+        .assert_status_ok();
+
+    server
+        .post("/admin/ban")
+        .authorization_bearer("s3cret")
+        .json(&json!({"ip": "not-an-ip"}))
+        .await
+        .assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_auto_ban_on_threshold_breach() {
+    let config = Config {
+        admin_api_token: Some("s3cret".into()),
+        trust_forwarded_for: true,
+        auto_ban_threshold: 3,
+        auto_ban_window_secs: 3600,
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    for _ in 0..3 {
+        server
+            .get("/tree/root")
+            .add_header("x-forwarded-for", "203.0.113.9")
+            .await
+            .assert_status_ok();
+    }
+    server
+        .get("/tree/root")
+        .add_header("x-forwarded-for", "203.0.113.9")
+        .await
+        .assert_status_forbidden();
+
+    let banned = state.db.list_banned_sources().unwrap();
+    assert_eq!(banned.len(), 1);
+    assert_eq!(banned[0].ip, "203.0.113.9");
+    assert!(banned[0].automatic);
+
+    // The ban survives a restart: a fresh list loaded from the database has it
+    let reloaded = zylith_asp::api::bans::BanList::default();
+    assert_eq!(reloaded.load(&state.db).unwrap(), 1);
+    assert!(reloaded.is_banned(&"203.0.113.9".parse().unwrap()));
+
+    server
+        .get("/tree/root")
+        .add_header("x-forwarded-for", "203.0.113.10")
+        .await
+        .assert_status_ok();
+    server
+        .delete("/admin/ban/203.0.113.9")
+        .authorization_bearer("s3cret")
+        .await
+        .assert_status_ok();
+    server
+        .get("/tree/root")
+        .add_header("x-forwarded-for", "203.0.113.9")
+        .await
+        .assert_status_ok();
+}
+
+#[tokio::test]
+async fn test_forged_forwarded_for_neither_evades_nor_causes_ban() {
+    let config = Config {
+        trust_forwarded_for: true,
+        auto_ban_threshold: 3,
+        auto_ban_window_secs: 3600,
+        ..test_config()
+    };
+    let state = create_test_state_with(config, Box::new(MockRelayer::new())).await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+
+    // The abuser forges a different first entry on every request; the proxy
+    // appends their real address
+    for forged in ["198.51.100.50", "198.51.100.51", "198.51.100.52"] {
+        server
+            .get("/tree/root")
+            .add_header("x-forwarded-for", format!("{forged}, 203.0.113.9"))
+            .await
+            .assert_status_ok();
+    }
+    server
+        .get("/tree/root")
+        .add_header("x-forwarded-for", "198.51.100.53, 203.0.113.9")
+        .await
+        .assert_status_forbidden();
+
+    let banned = state.db.list_banned_sources().unwrap();
+    assert_eq!(banned.len(), 1);
+    assert_eq!(banned[0].ip, "203.0.113.9");
+    server
+        .get("/tree/root")
+        .add_header("x-forwarded-for", "198.51.100.50")
+        .await
+        .assert_status_ok();
+}
+
+#[tokio::test]
+async fn test_deposit_publishes_tree_event() {
+    let state = create_test_state().await;
+    let server = TestServer::new(create_test_router(state.clone())).unwrap();
+    let (backlog, mut events) = state.events.subscribe(None);
+    assert!(backlog.is_empty());
+
+    server
+        .post("/deposit")
+        .json(&json!({"commitment": "0xdead"}))
+        .await
+        .assert_status_ok();
+
+    let received = events.try_recv().expect("deposit should publish an event");
+    let root = state.tree_snapshot().root.unwrap();
+    assert_eq!(
+        received.event,
+        TreeEvent::CommitmentAdded {
+            leaf_index: 0,
+            commitment: "57005".into(),
+            root,
+        }
+    );
+
+    // A client resuming from before the deposit gets it replayed
+    let (backlog, _) = state.events.subscribe(Some(received.id - 1));
+    assert_eq!(backlog, vec![received]);
+}
+
+#[tokio::test]
+async fn test_request_id_header() {
+    let server = create_test_server().await;
+
+    let first = server.get("/status").await;
+    let second = server.get("/status").await;
+
+    let first_id = first.header("x-request-id");
+    let second_id = second.header("x-request-id");
+    assert!(!first_id.is_empty());
+    assert_ne!(first_id, second_id);
+
+    // Error responses carry it too
+    let resp = server
+        .post("/deposit")
+        .json(&json!({"commitment": "not-hex"}))
+        .await;
+    resp.assert_status_bad_request();
+    assert!(!resp.header("x-request-id").is_empty());
 }
 
 #[tokio::test]
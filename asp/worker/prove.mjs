@@ -0,0 +1,41 @@
+/**
+ * One-shot Groth16 prover, run by worker.mjs as a child process so that a
+ * cancelled proof can be killed without taking the worker down with it.
+ *
+ * Reads { circuit, inputs } as JSON on stdin and writes
+ * { proof, publicSignals, verified } as JSON on stdout. Exits with
+ * INPUT_ERROR_EXIT_CODE when the inputs fail witness generation and 1 on
+ * any other error.
+ */
+import { generateProof } from "../../circuits/scripts/lib/prover.mjs";
+
+// Must match INPUT_ERROR_EXIT_CODE in worker.mjs
+const INPUT_ERROR_EXIT_CODE = 2;
+
+// Witness calculator errors: the inputs do not satisfy the circuit or do not
+// fit its signals
+const WITNESS_ERROR = new RegExp(
+  [
+    "Assert Failed",
+    "Not enough values for input signal",
+    "Too many values for input signal",
+    "Signal not found",
+  ].join("|"),
+);
+
+let input = "";
+process.stdin.setEncoding("utf8");
+process.stdin.on("data", (chunk) => (input += chunk));
+process.stdin.on("end", async () => {
+  try {
+    const { circuit, inputs } = JSON.parse(input);
+    const result = await generateProof(circuit, inputs);
+    process.stdout.write(JSON.stringify(result));
+    // snarkjs leaves curve worker threads running; exit explicitly
+    process.exit(0);
+  } catch (err) {
+    const message = err.message || String(err);
+    process.stderr.write(`${message}\n`);
+    process.exit(WITNESS_ERROR.test(message) ? INPUT_ERROR_EXIT_CODE : 1);
+  }
+});
@@ -6,7 +6,12 @@
  * and Groth16 proof generation using the existing circuits pipeline.
  *
  * Spawned by Rust with: bun run worker/worker.mjs
+ *
+ * Proofs run in a child process (prove.mjs) so that a `cancel` command,
+ * sent when the ASP caller goes away, can kill the proving for that id.
  */
+import { spawn } from "child_process";
+import { createHash } from "crypto";
 import { createInterface } from "readline";
 import { MerkleTree } from "../../circuits/scripts/lib/merkle.mjs";
 import {
@@ -14,8 +19,8 @@ import {
   computePositionCommitment,
 } from "../../circuits/scripts/lib/commitment.mjs";
 import {
-  generateProof,
   exportProofArtifacts,
+  verifyProof,
 } from "../../circuits/scripts/lib/prover.mjs";
 import {
   generateCalldata,
@@ -29,23 +34,161 @@ import { fileURLToPath } from "url";
 const __dirname = path.dirname(fileURLToPath(import.meta.url));
 const GARAGA_DIR = path.resolve(__dirname, "../../garaga_verifiers");
 const BUILD_DIR = path.resolve(__dirname, "../../circuits/build");
+const PROVE_SCRIPT = path.join(__dirname, "prove.mjs");
+
+// Garaga calldata layout produced below. Must match EXPECTED_CALLDATA_VERSION
+// in src/prover/worker.rs; bump both when the layout changes.
+const CALLDATA_VERSION = 1;
+
+// Command set announced in the ready message. Must match
+// WORKER_PROTOCOL_VERSION in src/prover/worker.rs; bump both when a command
+// is added, removed or changes shape.
+const PROTOCOL_VERSION = 4;
+
+// Commitment schemes this worker hashes with (see computeCommitment and
+// computePositionCommitment). Must match DEFAULT_NOTE_DOMAIN and
+// DEFAULT_POSITION_DOMAIN in src/prover/worker.rs; the circuits fix the
+// schemes, so a new scheme means new names here and there.
+const DOMAINS = { note: "zylith.note.v1", position: "zylith.position.v1" };
+
+// Circuits whose verification keys are reported by `circuit_info`
+const CIRCUITS = ["membership", "swap", "mint", "burn"];
+
+// Tree height is set by the Rust server on spawn (TREE_HEIGHT env var)
+const TREE_HEIGHT = Number(process.env.TREE_HEIGHT) || undefined;
 
 // In-memory Merkle tree (rebuilt from leaves on build_tree command)
-let tree = new MerkleTree();
+let tree = new MerkleTree(TREE_HEIGHT);
+
+// Running proof processes by request id, so `cancel` can kill them
+const provers = new Map();
 
 // Send JSON response to Rust via stdout
 function respond(data) {
   process.stdout.write(JSON.stringify(data) + "\n");
 }
 
+// Exit code prove.mjs uses for inputs that fail witness generation
+const INPUT_ERROR_EXIT_CODE = 2;
+
+// A failure caused by the command's inputs rather than the worker. Reported
+// with kind "invalid_input" (HTTP 400 on the Rust side); everything else is
+// kind "internal" (503).
+class InputError extends Error {}
+
+// Error response for `err`, tagged with its kind
+function respondError(id, err) {
+  respond({
+    id,
+    ok: false,
+    kind: err instanceof InputError ? "invalid_input" : "internal",
+    error: err.message || String(err),
+  });
+}
+
+// Installed snarkjs version, from whichever node_modules the prover resolves
+function snarkjsVersion() {
+  const candidates = [
+    path.join(__dirname, "node_modules/snarkjs/package.json"),
+    path.resolve(__dirname, "../../circuits/node_modules/snarkjs/package.json"),
+    path.resolve(__dirname, "../../node_modules/snarkjs/package.json"),
+  ];
+  for (const candidate of candidates) {
+    if (fs.existsSync(candidate)) {
+      return JSON.parse(fs.readFileSync(candidate, "utf8")).version;
+    }
+  }
+  return "unknown";
+}
+
+// SHA-256 of each built circuit's verification key; missing builds are omitted
+function circuitHashes() {
+  const hashes = {};
+  for (const circuit of CIRCUITS) {
+    const vkPath = path.join(BUILD_DIR, circuit, "verification_key.json");
+    if (fs.existsSync(vkPath)) {
+      const digest = createHash("sha256").update(fs.readFileSync(vkPath)).digest("hex");
+      hashes[circuit] = `0x${digest}`;
+    }
+  }
+  return hashes;
+}
+
+// Read every built circuit's witness generator, proving key and verification
+// key once, so the first proof does not pay for loading them from disk.
+// Proofs run in fresh prove.mjs processes, which then read the artifacts from
+// the page cache. Returns the circuits whose artifacts were all present.
+async function warmup() {
+  const loaded = [];
+  for (const circuit of CIRCUITS) {
+    const artifacts = [
+      path.join(BUILD_DIR, circuit, `${circuit}_js`, `${circuit}.wasm`),
+      path.join(BUILD_DIR, circuit, `${circuit}_0000.zkey`),
+      path.join(BUILD_DIR, circuit, "verification_key.json"),
+    ];
+    if (!artifacts.every((artifact) => fs.existsSync(artifact))) {
+      continue;
+    }
+    await Promise.all(artifacts.map((artifact) => fs.promises.readFile(artifact)));
+    loaded.push(circuit);
+  }
+  return loaded;
+}
+
+// Generate a proof in a child process registered under `id`. Rejects if the
+// process is killed by `cancel` or exits with an error.
+function runProver(id, circuit, inputs) {
+  return new Promise((resolve, reject) => {
+    const child = spawn(process.execPath, [PROVE_SCRIPT], {
+      stdio: ["pipe", "pipe", "pipe"],
+    });
+    provers.set(id, child);
+
+    let stdout = "";
+    let stderr = "";
+    child.stdout.on("data", (chunk) => (stdout += chunk));
+    child.stderr.on("data", (chunk) => (stderr += chunk));
+    child.on("error", (err) => {
+      provers.delete(id);
+      reject(err);
+    });
+    child.on("close", (code, signal) => {
+      provers.delete(id);
+      if (signal) {
+        reject(new Error(`Proof generation cancelled (${signal})`));
+      } else if (code === INPUT_ERROR_EXIT_CODE) {
+        reject(new InputError(stderr.trim() || "Circuit inputs rejected"));
+      } else if (code !== 0) {
+        reject(new Error(stderr.trim() || `Prover exited with code ${code}`));
+      } else {
+        resolve(JSON.parse(stdout));
+      }
+    });
+
+    child.stdin.end(JSON.stringify({ circuit, inputs }));
+  });
+}
+
 // Handle a single command from Rust
+// Tree as it was when it held its first `leafCount` leaves
+function treePrefix(leafCount) {
+  if (leafCount > tree.leaves.length) {
+    throw new Error(
+      `Tree has ${tree.leaves.length} leaves, cannot rebuild ${leafCount}`,
+    );
+  }
+  const prefix = new MerkleTree(TREE_HEIGHT);
+  prefix.leaves = tree.leaves.slice(0, leafCount);
+  return prefix;
+}
+
 async function handleCommand(msg) {
   const { id, command, params } = msg;
 
   try {
     switch (command) {
       case "build_tree": {
-        tree = new MerkleTree();
+        tree = new MerkleTree(TREE_HEIGHT);
         for (const leaf of params.leaves) {
           tree.insert(String(leaf));
         }
@@ -54,6 +197,16 @@ async function handleCommand(msg) {
         break;
       }
 
+      case "compute_root": {
+        // Root of a tree of `leaves`, leaving the live tree untouched
+        const scratch = new MerkleTree(TREE_HEIGHT);
+        for (const leaf of params.leaves) {
+          scratch.insert(String(leaf));
+        }
+        respond({ id, ok: true, data: { root: scratch.getRoot() } });
+        break;
+      }
+
       case "insert_leaf": {
         tree.insert(String(params.leaf));
         const root = tree.getRoot();
@@ -68,7 +221,10 @@ async function handleCommand(msg) {
       }
 
       case "get_proof": {
-        const proof = tree.getProof(params.leafIndex);
+        // With leafCount, prove against the historical tree of that many leaves
+        const source =
+          params.leafCount === undefined ? tree : treePrefix(params.leafCount);
+        const proof = source.getProof(params.leafIndex);
         respond({
           id,
           ok: true,
@@ -122,18 +278,18 @@ async function handleCommand(msg) {
       case "generate_proof": {
         const { circuit, inputs } = params;
 
-        // 1. Generate Groth16 proof via snarkjs
-        const { proof, publicSignals, verified } = await generateProof(
+        // 1. Generate Groth16 proof via snarkjs (cancellable child process)
+        const { proof, publicSignals, verified } = await runProver(
+          id,
           circuit,
           inputs,
         );
 
         if (!verified) {
-          respond({
+          respondError(
             id,
-            ok: false,
-            error: `Local verification failed for ${circuit}`,
-          });
+            new Error(`Local verification failed for ${circuit}`),
+          );
           return;
         }
 
@@ -142,17 +298,16 @@ async function handleCommand(msg) {
 
         // 3. Generate Garaga calldata
         if (!isGaragaAvailable()) {
-          respond({ id, ok: false, error: "garaga CLI not available" });
+          respondError(id, new Error("garaga CLI not available"));
           return;
         }
 
         const calldataGenerated = generateCalldata(circuit);
         if (!calldataGenerated) {
-          respond({
+          respondError(
             id,
-            ok: false,
-            error: `garaga calldata generation failed for ${circuit}`,
-          });
+            new Error(`garaga calldata generation failed for ${circuit}`),
+          );
           return;
         }
 
@@ -175,21 +330,55 @@ async function handleCommand(msg) {
           data: {
             calldata,
             publicSignals,
+            proof,
+            circuit,
+            calldataVersion: CALLDATA_VERSION,
           },
         });
         break;
       }
 
+      case "verify_proof": {
+        const { circuit, proof, publicSignals } = params;
+        const verified = await verifyProof(circuit, proof, publicSignals);
+        respond({ id, ok: true, data: { verified } });
+        break;
+      }
+
+      case "cancel": {
+        // No response: the cancelled generate_proof answers with an error
+        const child = provers.get(params.id);
+        if (child) {
+          child.kill("SIGKILL");
+        }
+        break;
+      }
+
+      case "circuit_info": {
+        respond({
+          id,
+          ok: true,
+          data: { version: snarkjsVersion(), circuits: circuitHashes() },
+        });
+        break;
+      }
+
+      case "warmup": {
+        const circuits = await warmup();
+        respond({ id, ok: true, data: { circuits } });
+        break;
+      }
+
       case "ping": {
         respond({ id, ok: true, data: { pong: true } });
         break;
       }
 
       default:
-        respond({ id, ok: false, error: `Unknown command: ${command}` });
+        respondError(id, new Error(`Unknown command: ${command}`));
     }
   } catch (err) {
-    respond({ id, ok: false, error: err.message || String(err) });
+    respondError(id, err);
   }
 }
 
@@ -199,19 +388,28 @@ async function handleCommand(msg) {
 
   const rl = createInterface({ input: process.stdin });
 
+  // The server passes the domains it expects; report ours either way and let
+  // it refuse a mismatch.
+  const requested = {
+    note: process.env.NOTE_DOMAIN_SEPARATOR ?? DOMAINS.note,
+    position: process.env.POSITION_DOMAIN_SEPARATOR ?? DOMAINS.position,
+  };
+  if (requested.note !== DOMAINS.note || requested.position !== DOMAINS.position) {
+    console.error(
+      `Requested commitment domains ${JSON.stringify(requested)}, ` +
+        `this worker implements ${JSON.stringify(DOMAINS)}`,
+    );
+  }
+
   // Send ready signal AFTER Poseidon is initialized
-  respond({ ready: true });
+  respond({ ready: true, protocol_version: PROTOCOL_VERSION, domains: DOMAINS });
 
   rl.on("line", async (line) => {
     try {
       const msg = JSON.parse(line);
       await handleCommand(msg);
     } catch (err) {
-      respond({
-        id: "unknown",
-        ok: false,
-        error: `Parse error: ${err.message}`,
-      });
+      respondError("unknown", new Error(`Parse error: ${err.message}`));
     }
   });
 
@@ -17,8 +17,9 @@ const TREE_HEIGHT = 20;
 const ZERO = "0";
 
 export class MerkleTree {
-  constructor() {
-    this.height = TREE_HEIGHT;
+  /** @param {number} height - tree depth; defaults to the circuits' TREE_HEIGHT */
+  constructor(height = TREE_HEIGHT) {
+    this.height = height;
     this.leaves = []; // stored as decimal strings
   }
 
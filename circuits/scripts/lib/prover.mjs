@@ -60,6 +60,23 @@ export async function generateProof(circuitName, inputSignals) {
   return { proof, publicSignals, verified };
 }
 
+/**
+ * Verify a Groth16 proof against the circuit's verification key.
+ *
+ * @param {string} circuitName - Circuit name (membership, swap, mint, burn)
+ * @param {object} proof - snarkjs proof (pi_a, pi_b, pi_c)
+ * @param {string[]} publicSignals - Public signals the proof commits to
+ * @returns {Promise<boolean>}
+ */
+export async function verifyProof(circuitName, proof, publicSignals) {
+  const vkPath = path.join(BUILD_DIR, circuitName, "verification_key.json");
+  if (!fs.existsSync(vkPath)) {
+    throw new Error(`Missing vk artifact: ${vkPath}`);
+  }
+  const vk = JSON.parse(fs.readFileSync(vkPath, "utf8"));
+  return snarkjs.groth16.verify(vk, publicSignals, proof);
+}
+
 /**
  * Export proof and public signals to JSON files in the build directory.
  * These are needed by `garaga calldata`.